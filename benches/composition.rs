@@ -0,0 +1,97 @@
+// Allocations made parsing the shipped database, against those its formulas would add stored as
+// `Vec<(String, f64)>` as they once were, and timings of summing element abundances over every
+// species with `Composition` against the same sum over those strings. Run with
+// `cargo bench --bench composition`.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use equilibrium_rs::database::parse_thermo;
+use equilibrium_rs::elements::Composition;
+
+const ROUNDS: u32 = 200;
+
+// The system allocator, counting each allocation it makes
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Allocations made by one call, with its result
+fn allocations<T>(run: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = run();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before, result)
+}
+
+// Mean time of one call, over a warm-up call and then `ROUNDS` more
+fn time<T>(mut run: impl FnMut() -> T) -> Duration {
+    black_box(run());
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(run());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let raw_text = std::fs::read_to_string("thermo.inp").expect("run from the crate root");
+    let (parse, db) = allocations(|| parse_thermo(&raw_text).unwrap());
+    let (strings, formulas) = allocations(|| {
+        db.species()
+            .iter()
+            .map(|species| {
+                let formula = species.elements.iter();
+                formula
+                    .map(|(element, count)| (element.symbol().to_string(), *count))
+                    .collect::<Vec<(String, f64)>>()
+            })
+            .collect::<Vec<_>>()
+    });
+    println!("{} species", db.species().len());
+    println!("parse allocations     {:>8}", parse);
+    println!("string formulas add   {:>8}", strings);
+
+    // A thousandth of a mole of every species, the electron and all
+    let moles = 1.0e-3;
+    let interned = time(|| {
+        let mut totals = Composition::new();
+        for species in db.species() {
+            totals.add(&species.elements.scale(moles));
+        }
+        totals
+    });
+    let named = time(|| {
+        let mut totals: Vec<(String, f64)> = Vec::new();
+        for formula in &formulas {
+            for (symbol, count) in formula {
+                let symbol = symbol.to_ascii_uppercase();
+                match totals.iter_mut().find(|(s, _)| *s == symbol) {
+                    Some((_, total)) => *total += moles * count,
+                    None => totals.push((symbol, moles * count)),
+                }
+            }
+        }
+        totals
+    });
+    println!("abundances, interned  {:>10.2?}", interned);
+    println!("abundances, strings   {:>10.2?}", named);
+    println!(
+        "speed-up              {:>10.1}x",
+        named.as_secs_f64() / interned.as_secs_f64()
+    );
+}
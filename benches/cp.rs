@@ -0,0 +1,50 @@
+// Timings of Cp/R from the fits as stored against the same fits as cached polynomials, each
+// evaluated across its temperature range as a solver's iterations would. Run with
+// `cargo bench --bench cp`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use equilibrium_rs::database::{TemperatureRange, parse_thermo};
+use equilibrium_rs::properties::CpPolynomial;
+
+const ROUNDS: usize = 50;
+const POINTS: usize = 64; // Temperatures evaluated in each fit
+
+// Temperatures spread across a fit
+fn sweep(range: &TemperatureRange) -> impl Iterator<Item = f64> + '_ {
+    let step = (range.temp_high - range.temp_low) / POINTS as f64;
+    (0..POINTS).map(move |i| range.temp_low + step * i as f64)
+}
+
+// Mean time of one evaluation, over every fit in every round
+fn time(ranges: &[&TemperatureRange], cp_over_r: impl Fn(usize, f64) -> f64) -> f64 {
+    let mut values = [0.0; POINTS];
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for (i, range) in ranges.iter().enumerate() {
+            for (value, t) in values.iter_mut().zip(sweep(range)) {
+                *value = cp_over_r(i, t);
+            }
+            black_box(&values);
+        }
+    }
+    let elapsed: Duration = start.elapsed();
+    elapsed.as_nanos() as f64 / (ROUNDS * ranges.len() * POINTS) as f64
+}
+
+fn main() {
+    let raw_text = std::fs::read_to_string("thermo.inp").expect("run from the crate root");
+    let db = parse_thermo(&raw_text).unwrap();
+    let ranges: Vec<&TemperatureRange> = db
+        .species()
+        .iter()
+        .flat_map(|species| species.temperature_ranges())
+        .collect();
+    let polynomials: Vec<CpPolynomial> = ranges.iter().map(|&range| range.into()).collect();
+
+    let direct = time(&ranges, |i, t| ranges[i].cp_over_r(t).unwrap());
+    let cached = time(&ranges, |i, t| polynomials[i].cp_over_r(t));
+    println!("fits       {:>6.2} ns per evaluation", direct);
+    println!("cached     {:>6.2} ns per evaluation", cached);
+    println!("speed-up   {:>6.1}x", direct / cached);
+}
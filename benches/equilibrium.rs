@@ -0,0 +1,70 @@
+// Timings of the equilibrium solver with species' properties evaluated on every iteration
+// against the same solves drawing them from a property cache, for humid air burning a little
+// methane (about 150 candidate species). Run with `cargo bench --bench equilibrium`.
+use std::hint::black_box;
+use std::time::Instant;
+
+use equilibrium_rs::cache::PropertyCache;
+use equilibrium_rs::database::parse_thermo;
+use equilibrium_rs::equilibrium::{
+    EquilibriumOptions, Problem, solve_equilibrium, solve_equilibrium_cached,
+};
+use equilibrium_rs::mixture::Mixture;
+
+const ROUNDS: usize = 20;
+
+// Mean time of one outer iteration, in microseconds, over `ROUNDS` solves after a warm-up
+fn time(mut solve: impl FnMut() -> usize) -> f64 {
+    black_box(solve());
+    let start = Instant::now();
+    let iterations: usize = (0..ROUNDS).map(|_| solve()).sum();
+    start.elapsed().as_secs_f64() * 1e6 / iterations as f64
+}
+
+fn main() {
+    let raw_text = std::fs::read_to_string("thermo.inp").expect("run from the crate root");
+    let db = parse_thermo(&raw_text).unwrap();
+    let reactants = [
+        ("N2", 78.08),
+        ("O2", 20.95),
+        ("Ar", 0.93),
+        ("CO2", 0.04),
+        ("H2O", 2.0),
+        ("CH4", 1.0),
+    ];
+    let air = Mixture::from_names(&db, &reactants).unwrap();
+    let options = EquilibriumOptions::default();
+    let tp = Problem::TP {
+        temperature: 2500.0,
+        pressure: 1.0e6,
+    };
+    let hp = Problem::HP {
+        enthalpy: air.enthalpy(&db, 800.0).unwrap(),
+        pressure: 1.0e6,
+    };
+    let species = solve_equilibrium(&db, &air, tp, &options)
+        .unwrap()
+        .species
+        .len();
+    println!("{} candidate species", species);
+
+    for (label, problem, epsilon) in [("TP", tp, 0.0), ("HP", hp, 0.0), ("HP", hp, 0.5)] {
+        let direct = time(|| {
+            let result = solve_equilibrium(&db, &air, problem, &options).unwrap();
+            result.iterations
+        });
+        let cached = time(|| {
+            let mut cache = PropertyCache::new(epsilon);
+            let result = solve_equilibrium_cached(&db, &air, problem, &options, &mut cache);
+            result.unwrap().iterations
+        });
+        println!(
+            "{} epsilon {:<4} {:>8.2} us per iteration uncached, {:>8.2} cached, {:>5.2}x",
+            label,
+            epsilon,
+            direct,
+            cached,
+            direct / cached
+        );
+    }
+}
@@ -0,0 +1,30 @@
+// Timings of a full parse of the shipped database against an index-only scan of it. Run with
+// `cargo bench --bench parse`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use equilibrium_rs::database::{parse_index, parse_thermo};
+
+const ROUNDS: u32 = 20;
+
+// Mean time of one call, over a warm-up call and then `ROUNDS` more
+fn time<T>(mut run: impl FnMut() -> T) -> Duration {
+    black_box(run());
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(run());
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let raw_text = std::fs::read_to_string("thermo.inp").expect("run from the crate root");
+    let full = time(|| parse_thermo(&raw_text).unwrap());
+    let index = time(|| parse_index(&raw_text));
+    println!("full parse  {:>10.2?}", full);
+    println!("index only  {:>10.2?}", index);
+    println!(
+        "speed-up    {:>10.1}x",
+        full.as_secs_f64() / index.as_secs_f64()
+    );
+}
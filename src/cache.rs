@@ -0,0 +1,636 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::database::ThermoFile;
+use crate::elements::Element;
+use crate::equilibrium::{
+    EquilibriumOptions, EquilibriumResult, Problem, SolverError, SweepResult, element_abundances,
+    solve_equilibrium, sweep_with,
+};
+use crate::mixture::Mixture;
+use crate::numeric::positive;
+use crate::phases::nearest_range;
+use crate::properties::TemperaturePowers;
+
+// Relative difference below which two inputs share a cache entry
+pub const DEFAULT_KEY_TOLERANCE: f64 = 1.0e-9;
+
+// Hits and misses of a cache since it was created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+// The inputs deciding an equilibrium state, with every number quantized on a logarithmic
+// grid so that values within the key tolerance of each other usually share a key, and values
+// further apart never do. The options deciding which species are candidates are part of it, as
+// a result lists its candidates. The warm start is left out on purpose: it only moves where the
+// iteration begins, and a sweep warm-starts each point from the last, so keying on it would
+// keep a sweep from ever reusing an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    database: ([u8; 32], u64),     // `ThermoFile::fingerprint` and `layout`
+    species: Vec<usize>,           // Reactant species, sorted and deduplicated
+    elements: Vec<(Element, i64)>, // Elements with quantized abundances, sorted by symbol
+    problem: (u8, i64, i64),       // Problem type with its two quantized constraints
+    reference_pressure: i64,       // Standard-state pressure of the mixture
+    trace: i64,
+    max_iterations: usize,
+    condensed: bool,
+    omit: Vec<String>, // Sorted and deduplicated, as `insert`
+    insert: Vec<String>,
+    // Step bounds, bit for bit: they change the iterations taken, and so the result's last
+    // digits and its iteration count
+    step: (u64, u64, u64, bool),
+}
+
+// Bounded least-recently-used store of equilibrium results, safe to share between threads
+#[derive(Debug)]
+pub struct EquilibriumCache {
+    capacity: usize,
+    tolerance: f64,
+    inner: Mutex<CacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<CacheKey, (u64, EquilibriumResult)>, // Last use stamp and result
+    recency: BTreeMap<u64, CacheKey>,                     // Keys by last use, oldest first
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+// Index of a value on a logarithmic grid of relative spacing `tolerance`, keeping its sign
+fn quantize(value: f64, tolerance: f64) -> i64 {
+    if value == 0.0 || !value.is_finite() {
+        return 0;
+    }
+    let step = (value.abs().ln() / tolerance.ln_1p()).round() as i64;
+    // Offset so that no magnitude maps to zero and the sign stays distinguishable
+    let index = step.saturating_add(1 << 48);
+    if value < 0.0 { -index } else { index }
+}
+
+impl EquilibriumCache {
+    // An empty cache holding at most `capacity` results, keyed at `DEFAULT_KEY_TOLERANCE`
+    pub fn new(capacity: usize) -> Self {
+        Self::with_tolerance(capacity, DEFAULT_KEY_TOLERANCE)
+    }
+
+    pub fn with_tolerance(capacity: usize, tolerance: f64) -> Self {
+        EquilibriumCache {
+            capacity,
+            tolerance,
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        CacheStats {
+            hits: inner.hits,
+            misses: inner.misses,
+            entries: inner.entries.len(),
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+
+    fn key(
+        &self,
+        db: &ThermoFile,
+        initial: &Mixture,
+        problem: Problem,
+        options: &EquilibriumOptions,
+    ) -> CacheKey {
+        let q = |value: f64| quantize(value, self.tolerance);
+        let mut species: Vec<usize> = initial
+            .components
+            .iter()
+            .filter(|&&(_, moles)| moles > 0.0)
+            .map(|&(index, _)| index)
+            .collect();
+        species.sort_unstable();
+        species.dedup();
+
+        let (symbols, abundances) = element_abundances(db, initial);
+        let mut elements: Vec<(Element, i64)> = symbols
+            .into_iter()
+            .zip(abundances.into_iter().map(q))
+            .collect();
+        elements.sort();
+
+        let problem = match problem {
+            Problem::TP {
+                temperature,
+                pressure,
+            } => (0, q(temperature), q(pressure)),
+            Problem::HP { enthalpy, pressure } => (1, q(enthalpy), q(pressure)),
+            Problem::SP { entropy, pressure } => (2, q(entropy), q(pressure)),
+            Problem::TV {
+                temperature,
+                density,
+            } => (3, q(temperature), q(density)),
+            Problem::UV { energy, density } => (4, q(energy), q(density)),
+        };
+        let sorted = |names: &[String]| {
+            let mut names = names.to_vec();
+            names.sort_unstable();
+            names.dedup();
+            names
+        };
+        CacheKey {
+            database: (db.fingerprint(), db.layout()),
+            species,
+            elements,
+            problem,
+            reference_pressure: q(initial.standard_state.p_ref),
+            trace: q(options.trace),
+            max_iterations: options.max_iterations,
+            condensed: options.condensed,
+            omit: sorted(&options.omit),
+            insert: sorted(&options.insert),
+            step: (
+                options.step.major.to_bits(),
+                options.step.trace.to_bits(),
+                options.step.temperature.to_bits(),
+                options.step.line_search,
+            ),
+        }
+    }
+
+    // The cached result for a problem, or the solver's answer (stored on success)
+    pub fn solve(
+        &self,
+        db: &ThermoFile,
+        initial: &Mixture,
+        problem: Problem,
+        options: &EquilibriumOptions,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let key = self.key(db, initial, problem, options);
+        {
+            let mut guard = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+            let inner = &mut *guard;
+            inner.clock += 1;
+            let now = inner.clock;
+            if let Some((stamp, result)) = inner.entries.get_mut(&key) {
+                let (previous, result) = (*stamp, result.clone());
+                *stamp = now;
+                inner.recency.remove(&previous);
+                inner.recency.insert(now, key);
+                inner.hits += 1;
+                return Ok(result);
+            }
+            inner.misses += 1;
+        }
+
+        // Solve without holding the lock, so other threads aren't held up
+        let result = solve_equilibrium(db, initial, problem, options)?;
+        if self.capacity == 0 {
+            return Ok(result);
+        }
+        let mut guard = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let inner = &mut *guard;
+        inner.clock += 1;
+        let now = inner.clock;
+        if let Some((previous, _)) = inner.entries.insert(key.clone(), (now, result.clone())) {
+            inner.recency.remove(&previous);
+        }
+        inner.recency.insert(now, key);
+        while inner.entries.len() > self.capacity {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+        Ok(result)
+    }
+}
+
+// G/RT, H/RT, S/R and Cp/R of a list of species at one temperature, for a solver's inner loop.
+// They are computed in one pass, sharing the temperature's powers between every fit, and kept
+// until asked for at a temperature more than `epsilon` (K) from the one they were computed at.
+// A non-zero epsilon trades accuracy for speed where the temperature is itself being solved
+// for: a solver converges onto properties up to epsilon out of date. Properties are only reused
+// for the database they came from, told apart by its fingerprint and layout.
+#[derive(Debug, Clone, Default)]
+pub struct PropertyCache {
+    epsilon: Option<f64>,              // None to compute afresh on every request
+    database: Option<([u8; 32], u64)>, // Fingerprint and layout of the last database
+    temperature: Option<f64>,
+    species: Vec<usize>, // Database indices the properties are of
+    properties: Vec<(f64, f64, f64, f64)>, // G/RT, H/RT, S/R and Cp/R of each species
+    evaluations: u64,
+    reuses: u64,
+}
+
+impl PropertyCache {
+    pub fn new(epsilon: f64) -> Self {
+        PropertyCache {
+            epsilon: Some(epsilon),
+            ..Self::default()
+        }
+    }
+
+    // A cache that keeps nothing, for solvers given none
+    pub(crate) fn uncached() -> Self {
+        Self::default()
+    }
+
+    // Bring the properties up to date for these species at this temperature, each from the
+    // fit nearest to it
+    pub fn evaluate(
+        &mut self,
+        db: &ThermoFile,
+        species: &[usize],
+        temperature: f64,
+    ) -> Result<(), SolverError> {
+        positive("temperature", temperature)?;
+        let database = (db.fingerprint(), db.layout());
+        if let (Some(epsilon), Some(cached)) = (self.epsilon, self.temperature)
+            && (temperature - cached).abs() <= epsilon
+            && self.species == species
+            && self.database == Some(database)
+        {
+            self.reuses += 1;
+            return Ok(());
+        }
+        self.temperature = None;
+        let powers = TemperaturePowers::new(temperature);
+        self.properties.clear();
+        for &index in species {
+            let species = &db.species[index];
+            let range =
+                nearest_range(species, temperature).ok_or_else(|| SolverError::OutOfRange {
+                    species: species.name.clone(),
+                    temperature,
+                })?;
+            self.properties.push(range.properties_at(&powers));
+        }
+        self.species.clear();
+        self.species.extend_from_slice(species);
+        self.database = Some(database);
+        self.temperature = Some(temperature);
+        self.evaluations += 1;
+        Ok(())
+    }
+
+    // G/RT, H/RT, S/R and Cp/R of each species last evaluated, in the order given
+    pub fn properties(&self) -> &[(f64, f64, f64, f64)] {
+        &self.properties
+    }
+
+    // The temperature the properties were computed at, K
+    pub fn temperature(&self) -> Option<f64> {
+        self.temperature
+    }
+
+    // Passes computing the properties, and requests answered from the last pass
+    pub fn evaluations(&self) -> u64 {
+        self.evaluations
+    }
+
+    pub fn reuses(&self) -> u64 {
+        self.reuses
+    }
+}
+
+// The equilibrium solver for one database, answering repeated problems from a cache
+#[derive(Debug)]
+pub struct CachedSolver<'a> {
+    db: &'a ThermoFile,
+    cache: EquilibriumCache,
+}
+
+impl<'a> CachedSolver<'a> {
+    pub fn new(db: &'a ThermoFile, capacity: usize) -> Self {
+        CachedSolver {
+            db,
+            cache: EquilibriumCache::new(capacity),
+        }
+    }
+
+    pub fn solve(
+        &self,
+        initial: &Mixture,
+        problem: Problem,
+        options: &EquilibriumOptions,
+    ) -> Result<EquilibriumResult, SolverError> {
+        self.cache.solve(self.db, initial, problem, options)
+    }
+
+    // As `solve_sweep`, with each point looked up in the cache first
+    pub fn solve_sweep(
+        &self,
+        initial: &Mixture,
+        problems: &[Problem],
+        options: &EquilibriumOptions,
+    ) -> Result<SweepResult, SolverError> {
+        sweep_with(problems, options, |problem, options| {
+            self.solve(initial, problem, options)
+        })
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    pub fn cache(&self) -> &EquilibriumCache {
+        &self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equilibrium::{Preset, StepControl, solve_equilibrium_cached};
+    use crate::numeric::NumericError;
+    use crate::test_data::full_database;
+
+    fn hydrogen_air() -> Mixture {
+        Mixture::from_names(full_database(), &[("H2", 2.0), ("O2", 1.0), ("N2", 3.76)]).unwrap()
+    }
+
+    fn at(temperature: f64, pressure: f64) -> Problem {
+        Problem::TP {
+            temperature,
+            pressure,
+        }
+    }
+
+    #[test]
+    fn test_repeated_solve_is_cached() {
+        let db = full_database();
+        let solver = CachedSolver::new(db, 8);
+        let options = EquilibriumOptions::default();
+        let first = solver
+            .solve(&hydrogen_air(), at(2500.0, 1.0e5), &options)
+            .unwrap();
+        let second = solver
+            .solve(&hydrogen_air(), at(2500.0, 1.0e5), &options)
+            .unwrap();
+        assert_eq!(first, second);
+        let bits = |result: &EquilibriumResult| -> Vec<u64> {
+            result.moles.iter().map(|m| m.to_bits()).collect()
+        };
+        assert_eq!(bits(&first), bits(&second));
+        let direct = solve_equilibrium(db, &hydrogen_air(), at(2500.0, 1.0e5), &options).unwrap();
+        assert_eq!(first, direct);
+        assert_eq!(
+            solver.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_key_tolerance() {
+        let db = full_database();
+        let solver = CachedSolver::new(db, 8);
+        let options = EquilibriumOptions::default();
+        let air = hydrogen_air();
+
+        // Within the tolerance the entry is shared; beyond it each state is solved afresh
+        solver.solve(&air, at(2500.0, 1.0e5), &options).unwrap();
+        solver
+            .solve(&air, at(2500.0, 1.0e5 * (1.0 + 1e-12)), &options)
+            .unwrap();
+        let nearby = solver
+            .solve(&air, at(2500.0, 1.0e5 * (1.0 + 1e-6)), &options)
+            .unwrap();
+        assert_eq!(nearby.pressure, 1.0e5 * (1.0 + 1e-6));
+        solver
+            .solve(&air, at(2500.0 * (1.0 + 1e-6), 1.0e5), &options)
+            .unwrap();
+
+        // Scaling every amount scales the abundances, which is a different key
+        let doubled = Mixture::new(air.with_total_moles(2.0 * air.total()));
+        solver.solve(&doubled, at(2500.0, 1.0e5), &options).unwrap();
+        assert_eq!(solver.stats().hits, 1);
+        assert_eq!(solver.stats().misses, 4);
+
+        // Neighbouring magnitudes and signs never share a grid index
+        let q = |x| quantize(x, DEFAULT_KEY_TOLERANCE);
+        assert_ne!(q(1.0), q(2.0));
+        assert_ne!(q(1.0), q(-1.0));
+        assert_ne!(q(1e-300), q(0.0));
+        assert_eq!(q(1.0), q(1.0 + 1e-12));
+    }
+
+    #[test]
+    fn test_candidate_options_in_key() {
+        let db = full_database();
+        let solver = CachedSolver::new(db, 8);
+        let methane = Mixture::from_names(db, &[("CH4", 1.0), ("O2", 1.0)]).unwrap();
+        let problem = at(1000.0, 1.0e5);
+        let graphite = db.index_of("C(gr)").unwrap();
+        let lists_graphite = |result: &EquilibriumResult| {
+            result
+                .candidate_species()
+                .iter()
+                .any(|&(index, _)| index == graphite)
+        };
+
+        // The same state under each preset is solved twice, each keeping its own candidates
+        let gas_only = EquilibriumOptions::preset(Preset::GasOnly);
+        let condensed = EquilibriumOptions::preset(Preset::GasPlusCondensed);
+        assert!(!lists_graphite(
+            &solver.solve(&methane, problem, &gas_only).unwrap()
+        ));
+        assert!(lists_graphite(
+            &solver.solve(&methane, problem, &condensed).unwrap()
+        ));
+        assert!(!lists_graphite(
+            &solver.solve(&methane, problem, &gas_only).unwrap()
+        ));
+        assert_eq!((solver.stats().hits, solver.stats().misses), (1, 2));
+
+        // As are omit and insert lists, which match in any order
+        let omitting = |names: &[&str]| EquilibriumOptions {
+            omit: names.iter().map(|name| name.to_string()).collect(),
+            ..condensed.clone()
+        };
+        solver
+            .solve(&methane, problem, &omitting(&["CH3", "C2H2"]))
+            .unwrap();
+        solver
+            .solve(&methane, problem, &omitting(&["C2H2", "CH3"]))
+            .unwrap();
+        let inserting = EquilibriumOptions {
+            insert: vec!["C(gr)".to_string()],
+            ..gas_only.clone()
+        };
+        assert!(lists_graphite(
+            &solver.solve(&methane, problem, &inserting).unwrap()
+        ));
+        assert_eq!((solver.stats().hits, solver.stats().misses), (2, 4));
+
+        // And the step bounds, which change how the answer is reached
+        let searching = EquilibriumOptions {
+            step: StepControl {
+                line_search: true,
+                ..StepControl::default()
+            },
+            ..condensed.clone()
+        };
+        let result = solver.solve(&methane, problem, &searching).unwrap();
+        assert_eq!(solver.stats().misses, 5);
+        assert_eq!(
+            result,
+            solve_equilibrium(db, &methane, problem, &searching).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_database_in_key() {
+        let db = full_database();
+        let cache = EquilibriumCache::new(8);
+        let options = EquilibriumOptions::default();
+        let air = hydrogen_air();
+        let problem = at(2500.0, 1.0e5);
+        let first = cache.solve(db, &air, problem, &options).unwrap();
+
+        // Databases differing in one coefficient, or only in species order, each miss
+        let mut patched = db.clone();
+        let water = db.index_of("H2O").unwrap();
+        let a6 = patched.species()[water].coefficient(1, 5).unwrap();
+        patched.patch("H2O", 1, 5, a6 + 1.0).unwrap();
+        let second = cache.solve(&patched, &air, problem, &options).unwrap();
+        assert_ne!(second.moles, first.moles);
+        let mut reordered = db.clone();
+        reordered.species_mut().reverse();
+        let air = Mixture::from_names(&reordered, &[("H2", 2.0), ("O2", 1.0), ("N2", 3.76)]);
+        let third = cache.solve(&reordered, &air.unwrap(), problem, &options);
+        assert_eq!(third.unwrap().temperature, first.temperature);
+        assert_eq!(cache.stats().misses, 3);
+
+        // A copy of a database shares its entries
+        cache
+            .solve(&db.clone(), &hydrogen_air(), problem, &options)
+            .unwrap();
+        assert_eq!((cache.stats().hits, cache.stats().misses), (1, 3));
+    }
+
+    #[test]
+    fn test_least_recently_used_eviction() {
+        let db = full_database();
+        let solver = CachedSolver::new(db, 2);
+        let options = EquilibriumOptions::default();
+        let air = hydrogen_air();
+        for temperature in [2000.0, 2500.0, 2000.0, 3000.0, 2000.0, 2500.0] {
+            solver
+                .solve(&air, at(temperature, 1.0e5), &options)
+                .unwrap();
+        }
+        // 2500 K was evicted when 3000 K arrived, 2000 K stayed in use throughout
+        let stats = solver.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 4, 2));
+    }
+
+    #[test]
+    fn test_shared_between_threads() {
+        let db = full_database();
+        let solver = CachedSolver::new(db, 16);
+        let options = EquilibriumOptions::default();
+        let air = hydrogen_air();
+        let problems: Vec<Problem> = [1500.0, 2000.0, 2500.0].map(|t| at(t, 1.0e5)).to_vec();
+        let sweeps: Vec<SweepResult> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| solver.solve_sweep(&air, &problems, &options).unwrap()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert!(sweeps.windows(2).all(|pair| pair[0] == pair[1]));
+        let stats = solver.stats();
+        assert_eq!(stats.hits + stats.misses, 12);
+        assert_eq!(stats.entries, 3);
+    }
+
+    #[test]
+    fn test_property_cache() {
+        let db = full_database();
+        let options = EquilibriumOptions::default();
+        let air = hydrogen_air();
+
+        // At an assigned temperature one evaluation serves every iteration, and the next
+        // problem at that temperature, with the answer unchanged
+        let mut cache = PropertyCache::new(0.0);
+        let problem = at(2500.0, 1.0e5);
+        let direct = solve_equilibrium(db, &air, problem, &options).unwrap();
+        let cached = solve_equilibrium_cached(db, &air, problem, &options, &mut cache).unwrap();
+        assert_eq!(cached, direct);
+        assert_eq!(cache.evaluations(), 1);
+        assert_eq!(cache.reuses() as usize, direct.iterations - 1);
+        let again = solve_equilibrium_cached(db, &air, at(2500.0, 2.0e5), &options, &mut cache);
+        assert!(again.is_ok());
+        assert_eq!(cache.evaluations(), 1);
+        assert_eq!(cache.temperature(), Some(2500.0));
+
+        // Solving for temperature, a zero epsilon changes nothing and a small one little
+        let problem = Problem::HP {
+            enthalpy: air.enthalpy(db, 600.0).unwrap(),
+            pressure: 1.0e5,
+        };
+        let direct = solve_equilibrium(db, &air, problem, &options).unwrap();
+        let mut exact = PropertyCache::new(0.0);
+        let cached = solve_equilibrium_cached(db, &air, problem, &options, &mut exact).unwrap();
+        assert_eq!(cached, direct);
+        let mut loose = PropertyCache::new(0.5);
+        let cached = solve_equilibrium_cached(db, &air, problem, &options, &mut loose).unwrap();
+        assert!((cached.temperature - direct.temperature).abs() < 1.0);
+        assert!(loose.reuses() > 0);
+
+        // Another database with the same indices is evaluated afresh, as is this one patched
+        let mut swapped = db.clone();
+        swapped.species_mut().swap(0, 1);
+        let mut cache = PropertyCache::new(1.0);
+        cache.evaluate(db, &[0], 2500.0).unwrap();
+        cache.evaluate(&swapped, &[0], 2500.0).unwrap();
+        assert_eq!(cache.evaluations(), 2);
+        let expected = nearest_range(&db.species[1], 2500.0).unwrap();
+        let powers = TemperaturePowers::new(2500.0);
+        assert_eq!(cache.properties(), [expected.properties_at(&powers)]);
+        let name = swapped.species[0].name.clone();
+        swapped.patch(&name, 1, 0, 0.0).unwrap();
+        cache.evaluate(&swapped, &[0], 2500.0).unwrap();
+        assert_eq!(cache.evaluations(), 3);
+        cache.evaluate(&swapped, &[0], 2500.0).unwrap();
+        assert_eq!((cache.evaluations(), cache.reuses()), (3, 1));
+        let edited = &mut swapped.species_mut()[0].temperature_ranges[1];
+        edited.integration_constants[0] += 1.0;
+        cache.evaluate(&swapped, &[0], 2500.0).unwrap();
+        assert_eq!(cache.evaluations(), 4);
+
+        // A temperature that is not positive is refused, not evaluated to NaN
+        for temperature in [0.0, -300.0, f64::NAN] {
+            assert!(matches!(
+                cache.evaluate(db, &[0], temperature),
+                Err(SolverError::Numeric(NumericError::NonPositive { .. }))
+            ));
+        }
+
+        // A species without fits is reported as the solver would
+        let db = crate::test_data::database_with_reactants();
+        let unfitted = db
+            .species
+            .iter()
+            .position(|species| species.temperature_ranges().is_empty())
+            .unwrap();
+        let mut cache = PropertyCache::new(0.0);
+        assert!(matches!(
+            cache.evaluate(db, &[unfitted], 300.0),
+            Err(SolverError::OutOfRange { .. })
+        ));
+        assert_eq!(cache.temperature(), None);
+    }
+}
@@ -1,34 +1,212 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Range;
+use std::sync::OnceLock;
+
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::complete::{tag, take_until, take_while1},
-    character::complete::{char, digit1, line_ending, multispace0, space0, space1},
-    combinator::{opt, recognize},
-    multi::many0,
+    bytes::complete::{tag_no_case, take_until, take_while1},
+    character::complete::{char, digit1, line_ending, multispace0, not_line_ending, space0},
+    combinator::{all_consuming, eof, opt, recognize},
+    multi::{count, many0},
     number::complete::double,
-    sequence::delimited,
+    sequence::{delimited, preceded, terminated},
 };
 
+use sha2::{Digest, Sha256};
+
+use crate::elements::{Composition, Element, atomic_weight, formula_weight};
+use crate::patch::AppliedPatch;
+use crate::validation::RepairPolicy;
+
+// Built with `ThermoFile::new`, or collected from species, as its fields are private to the
+// crate and a struct literal can't set them. The header and species are read with `header`
+// and `species` and edited with `header_mut` and `species_mut`, which drop the fingerprint.
 #[derive(Debug, Clone)]
 pub struct ThermoFile {
+    pub(crate) header: ThermoHeader,
+    pub(crate) species: Vec<Species>,
+    pub(crate) aliases: BTreeMap<String, String>, // Added with `add_alias`, alias to target
+    pub(crate) patches: Vec<AppliedPatch>,        // Made with `patch`, oldest first
+    derived: Derived,
+}
+
+// What is worked out from the whole database, once and then kept until an edit: the
+// fingerprint and layout, and the first fit that can't be evaluated. A copy starts without it.
+#[derive(Debug, Default)]
+struct Derived {
+    identity: OnceLock<([u8; 32], u64)>,
+    nonfinite_fit: OnceLock<Option<(usize, usize)>>, // Species and fit index
+}
+
+impl Clone for Derived {
+    fn clone(&self) -> Self {
+        Derived::default()
+    }
+}
+
+// A parsed thermo file whose species borrow their text from the input, as `parse_thermo_ref`
+// gives it, for reading a large file without a copy of every name and description
+#[derive(Debug, Clone)]
+pub struct ThermoFileRef<'a> {
     pub header: ThermoHeader,
-    pub species: Vec<Species>,
+    pub species: Vec<SpeciesRef<'a>>,
 }
 
+// A species record as `Species`, its name, description and reference code slices of the
+// input it was parsed from
 #[derive(Debug, Clone)]
+pub struct SpeciesRef<'a> {
+    pub name: &'a str,
+    pub description: &'a str,
+    pub reference_code: &'a str,
+    pub elements: Composition,
+    pub phase: u8,
+    pub molecular_weight: f64,
+    pub enthalpy_datum: EnthalpyDatum,
+    pub temperature_ranges: Vec<TemperatureRange>,
+    pub source_digits: SourceDigits,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ThermoHeader {
     pub temp_ranges: [f64; 4], // 200.00, 1000.00, 6000.00, 20000.0
     pub date: String,          // 9/09/04
+    pub format: ThermoFormat,  // From an optional token after 'thermo'
+}
+
+// Calendar date of a database, ordered by year, then month, then day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+// Two-digit years from here on are read as 19xx, and those below as 20xx. The NASA fits began
+// in the 1960s, so no database predates the pivot.
+const CENTURY_PIVOT: u16 = 50;
+
+impl Date {
+    // A date, if the day exists in the month (and the month in the year)
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        (1..=days)
+            .contains(&day)
+            .then_some(Date { year, month, day })
+    }
+}
+
+// Coefficient layout named by the token after 'thermo', as in "thermo nasa9"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThermoFormat {
+    // Nine-coefficient CEA records, the layout assumed when no token is given
+    #[default]
+    Nasa9,
+    // Seven-coefficient records, recognised but not yet readable
+    Nasa7,
+}
+
+// How the fields of species records are laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    // Fortran fixed columns as CEA writes them, where neighbouring fields may touch
+    #[default]
+    FixedColumn,
+    // Fields separated by whitespace in whatever columns they fall, none left blank
+    Whitespace,
+    // Either, judged by whether the header's temperatures sit in their ten-column fields
+    Auto,
 }
 
 #[derive(Debug, Clone)]
 pub struct Species {
     pub name: String,
     pub description: String,
-    pub elements: Vec<(String, f64)>, // Element name and count
+    pub reference_code: String, // g 8/89
+    pub elements: Composition,  // Element symbols and counts, as listed
+    pub phase: u8,              // Zero for gas, non-zero for condensed
     pub molecular_weight: f64,
-    pub heat_of_formation: f64,
-    pub temperature_ranges: Vec<TemperatureRange>,
+    pub(crate) enthalpy_datum: EnthalpyDatum, // Assigned exactly when there are no fits
+    pub(crate) temperature_ranges: Vec<TemperatureRange>, // Ascending; parsing warns if not
+    pub source_digits: SourceDigits,
+    pub provenance: Option<Provenance>, // Set by `load_thermo`, None when parsed from text
+}
+
+// Where a species record was read from: the file as it was named, and the line of its name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub source: String,
+    pub line: u32,
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
+    }
+}
+
+// Significant digits the formula-line constants were written with, None where a value wasn't
+// read from text (a blank or computed molecular weight, or a species built in code)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceDigits {
+    pub molecular_weight: Option<u8>,
+    pub enthalpy: Option<u8>,
+}
+
+// A number read from a fixed-format field, with the significant digits written in it. Zeros
+// after the point count as significant, so "0.000" has three, the digits it can be written
+// back with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParsedF64 {
+    pub value: f64,
+    pub sig_digits: u8,
+}
+
+impl ParsedF64 {
+    // Digits after the decimal point that show `sig_digits` significant digits of the value
+    pub fn decimals(&self) -> usize {
+        decimals_for(self.value, self.sig_digits)
+    }
+}
+
+// Digits after the decimal point that show a value to the given significant digits
+pub(crate) fn decimals_for(value: f64, sig_digits: u8) -> usize {
+    if value == 0.0 || !value.is_finite() {
+        return sig_digits as usize;
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    (sig_digits as i32 - 1 - magnitude).max(0) as usize
+}
+
+// The enthalpy listed on a species' formula line, J/mol. Its meaning depends on the record:
+// species with fits list their heat of formation at 298.15 K, while reactant-only records
+// (with no fits) list an assigned enthalpy at the temperature on the line that follows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnthalpyDatum {
+    FormationAt298 { value: f64 },
+    Assigned { value: f64, temperature: f64 }, // Temperature in K
+}
+
+impl EnthalpyDatum {
+    // The listed enthalpy, J/mol, whichever kind it is
+    pub fn value(&self) -> f64 {
+        match *self {
+            EnthalpyDatum::FormationAt298 { value } | EnthalpyDatum::Assigned { value, .. } => {
+                value
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -36,7 +214,731 @@ pub struct TemperatureRange {
     pub temp_low: f64,
     pub temp_high: f64,
     pub coefficients: [f64; 7],          // NASA polynomial coefficients
+    pub used_coefficients: u8,           // Leading coefficients in use, the rest are 0.0
     pub integration_constants: [f64; 2], // Last two values on coefficient lines
+    pub h298_minus_h0: f64,              // H(298.15) - H(0), J/mol
+}
+
+// Temperature exponents of the seven NASA-9 polynomial terms
+pub const NASA9_EXPONENTS: [i32; 7] = [-2, -1, 0, 1, 2, 3, 4];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ThermoParseError {
+    // The 'thermo' keyword or global temperature line is malformed
+    Header {
+        message: String,
+    },
+    // A species record starting at the given line is malformed
+    Species {
+        name: String,
+        line: usize,
+        message: String,
+    },
+    // A line outside any species record that could not be interpreted
+    UnexpectedLine {
+        line: usize,
+        text: String,
+    },
+    // Parsing stopped early, leaving the input beginning with this snippet unread
+    Context {
+        remaining_snippet: String,
+    },
+    // The input could not be read (or decompressed) as text
+    Io {
+        message: String,
+    },
+    // A resume offset that isn't the start of a line within the input
+    InvalidOffset {
+        offset: usize,
+    },
+    // No species record remains after the resume offset
+    EndOfInput,
+}
+
+impl fmt::Display for ThermoParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThermoParseError::Header { message } => write!(f, "invalid header: {}", message),
+            ThermoParseError::Species {
+                name,
+                line,
+                message,
+            } => write!(f, "line {}: invalid species '{}': {}", line, name, message),
+            ThermoParseError::UnexpectedLine { line, text } => {
+                write!(f, "line {}: unexpected line '{}'", line, text)
+            }
+            ThermoParseError::Context { remaining_snippet } => {
+                write!(f, "parsing stopped before:\n{}", remaining_snippet)
+            }
+            ThermoParseError::Io { message } => write!(f, "could not read input: {}", message),
+            ThermoParseError::InvalidOffset { offset } => {
+                write!(f, "offset {} is not the start of a line", offset)
+            }
+            ThermoParseError::EndOfInput => write!(f, "no species record before the end"),
+        }
+    }
+}
+
+impl std::error::Error for ThermoParseError {}
+
+// What kind of doubt a `ParseWarning` raises
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseWarningKind {
+    // A field left blank was filled in, as a molecular weight is from the formula
+    DefaultedField,
+    // An element with no known atomic weight
+    UnknownElement,
+    // A fit boundary moved to meet the next fit
+    SnappedInterval,
+    // A species name already used by an earlier record
+    DuplicateSpecies,
+    // A '!' comment line, passed over
+    SkippedComment,
+    // A coefficient too large for f64, leaving its fit unusable
+    CoefficientOverflow,
+    // A coefficient too small for f64 at full precision, read as zero
+    CoefficientUnderflow,
+    // A fit spanning no increasing range, or overlapping or preceding the fit before it
+    UnorderedFits,
+}
+
+impl fmt::Display for ParseWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            ParseWarningKind::DefaultedField => "defaulted field",
+            ParseWarningKind::UnknownElement => "unknown element",
+            ParseWarningKind::SnappedInterval => "snapped interval",
+            ParseWarningKind::DuplicateSpecies => "duplicate species",
+            ParseWarningKind::SkippedComment => "skipped comment",
+            ParseWarningKind::CoefficientOverflow => "coefficient overflow",
+            ParseWarningKind::CoefficientUnderflow => "coefficient underflow",
+            ParseWarningKind::UnorderedFits => "unordered fits",
+        })
+    }
+}
+
+// Something read from a file that didn't stop the parse but may deserve a look
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseWarning {
+    pub kind: ParseWarningKind,
+    pub species: Option<String>, // The record it concerns, if any
+    pub line: usize,             // One-based, the record's first line unless a field is at fault
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        if let Some(species) = &self.species {
+            write!(f, "species '{}': ", species)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+// A parsed file with the warnings raised reading it, in line order
+#[derive(Debug, Clone)]
+pub struct ParseOutcome {
+    pub file: ThermoFile,
+    pub warnings: Vec<ParseWarning>,
+}
+
+// Why `Species::new` refused to build a species
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeciesError {
+    // A fit (by index) whose bounds are not finite and increasing
+    InvalidRange { range: usize },
+    // A fit (by index) that starts below the end of the one before it
+    UnorderedRanges { range: usize },
+    // An assigned enthalpy with fits, or a heat of formation without any
+    DatumMismatch,
+}
+
+impl fmt::Display for SpeciesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpeciesError::InvalidRange { range } => {
+                write!(
+                    f,
+                    "fit {} does not span an increasing temperature range",
+                    range
+                )
+            }
+            SpeciesError::UnorderedRanges { range } => {
+                write!(f, "fit {} overlaps or precedes the fit before it", range)
+            }
+            SpeciesError::DatumMismatch => write!(
+                f,
+                "an assigned enthalpy is only for records without fits, and a heat of formation \
+                 only for records with them"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpeciesError {}
+
+impl ThermoHeader {
+    // The header's date, written month first as in "9/09/04" or "12/18/2001", or as ISO
+    // "2001-12-18". Two-digit years are placed either side of `CENTURY_PIVOT`. None for any
+    // other layout or a day that doesn't exist.
+    pub fn parsed_date(&self) -> Option<Date> {
+        let date = self.date.trim();
+        let number = |field: &str| -> Option<u16> {
+            let all_digits = !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit());
+            all_digits.then(|| field.parse().ok()).flatten()
+        };
+        let (year, month, day) = match date.split('/').collect::<Vec<_>>()[..] {
+            [month, day, year] => {
+                let year = match (year.len(), number(year)?) {
+                    (2, yy) if yy >= CENTURY_PIVOT => 1900 + yy,
+                    (2, yy) => 2000 + yy,
+                    (4, yyyy) => yyyy,
+                    _ => return None,
+                };
+                (year, number(month)?, number(day)?)
+            }
+            _ => match date.split('-').collect::<Vec<_>>()[..] {
+                [year, month, day] if year.len() == 4 => {
+                    (number(year)?, number(month)?, number(day)?)
+                }
+                _ => return None,
+            },
+        };
+        Date::new(year, u8::try_from(month).ok()?, u8::try_from(day).ok()?)
+    }
+}
+
+// A database fingerprint as 64 lower-case hex digits, as reports and exports give it
+pub fn fingerprint_hex(fingerprint: &[u8; 32]) -> String {
+    fingerprint
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Temperatures on the header line of CEA's thermo.inp: the bounds of the NASA-9 fits and the
+// two breakpoints most of them share
+const STANDARD_TEMP_RANGES: [f64; 4] = [200.0, 1000.0, 6000.0, 20000.0];
+
+// Collect species into a database without aliases, under an undated header. The header keeps
+// CEA's breakpoints, its first and last temperatures widened to any fits reaching beyond them.
+impl FromIterator<Species> for ThermoFile {
+    fn from_iter<I: IntoIterator<Item = Species>>(iter: I) -> Self {
+        let species: Vec<Species> = iter.into_iter().collect();
+        let mut temp_ranges = STANDARD_TEMP_RANGES;
+        for range in species
+            .iter()
+            .flat_map(|species| species.temperature_ranges())
+        {
+            temp_ranges[0] = temp_ranges[0].min(range.temp_low);
+            temp_ranges[3] = temp_ranges[3].max(range.temp_high);
+        }
+        let header = ThermoHeader {
+            temp_ranges,
+            ..ThermoHeader::default()
+        };
+        ThermoFile::new(header, species)
+    }
+}
+
+impl ThermoFile {
+    // A database of these species, without aliases or patches
+    pub fn new(header: ThermoHeader, species: Vec<Species>) -> Self {
+        ThermoFile {
+            header,
+            species,
+            aliases: BTreeMap::new(),
+            patches: Vec::new(),
+            derived: Derived::default(),
+        }
+    }
+
+    pub fn header(&self) -> &ThermoHeader {
+        &self.header
+    }
+
+    // The header, to be edited, dropping the fingerprint
+    pub fn header_mut(&mut self) -> &mut ThermoHeader {
+        self.clear_derived();
+        &mut self.header
+    }
+
+    // Every species record, in file order
+    pub fn species(&self) -> &[Species] {
+        &self.species
+    }
+
+    // The species records, to be edited, added to or removed, dropping the fingerprint
+    pub fn species_mut(&mut self) -> &mut Vec<Species> {
+        self.clear_derived();
+        &mut self.species
+    }
+
+    // Names of the species in a database file, read through a memory map and found from their
+    // header lines alone, for a cheap look at what a large file holds
+    #[cfg(feature = "memmap2")]
+    pub fn species_names_mmap(path: impl AsRef<std::path::Path>) -> std::io::Result<Vec<String>> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the map is only read, and dropped before returning. Another process
+        // truncating the file meanwhile would be undefined behaviour, as with any mapped file.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let text = std::str::from_utf8(&map)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        Ok(scan_species_names(text)
+            .into_iter()
+            .map(str::to_string)
+            .collect())
+    }
+
+    // The database's vintage, from the date on its header line
+    pub fn header_date(&self) -> Option<Date> {
+        self.header.parsed_date()
+    }
+
+    // A SHA-256 hash naming the data a result was computed from: the header's temperatures
+    // and date, then every species' data as `Species::content_hash` covers it, though with
+    // numbers exact rather than rounded. Species are taken in order of name (and, for records
+    // sharing one, of their data), so reordering a file or changing its spacing leaves the
+    // fingerprint alone. It hashes the whole database, so is computed once and kept until an
+    // edit drops it.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        self.identity().0
+    }
+
+    // A hash of where each species sits in the file, which the fingerprint leaves out. With it,
+    // the fingerprint names what a species index points at, for caches keyed on indices.
+    pub(crate) fn layout(&self) -> u64 {
+        self.identity().1
+    }
+
+    fn identity(&self) -> ([u8; 32], u64) {
+        *self
+            .derived
+            .identity
+            .get_or_init(|| self.compute_identity())
+    }
+
+    // The first fit holding a NaN or infinity, with its species, as an overflowing coefficient
+    // in the file leaves one. Such a fit can't be evaluated, so the solver refuses the database.
+    pub fn nonfinite_fit(&self) -> Option<(&Species, usize)> {
+        let found = self.derived.nonfinite_fit.get_or_init(|| {
+            self.species
+                .iter()
+                .enumerate()
+                .find_map(|(index, species)| {
+                    let fit = species
+                        .temperature_ranges
+                        .iter()
+                        .position(|range| !range.is_finite())?;
+                    Some((index, fit))
+                })
+        });
+        found.map(|(index, fit)| (&self.species[index], fit))
+    }
+
+    // Drop what was worked out from the database, for the next request to do it again
+    pub(crate) fn clear_derived(&mut self) {
+        self.derived = Derived::default();
+    }
+
+    // The fingerprint, and the hash of the order the file's species take among those it sorts
+    fn compute_identity(&self) -> ([u8; 32], u64) {
+        let mut hash = Sha256::new();
+        for temperature in self.header.temp_ranges {
+            hash.write_number(temperature);
+        }
+        hash.write_str(self.header.date.trim());
+        let mut order: Vec<usize> = (0..self.species.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&self.species[a], &self.species[b]);
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.content_hash().cmp(&b.content_hash()))
+        });
+        let mut layout = ContentHash::new();
+        for &index in &order {
+            self.species[index].write_canonical(&mut hash);
+            layout.write_str(&index.to_string());
+        }
+        (hash.finalize().into(), layout.finish())
+    }
+
+    // Look up a species by its exact name
+    pub fn find_species(&self, name: &str) -> Option<&Species> {
+        self.species.iter().find(|species| species.name == name)
+    }
+
+    // Position of a species in the species list, used to refer to it from mixtures and reactions
+    pub fn species_index(&self, name: &str) -> Option<usize> {
+        self.species.iter().position(|species| species.name == name)
+    }
+
+    // Species containing an element, given by its symbol in either case, in database order
+    pub fn species_with_element(&self, symbol: &str) -> Vec<&Species> {
+        let Some(element) = Element::new(symbol) else {
+            return Vec::new();
+        };
+        self.species
+            .iter()
+            .filter(|species| species.elements.count(element) != 0.0)
+            .collect()
+    }
+
+    // Add another database's species to these. Where both define a name, every record of it
+    // here gives way to the other's, so a condensed phase split across records is replaced
+    // whole. The other's aliases and patches are added too, patches to replaced species are
+    // dropped, and the other's header is dropped.
+    pub fn merge(&mut self, other: ThermoFile) {
+        let replaced: BTreeSet<&str> = other.species.iter().map(|s| s.name.as_str()).collect();
+        self.species
+            .retain(|species| !replaced.contains(species.name.as_str()));
+        self.patches
+            .retain(|patch| !replaced.contains(patch.species.as_str()));
+        self.species.extend(other.species);
+        self.aliases.extend(other.aliases);
+        self.patches.extend(other.patches);
+        self.clear_derived();
+    }
+
+    // Remove each record that repeats the data of an earlier one of the same name: its phase,
+    // formula, molecular weight, enthalpy and fits, each number within a relative tolerance.
+    // Text such as the description is not compared. Returns the names of species left with two
+    // records whose fits overlap but disagree, which no one record can stand for. (Records of a
+    // condensed phase continuing one another don't overlap, and are not flagged.)
+    pub fn dedup_species(&mut self, tol: f64) -> Vec<String> {
+        let mut kept: Vec<Species> = Vec::with_capacity(self.species.len());
+        let mut conflicts = BTreeSet::new();
+        for species in self.species.drain(..) {
+            let earlier = kept.iter().filter(|other| other.name == species.name);
+            let mut overlapping = false;
+            let mut repeated = false;
+            for other in earlier {
+                repeated |= same_data(other, &species, tol);
+                overlapping |= fits_overlap(other, &species);
+            }
+            if repeated {
+                continue;
+            }
+            if overlapping {
+                conflicts.insert(species.name.clone());
+            }
+            kept.push(species);
+        }
+        self.species = kept;
+        self.clear_derived();
+        conflicts.into_iter().collect()
+    }
+
+    // A database of just the named species (every record of each), in database order
+    pub fn subset(&self, names: &[&str]) -> ThermoFile {
+        ThermoFile {
+            header: self.header.clone(),
+            species: self
+                .species
+                .iter()
+                .filter(|species| names.contains(&species.name.as_str()))
+                .cloned()
+                .collect(),
+            aliases: self.aliases.clone(),
+            patches: self
+                .patches
+                .iter()
+                .filter(|patch| names.contains(&patch.species.as_str()))
+                .cloned()
+                .collect(),
+            derived: Derived::default(),
+        }
+    }
+
+    // Sort species alphabetically by name, giving deterministic output for merged databases
+    pub fn sort_species_by_name(&mut self) {
+        self.sort_species_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    // Sort species with a custom comparison, keeping the original order of equal entries
+    pub fn sort_species_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&Species, &Species) -> Ordering,
+    {
+        self.species.sort_by(compare);
+        self.clear_derived();
+    }
+}
+
+// Whether two numbers agree within a tolerance relative to the larger
+fn close(a: f64, b: f64, tol: f64) -> bool {
+    a == b || (a - b).abs() <= tol * a.abs().max(b.abs())
+}
+
+// Whether two records hold the same data, as `ThermoFile::dedup_species` judges
+fn same_data(a: &Species, b: &Species, tol: f64) -> bool {
+    let same_enthalpy = match (a.enthalpy_datum, b.enthalpy_datum) {
+        (
+            EnthalpyDatum::FormationAt298 { value: x },
+            EnthalpyDatum::FormationAt298 { value: y },
+        ) => close(x, y, tol),
+        (
+            EnthalpyDatum::Assigned {
+                value: x,
+                temperature: s,
+            },
+            EnthalpyDatum::Assigned {
+                value: y,
+                temperature: t,
+            },
+        ) => close(x, y, tol) && close(s, t, tol),
+        _ => false,
+    };
+    let same_fits = a.temperature_ranges.len() == b.temperature_ranges.len()
+        && a.temperature_ranges
+            .iter()
+            .zip(&b.temperature_ranges)
+            .all(|(x, y)| {
+                x.named_values()
+                    .iter()
+                    .zip(y.named_values())
+                    .all(|(&(_, u), (_, v))| close(u, v, tol))
+            });
+    a.phase == b.phase
+        && a.elements == b.elements
+        && close(a.molecular_weight, b.molecular_weight, tol)
+        && same_enthalpy
+        && same_fits
+}
+
+// Whether two records cover some temperature in common. Two with no fits both stand at their
+// assigned temperature, so are taken to.
+fn fits_overlap(a: &Species, b: &Species) -> bool {
+    let span = |species: &Species| {
+        let ranges = species.temperature_ranges();
+        Some((ranges.first()?.temp_low, ranges.last()?.temp_high))
+    };
+    match (span(a), span(b)) {
+        (Some((a_low, a_high)), Some((b_low, b_high))) => a_low < b_high && b_low < a_high,
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// A hash fed the pieces of a species' data as text, each ended by a separator, so "ab" then
+// "c" differs from "a" then "bc"
+trait CanonicalHash {
+    fn write_str(&mut self, text: &str);
+
+    // A number rounded to 12 significant digits, with both zeros alike
+    fn write_number(&mut self, value: f64) {
+        let value = if value == 0.0 { 0.0 } else { value };
+        self.write_str(&format!("{:.11e}", value));
+    }
+}
+
+// 64-bit FNV-1a, chosen over std's hashers as its output is fixed for all time
+struct ContentHash(u64);
+
+impl ContentHash {
+    fn new() -> Self {
+        ContentHash(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl CanonicalHash for ContentHash {
+    fn write_str(&mut self, text: &str) {
+        for byte in text.bytes().chain([0xff]) {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+impl CanonicalHash for Sha256 {
+    fn write_str(&mut self, text: &str) {
+        self.update(text.as_bytes());
+        self.update([0xff]);
+    }
+
+    // A number exactly, by its bits, with both zeros alike
+    fn write_number(&mut self, value: f64) {
+        let value = if value == 0.0 { 0.0 } else { value };
+        self.update(value.to_bits().to_le_bytes());
+    }
+}
+
+impl<'a> ThermoFileRef<'a> {
+    // Look up a species by its exact name
+    pub fn find_species(&self, name: &str) -> Option<&SpeciesRef<'a>> {
+        self.species.iter().find(|species| species.name == name)
+    }
+
+    // The file with its species' text copied out, as `parse_thermo` would have read it
+    pub fn into_owned(self) -> ThermoFile {
+        let species = self
+            .species
+            .into_iter()
+            .map(SpeciesRef::into_owned)
+            .collect();
+        ThermoFile::new(self.header, species)
+    }
+}
+
+impl SpeciesRef<'_> {
+    // The species with its text copied out
+    pub fn into_owned(self) -> Species {
+        Species {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            reference_code: self.reference_code.to_string(),
+            elements: self.elements,
+            phase: self.phase,
+            molecular_weight: self.molecular_weight,
+            enthalpy_datum: self.enthalpy_datum,
+            temperature_ranges: self.temperature_ranges,
+            source_digits: self.source_digits,
+            provenance: None,
+        }
+    }
+}
+
+// That each fit spans an increasing range, and that the fits ascend without overlapping
+fn check_ranges(ranges: &[TemperatureRange]) -> Result<(), SpeciesError> {
+    for (index, range) in ranges.iter().enumerate() {
+        // Written to also reject NaN bounds
+        if !(range.temp_low.is_finite() && range.temp_high.is_finite())
+            || range.temp_low >= range.temp_high
+        {
+            return Err(SpeciesError::InvalidRange { range: index });
+        }
+    }
+    if let Some(index) = ranges
+        .windows(2)
+        .position(|pair| pair[1].temp_low < pair[0].temp_high)
+    {
+        return Err(SpeciesError::UnorderedRanges { range: index + 1 });
+    }
+    Ok(())
+}
+
+impl Species {
+    // A species with no description or reference code, checking that its fits are ascending
+    // and don't overlap, and that its enthalpy datum suits whether it has any fits at all
+    pub fn new(
+        name: impl Into<String>,
+        elements: Composition,
+        phase: u8,
+        molecular_weight: f64,
+        enthalpy_datum: EnthalpyDatum,
+        temperature_ranges: Vec<TemperatureRange>,
+    ) -> Result<Self, SpeciesError> {
+        check_ranges(&temperature_ranges)?;
+        let assigned = matches!(enthalpy_datum, EnthalpyDatum::Assigned { .. });
+        if assigned != temperature_ranges.is_empty() {
+            return Err(SpeciesError::DatumMismatch);
+        }
+        Ok(Species {
+            name: name.into(),
+            description: String::new(),
+            reference_code: String::new(),
+            elements,
+            phase,
+            molecular_weight,
+            enthalpy_datum,
+            temperature_ranges,
+            source_digits: SourceDigits::default(),
+            provenance: None,
+        })
+    }
+
+    // The polynomial fits in ascending temperature order, empty for a reactant-only record
+    pub fn temperature_ranges(&self) -> &[TemperatureRange] {
+        &self.temperature_ranges
+    }
+
+    // The enthalpy listed on the formula line, and what it means
+    pub fn enthalpy_datum(&self) -> EnthalpyDatum {
+        self.enthalpy_datum
+    }
+
+    // Whether the species is a condensed (solid or liquid) phase
+    pub fn is_condensed(&self) -> bool {
+        self.phase != 0
+    }
+
+    // Net charge in units of the elementary charge. CEA lists a charge as a count of the
+    // electron pseudo-element E, so HCO+ (E -1) has charge +1 and e- (E 1) has -1.
+    pub fn charge(&self) -> f64 {
+        -self.elements.count(Element::ELECTRON)
+    }
+
+    // A hash of the species' data, the same on every platform and build: its name, phase,
+    // element totals, enthalpy datum and fits. Numbers are rounded to 12 significant digits
+    // first, so values differing only in their last bits (as from another route through the
+    // same arithmetic) hash alike. The description, reference code and provenance are left out.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = ContentHash::new();
+        self.write_canonical(&mut hash);
+        hash.finish()
+    }
+
+    // Feed the fields `content_hash` covers to a hash
+    fn write_canonical(&self, hash: &mut impl CanonicalHash) {
+        hash.write_str(&self.name);
+        hash.write_str(&self.phase.to_string());
+        let mut elements: Vec<(Element, f64)> =
+            self.elements.element_moles().iter().copied().collect();
+        elements.sort_by_key(|&(element, _)| element);
+        for (element, count) in elements {
+            hash.write_str(element.symbol());
+            hash.write_number(count);
+        }
+        match self.enthalpy_datum {
+            EnthalpyDatum::FormationAt298 { value } => hash.write_number(value),
+            EnthalpyDatum::Assigned { value, temperature } => {
+                hash.write_number(value);
+                hash.write_number(temperature);
+            }
+        }
+        for range in &self.temperature_ranges {
+            for (_, value) in range.named_values() {
+                hash.write_number(value);
+            }
+        }
+    }
+
+    // Whether the species carries a charge, the electron included
+    pub fn is_ion(&self) -> bool {
+        self.charge() != 0.0
+    }
+
+    // Whether the record stands for a mixture rather than a molecule, taken as any element count
+    // that isn't whole: Air is listed as N 1.5617 O .41959 AR .00937 C .00032, the average atoms
+    // of its mean molecule. Such a species can be a reactant, its atoms entering the element
+    // totals as they are, but is never an equilibrium product.
+    pub fn is_pseudo_species(&self) -> bool {
+        self.elements
+            .iter()
+            .any(|&(_, count)| count.is_finite() && count != count.round())
+    }
+
+    // The elements the species is made of, each once in the order listed. The electron
+    // pseudo-element is not one of them; its charge is given by `charge`.
+    pub fn chemical_elements(&self) -> Vec<Element> {
+        self.elements
+            .element_moles()
+            .iter()
+            .map(|&(element, _)| element)
+            .filter(|&element| element != Element::ELECTRON)
+            .collect()
+    }
+
+    // The listed enthalpy, whether a heat of formation or an assigned enthalpy
+    #[deprecated(note = "use `enthalpy_datum`, which says what the value means")]
+    pub fn heat_of_formation(&self) -> f64 {
+        self.enthalpy_datum.value()
+    }
 }
 
 // Parse scientific notation with 'D' instead of 'E' (common in Fortran)
@@ -61,19 +963,58 @@ fn parse_scientific_d(input: &str) -> IResult<&str, f64> {
     }
 }
 
-// Parse regular floating point number
-fn parse_float(input: &str) -> IResult<&str, f64> {
+// How a number read from a file sits in the range of f64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberClass {
+    // Zero or a normal number
+    Finite,
+    // Too large to hold, read as infinite (or written as a NaN or infinity)
+    Overflow,
+    // Too small to hold at full precision, so read as zero
+    Underflow,
+}
+
+// Where a value converted from text falls. Conversion doesn't depend on the locale, so a
+// decimal comma is never taken for a point; only the size of the number matters.
+pub fn classify(value: f64) -> NumberClass {
+    if !value.is_finite() {
+        NumberClass::Overflow
+    } else if value != 0.0 && !value.is_normal() {
+        NumberClass::Underflow
+    } else {
+        NumberClass::Finite
+    }
+}
+
+// Parse a number in either notation as converted, without rounding subnormals
+fn parse_raw_float(input: &str) -> IResult<&str, f64> {
     alt((parse_scientific_d, double)).parse(input)
 }
 
+// Parse regular floating point number. A subnormal result is rounded to zero, as nothing a
+// fit holds is that small on purpose; an overflow is left infinite, for validation to catch.
+fn parse_float(input: &str) -> IResult<&str, f64> {
+    let (input, value) = parse_raw_float(input)?;
+    match classify(value) {
+        NumberClass::Underflow => Ok((input, 0.0_f64.copysign(value))),
+        _ => Ok((input, value)),
+    }
+}
+
 // Parse whitespace-separated floating point number
 fn parse_spaced_float(input: &str) -> IResult<&str, f64> {
     delimited(space0, parse_float, space0).parse(input)
 }
 
-// Parse the main header line
+// Parse the main header line. The keyword is matched ignoring case, some files writing THERMO.
 fn parse_header(input: &str) -> IResult<&str, ThermoHeader> {
-    let (input, _) = tag("thermo")(input)?;
+    let (input, _) = tag_no_case("thermo")(input)?;
+    let (input, _) = space0(input)?;
+    let (input, format) = opt(alt((
+        tag_no_case("nasa9").map(|_| ThermoFormat::Nasa9),
+        tag_no_case("nasa7").map(|_| ThermoFormat::Nasa7),
+    )))
+    .parse(input)?;
     let (input, _) = multispace0(input)?;
     let (input, temp1) = parse_spaced_float(input)?;
     let (input, temp2) = parse_spaced_float(input)?;
@@ -88,13 +1029,48 @@ fn parse_header(input: &str) -> IResult<&str, ThermoHeader> {
         ThermoHeader {
             temp_ranges: [temp1, temp2, temp3, temp4],
             date: date.trim().to_string(),
+            format: format.unwrap_or_default(),
         },
     ))
 }
 
+// Parse the header after any leading comments, rejecting layouts the species parser can't read
+fn parse_supported_header(input: &str) -> IResult<&str, ThermoHeader> {
+    let (rest, header) = preceded(skip_comments, parse_header).parse(input)?;
+    match header.format {
+        ThermoFormat::Nasa9 => Ok((rest, header)),
+        ThermoFormat::Nasa7 => Err(nom::Err::Failure(nom::error::Error::new(
+            input.trim_start(),
+            nom::error::ErrorKind::Verify,
+        ))),
+    }
+}
+
+// Parse an element count, which never carries an exponent (so "1.00E" leaves the "E")
+fn parse_element_count(input: &str) -> IResult<&str, f64> {
+    let (input, _) = space0(input)?;
+    let (input, count) = recognize((
+        opt(alt((char('+'), char('-')))),
+        alt((
+            recognize((digit1, opt((char('.'), opt(digit1))))),
+            recognize((char('.'), digit1)),
+        )),
+    ))
+    .parse(input)?;
+    let (input, _) = space0(input)?;
+
+    match count.parse::<f64>() {
+        Ok(val) => Ok((input, val)),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Float,
+        ))),
+    }
+}
+
 // Parse element composition (like "N   2.00O   2.00")
-fn parse_elements(input: &str) -> IResult<&str, Vec<(String, f64)>> {
-    let mut elements = Vec::new();
+fn parse_elements(input: &str) -> IResult<&str, Composition> {
+    let mut elements = Composition::new();
     let mut remaining = input;
 
     // Parse element-count pairs until we hit a number that looks like molecular weight
@@ -107,14 +1083,13 @@ fn parse_elements(input: &str) -> IResult<&str, Vec<(String, f64)>> {
         // Try to parse an element name (letters)
         if let Ok((rest2, element)) =
             take_while1::<_, _, nom::error::Error<_>>(|c: char| c.is_alphabetic())(rest)
+            && let Some(element) = Element::new(element)
         {
-            if element.len() > 0 {
-                // Parse the count that follows
-                if let Ok((rest3, count)) = parse_spaced_float(rest2) {
-                    elements.push((element.to_string(), count));
-                    remaining = rest3;
-                    continue;
-                }
+            // Parse the count that follows
+            if let Ok((rest3, count)) = parse_element_count(rest2) {
+                elements.push(element, count);
+                remaining = rest3;
+                continue;
             }
         }
         break;
@@ -123,172 +1098,2555 @@ fn parse_elements(input: &str) -> IResult<&str, Vec<(String, f64)>> {
     Ok((remaining, elements))
 }
 
-// Parse species header line
-fn parse_species_header(
-    input: &str,
-) -> IResult<&str, (String, String, Vec<(String, f64)>, f64, f64)> {
-    let (input, name) = take_while1(|c: char| !c.is_whitespace())(input)?;
-    let (input, _) = space1(input)?;
+// Parse the five 8-column element fields of a formula line (columns 11-50): a symbol in two
+// columns, then its count in six. Wholly blank fields, and zero counts with no symbol, are
+// skipped; a count may leave out its decimal point ("     2" for 2.00) or fill its six
+// columns, touching the next symbol. None if any field doesn't fit, for the caller to read
+// the elements free-form instead.
+fn parse_fixed_elements(field: &str) -> Option<Composition> {
+    let mut elements = Composition::new();
+    for slot in 0..5 {
+        let start = slot * 8;
+        let symbol = column(field, start, start + 2).trim();
+        let count = column(field, start + 2, start + 8).trim();
+        let count: f64 = if count.is_empty() {
+            0.0
+        } else {
+            count.parse().ok()?
+        };
+        if symbol.is_empty() {
+            if count != 0.0 {
+                return None;
+            }
+            continue;
+        }
+        elements.push(Element::new(symbol)?, count);
+    }
+    Some(elements)
+}
+
+// The elements of a formula line, from their fixed columns where they sit in them and
+// free-form where they don't
+fn parse_formula_elements(line: &str) -> IResult<&str, Composition> {
+    let field = column(line, 10, 50);
+    match parse_fixed_elements(field) {
+        Some(elements) => Ok(("", elements)),
+        None => parse_elements(field),
+    }
+}
 
-    // Parse description until we hit the element composition
-    let (input, description_part) = take_until(" ")(input)?;
-    let (input, _) = space1(input)?;
+// Slice a fixed-width column out of a record line, tolerating short lines
+fn column(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len())).unwrap_or("")
+}
 
-    // Parse remaining line to extract elements, molecular weight, and heat of formation
-    let (input, rest_of_line) = take_until("\n")(input)?;
-    let (input, _) = line_ending(input)?;
+// Take a single line, consuming its line ending if present
+fn take_line(input: &str) -> IResult<&str, &str> {
+    terminated(not_line_ending, alt((line_ending, eof))).parse(input)
+}
 
-    // Parse elements from the rest of the line
-    let (remaining, elements) = parse_elements(rest_of_line)?;
+// Parse a fixed-width numeric field, treating a blank field as zero
+fn parse_field(field: &str) -> IResult<&str, f64> {
+    if field.trim().is_empty() {
+        return Ok(("", 0.0));
+    }
+    all_consuming(parse_spaced_float).parse(field)
+}
 
-    // The remaining should have molecular weight and heat of formation
-    let parts: Vec<&str> = remaining.trim().split_whitespace().collect();
-    let molecular_weight = if parts.len() >= 2 {
-        parts[parts.len() - 2].parse().unwrap_or(0.0)
+// Parse a field as `parse_field` does, counting the significant digits written in it. Leading
+// zeros don't count, but zeros after the point do; a blank field has none.
+fn parse_counted_field(field: &str) -> IResult<&str, ParsedF64> {
+    let (rest, value) = parse_field(field)?;
+    let token = field.trim().trim_start_matches(['+', '-']);
+    let mantissa = token.split(['E', 'e', 'D', 'd']).next().unwrap_or("");
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let significant = digits.trim_start_matches('0');
+    let sig_digits = if significant.is_empty() {
+        // A zero shows its precision by the zeros after the point
+        mantissa
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len())
     } else {
-        0.0
+        significant.len()
+    };
+    Ok((
+        rest,
+        ParsedF64 {
+            value,
+            sig_digits: sig_digits.min(u8::MAX as usize) as u8,
+        },
+    ))
+}
+
+// The constants columns (molecular weight, heat of formation) when they hold fewer than two
+// numbers: the single number present, or an empty field if there is none. A single number
+// running across both fields is a full-width pair touching, and is left to the fixed columns.
+fn lone_constant(constants: &str) -> Option<&str> {
+    let mut tokens = constants.split_whitespace();
+    let Some(token) = tokens.next() else {
+        return Some("");
     };
-    let heat_of_formation = if parts.len() >= 1 {
-        parts[parts.len() - 1].parse().unwrap_or(0.0)
+    if tokens.next().is_some() {
+        return None;
+    }
+    let start = constants.find(token).unwrap_or(0);
+    let straddles = start < 13 && start + token.len() > 13;
+    (!straddles).then_some(token)
+}
+
+// Fields of the two species header lines, the text borrowed from the input
+struct SpeciesHeader<'a> {
+    name: &'a str,
+    description: &'a str,
+    interval_count: usize,
+    reference_code: &'a str,
+    elements: Composition,
+    phase: u8,
+    molecular_weight: f64,
+    heat_of_formation: f64,
+    source_digits: SourceDigits,
+}
+
+// Parse species header lines (name and comments, then formula and constants)
+fn parse_species_header(input: &str) -> IResult<&str, SpeciesHeader<'_>> {
+    let (input, name) = take_while1(|c: char| !c.is_whitespace())(input)?;
+    let (input, description) = take_line(input)?;
+
+    // Second line is fixed-column: (I2, 1X, A6, 1X, 5(A2, F6.2), 1X, I1, F13.7, F15.3)
+    let (input, line) = take_line(input)?;
+    let (_, interval_count) =
+        all_consuming(delimited(space0, digit1, space0)).parse(column(line, 0, 2))?;
+    let reference_code = column(line, 3, 9).trim();
+
+    let (_, elements) = parse_formula_elements(line)?;
+
+    let phase_field = column(line, 50, 52).trim();
+    let phase = if phase_field.is_empty() {
+        0
     } else {
-        0.0
+        let (_, phase) = all_consuming(digit1).parse(phase_field)?;
+        phase.parse().unwrap_or(0)
+    };
+    let (molecular_weight, heat_of_formation) = match lone_constant(column(line, 52, 80)) {
+        // Some entries leave the molecular weight blank, expecting it from the formula; a lone
+        // number is then the heat of formation, wherever it was placed
+        Some(field) => {
+            let (_, heat_of_formation) = parse_counted_field(field)?;
+            let computed = ParsedF64 {
+                value: formula_weight(&elements).unwrap_or(f64::NAN),
+                sig_digits: 0,
+            };
+            (computed, heat_of_formation)
+        }
+        None => {
+            let (_, molecular_weight) = parse_counted_field(column(line, 52, 65))?;
+            let (_, heat_of_formation) = parse_counted_field(column(line, 65, 80))?;
+            (molecular_weight, heat_of_formation)
+        }
+    };
+    // Some databases list the electron with a molecular weight of zero. A zero weight is taken
+    // from the formula as a blank one is, so that nothing later divides by it.
+    let molecular_weight = match formula_weight(&elements) {
+        Some(weight) if molecular_weight.value == 0.0 && weight > 0.0 => ParsedF64 {
+            value: weight,
+            sig_digits: 0,
+        },
+        _ => molecular_weight,
+    };
+    let read = |parsed: ParsedF64| (parsed.sig_digits > 0).then_some(parsed.sig_digits);
+    let source_digits = SourceDigits {
+        molecular_weight: read(molecular_weight),
+        enthalpy: read(heat_of_formation),
     };
 
     Ok((
         input,
-        (
-            name.to_string(),
-            description_part.to_string(),
+        SpeciesHeader {
+            name,
+            description: description.trim(),
+            interval_count: interval_count.parse().unwrap_or(0),
+            reference_code,
             elements,
-            molecular_weight,
-            heat_of_formation,
-        ),
+            phase,
+            molecular_weight: molecular_weight.value,
+            heat_of_formation: heat_of_formation.value,
+            source_digits,
+        },
     ))
 }
 
 // Parse temperature range with coefficients
 fn parse_temperature_range(input: &str) -> IResult<&str, TemperatureRange> {
-    // First line: temperature range and metadata
-    let (input, _) = space0(input)?;
-    let (input, temp_low) = parse_spaced_float(input)?;
-    let (input, temp_high) = parse_spaced_float(input)?;
-    let (input, _) = take_until("\n")(input)?; // Skip the rest of the metadata
-    let (input, _) = line_ending(input)?;
+    // First line is fixed-column: (2F11.3, I1, 8F5.1, 2X, F15.3)
+    let (input, range_line) = take_line(input)?;
+    let (_, temp_low) = parse_field(column(range_line, 0, 11))?;
+    let (_, temp_high) = parse_field(column(range_line, 11, 22))?;
+    let (_, h298_minus_h0) = parse_field(column(range_line, 65, 80))?;
 
-    // Parse coefficient lines (typically 2 lines with scientific notation)
-    let (input, coeff_line1) = take_until("\n")(input)?;
-    let (input, _) = line_ending(input)?;
-    let (input, coeff_line2) = take_until("\n")(input)?;
-    let (input, _) = line_ending(input)?;
+    // Condensed species may use fewer than seven terms, listing only their exponents
+    let count_field = column(range_line, 22, 23);
+    let (_, used_coefficients) = all_consuming(digit1).parse(count_field)?;
+    let used_coefficients: usize = used_coefficients.parse().unwrap_or(0);
+    if used_coefficients > NASA9_EXPONENTS.len() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            count_field,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    for (i, expected) in NASA9_EXPONENTS.iter().take(used_coefficients).enumerate() {
+        let exponent_field = column(range_line, 23 + 5 * i, 28 + 5 * i);
+        let (_, exponent) = parse_field(exponent_field)?;
+        if exponent != *expected as f64 {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                exponent_field,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+    }
+
+    // Coefficient lines hold 16-character fields which may touch each other
+    let (input, coeff_line1) = take_line(input)?;
+    let (input, coeff_line2) = take_line(input)?;
+    let (coeff_line1, coeff_line2) = (coefficient_data(coeff_line1), coefficient_data(coeff_line2));
 
-    // Parse coefficients from both lines
     let mut coefficients = [0.0; 7];
     let mut integration_constants = [0.0; 2];
 
-    // Parse first coefficient line (usually has 5 coefficients)
-    let coeff1_parts: Vec<&str> = coeff_line1.trim().split_whitespace().collect();
-    for (i, part) in coeff1_parts.iter().take(5).enumerate() {
-        if let Ok((_, val)) = parse_scientific_d(part) {
-            coefficients[i] = val;
-        }
+    // First coefficient line has 5 coefficients
+    for (i, coefficient) in coefficients.iter_mut().take(5).enumerate() {
+        let (_, val) = parse_field(column(coeff_line1, 16 * i, 16 * (i + 1)))?;
+        *coefficient = val;
+    }
+
+    // Second coefficient line has 2 coefficients, a blank field, then 2 integration constants
+    for (i, coefficient) in coefficients.iter_mut().skip(5).enumerate() {
+        let (_, val) = parse_field(column(coeff_line2, 16 * i, 16 * (i + 1)))?;
+        *coefficient = val;
+    }
+    for (i, constant) in integration_constants.iter_mut().enumerate() {
+        let (_, val) = parse_field(column(coeff_line2, 16 * (i + 3), 16 * (i + 4)))?;
+        *constant = val;
+    }
+
+    // Slots beyond the coefficient count are padding, whatever they hold
+    for coefficient in coefficients.iter_mut().skip(used_coefficients) {
+        *coefficient = 0.0;
+    }
+
+    Ok((
+        input,
+        TemperatureRange {
+            temp_low,
+            temp_high,
+            coefficients,
+            used_coefficients: used_coefficients as u8,
+            integration_constants,
+            h298_minus_h0,
+        },
+    ))
+}
+
+// Columns holding data on a fixed-column record line
+const RECORD_WIDTH: usize = 80;
+
+// The data of a fixed-column coefficient line: its first 80 columns, up to any '!'. Some files
+// append notes to these lines, which would otherwise be read as more numbers.
+fn coefficient_data(line: &str) -> &str {
+    let line = line.split('!').next().unwrap_or(line);
+    line.char_indices()
+        .nth(RECORD_WIDTH)
+        .map_or(line, |(end, _)| &line[..end])
+}
+
+// An error at a whitespace-delimited line that doesn't hold the fields expected of it
+fn malformed(line: &str) -> nom::Err<nom::error::Error<&str>> {
+    nom::Err::Error(nom::error::Error::new(line, nom::error::ErrorKind::Verify))
+}
+
+// The numbers opening a whitespace-delimited line, in any notation a fixed field accepts. They
+// end at the first word that isn't one, or at a '!', leaving any note after them unread.
+fn leading_numbers(line: &str) -> Vec<f64> {
+    let data = line.split('!').next().unwrap_or(line);
+    data.split_whitespace()
+        .map_while(|token| loose_number(token).ok())
+        .collect()
+}
+
+fn loose_number(token: &str) -> Result<f64, nom::Err<nom::error::Error<&str>>> {
+    parse_field(token).map(|(_, value)| value)
+}
+
+// Whether a token is an element symbol on its own, or symbols and counts run together
+fn starts_elements(tokens: &[&str]) -> bool {
+    let Some(&first) = tokens.first() else {
+        return false;
+    };
+    if first.chars().all(|c| c.is_ascii_alphabetic()) && Element::new(first).is_some() {
+        return tokens.get(1).is_some_and(|next| parse_field(next).is_ok());
     }
+    first.starts_with(|c: char| c.is_ascii_alphabetic())
+        && parse_elements(first)
+            .is_ok_and(|(rest, elements)| rest.is_empty() && !elements.is_empty())
+}
+
+// Parse species header lines whose fields are separated by whitespace. The formula line holds
+// the interval count, a reference code (which may itself contain a space), element symbols
+// and counts, then the phase, molecular weight and heat of formation, all three required.
+fn parse_loose_species_header(input: &str) -> IResult<&str, SpeciesHeader<'_>> {
+    let (input, name) = take_while1(|c: char| !c.is_whitespace())(input)?;
+    let (input, description) = take_line(input)?;
 
-    // Parse second coefficient line (usually has 2 coefficients + 2 integration constants)
-    let coeff2_parts: Vec<&str> = coeff_line2.trim().split_whitespace().collect();
-    for (i, part) in coeff2_parts.iter().take(2).enumerate() {
-        if let Ok((_, val)) = parse_scientific_d(part) {
-            coefficients[5 + i] = val;
+    let (input, line) = take_line(input)?;
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 5 {
+        return Err(malformed(line));
+    }
+    let interval_count: usize = tokens[0].parse().map_err(|_| malformed(line))?;
+    let (body, constants) = tokens[1..].split_at(tokens.len() - 4);
+    let start = (0..body.len())
+        .find(|&i| starts_elements(&body[i..]))
+        .ok_or_else(|| malformed(line))?;
+    // The reference code's words as the line spaces them, from the first word to the last
+    let reference_code = match body[..start] {
+        [] => "",
+        [first, .., last] | [first @ last] => {
+            let offset = |word: &str| word.as_ptr() as usize - line.as_ptr() as usize;
+            &line[offset(first)..offset(last) + last.len()]
         }
+    };
+    let listed = body[start..].join(" ");
+    let (rest, elements) = parse_elements(&listed).map_err(|_| malformed(line))?;
+    // Blank element slots may still be written as zero counts
+    if rest
+        .split_whitespace()
+        .any(|token| !matches!(parse_field(token), Ok((_, 0.0))))
+    {
+        return Err(malformed(line));
     }
 
-    // Get integration constants (last 2 values)
-    if coeff2_parts.len() >= 4 {
-        for (i, part) in coeff2_parts.iter().skip(coeff2_parts.len() - 2).enumerate() {
-            if let Ok((_, val)) = parse_scientific_d(part) {
-                integration_constants[i] = val;
-            }
+    let phase: u8 = constants[0].parse().map_err(|_| malformed(line))?;
+    let (_, molecular_weight) = parse_counted_field(constants[1])?;
+    let (_, heat_of_formation) = parse_counted_field(constants[2])?;
+    Ok((
+        input,
+        SpeciesHeader {
+            name,
+            description: description.trim(),
+            interval_count,
+            reference_code,
+            elements,
+            phase,
+            molecular_weight: molecular_weight.value,
+            heat_of_formation: heat_of_formation.value,
+            source_digits: SourceDigits {
+                molecular_weight: Some(molecular_weight.sig_digits),
+                enthalpy: Some(heat_of_formation.sig_digits),
+            },
+        },
+    ))
+}
+
+// Parse a temperature range whose fields are separated by whitespace: the limits, the term
+// count, at least that many exponents and H(298.15) - H(0), then five coefficients on the next
+// line and the last two with the integration constants on the one after
+fn parse_loose_temperature_range(input: &str) -> IResult<&str, TemperatureRange> {
+    let (input, range_line) = take_line(input)?;
+    let tokens: Vec<&str> = range_line.split_whitespace().collect();
+    if tokens.len() < 4 {
+        return Err(malformed(range_line));
+    }
+    let temp_low = loose_number(tokens[0])?;
+    let temp_high = loose_number(tokens[1])?;
+    let h298_minus_h0 = loose_number(tokens[tokens.len() - 1])?;
+    let used_coefficients: usize = tokens[2].parse().map_err(|_| malformed(range_line))?;
+    let exponents = &tokens[3..tokens.len() - 1];
+    if used_coefficients > NASA9_EXPONENTS.len() || exponents.len() < used_coefficients {
+        return Err(malformed(range_line));
+    }
+    for (token, expected) in exponents
+        .iter()
+        .zip(NASA9_EXPONENTS)
+        .take(used_coefficients)
+    {
+        if loose_number(token)? != expected as f64 {
+            return Err(malformed(range_line));
         }
     }
 
+    let (input, coeff_line1) = take_line(input)?;
+    let (input, coeff_line2) = take_line(input)?;
+    let (first, second) = (leading_numbers(coeff_line1), leading_numbers(coeff_line2));
+    if first.len() != 5 {
+        return Err(malformed(coeff_line1));
+    }
+    let [a6, a7, b1, b2] = second[..] else {
+        return Err(malformed(coeff_line2));
+    };
+
+    let mut coefficients = [0.0; 7];
+    coefficients[..5].copy_from_slice(&first);
+    coefficients[5] = a6;
+    coefficients[6] = a7;
+    for coefficient in coefficients.iter_mut().skip(used_coefficients) {
+        *coefficient = 0.0;
+    }
     Ok((
         input,
         TemperatureRange {
             temp_low,
             temp_high,
             coefficients,
-            integration_constants,
+            used_coefficients: used_coefficients as u8,
+            integration_constants: [b1, b2],
+            h298_minus_h0,
         },
     ))
 }
 
-// Parse a complete species entry
+// Parse a complete species entry laid out in fixed columns
 fn parse_species(input: &str) -> IResult<&str, Species> {
-    let (input, (name, description, elements, molecular_weight, heat_of_formation)) =
-        parse_species_header(input)?;
+    parse_species_as(input, ParseMode::FixedColumn)
+}
 
-    // Parse temperature ranges (usually 3 ranges)
-    let mut temperature_ranges = Vec::new();
-    let mut remaining = input;
+// Parse a complete species entry in the given layout, which must not be `Auto`
+fn parse_species_as(input: &str, mode: ParseMode) -> IResult<&str, Species> {
+    parse_species_ref_as(input, mode).map(|(rest, species)| (rest, species.into_owned()))
+}
 
-    // Keep parsing temperature ranges until we hit "END REACTANTS" or another species
-    while !remaining.trim_start().starts_with("END")
-        && !remaining
-            .trim_start()
-            .chars()
-            .next()
-            .map(|c| c.is_alphabetic())
-            .unwrap_or(false)
-        && !remaining.trim().is_empty()
-    {
-        if let Ok((rest, temp_range)) = parse_temperature_range(remaining) {
-            temperature_ranges.push(temp_range);
-            remaining = rest;
+// As `parse_species_as`, borrowing the record's text from the input
+fn parse_species_ref_as(input: &str, mode: ParseMode) -> IResult<&str, SpeciesRef<'_>> {
+    let loose = mode == ParseMode::Whitespace;
+    let (input, header) = if loose {
+        parse_loose_species_header(input)?
+    } else {
+        parse_species_header(input)?
+    };
+
+    // The header records how many temperature ranges follow
+    let range = if loose {
+        parse_loose_temperature_range
+    } else {
+        parse_temperature_range
+    };
+    let (input, temperature_ranges) = count(range, header.interval_count).parse(input)?;
+
+    // A record without fits is followed by the temperature its enthalpy was assigned at
+    let (input, enthalpy_datum) = if header.interval_count == 0 {
+        let (input, line) = take_line(input)?;
+        let field = if loose {
+            line.split_whitespace().next().unwrap_or("")
         } else {
+            column(line, 0, 11)
+        };
+        let (_, temperature) = parse_field(field)?;
+        let datum = EnthalpyDatum::Assigned {
+            value: header.heat_of_formation,
+            temperature,
+        };
+        (input, datum)
+    } else {
+        let datum = EnthalpyDatum::FormationAt298 {
+            value: header.heat_of_formation,
+        };
+        (input, datum)
+    };
+
+    Ok((
+        input,
+        SpeciesRef {
+            name: header.name,
+            description: header.description,
+            reference_code: header.reference_code,
+            elements: header.elements,
+            phase: header.phase,
+            molecular_weight: header.molecular_weight,
+            enthalpy_datum,
+            temperature_ranges,
+            source_digits: header.source_digits,
+        },
+    ))
+}
+
+// Skip blank lines and '!' comment lines
+fn skip_comments(input: &str) -> IResult<&str, ()> {
+    let (input, _) = many0(terminated(
+        alt((preceded(char('!'), not_line_ending), space0)),
+        line_ending,
+    ))
+    .parse(input)?;
+    let (input, _) = multispace0(input)?;
+    Ok((input, ()))
+}
+
+// Whether the input starts with a species record (a name line followed by a formula line)
+fn is_species_start(input: &str) -> bool {
+    is_species_start_as(input, ParseMode::FixedColumn)
+}
+
+// As `is_species_start`, finding the interval count as the given layout places it
+fn is_species_start_as(input: &str, mode: ParseMode) -> bool {
+    let Ok((rest, line)) = take_line(input) else {
+        return false;
+    };
+    let starts_with_name = line
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace() && c != '-' && c != '!')
+        && !line.starts_with("END");
+    let Ok((_, formula_line)) = take_line(rest) else {
+        return false;
+    };
+    let interval_count = match mode {
+        ParseMode::Whitespace => formula_line.split_whitespace().next().unwrap_or(""),
+        _ => column(formula_line, 0, 2).trim(),
+    };
+    starts_with_name
+        && !interval_count.is_empty()
+        && interval_count.chars().all(|c| c.is_ascii_digit())
+}
+
+// Names of the species records in a file, in order, borrowed from it. Only the name and
+// formula lines that start each record are looked at; the rest isn't parsed or checked.
+pub fn scan_species_names(input: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut remaining = input;
+    loop {
+        if is_species_start(remaining)
+            && let Some(name) = remaining.split_whitespace().next()
+        {
+            names.push(name);
+        }
+        match remaining.find('\n') {
+            Some(end) => remaining = &remaining[end + 1..],
+            None => break,
+        }
+    }
+    names
+}
+
+// Where a species record sits in a file, and what can be read from its first lines without
+// parsing its description or coefficients
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeciesIndexEntry {
+    pub name: String,
+    pub elements: Composition,
+    pub phase: u8,
+    pub interval_count: usize,
+    pub fitted_range: Option<(f64, f64)>, // From the first and last fits' bounds, K
+    pub line: usize,                      // One-based line of the name
+    pub byte_range: Range<usize>,         // The whole record, line endings included
+}
+
+// The index entry for the record starting `input`, with the input following it. Only the
+// formula line and the bounds of the first and last fits are read.
+fn parse_index_entry(input: &str) -> IResult<&str, SpeciesIndexEntry> {
+    let (rest, name_line) = take_line(input)?;
+    let (mut rest, formula_line) = take_line(rest)?;
+    let name = name_line.split_whitespace().next().unwrap_or("");
+    let (_, interval_count) =
+        all_consuming(delimited(space0, digit1, space0)).parse(column(formula_line, 0, 2))?;
+    let interval_count: usize = interval_count.parse().unwrap_or(0);
+    let (_, elements) = parse_formula_elements(formula_line)?;
+    let phase = column(formula_line, 50, 52).trim().parse().unwrap_or(0);
+
+    // Each fit takes three lines, and a record without any has a line for its temperature
+    let mut bounds = Vec::new();
+    for fit in 0..interval_count.max(1) {
+        let (after, range_line) = take_line(rest)?;
+        rest = after;
+        if interval_count == 0 {
             break;
         }
+        if fit == 0 || fit + 1 == interval_count {
+            let (_, low) = parse_field(column(range_line, 0, 11))?;
+            let (_, high) = parse_field(column(range_line, 11, 22))?;
+            bounds.push((low, high));
+        }
+        rest = take_line(rest)?.0;
+        rest = take_line(rest)?.0;
     }
+    let fitted_range = bounds.first().zip(bounds.last()).map(|(a, b)| (a.0, b.1));
 
     Ok((
-        remaining,
-        Species {
-            name,
-            description,
+        rest,
+        SpeciesIndexEntry {
+            name: name.to_string(),
             elements,
-            molecular_weight,
-            heat_of_formation,
-            temperature_ranges,
+            phase,
+            interval_count,
+            fitted_range,
+            line: 0,
+            byte_range: 0..input.len() - rest.len(),
         },
     ))
 }
 
-// Parse the complete thermo file
-pub fn parse_thermo_file(input: &str) -> IResult<&str, ThermoFile> {
-    let (input, _) = multispace0(input)?; // Skip any leading whitespace/comments
-    let (input, header) = parse_header(input)?;
-    let (input, species) = many0(parse_species).parse(input)?;
-    let (input, _) = multispace0(input)?; // Skip trailing content
+// An index of the species records in a file, found as `scan_species_names` finds them and read
+// only as far as `SpeciesIndexEntry` needs: much cheaper than a full parse when names, formulas
+// or coverage are all that's wanted. A record that can't be read that far is passed over.
+pub fn parse_index(input: &str) -> Vec<SpeciesIndexEntry> {
+    let mut entries = Vec::new();
+    let mut remaining = input;
+    let mut line = 1;
+    while !remaining.is_empty() {
+        let start = input.len() - remaining.len();
+        if is_species_start(remaining)
+            && let Ok((rest, mut entry)) = parse_index_entry(remaining)
+        {
+            let record = &remaining[..remaining.len() - rest.len()];
+            entry.line = line;
+            entry.byte_range = start..start + record.len();
+            line += record.matches('\n').count();
+            entries.push(entry);
+            remaining = rest;
+            continue;
+        }
+        match remaining.find('\n') {
+            Some(end) => {
+                remaining = &remaining[end + 1..];
+                line += 1;
+            }
+            None => break,
+        }
+    }
+    entries
+}
 
-    Ok((input, ThermoFile { header, species }))
+// One-based line number of the position `rest` within `full`
+fn line_number(full: &str, rest: &str) -> usize {
+    full[..full.len() - rest.len()].matches('\n').count() + 1
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// Describe a nom error for a human reader
+fn describe_error(error: nom::Err<nom::error::Error<&str>>) -> String {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            let near = e.input.lines().next().unwrap_or("").trim();
+            format!("{:?} parse failed near '{}'", e.code, near)
+        }
+        nom::Err::Incomplete(_) => "unexpected end of input".to_string(),
+    }
+}
 
-    #[test]
-    fn test_scientific_d_parsing() {
-        assert_eq!(parse_scientific_d("2.500000000D+00"), Ok(("", 2.5)));
-        assert_eq!(parse_scientific_d("-7.453750000D+02"), Ok(("", -745.375)));
-        assert_eq!(
-            parse_scientific_d("1.066859930D-05"),
-            Ok(("", 1.066859930e-5))
-        );
+// Longest excerpt of unparsed input kept in a `ThermoParseError::Context`
+const SNIPPET_LENGTH: usize = 120;
+
+// Error pointing at the input left unread when parsing stopped
+fn context_error(remaining: &str) -> ThermoParseError {
+    ThermoParseError::Context {
+        remaining_snippet: remaining.chars().take(SNIPPET_LENGTH).collect(),
     }
+}
 
-    #[test]
-    fn test_header_parsing() {
-        let input = "thermo                                                                          \n    200.00   1000.00   6000.00  20000.     9/09/04\n";
-        let result = parse_header(input);
-        assert!(result.is_ok());
+// The rest of `full` from the point where a parser stopped. Field parsers see only a column
+// slice of their line, so their error input is widened back out to the whole file.
+fn failure_point<'a>(full: &'a str, stopped: &'a str) -> &'a str {
+    let start = full.as_ptr() as usize;
+    let position = stopped.as_ptr() as usize;
+    if (start..=start + full.len()).contains(&position) {
+        &full[position - start..]
+    } else {
+        stopped
+    }
+}
+
+// Parse a complete thermo file, failing if any of it cannot be read. A failure inside a
+// species record reports the input from the point of failure. Records are read in fixed
+// columns; `parse_thermo_with` reads other layouts.
+pub fn parse_thermo(input: &str) -> Result<ThermoFile, ThermoParseError> {
+    parse_thermo_with(input, ParseMode::FixedColumn)
+}
+
+// The layout of a file's records, judged by its header line: CEA writes the four temperatures
+// as (4F10.3), so each sits inside its own ten columns. A file whose temperatures stray across
+// those boundaries was not written to columns, and its records are taken to be loosely spaced.
+fn sniff_mode(input: &str) -> ParseMode {
+    let Ok((rest, _)) = skip_comments(input) else {
+        return ParseMode::FixedColumn;
+    };
+    let mut lines = rest.lines();
+    let keyword = lines.next().unwrap_or("");
+    if keyword.split_whitespace().count() > 2 {
+        return ParseMode::Whitespace;
+    }
+    let line = lines.next().unwrap_or("");
+    let aligned = (0..4).all(|i| {
+        let field = column(line, 10 * i, 10 * (i + 1));
+        !field.trim().is_empty() && parse_field(field).is_ok()
+    });
+    if aligned {
+        ParseMode::FixedColumn
+    } else {
+        ParseMode::Whitespace
+    }
+}
+
+// Parse a complete thermo file as `parse_thermo` does, with records in the given layout
+pub fn parse_thermo_with(input: &str, mode: ParseMode) -> Result<ThermoFile, ThermoParseError> {
+    parse_records(input, mode).map(|(file, _)| file)
+}
+
+// Parse a complete thermo file as `parse_thermo` does, borrowing each species' name,
+// description and reference code from the input rather than copying them
+pub fn parse_thermo_ref(input: &str) -> Result<ThermoFileRef<'_>, ThermoParseError> {
+    parse_records_ref(input, ParseMode::FixedColumn).map(|(file, _)| file)
+}
+
+// Parse a complete thermo file as `parse_thermo` does, then hold every record to the exact
+// layout CEA writes, for curating data rather than reading whatever is at hand. Each field
+// must sit in its own columns in its own Fortran format: counts as integers, constants as F
+// fields with a decimal point and no exponent, and coefficients as D16.9, exponent and all.
+// Blank constants, notes after a record line and lines past column 80 are refused too.
+pub fn parse_thermo_strict(input: &str) -> Result<ThermoFile, ThermoParseError> {
+    let (file, starts) = parse_records(input, ParseMode::FixedColumn)?;
+    check_strict_header(input)?;
+    let lines: Vec<&str> = input.lines().collect();
+    for (species, &start) in file.species.iter().zip(&starts) {
+        check_strict_species(&lines, species, start).map_err(|message| {
+            ThermoParseError::Species {
+                name: species.name.clone(),
+                line: start,
+                message,
+            }
+        })?;
+    }
+    Ok(file)
+}
+
+// Whether a field holds one number as Fortran's F format reads it: a decimal point, no
+// exponent, and no blanks within it. CEA's own files don't always right-align these.
+fn is_f_field(field: &str) -> bool {
+    let number = field.trim();
+    let digits = number.strip_prefix('-').unwrap_or(number);
+    digits.matches('.').count() == 1
+        && digits.len() > 1
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+// Whether a field holds a number as D16.9, such as " 2.500000000D+00"
+fn is_d_field(field: &str) -> bool {
+    let bytes = field.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes[range].iter().all(u8::is_ascii_digit);
+    bytes.len() == 16
+        && matches!(bytes[0], b' ' | b'-')
+        && digits(1..2)
+        && bytes[2] == b'.'
+        && digits(3..12)
+        && bytes[12] == b'D'
+        && matches!(bytes[13], b'+' | b'-')
+        && digits(14..16)
+}
+
+// Whether a field holds a right-aligned unsigned integer
+fn is_i_field(field: &str) -> bool {
+    let number = field.trim_start();
+    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+}
+
+// Check the 'thermo' keyword and the (4F10.3, A10) temperature line after any comments
+fn check_strict_header(input: &str) -> Result<(), ThermoParseError> {
+    let header = |message: String| ThermoParseError::Header { message };
+    let mut lines = input.lines().skip_while(|line| line.starts_with('!'));
+    let keyword = lines.next().unwrap_or("");
+    if keyword.trim_end() != "thermo" {
+        return Err(header(format!(
+            "expected 'thermo' alone, found '{}'",
+            keyword
+        )));
+    }
+    let temperatures = lines.next().unwrap_or("");
+    for i in 0..4 {
+        let field = column(temperatures, 10 * i, 10 * (i + 1));
+        if !is_f_field(field) {
+            let columns = format!("columns {}-{}", 10 * i + 1, 10 * (i + 1));
+            return Err(header(format!("{} hold '{}', not F10.3", columns, field)));
+        }
+    }
+    if temperatures.trim_end().chars().count() > RECORD_WIDTH {
+        return Err(header(
+            "the temperature line runs past column 80".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Check each line of the species record starting on (one-based) line `start` against CEA's
+// layout, describing the first field out of place
+fn check_strict_species(lines: &[&str], species: &Species, start: usize) -> Result<(), String> {
+    // A record line by its offset from the name line, and its label in messages, refused if
+    // anything but spaces runs past the record's columns
+    let line_at = |offset: usize, label: &str| {
+        let line = lines.get(start - 1 + offset).copied().unwrap_or("");
+        if line.trim_end().chars().count() > RECORD_WIDTH {
+            return Err(format!("{}: runs past column 80", label));
+        }
+        Ok(line)
+    };
+    // A field, by one-based columns as the format is documented, in the format it must hold
+    let field = |label: &str, line: &str, first: usize, last: usize, format: &str| {
+        let text = column(line, first - 1, last);
+        let fits = match format {
+            "blank" => text.trim().is_empty(),
+            "I" => is_i_field(text),
+            "D16.9" => is_d_field(text),
+            _ => is_f_field(text),
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}, columns {}-{}: '{}' is not {}",
+                label, first, last, text, format
+            ))
+        }
+    };
+
+    let name_line = line_at(0, "name line")?;
+    let name = name_line.split_whitespace().next().unwrap_or("");
+    if name_line.starts_with(' ') || name.len() > 15 {
+        return Err(format!("name line: '{}' is not in columns 1-15", name));
+    }
+
+    let label = "formula line";
+    let formula = line_at(1, label)?;
+    field(label, formula, 1, 2, "I")?;
+    field(label, formula, 3, 3, "blank")?;
+    field(label, formula, 10, 10, "blank")?;
+    if parse_fixed_elements(column(formula, 10, 50)).is_none() {
+        return Err(format!(
+            "{}, columns 11-50: not five (A2, F6.2) fields",
+            label
+        ));
+    }
+    for slot in 0..5 {
+        let first = 13 + 8 * slot;
+        field(label, formula, first, first + 5, "F6.2")?;
+    }
+    field(label, formula, 51, 51, "blank")?;
+    field(label, formula, 52, 52, "I")?;
+    field(label, formula, 53, 65, "F13.7")?;
+    field(label, formula, 66, 80, "F15.3")?;
+
+    for fit in 0..species.temperature_ranges.len() {
+        let offset = 2 + 3 * fit;
+        let label = format!("fit {} temperature line", fit);
+        let interval = line_at(offset, &label)?;
+        field(&label, interval, 1, 11, "F11.3")?;
+        field(&label, interval, 12, 22, "F11.3")?;
+        field(&label, interval, 23, 23, "I")?;
+        for exponent in 0..8 {
+            let first = 24 + 5 * exponent;
+            field(&label, interval, first, first + 4, "F5.1")?;
+        }
+        field(&label, interval, 64, 65, "blank")?;
+        field(&label, interval, 66, 80, "F15.3")?;
+
+        // Five coefficients, then two, a skipped field (16X, which CEA's own files sometimes
+        // fill) and the two integration constants
+        let label = format!("fit {} coefficient line 1", fit);
+        let first = line_at(offset + 1, &label)?;
+        for slot in 0..5 {
+            field(&label, first, 16 * slot + 1, 16 * (slot + 1), "D16.9")?;
+        }
+        let label = format!("fit {} coefficient line 2", fit);
+        let second = line_at(offset + 2, &label)?;
+        for slot in [0, 1, 3, 4] {
+            field(&label, second, 16 * slot + 1, 16 * (slot + 1), "D16.9")?;
+        }
+    }
+
+    // A reactant-only record closes with the temperature of its assigned enthalpy
+    if species.temperature_ranges.is_empty() {
+        let label = "assigned temperature line";
+        field(label, line_at(2, label)?, 1, 11, "F11.3")?;
+    }
+    Ok(())
+}
+
+// Parse a complete thermo file, also returning the line each species record starts on
+fn parse_records(
+    input: &str,
+    mode: ParseMode,
+) -> Result<(ThermoFile, Vec<usize>), ThermoParseError> {
+    parse_records_ref(input, mode).map(|(file, lines)| (file.into_owned(), lines))
+}
+
+// As `parse_records`, borrowing the species' text from the input
+fn parse_records_ref(
+    input: &str,
+    mode: ParseMode,
+) -> Result<(ThermoFileRef<'_>, Vec<usize>), ThermoParseError> {
+    let mode = match mode {
+        ParseMode::Auto => sniff_mode(input),
+        mode => mode,
+    };
+    let (mut remaining, header) =
+        parse_supported_header(input).map_err(|e| ThermoParseError::Header {
+            message: describe_error(e),
+        })?;
+
+    let mut species = Vec::new();
+    let mut lines = Vec::new();
+    while !remaining.is_empty() {
+        let Ok((next_line, line)) = take_line(remaining) else {
+            break;
+        };
+        if line.trim().is_empty() || line.starts_with("END") || line.starts_with('!') {
+            remaining = next_line;
+            continue;
+        }
+        if !is_species_start_as(remaining, mode) {
+            return Err(context_error(remaining));
+        }
+        match parse_species_ref_as(remaining, mode) {
+            Ok((rest, parsed)) => {
+                species.push(parsed);
+                lines.push(line_number(input, remaining));
+                remaining = rest;
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(context_error(failure_point(input, e.input)));
+            }
+            Err(nom::Err::Incomplete(_)) => return Err(context_error("")),
+        }
+    }
+
+    Ok((ThermoFileRef { header, species }, lines))
+}
+
+// The coefficients of a fit by their CEA names, with the coefficient line (first or second
+// after the fit's temperature line) and the 16-column field holding each
+const COEFFICIENT_FIELDS: [(&str, usize, usize); 9] = [
+    ("a1", 1, 0),
+    ("a2", 1, 1),
+    ("a3", 1, 2),
+    ("a4", 1, 3),
+    ("a5", 1, 4),
+    ("a6", 2, 0),
+    ("a7", 2, 1),
+    ("b1", 2, 3),
+    ("b2", 2, 4),
+];
+
+// Warnings for coefficients of a species record beyond the range of f64, read again from the
+// record's lines as the parse left them infinite or zero
+fn coefficient_warnings(lines: &[&str], species: &Species, line: usize) -> Vec<ParseWarning> {
+    let mut warnings = Vec::new();
+    for fit in 0..species.temperature_ranges.len() {
+        for (name, offset, field) in COEFFICIENT_FIELDS {
+            let number = line + 2 + 3 * fit + offset; // One-based
+            let Some(text) = lines.get(number - 1) else {
+                continue;
+            };
+            let text = column(text, 16 * field, 16 * (field + 1)).trim();
+            let Ok((_, value)) = all_consuming(parse_raw_float).parse(text) else {
+                continue;
+            };
+            let (kind, consequence) = match classify(value) {
+                NumberClass::Finite => continue,
+                NumberClass::Overflow => (
+                    ParseWarningKind::CoefficientOverflow,
+                    "is beyond f64, so the fit can't be evaluated",
+                ),
+                NumberClass::Underflow => (
+                    ParseWarningKind::CoefficientUnderflow,
+                    "is below the smallest normal f64, so was read as zero",
+                ),
+            };
+            warnings.push(ParseWarning {
+                kind,
+                species: Some(species.name.clone()),
+                line: number,
+                message: format!("fit {} {} '{}' {}", fit, name, text, consequence),
+            });
+        }
+    }
+    warnings
+}
+
+// Parse a complete thermo file as `parse_thermo` does, failing on anything unreadable but
+// noting what was read only with some doubt: comment lines, repeated names, elements without
+// an atomic weight, molecular weights computed from the formula and coefficients out of the
+// range of f64. Fits are snapped together as the options allow, each species repaired being
+// noted too, as is each species whose fits are still out of order or overlapping.
+pub fn parse_thermo_file_with_warnings(
+    input: &str,
+    options: LenientOptions,
+) -> Result<ParseOutcome, ThermoParseError> {
+    parse_with_warnings(input, options).map(|(outcome, _)| outcome)
+}
+
+// As `parse_thermo_file_with_warnings`, also returning the line each species record starts on
+fn parse_with_warnings(
+    input: &str,
+    options: LenientOptions,
+) -> Result<(ParseOutcome, Vec<usize>), ThermoParseError> {
+    let (mut file, lines) = parse_records(input, ParseMode::FixedColumn)?;
+    let text_lines: Vec<&str> = input.lines().collect();
+    let mut warnings: Vec<ParseWarning> = text_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| text.starts_with('!'))
+        .map(|(index, text)| ParseWarning {
+            kind: ParseWarningKind::SkippedComment,
+            species: None,
+            line: index + 1,
+            message: format!("skipped comment '{}'", text.trim_end()),
+        })
+        .collect();
+
+    // The line each name was first defined on, and where its latest record's fits end
+    let mut seen: BTreeMap<String, (usize, Option<f64>)> = BTreeMap::new();
+    for (species, &line) in file.species.iter_mut().zip(&lines) {
+        let mut found = Vec::new();
+        let ranges = species.temperature_ranges();
+        let (low, high) = (ranges.first(), ranges.last());
+        if let Some(&(first, end)) = seen.get(&species.name) {
+            // CEA splits a condensed phase across records that take up where the last ended
+            let continues =
+                species.is_condensed() && end.is_some() && end == low.map(|r| r.temp_low);
+            if !continues {
+                let message = format!("repeats the species first defined on line {}", first);
+                found.push((ParseWarningKind::DuplicateSpecies, message));
+            }
+        }
+        let first = seen.get(&species.name).map_or(line, |&(first, _)| first);
+        seen.insert(species.name.clone(), (first, high.map(|r| r.temp_high)));
+        for (element, _) in &species.elements {
+            if atomic_weight(element.symbol()).is_none() {
+                let message = format!("element '{}' has no known atomic weight", element);
+                found.push((ParseWarningKind::UnknownElement, message));
+            }
+        }
+        if species.source_digits.molecular_weight.is_none() {
+            let message = format!(
+                "molecular weight left blank or zero, taken as {} from the formula",
+                species.molecular_weight
+            );
+            found.push((ParseWarningKind::DefaultedField, message));
+        }
+        if let Some(tolerance) = options.snap_tolerance {
+            let policy = RepairPolicy::SnapBoundaries { tolerance };
+            // Mismatches too large to snap are left for validation to report
+            if let Ok(repairs) = species.repair_intervals(policy) {
+                for repair in repairs {
+                    found.push((ParseWarningKind::SnappedInterval, repair.to_string()));
+                }
+            }
+        }
+        if let Err(error) = check_ranges(&species.temperature_ranges) {
+            found.push((ParseWarningKind::UnorderedFits, error.to_string()));
+        }
+        warnings.extend(found.into_iter().map(|(kind, message)| ParseWarning {
+            kind,
+            species: Some(species.name.clone()),
+            line,
+            message,
+        }));
+        warnings.extend(coefficient_warnings(&text_lines, species, line));
+    }
+    warnings.sort_by_key(|warning| warning.line);
+    Ok((ParseOutcome { file, warnings }, lines))
+}
+
+// Read and parse a database file as `parse_thermo_file_with_warnings` does, noting in each
+// species where it was defined. A file named .gz is decompressed when built with flate2.
+pub fn load_thermo(path: impl AsRef<std::path::Path>) -> Result<ParseOutcome, ThermoParseError> {
+    let path = path.as_ref();
+    let io_error = |e: std::io::Error| ThermoParseError::Io {
+        message: format!("{}: {}", path.display(), e),
+    };
+    let compressed = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    let raw_text = if cfg!(feature = "flate2") && compressed {
+        read_gz(path).map_err(io_error)?
+    } else {
+        std::fs::read_to_string(path).map_err(io_error)?
+    };
+
+    let (mut outcome, lines) = parse_with_warnings(&raw_text, LenientOptions::default())?;
+    let source = path.display().to_string();
+    for (species, line) in outcome.file.species.iter_mut().zip(lines) {
+        species.provenance = Some(Provenance {
+            source: source.clone(),
+            line: u32::try_from(line).unwrap_or(u32::MAX),
+        });
+    }
+    Ok(outcome)
+}
+
+// The text of a gzip-compressed file
+#[cfg(feature = "flate2")]
+fn read_gz(path: &std::path::Path) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let mut raw_text = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(path)?).read_to_string(&mut raw_text)?;
+    Ok(raw_text)
+}
+
+#[cfg(not(feature = "flate2"))]
+fn read_gz(path: &std::path::Path) -> std::io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+// Parse the next species record at or after a byte offset into the file, returning it with
+// the offset just past it, from which the following record can be parsed. The offset must
+// start a line; blank lines, comments, END markers and the header before the record are
+// skipped, so scanning can begin at offset zero.
+pub fn parse_species_at(input: &str, offset: usize) -> Result<(Species, usize), ThermoParseError> {
+    let at_line_start = offset == 0 || input.as_bytes().get(offset - 1) == Some(&b'\n');
+    if offset > input.len() || !at_line_start {
+        return Err(ThermoParseError::InvalidOffset { offset });
+    }
+    let mut remaining = &input[offset..];
+    while !remaining.is_empty() {
+        let Ok((next_line, line)) = take_line(remaining) else {
+            break;
+        };
+        if line.trim().is_empty() || line.starts_with("END") || line.starts_with('!') {
+            remaining = next_line;
+            continue;
+        }
+        if line
+            .get(..6)
+            .is_some_and(|word| word.eq_ignore_ascii_case("thermo"))
+        {
+            remaining = parse_supported_header(remaining)
+                .map_err(|e| ThermoParseError::Header {
+                    message: describe_error(e),
+                })?
+                .0;
+            continue;
+        }
+        if !is_species_start(remaining) {
+            return Err(ThermoParseError::UnexpectedLine {
+                line: line_number(input, remaining),
+                text: line.trim_end().to_string(),
+            });
+        }
+        return match parse_species(remaining) {
+            Ok((rest, species)) => Ok((species, input.len() - rest.len())),
+            Err(e) => Err(ThermoParseError::Species {
+                name: line.split_whitespace().next().unwrap_or("").to_string(),
+                line: line_number(input, remaining),
+                message: describe_error(e),
+            }),
+        };
+    }
+    Err(ThermoParseError::EndOfInput)
+}
+
+// Energy units of an inline enthalpy ("h,kj=..."), with their size in J/mol
+const INLINE_ENTHALPY_UNITS: &[(&str, f64)] =
+    &[("j", 1.0), ("kj", 1.0e3), ("cal", 4.184), ("kcal", 4.184e3)];
+
+// Parse a reactant given with its own thermo in a CEA input deck's 'reac' dataset, such as
+//   fuel=CH4(L) wt%=100 t(k)=111.643 h,cal=-21390.0 C 1 H 4
+// into a reactant-only species. The entry names the species (after fuel=, oxid= or name=),
+// its assigned enthalpy (h,j= h,kj= h,cal= or h,kcal=, per mole) and its formula as symbol
+// and count pairs; the enthalpy is assigned at t(k)= (or t(c), t(r), t(f)), by default
+// 298.15 K. Amounts and densities are the mixture's business and are passed over. A leading
+// 'reac' keyword may be included, and the entry may run over several lines. The molecular
+// weight comes from the formula, and a phase label other than (g) marks the species condensed.
+pub fn parse_inline_species(input: &str) -> Result<Species, ThermoParseError> {
+    let mut name: Option<(String, usize)> = None;
+    let mut temperature = 298.15;
+    let mut enthalpy = None;
+    let mut elements = Composition::new();
+    let error = |name: &Option<(String, usize)>, message: String| ThermoParseError::Species {
+        name: name
+            .as_ref()
+            .map_or(String::new(), |(name, _)| name.clone()),
+        line: name.as_ref().map_or(1, |&(_, line)| line),
+        message,
+    };
+
+    let mut tokens = input.lines().enumerate().flat_map(|(line, text)| {
+        let text = text.split('!').next().unwrap_or("");
+        text.split_whitespace().map(move |token| (line + 1, token))
+    });
+    let mut first = true;
+    while let Some((line, token)) = tokens.next() {
+        if std::mem::take(&mut first) && token.eq_ignore_ascii_case("reac") {
+            continue;
+        }
+        if let Some((key, value)) = token.split_once('=') {
+            let key = key.to_ascii_lowercase();
+            let number = || {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("'{}' is not a number", value))
+            };
+            if ["fu", "ox", "na"]
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+            {
+                if name.is_some() {
+                    return Err(error(&name, "more than one reactant given".to_string()));
+                }
+                name = Some((value.to_string(), line));
+            } else if let Some(unit) = key.strip_prefix("h,") {
+                let scale = INLINE_ENTHALPY_UNITS
+                    .iter()
+                    .find(|(u, _)| *u == unit)
+                    .map(|(_, scale)| *scale)
+                    .ok_or_else(|| error(&name, format!("unknown enthalpy unit '{}'", unit)))?;
+                enthalpy = Some(number().map_err(|e| error(&name, e))? * scale);
+            } else if let Some(unit) = key.strip_prefix('t') {
+                let unit = unit.trim_matches(|c| matches!(c, '(' | ')' | ','));
+                let text = format!("{}{}", value, unit);
+                temperature = crate::units::parse_temperature(&text)
+                    .map_err(|e| error(&name, e.to_string()))?;
+            }
+            continue;
+        }
+
+        // A formula element and its count
+        let element = Element::new(token)
+            .filter(|_| atomic_weight(token).is_some())
+            .ok_or_else(|| error(&name, format!("unexpected '{}'", token)))?;
+        let count = tokens
+            .next()
+            .and_then(|(_, count)| count.parse::<f64>().ok())
+            .ok_or_else(|| error(&name, format!("no count after element '{}'", token)))?;
+        elements.push(element, count);
+    }
+
+    let Some((species_name, _)) = &name else {
+        return Err(error(&name, "no fuel=, oxid= or name= given".to_string()));
+    };
+    let Some(value) = enthalpy else {
+        return Err(error(&name, "no enthalpy given".to_string()));
+    };
+    if elements.is_empty() {
+        return Err(error(&name, "no formula given".to_string()));
+    }
+    let molecular_weight = formula_weight(&elements).unwrap_or(f64::NAN);
+    let base = crate::phases::base_name(species_name);
+    let condensed = base != species_name && !species_name.ends_with("(g)");
+    let datum = EnthalpyDatum::Assigned { value, temperature };
+    Species::new(
+        species_name.clone(),
+        elements,
+        u8::from(condensed),
+        molecular_weight,
+        datum,
+        Vec::new(),
+    )
+    .map_err(|e| error(&name, e.to_string()))
+}
+
+// Decompress a gzip stream (such as a thermo.inp.gz file) and parse it as `parse_thermo` does
+#[cfg(feature = "flate2")]
+pub fn parse_thermo_gz_reader<R: std::io::Read>(reader: R) -> Result<ThermoFile, ThermoParseError> {
+    use std::io::Read;
+
+    let mut raw_text = String::new();
+    flate2::read::GzDecoder::new(reader)
+        .read_to_string(&mut raw_text)
+        .map_err(|e| ThermoParseError::Io {
+            message: e.to_string(),
+        })?;
+    parse_thermo(&raw_text)
+}
+
+// Parse a thermo file, skipping malformed species rather than failing on the first one.
+// Every problem encountered is returned alongside the species that did parse.
+pub fn parse_thermo_lenient(input: &str) -> (ThermoFile, Vec<ThermoParseError>) {
+    let (outcome, errors) = parse_thermo_lenient_with(input, LenientOptions::default());
+    (outcome.file, errors)
+}
+
+// Repairs `parse_thermo_lenient_with` may make to the species it keeps
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LenientOptions {
+    // Snap consecutive fits together where they miss by at most this much, K
+    pub snap_tolerance: Option<f64>,
+}
+
+// As `parse_thermo_lenient`, also applying the repairs the options allow. Each boundary moved
+// is noted with a `SnappedInterval` warning and each species left with fits out of order with
+// an `UnorderedFits` one, as `parse_thermo_file_with_warnings` notes them.
+pub fn parse_thermo_lenient_with(
+    input: &str,
+    options: LenientOptions,
+) -> (ParseOutcome, Vec<ThermoParseError>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let (mut remaining, header) = match parse_supported_header(input) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            errors.push(ThermoParseError::Header {
+                message: describe_error(e),
+            });
+            (input, ThermoHeader::default())
+        }
+    };
+
+    let mut species = Vec::new();
+    while !remaining.is_empty() {
+        let Ok((next_line, line)) = take_line(remaining) else {
+            break;
+        };
+        if line.trim().is_empty() || line.starts_with("END") || line.starts_with('!') {
+            remaining = next_line;
+            continue;
+        }
+        if !is_species_start(remaining) {
+            errors.push(ThermoParseError::UnexpectedLine {
+                line: line_number(input, remaining),
+                text: line.trim_end().to_string(),
+            });
+            remaining = next_line;
+            continue;
+        }
+
+        match parse_species(remaining) {
+            Ok((rest, mut parsed)) => {
+                let line = line_number(input, remaining);
+                if let Some(tolerance) = options.snap_tolerance {
+                    let policy = RepairPolicy::SnapBoundaries { tolerance };
+                    // Mismatches too large to snap are left for validation to report
+                    if let Ok(repairs) = parsed.repair_intervals(policy) {
+                        warnings.extend(repairs.iter().map(|repair| ParseWarning {
+                            kind: ParseWarningKind::SnappedInterval,
+                            species: Some(parsed.name.clone()),
+                            line,
+                            message: repair.to_string(),
+                        }));
+                    }
+                }
+                if let Err(error) = check_ranges(&parsed.temperature_ranges) {
+                    warnings.push(ParseWarning {
+                        kind: ParseWarningKind::UnorderedFits,
+                        species: Some(parsed.name.clone()),
+                        line,
+                        message: error.to_string(),
+                    });
+                }
+                species.push(parsed);
+                remaining = rest;
+            }
+            Err(e) => {
+                errors.push(ThermoParseError::Species {
+                    name: line.split_whitespace().next().unwrap_or("").to_string(),
+                    line: line_number(input, remaining),
+                    message: describe_error(e),
+                });
+
+                // Resynchronise on the next record that looks like a species
+                remaining = next_line;
+                while !remaining.is_empty() && !is_species_start(remaining) {
+                    match take_line(remaining) {
+                        Ok((rest, _)) => remaining = rest,
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+    }
+
+    let file = ThermoFile::new(header, species);
+    (ParseOutcome { file, warnings }, errors)
+}
+
+// Parse the complete thermo file
+pub fn parse_thermo_file(input: &str) -> IResult<&str, ThermoFile> {
+    let (input, header) = parse_supported_header(input)?; // Skips leading whitespace/comments
+    let (input, species) = many0(parse_species).parse(input)?;
+    let (input, _) = multispace0(input)?; // Skip trailing content
+
+    Ok((input, ThermoFile::new(header, species)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thermo_reports_remaining_input() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        assert!(parse_thermo(&raw_text).is_ok());
+
+        let broken = raw_text.replacen("2.500000000D+00", "2.5000garbled+0", 1);
+        let snippet = "oops, not a species record\n".to_string();
+        for (input, offending) in [
+            (format!("{}{}", raw_text, snippet), "oops, not a species"),
+            (broken, "garbled"),
+        ] {
+            match parse_thermo(&input) {
+                Err(ThermoParseError::Context { remaining_snippet }) => {
+                    assert!(remaining_snippet.len() <= SNIPPET_LENGTH);
+                    assert!(
+                        remaining_snippet.contains(offending),
+                        "{:?}",
+                        remaining_snippet
+                    );
+                }
+                other => panic!("expected a context error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_scientific_d_parsing() {
+        assert_eq!(parse_scientific_d("2.500000000D+00"), Ok(("", 2.5)));
+        assert_eq!(parse_scientific_d("-7.453750000D+02"), Ok(("", -745.375)));
+        assert_eq!(
+            parse_scientific_d("1.066859930D-05"),
+            Ok(("", 1.066859930e-5))
+        );
+    }
+
+    #[test]
+    fn test_sort_species_by_name() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, mut thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        thermo_db.species.reverse();
+
+        thermo_db.sort_species_by_name();
+        let names: Vec<&str> = thermo_db.species.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["air", "e-"]);
+
+        // Whatever order the species arrive in, the written output is the same
+        let mut shuffled = thermo_db.clone();
+        shuffled.species.reverse();
+        shuffled.sort_species_by_name();
+        assert_eq!(
+            shuffled.to_cea_string().unwrap(),
+            thermo_db.to_cea_string().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sort_species_by() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, mut thermo_db) = parse_thermo_file(&raw_text).unwrap();
+
+        thermo_db.sort_species_by_name();
+
+        thermo_db.sort_species_by(|a, b| a.molecular_weight.total_cmp(&b.molecular_weight));
+        let names: Vec<&str> = thermo_db.species.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["e-", "air"]);
+    }
+
+    #[test]
+    fn test_fixed_element_fields() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let composition = |pairs: &[(&str, f64)]| {
+            let mut elements = Composition::new();
+            for &(symbol, count) in pairs {
+                elements.push(Element::new(symbol).unwrap(), count);
+            }
+            elements
+        };
+        // The air record's first two element fields, rewritten as older files have them
+        let cases = [
+            ("N   2.00O   2.00", composition(&[("N", 2.0), ("O", 2.0)])),
+            ("N      2O      2", composition(&[("N", 2.0), ("O", 2.0)])),
+            ("N 2.0000O 1.5000", composition(&[("N", 2.0), ("O", 1.5)])),
+            ("N    1.5O    2.5", composition(&[("N", 1.5), ("O", 2.5)])),
+            ("        O   2.00", composition(&[("O", 2.0)])),
+            ("AR    12N  .5000", composition(&[("AR", 12.0), ("N", 0.5)])),
+        ];
+        for (fields, expected) in cases {
+            let record = raw_text.replacen("N   2.00O   2.00", fields, 1);
+            let db = parse_thermo(&record).unwrap();
+            assert_eq!(
+                db.find_species("air").unwrap().elements,
+                expected,
+                "{}",
+                fields
+            );
+            let index = parse_index(&record);
+            assert_eq!(index[1].elements, expected, "{}", fields);
+        }
+
+        // Pairs out of their columns are read free-form
+        let shifted = "N 2.00 O 2.00";
+        assert_eq!(parse_fixed_elements(shifted), None);
+        assert_eq!(
+            parse_formula_elements(&format!("{:10}{}", "", shifted))
+                .unwrap()
+                .1,
+            composition(&[("N", 2.0), ("O", 2.0)])
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let first = parse_thermo(&raw_text).unwrap();
+        let second = parse_thermo(&raw_text).unwrap();
+        let air = first.find_species("air").unwrap();
+        assert_eq!(
+            air.content_hash(),
+            second.find_species("air").unwrap().content_hash()
+        );
+        assert_ne!(air.content_hash(), first.species[0].content_hash());
+        // Fixed for all platforms and releases, as caches on disk rely on it
+        assert_eq!(air.content_hash(), 0x0956_fc99_335a_6e8c);
+
+        // Noise in the last bits and a new description leave it alone; new data changes it
+        let mut copy = air.clone();
+        copy.temperature_ranges[0].coefficients[3] *= 1.0 + 4.0 * f64::EPSILON;
+        copy.description = "re-keyed".to_string();
+        copy.provenance = Some(Provenance {
+            source: "other.inp".to_string(),
+            line: 1,
+        });
+        assert_eq!(copy.content_hash(), air.content_hash());
+        copy.temperature_ranges[0].coefficients[3] *= 1.0 + 1e-9;
+        assert_ne!(copy.content_hash(), air.content_hash());
+        let mut renamed = air.clone();
+        renamed.name = "AIR".to_string();
+        assert_ne!(renamed.content_hash(), air.content_hash());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let db = crate::test_data::full_database();
+        let fingerprint = db.fingerprint();
+        assert_eq!(fingerprint_hex(&fingerprint).len(), 64);
+
+        // Species in another order, and the file written out and read back, hash alike
+        let mut reordered = db.clone();
+        reordered.species_mut().reverse();
+        reordered.species_mut().swap(3, 100);
+        assert_eq!(reordered.fingerprint(), fingerprint);
+        assert_ne!(reordered.layout(), db.layout());
+        assert_eq!(db.clone().layout(), db.layout());
+        let rewritten = parse_thermo(&db.to_cea_string().unwrap()).unwrap();
+        assert_eq!(rewritten.fingerprint(), fingerprint);
+
+        // Any change to one coefficient, however small, or to the header does not
+        let mut patched = db.clone();
+        let a4 = patched.species[10].coefficient(0, 3).unwrap();
+        patched
+            .patch(&db.species[10].name, 0, 3, a4 * (1.0 + f64::EPSILON))
+            .unwrap();
+        assert_ne!(patched.fingerprint(), fingerprint);
+        let mut redated = db.clone();
+        redated.header_mut().date = "1/01/99".to_string();
+        assert_ne!(redated.fingerprint(), fingerprint);
+
+        // The fingerprint is kept, and dropped by every edit
+        let mut merged = db.clone();
+        assert_eq!(merged.fingerprint(), fingerprint);
+        merged.merge(redated.subset(&["O2"]));
+        assert_eq!(merged.fingerprint(), merged.compute_identity().0);
+        let before = merged.fingerprint();
+        merged.species_mut()[0].name = "renamed".to_string();
+        assert_ne!(merged.fingerprint(), before);
+        assert_eq!(merged.fingerprint(), merged.compute_identity().0);
+        let before = merged.fingerprint();
+        merged.header_mut().temp_ranges[3] = 30000.0;
+        assert_ne!(merged.fingerprint(), before);
+    }
+
+    #[test]
+    fn test_trailing_notes_on_coefficient_lines() {
+        // Append a note to every coefficient line, each padded out to a width first
+        let annotate = |text: &str, width: usize, note: &str| -> String {
+            let lines: Vec<String> = text
+                .lines()
+                .map(|line| match line.contains("D+") || line.contains("D-") {
+                    true => format!("{:width$}{}", line, note),
+                    false => line.to_string(),
+                })
+                .collect();
+            lines.join("\n") + "\n"
+        };
+        let fits = |db: &ThermoFile| -> Vec<_> {
+            let ranges = db.species.iter().flat_map(|s| s.temperature_ranges());
+            ranges.map(TemperatureRange::named_values).collect()
+        };
+        let same_fits = |a: &ThermoFile, b: &ThermoFile| fits(a) == fits(b);
+
+        // A fixed-column file's data ends at column 80, or at a '!' before it
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let expected = parse_thermo(&raw_text).unwrap();
+        for note in ["refit 2024-03 by hand", " ! 1.5e3 K check"] {
+            let db = parse_thermo(&annotate(&raw_text, 80, note)).unwrap();
+            assert!(same_fits(&db, &expected), "{}", note);
+        }
+        // A loose file's lines may run past it, their data ending at the first word or '!'
+        let loose = parse_thermo_with(LOOSE_AIR, ParseMode::Whitespace).unwrap();
+        for note in [" refit 2024-03 by hand", " ! 1.5e3 K check", "!1.5e3"] {
+            let annotated = annotate(LOOSE_AIR, 0, note);
+            let db = parse_thermo_with(&annotated, ParseMode::Whitespace).unwrap();
+            assert!(same_fits(&db, &loose), "{}", note);
+        }
+        let corrupted = LOOSE_AIR.replacen("1.009950160D+04", "1.00995O160D+04", 1);
+        assert!(parse_thermo_with(&corrupted, ParseMode::Whitespace).is_err());
+    }
+
+    #[test]
+    fn test_species_with_element() {
+        let mut thermo_db = crate::test_data::full_database().clone();
+        let kept = ["CH4", "CO", "H2O", "CO2", "N2", "C(gr)", "e-", "O2-"];
+        thermo_db
+            .species
+            .retain(|species| kept.contains(&species.name.as_str()));
+        let names = |symbol| -> Vec<String> {
+            let species = thermo_db.species_with_element(symbol);
+            species.iter().map(|s| s.name.clone()).collect()
+        };
+
+        let carbon = names("C");
+        assert_eq!(carbon.len(), 4);
+        assert!(
+            ["CH4", "CO", "CO2", "C(gr)"]
+                .iter()
+                .all(|name| carbon.contains(&name.to_string()))
+        );
+        assert_eq!(names("c"), carbon);
+        assert_eq!(names("e"), names("E"));
+        assert_eq!(names("E").len(), 2);
+        assert!(names("Ar").is_empty());
+        assert!(names("").is_empty());
+    }
+
+    #[test]
+    fn test_strict_parse() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let strict = parse_thermo_strict(&raw_text).unwrap();
+        assert_eq!(
+            strict.fingerprint(),
+            parse_thermo(&raw_text).unwrap().fingerprint()
+        );
+        let full = std::fs::read_to_string("thermo.inp").unwrap();
+        assert_eq!(parse_thermo_strict(&full).unwrap().species.len(), 2088);
+
+        // Each of these reads with the default parser, but strays from the layout
+        let air_line = |line: &str| format!("line 14: invalid species 'air': {}", line);
+        let cases = [
+            (
+                raw_text.replacen("1.009950160D+04", "1.009950160E+04", 1),
+                air_line("fit 0 coefficient line 1, columns 1-16: ' 1.009950160E+04' is not D16.9"),
+            ),
+            (
+                raw_text.replacen("-3.921504225D+00", "-3.921504225D+00 ! refit", 1),
+                air_line("fit 0 coefficient line 2: runs past column 80"),
+            ),
+            (
+                raw_text.replacen("   28.9600000", "             ", 1),
+                air_line("formula line, columns 53-65: '             ' is not F13.7"),
+            ),
+            (
+                raw_text.replacen("N   2.00O   2.00", "N 2.00 O 2.00   ", 1),
+                air_line("formula line, columns 11-50: not five (A2, F6.2) fields"),
+            ),
+            (
+                raw_text.replacen("7 -2.0", "7   -2", 1),
+                "line 3: invalid species 'e-': fit 0 temperature line, columns 24-28: '   -2' \
+                 is not F5.1"
+                    .to_string(),
+            ),
+            (
+                raw_text.replacen("thermo", "THERMO", 1),
+                "invalid header: expected 'thermo' alone, found 'THERMO".to_string(),
+            ),
+        ];
+        for (text, expected) in cases {
+            assert!(parse_thermo(&text).is_ok(), "{}", expected);
+            let error = parse_thermo_strict(&text).unwrap_err().to_string();
+            assert!(error.starts_with(&expected), "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_lenient_parse_skips_bad_species() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        let good = thermo_db.species[1].to_cea_string().unwrap();
+
+        // A coefficient line with a corrupted field in the middle species
+        let mut broken = good.replacen("air", "broken", 1);
+        broken = broken.replacen("5.009155110D+00", "5.0091X5110D+00", 1);
+
+        let input = format!(
+            "{}{}{}{}END PRODUCTS\n",
+            thermo_db.header.to_cea_string(),
+            good.replacen("air", "first", 1),
+            broken,
+            good.replacen("air", "last", 1),
+        );
+
+        let (parsed, errors) = parse_thermo_lenient(&input);
+        let names: Vec<&str> = parsed.species.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["first", "last"]);
+
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ThermoParseError::Species { name, line, .. } => {
+                assert_eq!(name, "broken");
+                assert_eq!(*line, 11);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lenient_parse_of_valid_file_has_no_errors() {
+        let raw_text = std::fs::read_to_string("tests/reference/thermo.inp").unwrap();
+        let (_, strict) = parse_thermo_file(&raw_text).unwrap();
+        let (lenient, errors) = parse_thermo_lenient(&raw_text);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(lenient.species.len(), strict.species.len());
+    }
+
+    #[test]
+    fn test_fewer_than_seven_coefficients() {
+        // A condensed-phase fit using only the a1..a5 terms, with junk in the padding slots
+        let input = concat!(
+            "Test(cr)          Five-term fit.\n",
+            " 1 g 1/00 AL  2.00O   3.00    0.00    0.00    0.00 1  101.9612800   -1675700.000\n",
+            "    298.150   2327.0005 -2.0 -1.0  0.0  1.0  2.0  0.0  0.0  0.0         10016.000\n",
+            " 1.000000000D+05-2.000000000D+02 1.200000000D+01 1.000000000D-03 2.000000000D-07\n",
+            " 9.999999999D+09 9.999999999D+09                -2.000000000D+05-6.000000000D+01\n",
+        );
+        let (_, species) = parse_species(input).unwrap();
+        let range = &species.temperature_ranges[0];
+
+        assert_eq!(range.used_coefficients, 5);
+        assert_eq!(range.coefficients[4], 2.0e-7);
+        assert_eq!(range.coefficients[5], 0.0);
+        assert_eq!(range.coefficients[6], 0.0);
+        assert_eq!(range.integration_constants, [-2.0e5, -60.0]);
+
+        let t: f64 = 1000.0;
+        let expected = 1.0e5 / (t * t) - 200.0 / t + 12.0 + 1.0e-3 * t + 2.0e-7 * t * t;
+        assert!((range.cp_over_r(t).unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_missing_molecular_weight() {
+        let record = |formula_line: &str| {
+            format!(
+                "{}\n{}\n{}\n{}\n{}\n",
+                "CO2               Blank molecular weight.",
+                formula_line,
+                "    200.000   1000.0007 -2.0 -1.0  0.0  1.0  2.0  3.0  4.0  0.0         9365.469",
+                " 4.943650540D+04-6.264116010D+02 5.301725240D+00 2.503813816D-03-2.127308728D-07",
+                "-7.689988780D-10 2.849677801D-13                -4.528198460D+04-7.048279440D+00",
+            )
+        };
+
+        // Only the heat of formation, in its own field or shifted into the weight's columns
+        for line in [
+            " 1 g 9/99 C   1.00O   2.00    0.00    0.00    0.00 0                 -393510.000",
+            " 1 g 9/99 C   1.00O   2.00    0.00    0.00    0.00 0  -393510.000",
+        ] {
+            let (_, species) = parse_species(&record(line)).unwrap();
+            assert_eq!(
+                species.enthalpy_datum,
+                EnthalpyDatum::FormationAt298 { value: -393510.0 }
+            );
+            assert!((species.molecular_weight - 44.0095).abs() < 1e-9);
+        }
+
+        // Both constants present are read from their columns as before
+        let line =
+            " 1 g 9/99 C   1.00O   2.00    0.00    0.00    0.00 0   44.0095000    -393510.000";
+        let (_, species) = parse_species(&record(line)).unwrap();
+        assert_eq!(species.molecular_weight, 44.0095);
+        assert_eq!(
+            species.enthalpy_datum,
+            EnthalpyDatum::FormationAt298 { value: -393510.0 }
+        );
+    }
+
+    #[test]
+    fn test_electron() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let record: Vec<&str> = raw_text.lines().skip(2).take(11).collect();
+        let record = record.join("\n") + "\n";
+        let (_, electron) = parse_species(&record).unwrap();
+        assert_eq!(electron.name, "e-");
+        assert_eq!((electron.charge(), electron.is_ion()), (-1.0, true));
+        assert_eq!(electron.molecular_weight, 0.000548579903);
+        assert_eq!(electron.source_digits.molecular_weight, Some(9));
+
+        // A monatomic gas without internal structure: Cp = 5/2 R over its whole span, the
+        // enthalpy rising as 5/2 R T from the datum of its fits
+        for t in [298.15, 1000.0, 5000.0, 20000.0] {
+            let cp = electron.cp(t).unwrap();
+            assert!(
+                (cp - 2.5 * crate::properties::GAS_CONSTANT).abs() < 1e-12,
+                "cp at {}: {}",
+                t,
+                cp
+            );
+        }
+        let rise = electron.enthalpy(5000.0).unwrap() - electron.enthalpy(1000.0).unwrap();
+        assert!((rise - 2.5 * crate::properties::GAS_CONSTANT * 4000.0).abs() < 1e-6);
+
+        // Listed with a weight of zero, or none, it takes the electron's mass from its formula
+        for weight in ["0.000000000000", "0.0", "            "] {
+            let zeroed = record.replacen("0.000548579903", &format!("{:>14}", weight), 1);
+            let (_, species) = parse_species(&zeroed).unwrap();
+            assert_eq!(species.molecular_weight, 5.485_799_03e-4, "'{}'", weight);
+            assert_eq!(species.source_digits.molecular_weight, None);
+            assert_eq!(species.charge(), -1.0);
+            assert_eq!(species.cp(1000.0), electron.cp(1000.0));
+        }
+
+        // Its mass, though slight, counts: a kilogram of electrons is 1/5.486e-4 kg-mol of them
+        let file: ThermoFile = [electron].into_iter().collect();
+        let mixture = crate::mixture::Mixture::from_names(&file, &[("e-", 1.0)]).unwrap();
+        let per_kg = mixture.element_moles_per_kg(&file).unwrap();
+        assert!((per_kg["E"] * 5.485_799_03e-4 - 1.0).abs() < 1e-9);
+        assert!(mixture.cp_mass(&file, 1000.0).unwrap().is_finite());
+    }
+
+    #[test]
+    fn test_nonstandard_exponents_rejected() {
+        let input = concat!(
+            "Test(cr)          Odd exponents.\n",
+            " 1 g 1/00 AL  2.00O   3.00    0.00    0.00    0.00 1  101.9612800   -1675700.000\n",
+            "    298.150   2327.0003  0.0  1.0  2.0  0.0  0.0  0.0  0.0  0.0         10016.000\n",
+            " 1.000000000D+01 1.000000000D-03 2.000000000D-07 0.000000000D+00 0.000000000D+00\n",
+            " 0.000000000D+00 0.000000000D+00                -2.000000000D+05-6.000000000D+01\n",
+        );
+        assert!(parse_species(input).is_err());
+    }
+
+    #[test]
+    fn test_reactant_only_records() {
+        let input = concat!(
+            "RP-1              Mehta et.al. AIAA 95-2962 1995. Hcomb(high) = 19923.BTU/#     \n",
+            " 0 gll/00 C   1.00H   1.95    0.00    0.00    0.00 1   13.9761830     -24717.700\n",
+            "    298.150      0.0000  0.0  0.0  0.0  0.0  0.0  0.0  0.0  0.0            0.000\n",
+            "O3(L)             Liquid ozone.\n",
+            " 0 g 6/96 O   3.00    0.00    0.00    0.00    0.00 1   47.9982000     122527.000\n",
+            "    161.850      0.0000  0.0  0.0  0.0  0.0  0.0  0.0  0.0  0.0            0.000\n",
+        );
+        let (rest, rp1) = parse_species(input).unwrap();
+        assert!(rp1.temperature_ranges.is_empty());
+        assert_eq!(
+            rp1.enthalpy_datum,
+            EnthalpyDatum::Assigned {
+                value: -24717.7,
+                temperature: 298.15
+            }
+        );
+        let (_, ozone) = parse_species(rest).unwrap();
+        assert_eq!(
+            ozone.enthalpy_datum,
+            EnthalpyDatum::Assigned {
+                value: 122527.0,
+                temperature: 161.85
+            }
+        );
+
+        // The enthalpy is known at the assigned temperature alone
+        assert!((rp1.enthalpy(298.15).unwrap() + 24717.7).abs() < 1e-6);
+        assert_eq!(rp1.enthalpy(300.0), None);
+
+        // With the reactant section readable, the whole shipped database parses strictly
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let db = parse_thermo(&raw_text).unwrap();
+        let rp1 = db.find_species("RP-1").unwrap();
+        assert!(matches!(rp1.enthalpy_datum, EnthalpyDatum::Assigned { .. }));
+        let water = db.find_species("H2O").unwrap();
+        assert!(matches!(
+            water.enthalpy_datum,
+            EnthalpyDatum::FormationAt298 { .. }
+        ));
+    }
+
+    #[test]
+    fn test_species_accessors_and_constructor() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let thermo_db = parse_thermo(&raw_text).unwrap();
+        let air = thermo_db.find_species("air").unwrap();
+        let bounds: Vec<(f64, f64)> = air
+            .temperature_ranges()
+            .iter()
+            .map(|range| (range.temp_low, range.temp_high))
+            .collect();
+        assert_eq!(bounds, [(200.0, 1000.0), (1000.0, 6000.0)]);
+        assert_eq!(
+            air.enthalpy_datum(),
+            EnthalpyDatum::FormationAt298 { value: 0.0 }
+        );
+
+        // Rebuilding from the accessors gives the same record
+        let rebuilt = Species::new(
+            air.name.clone(),
+            air.elements.clone(),
+            air.phase,
+            air.molecular_weight,
+            air.enthalpy_datum(),
+            air.temperature_ranges().to_vec(),
+        )
+        .unwrap();
+        assert_eq!(rebuilt.temperature_ranges().len(), 2);
+        assert_eq!(rebuilt.cp(500.0), air.cp(500.0));
+
+        let build = |datum, ranges: Vec<TemperatureRange>| {
+            Species::new("X", Composition::new(), 0, 1.0, datum, ranges)
+        };
+        let formation = EnthalpyDatum::FormationAt298 { value: 0.0 };
+        let assigned = EnthalpyDatum::Assigned {
+            value: 0.0,
+            temperature: 298.15,
+        };
+        let mut reversed = air.temperature_ranges().to_vec();
+        reversed.reverse();
+        assert_eq!(
+            build(formation, reversed).unwrap_err(),
+            SpeciesError::UnorderedRanges { range: 1 }
+        );
+        let mut inverted = air.temperature_ranges().to_vec();
+        inverted[0].temp_high = 100.0;
+        assert_eq!(
+            build(formation, inverted).unwrap_err(),
+            SpeciesError::InvalidRange { range: 0 }
+        );
+        assert_eq!(
+            build(assigned, air.temperature_ranges().to_vec()).unwrap_err(),
+            SpeciesError::DatumMismatch
+        );
+        assert_eq!(
+            build(formation, Vec::new()).unwrap_err(),
+            SpeciesError::DatumMismatch
+        );
+        assert!(build(assigned, Vec::new()).is_ok());
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_parse_gzip_compressed() {
+        use std::io::Write;
+
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(raw_text.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let from_gzip = parse_thermo_gz_reader(compressed.as_slice()).unwrap();
+        let plain = parse_thermo(&raw_text).unwrap();
+        let names = |db: &ThermoFile| -> Vec<String> {
+            db.species.iter().map(|s| s.name.clone()).collect()
+        };
+        assert_eq!(names(&from_gzip), names(&plain));
+
+        // Plain text isn't a gzip stream
+        let error = parse_thermo_gz_reader(raw_text.as_bytes()).unwrap_err();
+        assert!(matches!(error, ThermoParseError::Io { .. }));
+    }
+
+    #[test]
+    fn test_lenient_interval_repair() {
+        let raw_text = crate::test_data::mismatched_snippet();
+        let (db, problems) = parse_thermo_lenient(&raw_text);
+        assert!(problems.is_empty());
+        assert_eq!(db.species[0].temperature_ranges()[1].temp_low, 1000.01);
+
+        let options = LenientOptions {
+            snap_tolerance: Some(1.0),
+        };
+        let (outcome, problems) = parse_thermo_lenient_with(&raw_text, options);
+        assert!(problems.is_empty());
+        let repaired: Vec<(ParseWarningKind, Option<&str>, usize)> = outcome
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.species.as_deref(), w.line))
+            .collect();
+        assert_eq!(
+            repaired,
+            [
+                (ParseWarningKind::SnappedInterval, Some("e-"), 3),
+                (ParseWarningKind::SnappedInterval, Some("air"), 14)
+            ]
+        );
+        assert!(outcome.warnings[0].message.contains("both now meet at"));
+        let db = outcome.file;
+        assert!(db.species.iter().all(|s| s.check_continuity().is_empty()));
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        // The mismatched snippet with a comment, an element with no atomic weight, a blank
+        // molecular weight and the unaltered electron record repeated at the end
+        let snippet = crate::test_data::mismatched_snippet();
+        let lines: Vec<&str> = snippet.lines().collect();
+        let electron = lines[2..13].join("\n");
+        let messy = snippet
+            .replacen("9/09/04", "9/09/04\n! hand edited", 1)
+            .replacen("E   1.00    0.00", "E   1.00XX  0.00", 1)
+            .replacen("   28.9600000", &" ".repeat(13), 1)
+            .replacen("END REACTANTS", &format!("{}\nEND REACTANTS", electron), 1);
+
+        let options = LenientOptions {
+            snap_tolerance: Some(1.0),
+        };
+        let outcome = parse_thermo_file_with_warnings(&messy, options).unwrap();
+        assert_eq!(outcome.file.species.len(), 3);
+        use ParseWarningKind::*;
+        let found: Vec<(ParseWarningKind, Option<&str>, usize)> = outcome
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.species.as_deref(), w.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (SkippedComment, None, 3),
+                (UnknownElement, Some("e-"), 4),
+                (SnappedInterval, Some("e-"), 4),
+                (DefaultedField, Some("air"), 15),
+                (SnappedInterval, Some("air"), 15),
+                (DuplicateSpecies, Some("e-"), 23),
+                (SnappedInterval, Some("e-"), 23),
+            ]
+        );
+        assert_eq!(
+            outcome.warnings[5].to_string(),
+            "line 23: species 'e-': repeats the species first defined on line 4"
+        );
+        assert_eq!(
+            outcome.warnings[0].to_string(),
+            "line 3: skipped comment '! hand edited'"
+        );
+        let air = &outcome.file.species[1];
+        assert!((air.molecular_weight - 2.0 * (14.0067 + 15.9994)).abs() < 1e-2);
+
+        // Without snapping the fits are kept as written, and a clean file raises nothing
+        let outcome = parse_thermo_file_with_warnings(&messy, LenientOptions::default()).unwrap();
+        assert!(outcome.warnings.iter().all(|w| w.kind != SnappedInterval));
+        let clean = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let outcome = parse_thermo_file_with_warnings(&clean, options).unwrap();
+        assert!(outcome.warnings.is_empty());
+
+        // Anything unreadable is still an error
+        let broken = messy.replacen("6197.428", "6197.4x8", 1);
+        assert!(parse_thermo_file_with_warnings(&broken, options).is_err());
+    }
+
+    // Air's second fit starting 100 K inside its first, too far to snap
+    #[test]
+    fn test_unordered_fits_warned() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let raw_text = raw_text.replacen(
+            "   1000.000   6000.0007 -2.0 -1.0  0.0  1.0  2.0  3.0  4.0  0.0         8672.434",
+            "    900.000   6000.0007 -2.0 -1.0  0.0  1.0  2.0  3.0  4.0  0.0         8672.434",
+            1,
+        );
+        let options = LenientOptions {
+            snap_tolerance: Some(1.0),
+        };
+        let outcome = parse_thermo_file_with_warnings(&raw_text, options).unwrap();
+        let (lenient, problems) = parse_thermo_lenient_with(&raw_text, options);
+        assert!(problems.is_empty());
+        for warnings in [&outcome.warnings, &lenient.warnings] {
+            assert_eq!(
+                warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+                ["line 14: species 'air': fit 1 overlaps or precedes the fit before it"]
+            );
+            assert_eq!(warnings[0].kind, ParseWarningKind::UnorderedFits);
+        }
+    }
+
+    #[test]
+    fn test_coefficients_beyond_f64() {
+        assert_eq!(classify(9.999999999e99), NumberClass::Finite);
+        assert_eq!(classify(0.0), NumberClass::Finite);
+        assert_eq!(classify(f64::INFINITY), NumberClass::Overflow);
+        assert_eq!(classify(1e-310), NumberClass::Underflow);
+        assert_eq!(parse_float("1.0D+310").unwrap().1, f64::INFINITY);
+        assert_eq!(parse_float("-1.0D-320").unwrap().1, 0.0);
+
+        // Air's second fit overflows in a2, and its first underflows in a2
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let raw_text = raw_text
+            .replacen("-1.257874600D+03", "-1.25787460D+310", 1)
+            .replacen("-1.968275610D+02", "-1.96827561D-320", 1);
+        let outcome =
+            parse_thermo_file_with_warnings(&raw_text, LenientOptions::default()).unwrap();
+        let found: Vec<(ParseWarningKind, usize)> =
+            outcome.warnings.iter().map(|w| (w.kind, w.line)).collect();
+        assert_eq!(
+            found,
+            [
+                (ParseWarningKind::CoefficientUnderflow, 17),
+                (ParseWarningKind::CoefficientOverflow, 20),
+            ]
+        );
+        assert_eq!(
+            outcome.warnings[1].message,
+            "fit 1 a2 '-1.25787460D+310' is beyond f64, so the fit can't be evaluated"
+        );
+
+        let air = &outcome.file.species[1];
+        assert_eq!(air.temperature_ranges[0].coefficients[1], 0.0);
+        assert_eq!(air.temperature_ranges[1].coefficients[1], f64::NEG_INFINITY);
+        assert_eq!(
+            air.check_coefficients(),
+            [crate::validation::IssueKind::NonFiniteCoefficient {
+                range: 1,
+                values: vec!["a2"]
+            }]
+        );
+        assert!(air.cp_over_r(500.0).unwrap().is_finite());
+        assert!(!air.cp_over_r(2000.0).unwrap().is_finite());
+
+        // The database names the fit, the solver refuses it, and the nearest other fit stands in
+        let (species, fit) = outcome.file.nonfinite_fit().unwrap();
+        assert_eq!((species.name.as_str(), fit), ("air", 1));
+        let mixture = crate::mixture::Mixture::from_names(&outcome.file, &[("air", 1.0)]);
+        let problem = crate::equilibrium::Problem::TP {
+            temperature: 2000.0,
+            pressure: 1.0e5,
+        };
+        let options = crate::equilibrium::EquilibriumOptions::default();
+        let result = crate::equilibrium::solve_equilibrium(
+            &outcome.file,
+            &mixture.unwrap(),
+            problem,
+            &options,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "fit 1 of 'air' holds a NaN or infinity, so the database can't be solved with"
+        );
+        let nearest = crate::phases::nearest_range(air, 2000.0).unwrap();
+        assert_eq!(nearest.temp_high, 1000.0);
+        let mut repaired = outcome.file.clone();
+        repaired.species_mut()[1].temperature_ranges[1].coefficients[1] = -1.2578746e3;
+        assert!(repaired.nonfinite_fit().is_none());
+    }
+
+    #[test]
+    fn test_scan_species_names() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let full = parse_thermo(&raw_text).unwrap();
+        let names: Vec<&str> = full.species.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(scan_species_names(&raw_text), names);
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let full = parse_thermo(&raw_text).unwrap();
+        let index = parse_index(&raw_text);
+        assert_eq!(index.len(), full.species.len());
+        for (entry, species) in index.iter().zip(&full.species) {
+            assert_eq!(entry.name, species.name);
+            assert_eq!(entry.elements, species.elements);
+            assert_eq!(entry.phase, species.phase);
+            assert_eq!(entry.interval_count, species.temperature_ranges().len());
+        }
+
+        // Each entry locates its record exactly
+        for entry in [&index[0], &index[1000], &index[index.len() - 1]] {
+            let (species, end) = parse_species_at(&raw_text, entry.byte_range.start).unwrap();
+            assert_eq!(species.name, entry.name);
+            assert_eq!(end, entry.byte_range.end);
+            let name_line = raw_text.lines().nth(entry.line - 1).unwrap();
+            assert!(name_line.starts_with(&entry.name));
+        }
+        let air = index.iter().find(|entry| entry.name == "Air").unwrap();
+        assert_eq!(air.fitted_range, Some((200.0, 6000.0)));
+        let reactant = index
+            .iter()
+            .find(|entry| entry.interval_count == 0)
+            .unwrap();
+        assert_eq!(reactant.fitted_range, None);
+    }
+
+    #[test]
+    fn test_provenance_through_merge_and_subset() {
+        let mut db = load_thermo("thermo.inp").unwrap().file;
+        let water = db.find_species("H2O").unwrap();
+        assert_eq!(
+            water.provenance,
+            Some(Provenance {
+                source: "thermo.inp".to_string(),
+                line: 5703
+            })
+        );
+        let liquid_records = db.species.iter().filter(|s| s.name == "H2O(L)").count();
+
+        // The later file's records win, and the rest keep where they came from
+        let count = db.species.len();
+        db.merge(load_thermo("thermo-snippet.inp").unwrap().file);
+        assert_eq!(db.species.len(), count);
+        let provenance = |name| db.find_species(name).unwrap().provenance.clone().unwrap();
+        assert_eq!(provenance("e-").to_string(), "thermo-snippet.inp:3");
+        assert_eq!(provenance("air").to_string(), "thermo-snippet.inp:14");
+        assert_eq!(provenance("Air").to_string(), "thermo.inp:15404");
+
+        let subset = db.subset(&["H2O(L)", "air"]);
+        assert_eq!(subset.species.len(), liquid_records + 1);
+        assert_eq!(
+            subset.find_species("air").unwrap().provenance,
+            Some(provenance("air"))
+        );
+
+        // A species parsed from text has none, and a missing file is an I/O error
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        assert_eq!(parse_thermo(&raw_text).unwrap().species[0].provenance, None);
+        assert!(matches!(
+            load_thermo("no-such-file.inp"),
+            Err(ThermoParseError::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_dedup_species() {
+        let db = crate::test_data::database_with_reactants();
+        let pick = |name| db.find_species(name).unwrap().clone();
+        let (water, oxygen) = (pick("H2O"), pick("O2"));
+        let iron: Vec<Species> = db
+            .species
+            .iter()
+            .filter(|species| species.name == "Fe(a)")
+            .cloned()
+            .collect();
+        assert!(iron.len() > 1);
+
+        // A copy with another description and a last-digit difference is a repeat; a copy
+        // with a different heat of formation conflicts with the original
+        let mut copy = water.clone();
+        copy.description = "copied".to_string();
+        copy.temperature_ranges[0].coefficients[2] *= 1.0 + 1e-12;
+        let mut altered = oxygen.clone();
+        altered.enthalpy_datum = EnthalpyDatum::FormationAt298 { value: 1000.0 };
+        let mut species = vec![water.clone(), oxygen.clone()];
+        species.extend(iron.iter().cloned());
+        species.extend([copy, altered.clone(), oxygen.clone()]);
+        species.extend(iron.iter().cloned());
+        let mut merged = ThermoFile::new(db.header.clone(), species);
+
+        // Condensed records continuing one another are neither removed nor flagged
+        assert_eq!(merged.dedup_species(1e-9), ["O2"]);
+        let mut expected = vec![water.clone(), oxygen.clone()];
+        expected.extend(iron.iter().cloned());
+        expected.push(altered);
+        let json = |species: &[Species]| species.iter().map(Species::to_json).collect::<Vec<_>>();
+        assert_eq!(json(&merged.species), json(&expected));
+
+        // Nothing is left to remove a second time, and a tolerance of zero keeps a near copy
+        assert_eq!(merged.dedup_species(1e-9), ["O2"]);
+        assert_eq!(merged.species.len(), expected.len());
+        let mut copy = water.clone();
+        copy.molecular_weight += 1e-9;
+        merged.species.push(copy);
+        assert_eq!(merged.dedup_species(0.0), ["H2O", "O2"]);
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn test_species_names_mmap() {
+        let names = ThermoFile::species_names_mmap("thermo-snippet.inp").unwrap();
+        assert_eq!(names, ["e-", "air"]);
+        assert!(ThermoFile::species_names_mmap("no-such-file.inp").is_err());
+    }
+
+    // The snippet's air record with its fields loosely spaced, as a hand-edited file might have
+    // them: counts apart from their temperatures, an E exponent and a two-word reference code
+    const LOOSE_AIR: &str = "thermo
+ 200.0 1000.0 6000.0 20000.0 9/09/04
+air GDTk, 2024 (NNG)
+2 g 9/09 N 2.00 O 2.00 0 28.9600000 0.000
+200.000 1000.000 7 -2.0 -1.0 0.0 1.0 2.0 3.0 4.0 0.0 8672.434
+1.009950160D+04 -1.968275610D+02 5.009155110D+00 -5.761013730D-03 1.066859930D-05
+-7.940297970D-09 2.185231910D-12 -1.767967310E+02 -3.921504225D+00
+1000.000   6000.000  7 -2.0 -1.0 0.0 1.0 2.0 3.0 4.0 8672.434
+  2.415214430D+05 -1.257874600D+03 5.144558670D+00 -2.138541790D-04 7.065227840D-08
+  -1.071483490D-11 6.577800150D-16 6.462263190D+03 -8.147411905D+00
+END PRODUCTS
+";
+
+    #[test]
+    fn test_parse_modes() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let fixed = parse_thermo(&raw_text).unwrap();
+        assert_eq!(sniff_mode(&raw_text), ParseMode::FixedColumn);
+        let sniffed = parse_thermo_with(&raw_text, ParseMode::Auto).unwrap();
+        assert_eq!(
+            sniffed.to_cea_string().unwrap(),
+            fixed.to_cea_string().unwrap()
+        );
+
+        // The loose record holds the same species as the fixed one
+        assert_eq!(sniff_mode(LOOSE_AIR), ParseMode::Whitespace);
+        assert!(parse_thermo(LOOSE_AIR).is_err());
+        for mode in [ParseMode::Whitespace, ParseMode::Auto] {
+            let loose = parse_thermo_with(LOOSE_AIR, mode).unwrap();
+            let (air, expected) = (&loose.species[0], &fixed.species[1]);
+            assert_eq!(loose.header.temp_ranges, fixed.header.temp_ranges);
+            assert_eq!(air.reference_code, "g 9/09");
+            assert_eq!(air.elements, expected.elements);
+            assert_eq!(air.molecular_weight, expected.molecular_weight);
+            assert_eq!(air.enthalpy_datum, expected.enthalpy_datum);
+            let ranges = air
+                .temperature_ranges()
+                .iter()
+                .map(TemperatureRange::to_cea_string);
+            let expected = expected
+                .temperature_ranges()
+                .iter()
+                .map(TemperatureRange::to_cea_string);
+            assert!(ranges.eq(expected));
+        }
+
+        // A reactant-only record, and a formula line missing its heat of formation
+        let reactant = "thermo\n200 1000 6000 20000 9/09/04\nRP-1 test\n\
+                        0 g 6/88 C 1.00 H 1.9423 0 13.9661 -24717.700\n298.15 0.0 0.0\n";
+        let rp1 = &parse_thermo_with(reactant, ParseMode::Auto)
+            .unwrap()
+            .species[0];
+        let assigned = EnthalpyDatum::Assigned {
+            value: -24717.7,
+            temperature: 298.15,
+        };
+        assert_eq!(rp1.enthalpy_datum, assigned);
+        let truncated = reactant.replace(" -24717.700", "");
+        assert!(parse_thermo_with(&truncated, ParseMode::Whitespace).is_err());
+    }
+
+    #[test]
+    fn test_parse_species_at() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let full = parse_thermo(&raw_text).unwrap();
+
+        // Resuming from each returned offset walks the records in file order
+        let (first, offset) = parse_species_at(&raw_text, 0).unwrap();
+        let (second, next) = parse_species_at(&raw_text, offset).unwrap();
+        assert_eq!(first.name, full.species[0].name);
+        assert_eq!(second.name, full.species[1].name);
+        assert_eq!(
+            second.to_cea_string().unwrap(),
+            full.species[1].to_cea_string().unwrap()
+        );
+        assert!(raw_text[offset..next].starts_with(&second.name));
+
+        let mut offset = 0;
+        let mut count = 0;
+        while let Ok((_, next)) = parse_species_at(&raw_text, offset) {
+            offset = next;
+            count += 1;
+        }
+        assert_eq!(count, full.species.len());
+        assert_eq!(
+            parse_species_at(&raw_text, offset).err(),
+            Some(ThermoParseError::EndOfInput)
+        );
+        assert_eq!(
+            parse_species_at(&raw_text, 3).err(),
+            Some(ThermoParseError::InvalidOffset { offset: 3 })
+        );
+        assert!(matches!(
+            parse_species_at(&raw_text, raw_text.len() + 1),
+            Err(ThermoParseError::InvalidOffset { .. })
+        ));
+    }
+
+    #[test]
+    fn test_collect_species() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let db = parse_thermo(&raw_text).unwrap();
+        let water = db.find_species("H2O(L)").unwrap().clone();
+        let methane = parse_inline_species("fuel=CH4(L) t(k)=111.643 h,kj=-89.233 C 1 H 4");
+        let collected: ThermoFile = [water.clone(), methane.unwrap()].into_iter().collect();
+        assert_eq!(collected.species.len(), 2);
+        assert_eq!(collected.species[1].name, "CH4(L)");
+        assert!(collected.find_species("H2O(L)").is_some());
+        assert_eq!(collected.header.temp_ranges, STANDARD_TEMP_RANGES);
+        assert_eq!(collected.header.date, "");
+
+        // The written file reads back the same
+        let reread = parse_thermo(&collected.to_cea_string().unwrap()).unwrap();
+        assert_eq!(reread.species.len(), 2);
+        assert_eq!(reread.species[0].to_json(), collected.species[0].to_json());
+
+        // A fit reaching below 200 K lowers the header's first temperature
+        let mut supercooled = water;
+        supercooled.temperature_ranges[0].temp_low = 150.0;
+        let collected: ThermoFile = std::iter::once(supercooled).collect();
+        assert_eq!(
+            collected.header.temp_ranges,
+            [150.0, 1000.0, 6000.0, 20000.0]
+        );
+    }
+
+    #[test]
+    fn test_parse_inline_species() {
+        let block = "reac\n  fuel=CH4(L) wt%=100 t(k)=111.643 h,cal=-21390.0 C 1 H 4\n";
+        let methane = parse_inline_species(block).unwrap();
+        assert_eq!(methane.name, "CH4(L)");
+        assert!(methane.is_condensed());
+        assert_eq!(methane.elements.count(Element::new("C").unwrap()), 1.0);
+        assert_eq!(methane.elements.count(Element::new("H").unwrap()), 4.0);
+        assert!((methane.molecular_weight - 16.04246).abs() < 1e-9);
+        assert!(methane.temperature_ranges().is_empty());
+        assert_eq!(
+            methane.enthalpy_datum,
+            EnthalpyDatum::Assigned {
+                value: -21390.0 * 4.184,
+                temperature: 111.643
+            }
+        );
+        let h = methane.enthalpy(111.643).unwrap();
+        assert!((h + 21390.0 * 4.184).abs() < 1e-8, "{}", h);
+
+        // Over two lines, in kJ at a Celsius temperature, with a gas label and a comment
+        let peroxide = "  oxid=H2O2(g) mol=1 t(c)=25\n   h,kj=-136.1 H 2 O 2 ! inline\n";
+        let peroxide = parse_inline_species(peroxide).unwrap();
+        assert!(!peroxide.is_condensed());
+        let h = peroxide.enthalpy(298.15).unwrap();
+        assert!((h + 136_100.0).abs() < 1e-8, "{}", h);
+
+        let error = |input: &str| parse_inline_species(input).unwrap_err().to_string();
+        assert_eq!(
+            error("name=X t(k)=300 C 1"),
+            "line 1: invalid species 'X': no enthalpy given"
+        );
+        assert_eq!(
+            error("reac\n name=X h,kj=1 Qq 1"),
+            "line 2: invalid species 'X': unexpected 'Qq'"
+        );
+        assert_eq!(
+            error("name=X h,kj=1 C 1\n name=Y h,kj=1 C 1"),
+            "line 1: invalid species 'X': more than one reactant given"
+        );
+        assert_eq!(
+            error("name=X h,btu=1 C 1"),
+            "line 1: invalid species 'X': unknown enthalpy unit 'btu'"
+        );
+        assert!(
+            error("name=X t(f)=-500 h,kj=1 C 1")
+                .starts_with("line 1: invalid species 'X': temperature must be positive, not -")
+        );
+    }
+
+    #[test]
+    fn test_header_dates() {
+        let date = |text: &str| {
+            let header = ThermoHeader {
+                date: text.to_string(),
+                ..Default::default()
+            };
+            header.parsed_date()
+        };
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        assert_eq!(
+            parse_thermo(&raw_text).unwrap().header_date(),
+            Date::new(2004, 9, 9)
+        );
+        assert_eq!(date("12/18/01"), Date::new(2001, 12, 18));
+        assert_eq!(date(" 5/05/94 "), Date::new(1994, 5, 5));
+        assert_eq!(date("2/29/2000"), Date::new(2000, 2, 29));
+        assert_eq!(date("2002-05-14"), Date::new(2002, 5, 14));
+
+        // Vintages sort by year first
+        assert!(date("9/09/04") > date("12/18/01"));
+        assert!(date("12/18/01") > date("5/05/94"));
+
+        for bad in [
+            "", "9/09", "13/01/04", "2/29/01", "9/9/004", "9/+9/04", "sep 2004",
+        ] {
+            assert_eq!(date(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_header_parsing() {
+        let input = "thermo                                                                          \n    200.00   1000.00   6000.00  20000.     9/09/04\n";
+        let result = parse_header(input);
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().1.format, ThermoFormat::Nasa9);
+    }
+
+    #[test]
+    fn test_header_format_token() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let tagged = raw_text.replacen("thermo", "thermo nasa9", 1);
+        let thermo_db = parse_thermo(&tagged).unwrap();
+        assert_eq!(thermo_db.header.format, ThermoFormat::Nasa9);
+        assert_eq!(
+            thermo_db.header.temp_ranges,
+            [200.0, 1000.0, 6000.0, 20000.0]
+        );
+        assert_eq!(
+            thermo_db.species.len(),
+            parse_thermo(&raw_text).unwrap().species.len()
+        );
+
+        let (_, header) = parse_header("thermo NASA7\n 200. 1000. 6000. 20000. 9/09/04\n").unwrap();
+        assert_eq!(header.format, ThermoFormat::Nasa7);
+
+        // The seven-coefficient layout is recognised but cannot be read yet
+        let nasa7 = raw_text.replacen("thermo", "thermo nasa7", 1);
+        assert!(matches!(
+            parse_thermo(&nasa7),
+            Err(ThermoParseError::Header { message }) if message.contains("nasa7")
+        ));
+    }
+
+    #[test]
+    fn test_borrowed_parse_matches_owned() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let owned = parse_thermo(&raw_text).unwrap();
+        let borrowed = parse_thermo_ref(&raw_text).unwrap();
+        assert_eq!(borrowed.species.len(), owned.species.len());
+        assert_eq!(borrowed.header.temp_ranges, owned.header.temp_ranges);
+        assert_eq!(borrowed.header.date, owned.header.date);
+
+        let text = raw_text.as_bytes().as_ptr_range();
+        for (view, species) in borrowed.species.iter().zip(&owned.species) {
+            assert_eq!(view.name, species.name);
+            assert_eq!(view.description, species.description);
+            assert_eq!(view.reference_code, species.reference_code);
+            assert!(
+                text.contains(&view.name.as_ptr()),
+                "{} is a copy",
+                view.name
+            );
+            assert_eq!(view.elements, species.elements);
+            assert_eq!(view.phase, species.phase);
+            assert_eq!(
+                view.molecular_weight.to_bits(),
+                species.molecular_weight.to_bits()
+            );
+            assert_eq!(view.enthalpy_datum, species.enthalpy_datum);
+            assert_eq!(view.source_digits, species.source_digits);
+            let fits = |ranges: &[TemperatureRange]| -> Vec<_> {
+                ranges.iter().map(TemperatureRange::named_values).collect()
+            };
+            assert_eq!(
+                fits(&view.temperature_ranges),
+                fits(&species.temperature_ranges)
+            );
+        }
+
+        let methane = borrowed.find_species("CH4").unwrap();
+        assert_eq!(
+            methane.clone().into_owned().to_json(),
+            owned["CH4"].to_json()
+        );
+    }
+
+    #[test]
+    fn test_header_keyword_case() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let expected = parse_thermo(&raw_text).unwrap();
+        for keyword in ["THERMO", "Thermo"] {
+            let text = raw_text.replacen("thermo", keyword, 1);
+            let thermo_db = parse_thermo(&text).unwrap();
+            assert_eq!(thermo_db.header.temp_ranges, expected.header.temp_ranges);
+            assert_eq!(thermo_db.species.len(), expected.species.len());
+            let (first, _) = parse_species_at(&text, 0).unwrap();
+            assert_eq!(first.name, expected.species[0].name);
+        }
+        let (_, header) = parse_header("THERMO NASA9\n 200. 1000. 6000. 20000. 9/09/04\n").unwrap();
+        assert_eq!(header.format, ThermoFormat::Nasa9);
     }
 }
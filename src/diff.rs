@@ -0,0 +1,166 @@
+use crate::database::{EnthalpyDatum, Species, ThermoFile, decimals_for};
+
+// How a species differs between two databases
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added,
+    Removed,
+    // Names of the fields whose values differ
+    Modified(Vec<&'static str>),
+}
+
+// One species that differs, by name
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpeciesDiff {
+    pub name: String,
+    pub change: Change,
+}
+
+// Whether two constants agree to the digits both were written with. A value not read from
+// text is compared exactly.
+fn same_constant(a: f64, a_digits: Option<u8>, b: f64, b_digits: Option<u8>) -> bool {
+    match (a_digits, b_digits) {
+        (Some(a_digits), Some(b_digits)) => {
+            let decimals = decimals_for(a, a_digits).min(decimals_for(b, b_digits));
+            format!("{:.decimals$}", a) == format!("{:.decimals$}", b)
+        }
+        _ => a == b,
+    }
+}
+
+// Fields of two species that differ, ignoring how the constants were formatted
+fn modified_fields(old: &Species, new: &Species) -> Vec<&'static str> {
+    let (old_digits, new_digits) = (old.source_digits, new.source_digits);
+    let mut fields = Vec::new();
+    if old.elements != new.elements {
+        fields.push("elements");
+    }
+    if old.phase != new.phase {
+        fields.push("phase");
+    }
+    if !same_constant(
+        old.molecular_weight,
+        old_digits.molecular_weight,
+        new.molecular_weight,
+        new_digits.molecular_weight,
+    ) {
+        fields.push("molecular_weight");
+    }
+    let same_kind = match (old.enthalpy_datum(), new.enthalpy_datum()) {
+        (EnthalpyDatum::FormationAt298 { .. }, EnthalpyDatum::FormationAt298 { .. }) => true,
+        (
+            EnthalpyDatum::Assigned { temperature: a, .. },
+            EnthalpyDatum::Assigned { temperature: b, .. },
+        ) => a == b,
+        _ => false,
+    };
+    let (old_enthalpy, new_enthalpy) = (old.enthalpy_datum().value(), new.enthalpy_datum().value());
+    if !same_kind
+        || !same_constant(
+            old_enthalpy,
+            old_digits.enthalpy,
+            new_enthalpy,
+            new_digits.enthalpy,
+        )
+    {
+        fields.push("enthalpy");
+    }
+    let same_fits = old.temperature_ranges().len() == new.temperature_ranges().len()
+        && old
+            .temperature_ranges()
+            .iter()
+            .zip(new.temperature_ranges())
+            .all(|(a, b)| {
+                a.temp_low == b.temp_low
+                    && a.temp_high == b.temp_high
+                    && a.used_coefficients == b.used_coefficients
+                    && a.coefficients == b.coefficients
+                    && a.integration_constants == b.integration_constants
+                    && a.h298_minus_h0 == b.h298_minus_h0
+            });
+    if !same_fits {
+        fields.push("temperature_ranges");
+    }
+    fields
+}
+
+// Species added, removed or modified going from `old` to `new`, matched by name. Removals and
+// modifications follow `old`'s order, then additions follow `new`'s. Constants written with
+// different numbers of digits (44.0095 against 44.0095000) are not a difference.
+pub fn diff(old: &ThermoFile, new: &ThermoFile) -> Vec<SpeciesDiff> {
+    let mut diffs = Vec::new();
+    for species in &old.species {
+        let change = match new.find_species(&species.name) {
+            None => Change::Removed,
+            Some(other) => {
+                let fields = modified_fields(species, other);
+                if fields.is_empty() {
+                    continue;
+                }
+                Change::Modified(fields)
+            }
+        };
+        diffs.push(SpeciesDiff {
+            name: species.name.clone(),
+            change,
+        });
+    }
+    for species in &new.species {
+        if old.find_species(&species.name).is_none() {
+            diffs.push(SpeciesDiff {
+                name: species.name.clone(),
+                change: Change::Added,
+            });
+        }
+    }
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::parse_thermo;
+
+    #[test]
+    fn test_formatting_is_not_a_difference() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let original = parse_thermo(&raw_text).unwrap();
+
+        // Fewer digits on the formula line, and a rewritten file, change nothing
+        let shortened = raw_text.replacen(
+            "   28.9600000          0.000",
+            "   28.96               0.0",
+            1,
+        );
+        assert_ne!(shortened, raw_text);
+        let shortened = parse_thermo(&shortened).unwrap();
+        assert_eq!(diff(&original, &shortened), []);
+        let rewritten = parse_thermo(&original.to_cea_string().unwrap()).unwrap();
+        assert_eq!(diff(&original, &rewritten), []);
+
+        // A changed value is, as are species coming and going
+        let mut changed = original.clone();
+        changed.species_mut()[1].molecular_weight = 28.97;
+        changed.species_mut().remove(0);
+        let mut extra = original.species[0].clone();
+        extra.name = "e+".to_string();
+        changed.species_mut().push(extra);
+        assert_eq!(
+            diff(&original, &changed),
+            [
+                SpeciesDiff {
+                    name: "e-".to_string(),
+                    change: Change::Removed
+                },
+                SpeciesDiff {
+                    name: "air".to_string(),
+                    change: Change::Modified(vec!["molecular_weight"])
+                },
+                SpeciesDiff {
+                    name: "e+".to_string(),
+                    change: Change::Added
+                },
+            ]
+        );
+    }
+}
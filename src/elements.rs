@@ -0,0 +1,557 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+use smallvec::SmallVec;
+
+use crate::database::{Species, ThermoFile};
+
+// An element symbol of a thermo.inp formula, held inline in upper case so that it is cheap to
+// copy, compare and hash. The electron is the element 'E'.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Element([u8; 2]);
+
+impl Element {
+    pub const ELECTRON: Element = Element(*b"E ");
+
+    // The element written as a one- or two-letter symbol, in either case
+    pub fn new(symbol: &str) -> Option<Self> {
+        match *symbol.as_bytes() {
+            [a] if a.is_ascii_alphabetic() => Some(Element([a.to_ascii_uppercase(), b' '])),
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+                Some(Element([a.to_ascii_uppercase(), b.to_ascii_uppercase()]))
+            }
+            _ => None,
+        }
+    }
+
+    // The upper-case symbol, as in "AR"
+    pub fn symbol(&self) -> &str {
+        let length = if self.0[1] == b' ' { 1 } else { 2 };
+        std::str::from_utf8(&self.0[..length]).expect("symbols are ASCII")
+    }
+}
+
+impl fmt::Debug for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.symbol())
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(self.symbol())
+    }
+}
+
+// Serialised as the symbol, so element counts can key a JSON object
+impl serde::Serialize for Element {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.symbol())
+    }
+}
+
+// Symbols compare case-insensitively, as they do throughout the formats
+impl PartialEq<str> for Element {
+    fn eq(&self, other: &str) -> bool {
+        self.symbol().eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<&str> for Element {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+// Atoms of each element, in the order they were listed: one formula unit of a species, or the
+// totals of a mixture. Five entries, the most a thermo.inp record holds, are stored inline.
+// Equality and hashing ignore order and zero counts but otherwise compare counts exactly, so
+// compositions can be used as map keys.
+#[derive(Clone, Default)]
+pub struct Composition(SmallVec<[(Element, f64); 5]>);
+
+impl Composition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Append an entry as listed, without merging it with an earlier one of the same element
+    pub fn push(&mut self, element: Element, count: f64) {
+        self.0.push((element, count));
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // The entry listed in a slot
+    pub fn get(&self, slot: usize) -> Option<&(Element, f64)> {
+        self.0.get(slot)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (Element, f64)> {
+        self.0.iter()
+    }
+
+    // Total atoms of an element, zero if it isn't listed
+    pub fn count(&self, element: Element) -> f64 {
+        self.iter()
+            .filter(|(e, _)| *e == element)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    // Add another composition's atoms to these, merging each into the first entry of its
+    // element and listing new elements after the existing ones
+    pub fn add(&mut self, other: &Composition) {
+        for &(element, count) in other {
+            match self.0.iter_mut().find(|(e, _)| *e == element) {
+                Some((_, total)) => *total += count,
+                None => self.0.push((element, count)),
+            }
+        }
+    }
+
+    // Every count multiplied by a factor, as for the atoms in some moles of a species
+    pub fn scale(&self, factor: f64) -> Composition {
+        Composition(self.iter().map(|&(e, count)| (e, count * factor)).collect())
+    }
+
+    // Each element once with its total atoms, in order of first appearance, leaving out
+    // elements whose total is zero
+    pub fn element_moles(&self) -> Composition {
+        let mut merged = Composition::new();
+        merged.add(self);
+        merged.0.retain(|(_, count)| *count != 0.0);
+        merged
+    }
+
+    // Element totals in symbol order, with counts as bit patterns for exact comparison
+    fn canonical(&self) -> SmallVec<[(Element, u64); 5]> {
+        let mut entries: SmallVec<[(Element, u64); 5]> = self
+            .element_moles()
+            .iter()
+            .map(|&(e, count)| (e, count.to_bits()))
+            .collect();
+        entries.sort_unstable();
+        entries
+    }
+}
+
+impl fmt::Debug for Composition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for Composition {
+    fn eq(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+}
+
+// Counts compare by their bits, so even a NaN count equals itself
+impl Eq for Composition {}
+
+impl Hash for Composition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+impl FromIterator<(Element, f64)> for Composition {
+    fn from_iter<I: IntoIterator<Item = (Element, f64)>>(iter: I) -> Self {
+        Composition(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a Composition {
+    type Item = &'a (Element, f64);
+    type IntoIter = std::slice::Iter<'a, (Element, f64)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Where the reference state of an element is found in the database
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceState {
+    // A single species, holding the given number of atoms of the element
+    Molecule(&'static str, f64),
+    // The stable condensed phase of a substance at each temperature (like AL(cr) then AL(L)),
+    // falling back to its gas above the last condensed fit, with atoms per molecule
+    Phases(&'static str, f64),
+}
+
+// Reference states of the elements, keyed by their symbol as written in thermo.inp formulas.
+// These follow the JANAF conventions: diatomic gases for H, N, O and the halogens, graphite
+// for carbon, and the stable crystal or liquid for metals.
+pub const REFERENCE_STATES: &[(&str, ReferenceState)] = &[
+    ("E", ReferenceState::Molecule("e-", 1.0)),
+    ("H", ReferenceState::Molecule("H2", 2.0)),
+    ("D", ReferenceState::Molecule("D2", 2.0)),
+    ("HE", ReferenceState::Molecule("He", 1.0)),
+    ("NE", ReferenceState::Molecule("Ne", 1.0)),
+    ("AR", ReferenceState::Molecule("Ar", 1.0)),
+    ("KR", ReferenceState::Molecule("Kr", 1.0)),
+    ("XE", ReferenceState::Molecule("Xe", 1.0)),
+    ("C", ReferenceState::Molecule("C(gr)", 1.0)),
+    ("N", ReferenceState::Molecule("N2", 2.0)),
+    ("O", ReferenceState::Molecule("O2", 2.0)),
+    ("F", ReferenceState::Molecule("F2", 2.0)),
+    ("CL", ReferenceState::Molecule("CL2", 2.0)),
+    ("BR", ReferenceState::Molecule("Br2", 2.0)),
+    ("I", ReferenceState::Phases("I2", 2.0)),
+    ("S", ReferenceState::Phases("S", 1.0)),
+    ("P", ReferenceState::Phases("P", 1.0)),
+    ("AL", ReferenceState::Phases("AL", 1.0)),
+    ("B", ReferenceState::Phases("B", 1.0)),
+    ("K", ReferenceState::Phases("K", 1.0)),
+    ("W", ReferenceState::Phases("W", 1.0)),
+    ("HG", ReferenceState::Phases("Hg", 1.0)),
+    ("AG", ReferenceState::Phases("Ag", 1.0)),
+    ("BA", ReferenceState::Phases("Ba", 1.0)),
+    ("BE", ReferenceState::Phases("Be", 1.0)),
+    ("CA", ReferenceState::Phases("Ca", 1.0)),
+    ("CD", ReferenceState::Phases("Cd", 1.0)),
+    ("CO", ReferenceState::Phases("Co", 1.0)),
+    ("CR", ReferenceState::Phases("Cr", 1.0)),
+    ("CS", ReferenceState::Phases("Cs", 1.0)),
+    ("CU", ReferenceState::Phases("Cu", 1.0)),
+    ("FE", ReferenceState::Phases("Fe", 1.0)),
+    ("GA", ReferenceState::Phases("Ga", 1.0)),
+    ("GE", ReferenceState::Phases("Ge", 1.0)),
+    ("IN", ReferenceState::Phases("In", 1.0)),
+    ("LI", ReferenceState::Phases("Li", 1.0)),
+    ("MG", ReferenceState::Phases("Mg", 1.0)),
+    ("MN", ReferenceState::Phases("Mn", 1.0)),
+    ("MO", ReferenceState::Phases("Mo", 1.0)),
+    ("NA", ReferenceState::Phases("Na", 1.0)),
+    ("NB", ReferenceState::Phases("Nb", 1.0)),
+    ("NI", ReferenceState::Phases("Ni", 1.0)),
+    ("PB", ReferenceState::Phases("Pb", 1.0)),
+    ("RB", ReferenceState::Phases("Rb", 1.0)),
+    ("SI", ReferenceState::Phases("Si", 1.0)),
+    ("SN", ReferenceState::Phases("Sn", 1.0)),
+    ("SR", ReferenceState::Phases("Sr", 1.0)),
+    ("TA", ReferenceState::Phases("Ta", 1.0)),
+    ("TH", ReferenceState::Phases("Th", 1.0)),
+    ("TI", ReferenceState::Phases("Ti", 1.0)),
+    ("U", ReferenceState::Phases("U", 1.0)),
+    ("V", ReferenceState::Phases("V", 1.0)),
+    ("ZN", ReferenceState::Phases("Zn", 1.0)),
+    ("ZR", ReferenceState::Phases("Zr", 1.0)),
+];
+
+// Reference state of an element symbol, matched case-insensitively
+pub fn reference_state(symbol: &str) -> Option<ReferenceState> {
+    REFERENCE_STATES
+        .iter()
+        .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+        .map(|(_, state)| *state)
+}
+
+// The species forming an element's reference state at a temperature, with the number of atoms
+// of the element it holds. None if the element is unknown or no fit covers the temperature.
+pub fn reference_species<'a>(
+    db: &'a ThermoFile,
+    symbol: &str,
+    temperature: f64,
+) -> Option<(&'a Species, f64)> {
+    match reference_state(symbol)? {
+        ReferenceState::Molecule(name, atoms) => {
+            let species = db.find_species(name)?;
+            species.range_at(temperature)?;
+            Some((species, atoms))
+        }
+        ReferenceState::Phases(base, atoms) => {
+            let condensed = db
+                .phases_of(base)
+                .into_iter()
+                .filter(|species| species.is_condensed() && species.range_at(temperature).is_some())
+                .min_by(|a, b| {
+                    let g = |s: &Species| s.g_over_rt(temperature).unwrap_or(f64::INFINITY);
+                    g(a).total_cmp(&g(b))
+                });
+            let species = match condensed {
+                Some(species) => species,
+                None => db
+                    .phases_of(base)
+                    .into_iter()
+                    .find(|species| !species.is_condensed())
+                    .filter(|species| species.range_at(temperature).is_some())?,
+            };
+            Some((species, atoms))
+        }
+    }
+}
+
+// Atomic weights (g/mol) of the elements as written in thermo.inp formulas, with the values
+// CEA's database was assembled from. The electron is included so that ions balance.
+pub const ATOMIC_WEIGHTS: &[(&str, f64)] = &[
+    ("E", 5.485_799_03e-4),
+    ("H", 1.00794),
+    ("D", 2.014102),
+    ("HE", 4.002602),
+    ("LI", 6.941),
+    ("BE", 9.012182),
+    ("B", 10.811),
+    ("C", 12.0107),
+    ("N", 14.0067),
+    ("O", 15.9994),
+    ("F", 18.9984032),
+    ("NE", 20.1797),
+    ("NA", 22.98977),
+    ("MG", 24.305),
+    ("AL", 26.981538),
+    ("SI", 28.0855),
+    ("P", 30.973761),
+    ("S", 32.065),
+    ("CL", 35.453),
+    ("AR", 39.948),
+    ("K", 39.0983),
+    ("CA", 40.078),
+    ("SC", 44.95591),
+    ("TI", 47.867),
+    ("V", 50.9415),
+    ("CR", 51.9961),
+    ("MN", 54.938049),
+    ("FE", 55.845),
+    ("CO", 58.9332),
+    ("NI", 58.6934),
+    ("CU", 63.546),
+    ("ZN", 65.39),
+    ("GA", 69.723),
+    ("GE", 72.64),
+    ("AS", 74.9216),
+    ("SE", 78.96),
+    ("BR", 79.904),
+    ("KR", 83.8),
+    ("RB", 85.4678),
+    ("SR", 87.62),
+    ("Y", 88.90585),
+    ("ZR", 91.224),
+    ("NB", 92.90638),
+    ("MO", 95.94),
+    ("AG", 107.8682),
+    ("CD", 112.411),
+    ("IN", 114.818),
+    ("SN", 118.71),
+    ("SB", 121.76),
+    ("TE", 127.6),
+    ("I", 126.90447),
+    ("XE", 131.293),
+    ("CS", 132.90545),
+    ("BA", 137.327),
+    ("TA", 180.9479),
+    ("W", 183.84),
+    ("HG", 200.59),
+    ("PB", 207.2),
+    ("BI", 208.98038),
+    ("RN", 222.0176),
+    ("TH", 232.0381),
+    ("U", 238.02891),
+];
+
+impl ThermoFile {
+    // Every element some species is made of, the electron included where there are ions
+    pub fn elements(&self) -> BTreeSet<Element> {
+        self.species
+            .iter()
+            .flat_map(|species| species.elements.iter())
+            .map(|(element, _)| *element)
+            .collect()
+    }
+}
+
+// The default `AtomicWeights`, built once, behind the free functions below
+fn default_weights() -> &'static AtomicWeights {
+    static WEIGHTS: OnceLock<AtomicWeights> = OnceLock::new();
+    WEIGHTS.get_or_init(AtomicWeights::default)
+}
+
+// Atomic weight of an element symbol (g/mol), matched case-insensitively
+pub fn atomic_weight(symbol: &str) -> Option<f64> {
+    default_weights().get(symbol)
+}
+
+// Molecular weight (g/mol) summed from a formula's element counts, or None if an element has
+// no known atomic weight
+pub fn formula_weight(elements: &Composition) -> Option<f64> {
+    default_weights().formula_weight(elements)
+}
+
+// The composition of a formula written as symbols each followed by an optional count, such as
+// C3H6 or CH3OH. Symbols are read by case, an upper-case letter starting each, so CO is carbon
+// monoxide and Co cobalt. None if the formula doesn't read so.
+pub fn parse_formula(formula: &str) -> Option<Composition> {
+    let mut composition = Composition::new();
+    let mut rest = formula.trim();
+    while !rest.is_empty() {
+        let bytes = rest.as_bytes();
+        if !bytes[0].is_ascii_uppercase() {
+            return None;
+        }
+        let length = if bytes.get(1).is_some_and(u8::is_ascii_lowercase) {
+            2
+        } else {
+            1
+        };
+        let element = Element::new(&rest[..length])?;
+        rest = &rest[length..];
+        let digits = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let count = match &rest[..digits] {
+            "" => 1.0,
+            count => count.parse().ok()?,
+        };
+        composition.push(element, count);
+        rest = &rest[digits..];
+    }
+    (!composition.is_empty()).then_some(composition)
+}
+
+// A table of atomic weights (g/mol) keyed by upper-case symbol, starting from
+// `ATOMIC_WEIGHTS`, for checking a database against other values
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtomicWeights {
+    weights: Vec<(String, f64)>,
+}
+
+impl Default for AtomicWeights {
+    fn default() -> Self {
+        AtomicWeights {
+            weights: ATOMIC_WEIGHTS
+                .iter()
+                .map(|&(symbol, weight)| (symbol.to_string(), weight))
+                .collect(),
+        }
+    }
+}
+
+impl AtomicWeights {
+    // Atomic weight of an element symbol, matched case-insensitively
+    pub fn get(&self, symbol: &str) -> Option<f64> {
+        self.weights
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+            .map(|(_, weight)| *weight)
+    }
+
+    // Add an element or replace its weight
+    pub fn set(&mut self, symbol: &str, weight: f64) {
+        let symbol = symbol.to_ascii_uppercase();
+        match self.weights.iter_mut().find(|(s, _)| *s == symbol) {
+            Some(entry) => entry.1 = weight,
+            None => self.weights.push((symbol, weight)),
+        }
+    }
+
+    // Molecular weight of a formula, or None if an element has no weight in the table
+    pub fn formula_weight(&self, elements: &Composition) -> Option<f64> {
+        elements
+            .iter()
+            .map(|(element, count)| Some(self.get(element.symbol())? * count))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_composition_arithmetic() {
+        let element = |symbol| Element::new(symbol).unwrap();
+        assert_eq!(element("Ar"), element("AR"));
+        assert_eq!(element("Ar").to_string(), "AR");
+        assert_eq!(format!("{:<2}|", element("C")), "C |");
+        assert_eq!(Element::new("ABC"), None);
+        assert_eq!(Element::new(""), None);
+        assert!(element("C") < element("CA") && element("CA") < element("CL"));
+
+        let db = full_database();
+        let water = &db.find_species("H2O").unwrap().elements;
+        let carbon_dioxide = &db.find_species("CO2").unwrap().elements;
+        let mut products = water.scale(2.0);
+        products.add(carbon_dioxide);
+        assert_eq!(products.count(element("O")), 4.0);
+        assert_eq!(products.count(element("H")), 4.0);
+        assert_eq!(products.len(), 3);
+
+        // Order, repeats and zero counts don't matter to equality or hashing
+        let listed: Composition = [
+            (element("O"), 1.0),
+            (element("H"), 1.0),
+            (element("H"), 1.0),
+        ]
+        .into_iter()
+        .chain([(element("E"), 0.0)])
+        .collect();
+        assert_eq!(&listed, water);
+        assert_eq!(listed.element_moles().len(), 2);
+        let mut counts = std::collections::HashMap::new();
+        counts.insert(water.clone(), "water");
+        assert_eq!(counts.get(&listed), Some(&"water"));
+        assert_ne!(&water.scale(1.0 + 1e-15), water);
+    }
+
+    #[test]
+    fn test_parse_formula() {
+        let element = |symbol| Element::new(symbol).unwrap();
+        let methanol = parse_formula("CH3OH").unwrap();
+        assert_eq!(methanol.count(element("C")), 1.0);
+        assert_eq!(methanol.count(element("H")), 4.0);
+        assert_eq!(methanol.count(element("O")), 1.0);
+        let cobalt = parse_formula("Co").unwrap();
+        assert_eq!(cobalt.element_moles().len(), 1);
+        assert_eq!(cobalt.count(element("CO")), 1.0);
+        assert_eq!(parse_formula("Ar0.5").unwrap().count(element("AR")), 0.5);
+        for formula in ["", "c3h6", "C3H6-", "C1.2.3"] {
+            assert_eq!(parse_formula(formula), None, "{}", formula);
+        }
+    }
+
+    #[test]
+    fn test_reference_species() {
+        let db = full_database();
+        let name = |symbol, t| {
+            reference_species(db, symbol, t).map(|(species, atoms)| (species.name.as_str(), atoms))
+        };
+        assert_eq!(name("O", 298.15), Some(("O2", 2.0)));
+        assert_eq!(name("Cl", 298.15), Some(("CL2", 2.0)));
+        assert_eq!(name("C", 1000.0), Some(("C(gr)", 1.0)));
+        assert_eq!(name("AL", 298.15), Some(("AL(cr)", 1.0)));
+        assert_eq!(name("AL", 1500.0), Some(("AL(L)", 1.0)));
+        assert_eq!(name("XX", 298.15), None);
+    }
+
+    #[test]
+    fn test_formula_weights_match_database() {
+        let db = full_database();
+        for species in &db.species {
+            // The lower-case 'air' entry carries a placeholder N2O2 formula
+            if species.name == "air" {
+                continue;
+            }
+            let weight = formula_weight(&species.elements).unwrap();
+            let tolerance = 2e-4 * species.molecular_weight.max(100.0) / 100.0;
+            assert!(
+                (weight - species.molecular_weight).abs() < tolerance,
+                "{}: {} vs {}",
+                species.name,
+                weight,
+                species.molecular_weight
+            );
+        }
+    }
+}
@@ -0,0 +1,2803 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::cache::PropertyCache;
+use crate::database::{TemperatureRange, ThermoFile, fingerprint_hex};
+use crate::elements::{Composition, Element};
+use crate::export::{Cell, ExportError, Table};
+use crate::lookup::SpeciesQuery;
+use crate::mixture::{Mixture, ReactantConditions, ReactantState};
+use crate::numeric::{NumericError, finite, positive};
+use crate::phases::nearest_range;
+use crate::properties::{GAS_CONSTANT, StandardState};
+use crate::reaction::Reaction;
+use crate::rocket::Station;
+
+const MAX_ITERATIONS: usize = 100;
+
+// Default trace threshold, matching the size below which CEA drops a species (ln x < -18.42)
+pub const DEFAULT_TRACE: f64 = 1.0e-8;
+
+// Largest rise allowed in one step for a species below the trace threshold, as ln x
+const TRACE_STEP_LIMIT: f64 = -9.210_340_4;
+
+// Times the line search halves a step that would raise the residual before giving up and
+// taking it whole
+const LINE_SEARCH_HALVINGS: usize = 8;
+
+// Relative corrections and element residuals below which the Gibbs iteration has converged
+const MOLE_TOLERANCE: f64 = 0.5e-5;
+const TEMPERATURE_TOLERANCE: f64 = 1.0e-4;
+const ELEMENT_TOLERANCE: f64 = 1.0e-6;
+
+// Mole fraction below which `EquilibriumResult::report` leaves a species out, as CEA's tables
+const REPORT_THRESHOLD: f64 = 5.0e-6;
+
+// Relative temperature step of the central difference taken by `equilibrium_cp`
+const CP_TEMPERATURE_STEP: f64 = 1.0e-3;
+
+// Mismatch in ln V at which a constant-volume problem's pressure is accepted
+const VOLUME_TOLERANCE: f64 = 1.0e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SolverError {
+    // A species has no fit covering the requested temperature
+    OutOfRange {
+        species: String,
+        temperature: f64,
+    },
+    // A reactant-only species, known only at its assigned temperature, was asked for at
+    // another without a heat capacity to get there
+    ReactantOnly {
+        species: String,
+        assigned_at: f64,
+        temperature: f64,
+    },
+    // The iteration limit was reached before converging
+    NoConvergence {
+        iterations: usize,
+    },
+    // The problem as posed has no solution
+    InvalidInput(String),
+    // A rocket's frozen expansion asked to start where its combustor can't freeze: the chamber
+    // or throat of an infinite-area combustor, only the throat of a finite-area one
+    FreezeStation {
+        station: Station,
+        finite_area: bool,
+    },
+    // A fit of the database holds a NaN or infinity, so can't be evaluated
+    NonFiniteFit {
+        species: String,
+        range: usize,
+    },
+    // A non-physical input, or a computation gone singular or non-finite (as the Newton system)
+    Numeric(NumericError),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolverError::OutOfRange {
+                species,
+                temperature,
+            } => write!(f, "no fit for '{}' at {} K", species, temperature),
+            SolverError::ReactantOnly {
+                species,
+                assigned_at,
+                temperature,
+            } => write!(
+                f,
+                "'{}' has an enthalpy only at {} K; give its heat capacity to use it at {} K",
+                species, assigned_at, temperature
+            ),
+            SolverError::NoConvergence { iterations } => {
+                write!(f, "no convergence after {} iterations", iterations)
+            }
+            SolverError::InvalidInput(message) => write!(f, "invalid input: {}", message),
+            SolverError::FreezeStation {
+                station,
+                finite_area: true,
+            } => write!(
+                f,
+                "a finite-area combustor freezes only at the throat, not at {:?}",
+                station
+            ),
+            SolverError::FreezeStation {
+                station,
+                finite_area: false,
+            } => write!(
+                f,
+                "an infinite-area combustor freezes at the chamber or throat, not at {:?}",
+                station
+            ),
+            SolverError::NonFiniteFit { species, range } => write!(
+                f,
+                "fit {} of '{}' holds a NaN or infinity, so the database can't be solved with",
+                range, species
+            ),
+            SolverError::Numeric(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl From<NumericError> for SolverError {
+    fn from(error: NumericError) -> Self {
+        SolverError::Numeric(error)
+    }
+}
+
+// The first species of a reaction without a fit at the given temperature
+fn out_of_range(reaction: &Reaction, db: &ThermoFile, temperature: f64) -> SolverError {
+    let species = reaction
+        .net_coefficients()
+        .iter()
+        .map(|(i, _)| &db.species[*i])
+        .find(|species| species.g_over_rt(temperature).is_none())
+        .map(|species| species.name.clone())
+        .unwrap_or_default();
+    SolverError::OutOfRange {
+        species,
+        temperature,
+    }
+}
+
+// Solve for the extent of a single reaction at equilibrium, in moles per mole of the initial
+// mixture, at temperature T (K) and pressure P (Pa). Gas-phase species are treated as ideal
+// and condensed species as pure phases of unit activity.
+//
+// Newton's method is applied to ln Q(ξ) - ln Kp, which increases monotonically in ξ, with a
+// bisection fallback whenever a step would leave the bracket of physically allowed extents.
+pub fn equilibrium_extent(
+    reaction: &Reaction,
+    db: &ThermoFile,
+    initial: &Mixture,
+    temperature: f64,
+    pressure: f64,
+) -> Result<f64, SolverError> {
+    positive("temperature", temperature)?;
+    positive("pressure", pressure)?;
+    initial.check_amounts(db)?;
+    let ln_kp = reaction
+        .ln_kp(db, temperature)
+        .ok_or_else(|| out_of_range(reaction, db, temperature))?;
+
+    let net = reaction.net_coefficients();
+    let delta_n = reaction.delta_gas_moles(db);
+    let initial_gas: f64 = initial
+        .components
+        .iter()
+        .filter(|(i, _)| !db.species[*i].is_condensed())
+        .map(|(_, moles)| moles)
+        .sum();
+
+    // Every participant must keep a non-negative amount
+    let mut lower = f64::NEG_INFINITY;
+    let mut upper = f64::INFINITY;
+    for &(index, nu) in &net {
+        let n0 = initial.fraction_of(index);
+        if nu > 0.0 {
+            lower = lower.max(-n0 / nu);
+        } else if nu < 0.0 {
+            upper = upper.min(n0 / -nu);
+        }
+    }
+    if !lower.is_finite() || !upper.is_finite() || lower >= upper {
+        return Err(SolverError::InvalidInput(
+            "no reactants or products present to react".to_string(),
+        ));
+    }
+
+    // ln Q - ln Kp and its derivative with respect to the extent. The pressure term is left
+    // out where the gas moles don't change, as they can be nil with every participant condensed.
+    let residual = |extent: f64| {
+        let (mut value, mut slope) = (-ln_kp, 0.0);
+        if delta_n != 0.0 {
+            let total_gas = initial_gas + delta_n * extent;
+            let ln_p = initial.standard_state.ln_pressure_ratio(pressure);
+            value += delta_n * (ln_p - total_gas.ln());
+            slope -= delta_n * delta_n / total_gas;
+        }
+        for &(index, nu) in &net {
+            if db.species[index].is_condensed() {
+                continue;
+            }
+            let moles = initial.fraction_of(index) + nu * extent;
+            value += nu * moles.ln();
+            slope += nu * nu / moles;
+        }
+        (value, slope)
+    };
+
+    // A bound reached by a condensed species is a valid answer: that phase is used up
+    let span = upper - lower;
+    let epsilon = 1e-12 * span;
+    let (at_lower, _) = residual(lower + epsilon);
+    if at_lower >= 0.0 {
+        return Ok(lower);
+    }
+    let (at_upper, _) = residual(upper - epsilon);
+    if at_upper <= 0.0 {
+        return Ok(upper);
+    }
+
+    let (mut low, mut high) = (lower, upper);
+    let mut extent = 0.5 * (lower + upper);
+    for _ in 0..MAX_ITERATIONS {
+        debug_assert!(
+            low <= extent && extent <= high,
+            "the extent stays bracketed"
+        );
+        let (value, slope) = residual(extent);
+        if value < 0.0 {
+            low = extent;
+        } else {
+            high = extent;
+        }
+
+        let mut next = extent - value / slope;
+        if !(next > low && next < high) {
+            next = 0.5 * (low + high);
+        }
+        if (next - extent).abs() <= 1e-14 * span.max(1.0) {
+            return Ok(next);
+        }
+        extent = next;
+    }
+
+    Err(SolverError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+impl Reaction {
+    // The equilibrium extent at T (K) and each of the pressures (Pa), as (pressure, extent)
+    // pairs, starting from the reactants in their stoichiometric amounts so that the extent is
+    // the fraction converted. By Le Chatelier's principle it falls with pressure where the
+    // reaction makes moles of gas, rises where it consumes them, and stays put otherwise.
+    pub fn extent_vs_pressure(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        pressures: &[f64],
+    ) -> Result<Vec<(f64, f64)>, SolverError> {
+        let initial = Mixture::new(self.reactants.clone());
+        pressures
+            .iter()
+            .map(|&pressure| {
+                let extent = equilibrium_extent(self, db, &initial, temperature, pressure)?;
+                Ok((pressure, extent))
+            })
+            .collect()
+    }
+}
+
+// The state assigned in an equilibrium problem
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Problem {
+    // Temperature (K) and pressure (Pa)
+    TP { temperature: f64, pressure: f64 },
+    // Total enthalpy of the initial mixture (J, for the amounts given) and pressure (Pa)
+    HP { enthalpy: f64, pressure: f64 },
+    // Total entropy (J/K, for the amounts given) and pressure (Pa)
+    SP { entropy: f64, pressure: f64 },
+    // Temperature (K) and density (kg/m³), as in a closed vessel, the pressure solved for
+    TV { temperature: f64, density: f64 },
+    // Total internal energy (J, for the amounts given) and density (kg/m³), as in an adiabatic
+    // closed vessel, the temperature and pressure solved for
+    UV { energy: f64, density: f64 },
+}
+
+impl Problem {
+    // The HP problem of reactants burning at a pressure (Pa) from the given state, their
+    // enthalpy found as `Mixture::state_enthalpy` finds it: assigned outright, or summed over
+    // the species at their temperatures
+    pub fn hp_from_reactants(
+        db: &ThermoFile,
+        reactants: &Mixture,
+        state: &ReactantState,
+        conditions: &[(usize, ReactantConditions)],
+        pressure: f64,
+    ) -> Result<Problem, SolverError> {
+        Ok(Problem::HP {
+            enthalpy: reactants.state_enthalpy(db, state, conditions)?,
+            pressure,
+        })
+    }
+}
+
+// The constraint closing the Newton system, scaled by R
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Temperature,
+    Enthalpy(f64), // H/R, K
+    Entropy(f64),  // S/R
+}
+
+// Settings for the Gibbs minimisation in `solve_equilibrium`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquilibriumOptions {
+    pub trace: f64, // Mole fraction below which a species is held out of the Newton iteration
+    pub max_iterations: usize,
+    pub temperature_guess: f64, // Starting temperature when it is an unknown, K
+    // Moles of each gas (by database index) in a nearby solution to start from, in place of
+    // an even split of the initial moles
+    pub warm_start: Option<Vec<(usize, f64)>>,
+    pub condensed: bool,   // Whether condensed phases may join the solution
+    pub omit: Vec<String>, // Species, by name or alias, never to consider
+    // Species to consider even where `condensed` is off, condensed ones starting in the
+    // solution, as CEA's insert list
+    pub insert: Vec<String>,
+    pub step: StepControl,
+}
+
+// Bounds on each Newton step of the Gibbs iteration. The step is scaled by the largest factor,
+// at most one, keeping every correction within them: the control factor of RP-1311 eqs. 3.1-3.3.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepControl {
+    pub major: f64,       // Largest rise in ln n of a species above the trace threshold
+    pub trace: f64,       // ln x a species below the trace threshold may rise to
+    pub temperature: f64, // Largest change in ln T, and in ln n of the total moles
+    // Whether to halve a step that would raise the residual norm of the Gibbs conditions, as
+    // a fallback for starts far from the solution the bounds alone don't tame. A step no
+    // halving improves on is taken as it was.
+    pub line_search: bool,
+}
+
+impl Default for StepControl {
+    fn default() -> Self {
+        StepControl {
+            major: 2.0,
+            trace: TRACE_STEP_LIMIT,
+            temperature: 0.4,
+            line_search: false,
+        }
+    }
+}
+
+impl StepControl {
+    // Plain Newton steps, taken whole
+    pub fn unbounded() -> Self {
+        StepControl {
+            major: f64::INFINITY,
+            trace: f64::INFINITY,
+            temperature: f64::INFINITY,
+            line_search: false,
+        }
+    }
+}
+
+// Starting points for `EquilibriumOptions`, by which phases may form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    GasOnly,          // Gases alone, condensed species only where inserted
+    GasPlusCondensed, // Gases, with condensed phases joining where they lower the Gibbs energy
+    Custom,           // The defaults, for the fields to be set by hand
+}
+
+// Why a species made of the mixture's elements is, or is not, among a solution's candidates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateReason {
+    MatchedElements, // Made only of the mixture's elements
+    Inserted,        // Named in `EquilibriumOptions::insert`
+    OmittedByUser,   // Named in `EquilibriumOptions::omit`
+    // No fit covers the assigned temperature or, for a condensed phase, the one solved for
+    OmittedNoCoverage,
+    // Named in `EquilibriumOptions::insert`, but made of elements the mixture lacks
+    OmittedForeignElements,
+}
+
+impl CandidateReason {
+    // Whether the species was left out of the solution
+    pub fn is_omitted(self) -> bool {
+        matches!(
+            self,
+            CandidateReason::OmittedByUser
+                | CandidateReason::OmittedNoCoverage
+                | CandidateReason::OmittedForeignElements
+        )
+    }
+}
+
+impl fmt::Display for CandidateReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CandidateReason::MatchedElements => "matched-elements",
+            CandidateReason::Inserted => "inserted",
+            CandidateReason::OmittedByUser => "omitted-by-user",
+            CandidateReason::OmittedNoCoverage => "omitted-no-temperature-coverage",
+            CandidateReason::OmittedForeignElements => "omitted-foreign-elements",
+        })
+    }
+}
+
+impl Default for EquilibriumOptions {
+    fn default() -> Self {
+        EquilibriumOptions {
+            trace: DEFAULT_TRACE,
+            max_iterations: MAX_ITERATIONS,
+            temperature_guess: 3800.0,
+            warm_start: None,
+            condensed: true,
+            omit: Vec::new(),
+            insert: Vec::new(),
+            step: StepControl::default(),
+        }
+    }
+}
+
+impl EquilibriumOptions {
+    // The defaults, with condensed phases allowed or not as the preset says
+    pub fn preset(preset: Preset) -> Self {
+        EquilibriumOptions {
+            condensed: preset != Preset::GasOnly,
+            ..EquilibriumOptions::default()
+        }
+    }
+}
+
+// The converged composition of an equilibrium problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquilibriumResult {
+    pub temperature: f64, // K
+    pub pressure: f64,    // Pa
+    pub elements: Vec<Element>,
+    pub abundances: Vec<f64>, // Moles of each element in the initial mixture, as `elements`
+    pub standard_state: StandardState, // Of the initial mixture, for the pressure terms
+    pub element_potentials: Vec<f64>, // Chemical potential per atom of each element, over RT
+    pub species: Vec<usize>,  // Candidate species considered, as database indices
+    pub moles: Vec<f64>,      // Moles of each candidate, zero for those held out as trace
+    pub trace_fractions: Vec<f64>, // Estimated mole fractions of held-out candidates
+    pub total_moles: f64,     // Including condensed phases
+    pub gas_moles: f64,
+    pub iterations: usize,
+    pub fingerprint: [u8; 32], // `ThermoFile::fingerprint` of the database solved against
+    candidates: Vec<(usize, CandidateReason)>, // As `candidate_species` gives them
+    atoms: Vec<Vec<f64>>,      // Atoms of each element in each candidate
+    condensed: Vec<bool>,      // Whether each candidate is a condensed phase
+}
+
+impl EquilibriumResult {
+    // Mole fractions of the species solved for in the iteration, at or above a display
+    // threshold, largest first
+    pub fn mole_fractions_above(&self, threshold: f64) -> Vec<(usize, f64)> {
+        let mut fractions: Vec<(usize, f64)> = self
+            .species
+            .iter()
+            .zip(&self.moles)
+            .map(|(&index, &moles)| (index, moles / self.total_moles))
+            .filter(|&(_, fraction)| fraction > 0.0 && fraction >= threshold)
+            .collect();
+        fractions.sort_by(|a, b| b.1.total_cmp(&a.1));
+        fractions
+    }
+
+    // Mole fractions of every candidate in candidate order, with trace species estimated from
+    // the element potentials as if they had been part of the iteration
+    pub fn all_mole_fractions(&self) -> Vec<(usize, f64)> {
+        self.species
+            .iter()
+            .zip(self.moles.iter().zip(&self.trace_fractions))
+            .map(|(&index, (&moles, &trace))| {
+                if moles > 0.0 {
+                    (index, moles / self.total_moles)
+                } else {
+                    (index, trace)
+                }
+            })
+            .collect()
+    }
+
+    // Every species considered for the solution, by database index, with why it was used or
+    // left out. Condensed species are listed only where they could have joined, so not under
+    // `Preset::GasOnly` unless inserted.
+    pub fn candidate_species(&self) -> &[(usize, CandidateReason)] {
+        &self.candidates
+    }
+
+    // Moles of each gaseous candidate, trace species estimated as in `all_mole_fractions`: the
+    // warm start for a solve near this one
+    pub fn gas_moles_estimated(&self) -> Vec<(usize, f64)> {
+        self.all_mole_fractions()
+            .into_iter()
+            .zip(&self.condensed)
+            .filter(|&(_, &condensed)| !condensed)
+            .map(|((index, fraction), _)| (index, fraction * self.total_moles))
+            .collect()
+    }
+
+    // Mole fraction of a species, including trace estimates, or zero if it was not a candidate
+    pub fn mole_fraction(&self, index: usize) -> f64 {
+        self.all_mole_fractions()
+            .into_iter()
+            .find(|&(i, _)| i == index)
+            .map_or(0.0, |(_, fraction)| fraction)
+    }
+
+    // Sensitivities d(ln x_j)/d(ln b_i) of each gas's mole fraction in the gas phase to the
+    // total moles of each element at fixed T and p. Rows follow `species` and columns follow
+    // `elements`; trace species respond through the element potentials like the rest, and a
+    // condensed phase present gives d(ln n_j)/d(ln b_i) for its moles. Each column solves the
+    // converged Newton system with the perturbed abundance as its right-hand side.
+    pub fn element_sensitivities(&self) -> Vec<Vec<f64>> {
+        let l = self.elements.len();
+        let phases: Vec<usize> = (0..self.species.len())
+            .filter(|&j| self.condensed[j] && self.moles[j] > 0.0)
+            .collect();
+        let size = l + 1 + phases.len();
+        let mut matrix = vec![vec![0.0; size]; size];
+        let mut abundances = vec![0.0; l];
+        for (c, &j) in phases.iter().enumerate() {
+            for (k, &a) in self.atoms[j].iter().enumerate() {
+                matrix[k][l + 1 + c] += a;
+                matrix[l + 1 + c][k] += a;
+                abundances[k] += a * self.moles[j];
+            }
+        }
+        for ((atoms, &moles), &condensed) in self.atoms.iter().zip(&self.moles).zip(&self.condensed)
+        {
+            if condensed {
+                continue;
+            }
+            for k in 0..l {
+                let akn = atoms[k] * moles;
+                for i in 0..l {
+                    matrix[k][i] += akn * atoms[i];
+                }
+                matrix[k][l] += akn;
+                matrix[l][k] += akn;
+                abundances[k] += akn;
+            }
+        }
+
+        let mut sensitivities = vec![vec![0.0; l]; self.species.len()];
+        for (i, &b) in abundances.iter().enumerate() {
+            let mut rhs = vec![0.0; size];
+            rhs[i] = b;
+            let Some(solution) = solve_linear(matrix.clone(), rhs) else {
+                continue;
+            };
+            for (row, atoms) in sensitivities.iter_mut().zip(&self.atoms) {
+                row[i] = atoms.iter().zip(&solution[..l]).map(|(a, p)| a * p).sum();
+            }
+            for (c, &j) in phases.iter().enumerate() {
+                sensitivities[j][i] = solution[l + 1 + c] / self.moles[j];
+            }
+        }
+        sensitivities
+    }
+
+    // Moles of each element in the initial mixture less those held by the products, following
+    // `elements`. Trace species, held at zero moles, hold none.
+    pub fn element_residuals(&self) -> Vec<f64> {
+        self.abundances
+            .iter()
+            .enumerate()
+            .map(|(k, &b)| {
+                let held: f64 = self
+                    .atoms
+                    .iter()
+                    .zip(&self.moles)
+                    .map(|(atoms, moles)| atoms[k] * moles)
+                    .sum();
+                b - held
+            })
+            .collect()
+    }
+
+    // A plain-text summary of the solution: the state, total moles, the mole fractions of the
+    // species above 5e-6 largest first, and how closely each element balances
+    pub fn report(&self, db: &ThermoFile) -> String {
+        let mut report = format!(
+            "T = {:.2} K, p = {:.6} bar after {} iterations\n",
+            self.temperature,
+            self.pressure / 1.0e5,
+            self.iterations
+        );
+        report += &format!(
+            "total moles {:.6e} ({:.6e} gas)\n",
+            self.total_moles, self.gas_moles
+        );
+        report += "mole fractions\n";
+        for (index, fraction) in self.mole_fractions_above(REPORT_THRESHOLD) {
+            report += &format!("  {:<16}{:.6e}\n", db.species[index].name, fraction);
+        }
+        // Species left out, by reason, their names wrapped to the width of a terminal
+        for reason in [
+            CandidateReason::OmittedByUser,
+            CandidateReason::OmittedNoCoverage,
+            CandidateReason::OmittedForeignElements,
+        ] {
+            let mut line = format!("{}:", reason);
+            let mut any = false;
+            for &(index, _) in self.candidates.iter().filter(|&&(_, r)| r == reason) {
+                let name = &db.species[index].name;
+                if line.len() + 1 + name.len() > 80 {
+                    report += &line;
+                    report += "\n";
+                    line = " ".to_string();
+                }
+                line += " ";
+                line += name;
+                any = true;
+            }
+            if any {
+                report += &line;
+                report += "\n";
+            }
+        }
+        report += "element balance (initial moles, residual)\n";
+        for ((element, b), residual) in self
+            .elements
+            .iter()
+            .zip(&self.abundances)
+            .zip(self.element_residuals())
+        {
+            report += &format!("  {:<16}{:.6e}  {:+.3e}\n", element, b, residual);
+        }
+        report += &format!("database {}\n", fingerprint_hex(&self.fingerprint));
+        for patch in db.patches() {
+            report += &format!("patch {}\n", patch);
+        }
+        report
+    }
+
+    // Sum of a molar property over the solved-for species, weighted by their moles. Fits are
+    // extrapolated as in the solver.
+    fn molar_sum(
+        &self,
+        db: &ThermoFile,
+        property: impl Fn(&TemperatureRange) -> Result<f64, NumericError>,
+    ) -> f64 {
+        self.species
+            .iter()
+            .zip(&self.moles)
+            .filter(|&(_, &moles)| moles > 0.0)
+            .map(|(&index, &moles)| {
+                nearest_range(&db.species[index], self.temperature)
+                    .and_then(|range| property(range).ok())
+                    .map_or(f64::NAN, |value| moles * value)
+            })
+            .sum()
+    }
+
+    // Total assigned enthalpy of the products, J
+    pub fn enthalpy(&self, db: &ThermoFile) -> f64 {
+        let t = self.temperature;
+        GAS_CONSTANT * t * self.molar_sum(db, |range| range.h_over_rt(t))
+    }
+
+    // Total entropy of the products including the mixing and pressure terms of the gas, J/K
+    pub fn entropy(&self, db: &ThermoFile) -> f64 {
+        let t = self.temperature;
+        let standard = self.molar_sum(db, |range| range.s_over_r(t));
+        let ln_p = self.standard_state.ln_pressure_ratio(self.pressure);
+        let mixing: f64 = self
+            .moles
+            .iter()
+            .zip(&self.condensed)
+            .filter(|&(&moles, &condensed)| moles > 0.0 && !condensed)
+            .map(|(&moles, _)| moles * ((moles / self.gas_moles).ln() + ln_p))
+            .sum();
+        GAS_CONSTANT * (standard - mixing)
+    }
+
+    // Total frozen heat capacity of the products, J/K
+    pub fn cp_frozen(&self, db: &ThermoFile) -> f64 {
+        let t = self.temperature;
+        GAS_CONSTANT * self.molar_sum(db, |range| range.cp_over_r(t))
+    }
+
+    // Total mass of the products, kg
+    pub fn mass(&self, db: &ThermoFile) -> f64 {
+        self.species
+            .iter()
+            .zip(&self.moles)
+            .map(|(&index, &moles)| moles * db.species[index].molecular_weight / 1000.0)
+            .sum()
+    }
+
+    // Volume of the gas in the products, taken as ideal, m^3. Condensed phases are taken to
+    // occupy none.
+    pub fn volume(&self) -> f64 {
+        self.gas_moles * GAS_CONSTANT * self.temperature / self.pressure
+    }
+
+    // Density of the products, kg/m^3
+    pub fn density(&self, db: &ThermoFile) -> f64 {
+        self.mass(db) / self.volume()
+    }
+
+    // Mole fractions of the solved-for species keyed by name
+    pub fn mole_fractions(&self, db: &ThermoFile) -> BTreeMap<String, f64> {
+        self.to_mixture().mole_fractions(db).unwrap_or_default()
+    }
+
+    // Mass fractions of the solved-for species keyed by name
+    pub fn mass_fractions(&self, db: &ThermoFile) -> BTreeMap<String, f64> {
+        self.to_mixture().mass_fractions(db).unwrap_or_default()
+    }
+
+    // Moles of each solved-for species per kilogram of products, kg-mol/kg
+    pub fn moles_per_kg(&self, db: &ThermoFile) -> BTreeMap<String, f64> {
+        self.to_mixture().moles_per_kg(db).unwrap_or_default()
+    }
+
+    // The solved-for composition as a mixture of mole fractions
+    pub fn to_mixture(&self) -> Mixture {
+        let components = self
+            .species
+            .iter()
+            .zip(&self.moles)
+            .filter(|&(_, &moles)| moles > 0.0)
+            .map(|(&index, &moles)| (index, moles / self.total_moles))
+            .collect();
+        Mixture::new(components).with_standard_state(self.standard_state)
+    }
+}
+
+// Solve a dense linear system by Gaussian elimination with partial pivoting
+pub(crate) fn solve_linear(mut matrix: Vec<Vec<f64>>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let size = rhs.len();
+    debug_assert!(matrix.len() == size && matrix.iter().all(|row| row.len() == size));
+    for col in 0..size {
+        let pivot =
+            (col..size).max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))?;
+        if matrix[pivot][col] == 0.0 || !matrix[pivot][col].is_finite() {
+            return None;
+        }
+        matrix.swap(col, pivot);
+        rhs.swap(col, pivot);
+        let pivot_row = matrix[col].clone();
+        for row in col + 1..size {
+            let factor = matrix[row][col] / pivot_row[col];
+            for (value, pivot_value) in matrix[row][col..].iter_mut().zip(&pivot_row[col..]) {
+                *value -= factor * pivot_value;
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+    let mut solution = vec![0.0; size];
+    for row in (0..size).rev() {
+        let known: f64 = (row + 1..size).map(|k| matrix[row][k] * solution[k]).sum();
+        solution[row] = (rhs[row] - known) / matrix[row][row];
+    }
+    solution.iter().all(|x| x.is_finite()).then_some(solution)
+}
+
+// Elements and their total moles in a mixture, in order of first appearance, skipping the
+// electron
+pub(crate) fn element_abundances(db: &ThermoFile, initial: &Mixture) -> (Vec<Element>, Vec<f64>) {
+    let mut totals = Composition::new();
+    for &(index, moles) in &initial.components {
+        totals.add(&db.species[index].elements.scale(moles));
+    }
+    totals
+        .iter()
+        .filter(|&&(element, b)| element != Element::ELECTRON && b > 0.0)
+        .copied()
+        .unzip()
+}
+
+// Database indices of the species named in an option's list
+fn named_species(db: &ThermoFile, names: &[String]) -> Result<Vec<usize>, SolverError> {
+    names
+        .iter()
+        .map(|name| {
+            db.index_of(name)
+                .map_err(|error| SolverError::InvalidInput(error.to_string()))
+        })
+        .collect()
+}
+
+// Species solved for with their atoms of each element, then every species considered with why
+// it was used or left out
+type Candidates = (Vec<(usize, Vec<f64>)>, Vec<(usize, CandidateReason)>);
+
+// The values of those entries that are kept
+fn retain_kept<T>(values: Vec<T>, kept: &[bool]) -> Vec<T> {
+    values
+        .into_iter()
+        .zip(kept)
+        .filter_map(|(value, &kept)| kept.then_some(value))
+        .collect()
+}
+
+// Species made only of the given elements, with their atoms of each: gases, and condensed
+// phases that may join the solution. Charge counts as an element, and as the elements never
+// include the electron, ions are not yet considered; nor are reactant-only records, nor
+// pseudo-species such as Air. Those the options omit, and with `temperature` given those
+// without a fit covering it, are left out, but listed with the reason in the second vector, as
+// are inserted species that can't join: those of other elements, or without fits.
+fn candidate_species(
+    db: &ThermoFile,
+    elements: &[Element],
+    temperature: Option<f64>,
+    options: &EquilibriumOptions,
+) -> Result<Candidates, SolverError> {
+    let omit = named_species(db, &options.omit)?;
+    let insert = named_species(db, &options.insert)?;
+    let query = SpeciesQuery::new()
+        .only_elements(elements)
+        .include_charge(true);
+    let mut reasons: Vec<(usize, CandidateReason)> = db
+        .species
+        .iter()
+        .enumerate()
+        .filter(|(_, species)| !species.temperature_ranges.is_empty())
+        .filter(|(_, species)| !species.is_pseudo_species())
+        .filter(|(_, species)| query.matches(species))
+        .filter(|(index, species)| {
+            options.condensed || !species.is_condensed() || insert.contains(index)
+        })
+        .map(|(index, species)| {
+            let reason = if omit.contains(&index) {
+                CandidateReason::OmittedByUser
+            } else if temperature.is_some_and(|t| species.range_at(t).is_none()) {
+                CandidateReason::OmittedNoCoverage
+            } else if insert.contains(&index) {
+                CandidateReason::Inserted
+            } else {
+                CandidateReason::MatchedElements
+            };
+            (index, reason)
+        })
+        .collect();
+    for &index in &insert {
+        if reasons.iter().any(|&(i, _)| i == index) {
+            continue;
+        }
+        let reason = if query.matches(&db.species[index]) {
+            CandidateReason::OmittedNoCoverage
+        } else {
+            CandidateReason::OmittedForeignElements
+        };
+        reasons.push((index, reason));
+    }
+    let candidates = reasons
+        .iter()
+        .filter(|(_, reason)| !reason.is_omitted())
+        .map(|&(index, _)| {
+            let species = &db.species[index];
+            let atoms = elements
+                .iter()
+                .map(|&element| species.elements.count(element))
+                .collect();
+            (index, atoms)
+        })
+        .collect();
+    Ok((candidates, reasons))
+}
+
+// The condensed candidate most in need of joining the solution: the one, among those with a
+// fit covering T and not yet included, whose Gibbs energy lies furthest below that of its
+// elements at their potentials (RP-1311 section 3.5). None if every one would only be used up.
+fn condensed_to_include(
+    db: &ThermoFile,
+    candidates: &[(usize, Vec<f64>)],
+    condensed: &[bool],
+    included: &[bool],
+    pi: &[f64],
+    temperature: f64,
+) -> Option<usize> {
+    candidates
+        .iter()
+        .enumerate()
+        .filter(|&(j, _)| condensed[j] && !included[j])
+        .filter_map(|(j, (index, atoms))| {
+            let range = db.species[*index].range_at(temperature)?;
+            let potential: f64 = atoms.iter().zip(pi).map(|(a, p)| a * p).sum();
+            Some((j, range.g_over_rt(temperature).ok()? - potential))
+        })
+        .filter(|&(_, affinity)| affinity < 0.0)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(j, _)| j)
+}
+
+// A state of the Gibbs iteration, as ln n of each candidate, ln n, T and the moles of each
+// condensed candidate, or a correction to one
+type IterationState<'a> = (&'a [f64], f64, f64, &'a [f64]);
+
+// What the line search needs to judge a trial step: the candidates and the problem
+struct Trial<'a> {
+    db: &'a ThermoFile,
+    candidates: &'a [(usize, Vec<f64>)],
+    indices: &'a [usize],
+    condensed: &'a [bool],
+    included: &'a [bool],
+    abundances: &'a [f64],
+    target: Target,
+    ln_p: f64,
+    ln_trace: f64,
+}
+
+impl Trial<'_> {
+    // Root-sum-square of what the Gibbs conditions leave unsatisfied after `lambda` times the
+    // correction: the chemical potentials of the gases (weighted by mole fraction, so trace
+    // species don't swamp them) and included phases against the element potentials fitting
+    // them best, the element balances relative to the largest, the total moles, and the energy
+    // or entropy target. Infinite where no element potentials fit.
+    fn residual(
+        &self,
+        cache: &mut PropertyCache,
+        start: IterationState,
+        correction: IterationState,
+        lambda: f64,
+    ) -> Result<f64, SolverError> {
+        let (ln_nj, ln_n, temperature, condensed_moles) = start;
+        let (dln_nj, dln_n, dln_t, dn_condensed) = correction;
+        let ln_n = ln_n + lambda * dln_n;
+        let temperature = temperature * (lambda * dln_t).exp();
+        cache.evaluate(self.db, self.indices, temperature)?;
+        let n = ln_n.exp();
+        let l = self.abundances.len();
+        let (mut total, mut energy) = (0.0, 0.0);
+        let mut held = vec![0.0; l];
+        // Each species' weight, atoms and chemical potential over RT
+        let mut potentials: Vec<(f64, &[f64], f64)> = Vec::new();
+        for (j, (_, atoms)) in self.candidates.iter().enumerate() {
+            let (g, h, s, _) = cache.properties()[j];
+            let (moles, entropy) = if self.condensed[j] {
+                if !self.included[j] {
+                    continue;
+                }
+                potentials.push((1.0, atoms, g));
+                (condensed_moles[j] + lambda * dn_condensed[j], s)
+            } else {
+                let ln = ln_nj[j] + lambda * dln_nj[j];
+                if ln - ln_n <= self.ln_trace {
+                    continue;
+                }
+                let moles = ln.exp();
+                potentials.push((moles / n, atoms, g + ln - ln_n + self.ln_p));
+                total += moles;
+                (moles, s - (ln - ln_n) - self.ln_p)
+            };
+            for (held, a) in held.iter_mut().zip(atoms) {
+                *held += a * moles;
+            }
+            energy += moles
+                * match self.target {
+                    Target::Entropy(_) => entropy,
+                    _ => h,
+                };
+        }
+
+        // The element potentials by weighted least squares
+        let mut matrix = vec![vec![0.0; l]; l];
+        let mut rhs = vec![0.0; l];
+        for &(weight, atoms, mu) in &potentials {
+            for k in 0..l {
+                for i in 0..l {
+                    matrix[k][i] += weight * atoms[k] * atoms[i];
+                }
+                rhs[k] += weight * atoms[k] * mu;
+            }
+        }
+        let Some(pi) = solve_linear(matrix, rhs) else {
+            return Ok(f64::INFINITY);
+        };
+        let mut sum: f64 = potentials
+            .iter()
+            .map(|&(weight, atoms, mu)| {
+                let potential: f64 = atoms.iter().zip(&pi).map(|(a, p)| a * p).sum();
+                weight * (mu - potential).powi(2)
+            })
+            .sum();
+        let b_max = self.abundances.iter().copied().fold(0.0, f64::max);
+        for (b, held) in self.abundances.iter().zip(&held) {
+            sum += ((b - held) / b_max).powi(2);
+        }
+        sum += ((total - n) / n).powi(2);
+        match self.target {
+            Target::Temperature => {}
+            Target::Enthalpy(h0) => sum += ((energy - h0 / temperature) / n).powi(2),
+            Target::Entropy(s0) => sum += ((energy - s0) / n).powi(2),
+        }
+        Ok(sum.sqrt())
+    }
+}
+
+// Find the equilibrium composition of the initial mixture (in moles) by minimising the Gibbs
+// energy, following the element-potential method of NASA RP-1311 (Gordon & McBride). Gases
+// are ideal. Species whose mole fraction falls below `options.trace` are held out of the
+// Newton system, and their amounts are afterwards estimated from the element potentials.
+// For HP and SP problems, fits are extrapolated from their nearest interval while T is iterated.
+//
+// Condensed species are pure phases of unit activity. They start outside the solution; each
+// time the iteration converges, the condensed species that would most lower the Gibbs energy
+// is brought in and the iteration resumed, and one whose amount goes negative, or whose fits
+// no longer cover T, is taken back out. The answer is the first converged state needing
+// neither.
+pub fn solve_equilibrium(
+    db: &ThermoFile,
+    initial: &Mixture,
+    problem: Problem,
+    options: &EquilibriumOptions,
+) -> Result<EquilibriumResult, SolverError> {
+    solve_equilibrium_cached(
+        db,
+        initial,
+        problem,
+        options,
+        &mut PropertyCache::uncached(),
+    )
+}
+
+// As `solve_equilibrium`, taking species' properties from a cache rather than evaluating them
+// on every iteration. At an assigned temperature each is then evaluated only once; where the
+// temperature is solved for, see `PropertyCache` for what its epsilon trades away. The cache
+// may be carried from one problem to the next.
+pub fn solve_equilibrium_cached(
+    db: &ThermoFile,
+    initial: &Mixture,
+    problem: Problem,
+    options: &EquilibriumOptions,
+    cache: &mut PropertyCache,
+) -> Result<EquilibriumResult, SolverError> {
+    if let Some((species, range)) = db.nonfinite_fit() {
+        return Err(SolverError::NonFiniteFit {
+            species: species.name.clone(),
+            range,
+        });
+    }
+    let (pressure, mut temperature, target) = match problem {
+        Problem::TP {
+            temperature,
+            pressure,
+        } => (pressure, temperature, Target::Temperature),
+        Problem::HP { enthalpy, pressure } => (
+            pressure,
+            options.temperature_guess,
+            Target::Enthalpy(enthalpy / GAS_CONSTANT),
+        ),
+        Problem::SP { entropy, pressure } => (
+            pressure,
+            options.temperature_guess,
+            Target::Entropy(entropy / GAS_CONSTANT),
+        ),
+        Problem::TV { .. } | Problem::UV { .. } => {
+            return solve_at_density(db, initial, problem, options, cache);
+        }
+    };
+    let solves_temperature = target != Target::Temperature;
+    positive("temperature", temperature)?;
+    positive("pressure", pressure)?;
+    if let Target::Enthalpy(value) | Target::Entropy(value) = target {
+        finite("the energy or entropy target", value)?;
+    }
+    initial.check_amounts(db)?;
+    if !(options.trace > 0.0 && options.trace < 1.0) {
+        return Err(SolverError::InvalidInput(
+            "trace threshold must lie between 0 and 1".to_string(),
+        ));
+    }
+
+    let (elements, abundances) = element_abundances(db, initial);
+    if elements.is_empty() {
+        return Err(NumericError::NoElements.into());
+    }
+    let fixed_temperature = (!solves_temperature).then_some(temperature);
+    let (candidates, mut reasons) = candidate_species(db, &elements, fixed_temperature, options)?;
+    let condensed: Vec<bool> = candidates
+        .iter()
+        .map(|(index, _)| db.species[*index].is_condensed())
+        .collect();
+    if let Some(missing) = elements.iter().enumerate().find(|&(k, _)| {
+        candidates
+            .iter()
+            .zip(&condensed)
+            .all(|((_, atoms), &condensed)| condensed || atoms[k] == 0.0)
+    }) {
+        return Err(SolverError::InvalidInput(format!(
+            "no gas species available to hold element {}",
+            missing.1
+        )));
+    }
+
+    #[cfg(feature = "tracing")]
+    let _span =
+        tracing::debug_span!("solve_equilibrium", ?problem, species = candidates.len()).entered();
+
+    let l = elements.len();
+    let ln_p = initial.standard_state.ln_pressure_ratio(pressure);
+    let ln_trace = options.trace.ln();
+    let b_max = abundances.iter().copied().fold(0.0, f64::max);
+
+    let initial_moles = initial.total().max(f64::MIN_POSITIVE);
+    let gas_count = condensed.iter().filter(|&&c| !c).count();
+    let mut ln_n = initial_moles.ln();
+    let mut ln_nj = vec![(initial_moles / gas_count as f64).ln(); candidates.len()];
+    // From a nearby solution each gas starts where it was, or just under the trace threshold
+    // if it was held out there or absent
+    if let Some(start) = &options.warm_start {
+        let total: f64 = start.iter().map(|&(_, moles)| moles.max(0.0)).sum();
+        if total > 0.0 && total.is_finite() {
+            ln_n = total.ln();
+            let floor = ln_n + ln_trace - 1.0;
+            for (ln, &(index, _)) in ln_nj.iter_mut().zip(&candidates) {
+                let moles = start.iter().find(|&&(i, _)| i == index).map(|&(_, m)| m);
+                *ln = moles.map_or(floor, |moles| moles.ln().max(floor));
+            }
+        }
+    }
+    let mut pi = vec![0.0; l];
+    // Condensed species in the solution, and their moles; those inserted start there
+    let mut included: Vec<bool> = candidates
+        .iter()
+        .zip(&condensed)
+        .map(|(&(index, _), &condensed)| {
+            condensed && reasons.contains(&(index, CandidateReason::Inserted))
+        })
+        .collect();
+    let mut condensed_moles = vec![0.0; candidates.len()];
+
+    let indices: Vec<usize> = candidates.iter().map(|&(index, _)| index).collect();
+    for iteration in 1..=options.max_iterations {
+        debug_assert!(
+            temperature > 0.0 && ln_n.is_finite(),
+            "the iteration stops once T or n goes astray"
+        );
+        // Dimensionless g, h, s and cp of every candidate at the current temperature
+        cache.evaluate(db, &indices, temperature)?;
+        let properties = cache.properties();
+
+        let n = ln_n.exp();
+        let nj: Vec<f64> = ln_nj
+            .iter()
+            .zip(&condensed)
+            .map(|(&ln, &condensed)| {
+                if !condensed && ln - ln_n > ln_trace {
+                    ln.exp()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let mu: Vec<f64> = ln_nj
+            .iter()
+            .zip(properties)
+            .zip(&condensed)
+            .map(
+                |((&ln, &(g, ..)), &condensed)| {
+                    if condensed { g } else { g + ln - ln_n + ln_p }
+                },
+            )
+            .collect();
+        let phases: Vec<usize> = (0..candidates.len()).filter(|&j| included[j]).collect();
+
+        // Assemble the reduced Newton system in the element potentials, the change in ln n, the
+        // change in moles of each included condensed species and, when T is unknown, the
+        // change in ln T with the energy or entropy constraint last
+        let t = l + 1 + phases.len();
+        let size = t + usize::from(solves_temperature);
+        let mut matrix = vec![vec![0.0; size]; size];
+        let mut rhs = vec![0.0; size];
+        for (k, &b) in abundances.iter().enumerate() {
+            rhs[k] = b;
+        }
+        rhs[l] = n;
+        match target {
+            Target::Temperature => {}
+            Target::Enthalpy(h0) => rhs[t] = h0 / temperature,
+            Target::Entropy(s0) => rhs[t] = s0,
+        }
+        for (j, (_, atoms)) in candidates.iter().enumerate() {
+            if nj[j] == 0.0 {
+                continue;
+            }
+            let (_, h, s, cp) = properties[j];
+            // Coefficients of the last row for this species: on the potentials (per atom), on
+            // ln n and on ln T, and its right-hand side contribution
+            let (on_pi, on_n, on_t, residual) = match target {
+                Target::Temperature => (0.0, 0.0, 0.0, 0.0),
+                Target::Enthalpy(_) => (h, h, h * h + cp, h * (mu[j] - 1.0)),
+                Target::Entropy(_) => {
+                    let s = s - (ln_nj[j] - ln_n) - ln_p;
+                    (s - 1.0, s, (s - 1.0) * h + cp, (s - 1.0) * mu[j] - s)
+                }
+            };
+            for k in 0..l {
+                let akn = atoms[k] * nj[j];
+                for i in 0..l {
+                    matrix[k][i] += akn * atoms[i];
+                }
+                matrix[k][l] += akn;
+                rhs[k] += akn * (mu[j] - 1.0);
+                matrix[l][k] += akn;
+                if solves_temperature {
+                    matrix[k][t] += akn * h;
+                    matrix[t][k] += akn * on_pi;
+                }
+            }
+            matrix[l][l] += nj[j];
+            rhs[l] += nj[j] * (mu[j] - 1.0);
+            if solves_temperature {
+                matrix[l][t] += nj[j] * h;
+                matrix[t][l] += nj[j] * on_n;
+                matrix[t][t] += nj[j] * on_t;
+                rhs[t] += nj[j] * residual;
+            }
+        }
+        matrix[l][l] -= n;
+        // Each condensed phase holds its atoms, and at equilibrium its Gibbs energy equals that
+        // of the elements in it
+        for (c, &j) in phases.iter().enumerate() {
+            let row = l + 1 + c;
+            let atoms = &candidates[j].1;
+            let moles = condensed_moles[j];
+            let (g, h, s, cp) = properties[j];
+            for k in 0..l {
+                matrix[k][row] += atoms[k];
+                matrix[row][k] += atoms[k];
+                rhs[k] -= atoms[k] * moles;
+            }
+            rhs[row] = g;
+            if solves_temperature {
+                let property = match target {
+                    Target::Entropy(_) => s,
+                    _ => h,
+                };
+                matrix[row][t] += h;
+                matrix[t][row] += property;
+                matrix[t][t] += moles * cp;
+                rhs[t] -= moles * property;
+            }
+        }
+
+        let solution = solve_linear(matrix, rhs).ok_or(NumericError::SingularMatrix)?;
+        pi.copy_from_slice(&solution[..l]);
+        let dln_n = solution[l];
+        let dln_t = if solves_temperature { solution[t] } else { 0.0 };
+        let dln_nj: Vec<f64> = candidates
+            .iter()
+            .zip(&mu)
+            .zip(properties)
+            .map(|(((_, atoms), &mu), &(_, h, ..))| {
+                let potential: f64 = atoms.iter().zip(&pi).map(|(a, p)| a * p).sum();
+                -mu + h * dln_t + potential + dln_n
+            })
+            .collect();
+        let dn_phases = &solution[l + 1..t];
+
+        // Convergence is judged on the corrections before they are applied
+        let sum_nj: f64 = nj.iter().sum::<f64>() + condensed_moles.iter().sum::<f64>();
+        let element_residual = (0..l)
+            .map(|k| {
+                let held: f64 = candidates
+                    .iter()
+                    .zip(nj.iter().zip(&condensed_moles))
+                    .map(|((_, atoms), (n, c))| atoms[k] * (n + c))
+                    .sum();
+                (abundances[k] - held).abs()
+            })
+            .fold(0.0, f64::max);
+        let converged = nj
+            .iter()
+            .zip(&dln_nj)
+            .all(|(n, d)| n * d.abs() <= MOLE_TOLERANCE * sum_nj)
+            && dn_phases.iter().all(|d| d.abs() <= MOLE_TOLERANCE * sum_nj)
+            && n * dln_n.abs() <= MOLE_TOLERANCE * sum_nj
+            && dln_t.abs() <= TEMPERATURE_TOLERANCE
+            && element_residual <= ELEMENT_TOLERANCE * b_max;
+
+        // Control factor limiting the step, as in RP-1311 eqs. 3.1-3.3
+        // (ln T and ln n count against the majors' bound weighted by their ratio, 5 by default)
+        let step = &options.step;
+        let mut largest = step.major / step.temperature * dln_t.abs().max(dln_n.abs());
+        let mut lambda: f64 = 1.0;
+        for (j, &d) in dln_nj.iter().enumerate() {
+            if condensed[j] {
+                continue;
+            }
+            let ln_x = ln_nj[j] - ln_n;
+            if ln_x > ln_trace {
+                if d > 0.0 {
+                    largest = largest.max(d);
+                }
+            } else if d >= 0.0 && d - dln_n > 0.0 {
+                lambda = lambda.min(((-ln_x + step.trace) / (d - dln_n)).abs());
+            }
+        }
+        if largest > step.major {
+            lambda = lambda.min(step.major / largest);
+        }
+        if step.line_search {
+            let mut dn_condensed = vec![0.0; candidates.len()];
+            for (&j, &d) in phases.iter().zip(dn_phases) {
+                dn_condensed[j] = d;
+            }
+            let trial = Trial {
+                db,
+                candidates: &candidates,
+                indices: &indices,
+                condensed: &condensed,
+                included: &included,
+                abundances: &abundances,
+                target,
+                ln_p,
+                ln_trace,
+            };
+            let start = (&ln_nj[..], ln_n, temperature, &condensed_moles[..]);
+            let correction = (&dln_nj[..], dln_n, dln_t, &dn_condensed[..]);
+            let before = trial.residual(cache, start, correction, 0.0)?;
+            let mut shorter = lambda;
+            for _ in 0..LINE_SEARCH_HALVINGS {
+                if trial.residual(cache, start, correction, shorter)? <= before {
+                    lambda = shorter;
+                    break;
+                }
+                shorter *= 0.5;
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        {
+            let largest_species = nj
+                .iter()
+                .zip(&dln_nj)
+                .map(|(n, d)| n * d.abs() / sum_nj)
+                .fold(0.0, f64::max);
+            tracing::debug!(
+                iteration,
+                temperature,
+                element_residual,
+                largest_species,
+                dln_n,
+                dln_t,
+                lambda,
+                phases = phases.len(),
+                "equilibrium iteration"
+            );
+            tracing::trace!(?pi, ?dln_nj, ?dn_phases, "equilibrium corrections");
+        }
+
+        for ((ln, d), &condensed) in ln_nj.iter_mut().zip(&dln_nj).zip(&condensed) {
+            if !condensed {
+                *ln += lambda * d;
+            }
+        }
+        for (&j, d) in phases.iter().zip(dn_phases) {
+            condensed_moles[j] += lambda * d;
+        }
+        ln_n += lambda * dln_n;
+        temperature *= (lambda * dln_t).exp();
+        if !(temperature > 0.0 && temperature.is_finite()) {
+            return Err(NumericError::NonFinite {
+                location: "the temperature iteration",
+            }
+            .into());
+        }
+        finite("the total moles", ln_n)?;
+
+        // A condensed phase used up, or carried outside its fits by T, leaves the solution
+        let mut removed = false;
+        for &j in &phases {
+            let covered = db.species[candidates[j].0].range_at(temperature).is_some();
+            if condensed_moles[j] <= 0.0 || (converged && !covered) {
+                included[j] = false;
+                condensed_moles[j] = 0.0;
+                removed = true;
+            }
+        }
+        if !converged || removed {
+            continue;
+        }
+        if let Some(j) =
+            condensed_to_include(db, &candidates, &condensed, &included, &pi, temperature)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(species = %db.species[candidates[j].0].name, "condensed phase added");
+            included[j] = true;
+            continue;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(iterations = iteration, temperature, "equilibrium converged");
+        let moles: Vec<f64> = ln_nj
+            .iter()
+            .zip(&condensed_moles)
+            .zip(&condensed)
+            .map(|((&ln, &held), &condensed)| {
+                if condensed {
+                    held
+                } else if ln - ln_n > ln_trace {
+                    ln.exp()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let gas_moles = moles
+            .iter()
+            .zip(&condensed)
+            .filter(|&(_, &condensed)| !condensed)
+            .map(|(moles, _)| moles)
+            .sum();
+        let total_moles = moles.iter().sum();
+        let trace_fractions = candidates
+            .iter()
+            .zip(moles.iter().zip(&condensed))
+            .map(|((index, atoms), (&moles, &condensed))| {
+                if moles > 0.0 || condensed {
+                    return 0.0;
+                }
+                let range = nearest_range(&db.species[*index], temperature);
+                let g = range
+                    .and_then(|r| r.g_over_rt(temperature).ok())
+                    .unwrap_or(f64::INFINITY);
+                let potential: f64 = atoms.iter().zip(&pi).map(|(a, p)| a * p).sum();
+                (potential - g - ln_p).exp()
+            })
+            .collect();
+        // At a temperature solved for, condensed phases whose fits don't reach it are left out
+        // as they would be at an assigned one. They hold nothing, having been taken out above;
+        // gases are extrapolated.
+        let kept: Vec<bool> = candidates
+            .iter()
+            .zip(&condensed)
+            .map(|(&(index, _), &condensed)| {
+                !(solves_temperature
+                    && condensed
+                    && db.species[index].range_at(temperature).is_none())
+            })
+            .collect();
+        for (&(index, _), _) in candidates.iter().zip(&kept).filter(|&(_, &kept)| !kept) {
+            for (_, reason) in reasons.iter_mut().filter(|(i, _)| *i == index) {
+                *reason = CandidateReason::OmittedNoCoverage;
+            }
+        }
+        let candidates = retain_kept(candidates, &kept);
+        let (moles, trace_fractions) = (
+            retain_kept(moles, &kept),
+            retain_kept(trace_fractions, &kept),
+        );
+        let condensed = retain_kept(condensed, &kept);
+        return Ok(EquilibriumResult {
+            temperature,
+            pressure,
+            elements,
+            abundances,
+            standard_state: initial.standard_state,
+            element_potentials: pi,
+            species: candidates.iter().map(|(index, _)| *index).collect(),
+            moles,
+            trace_fractions,
+            total_moles,
+            gas_moles,
+            iterations: iteration,
+            fingerprint: db.fingerprint(),
+            candidates: reasons,
+            atoms: candidates.into_iter().map(|(_, atoms)| atoms).collect(),
+            condensed,
+        });
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(temperature, "equilibrium did not converge");
+    Err(SolverError::NoConvergence {
+        iterations: options.max_iterations,
+    })
+}
+
+// Equilibrium solutions over a list of assigned states, one point each
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepResult {
+    pub points: Vec<EquilibriumResult>,
+}
+
+// Scalar columns of an exported sweep, before the selected mole fractions. Enthalpy, entropy
+// and heat capacity are per kilogram of products.
+const SWEEP_COLUMNS: &[&str] = &[
+    "temperature",
+    "pressure",
+    "density",
+    "enthalpy",
+    "entropy",
+    "cp_frozen",
+    "total_moles",
+    "iterations",
+];
+
+impl SweepResult {
+    fn table(&self, db: &ThermoFile, species: &[&str]) -> Result<Table, ExportError> {
+        let mut table = Table::new(db, SWEEP_COLUMNS, species)?;
+        for point in &self.points {
+            let mass = point.mass(db);
+            let row = [
+                point.temperature,
+                point.pressure,
+                point.density(db),
+                point.enthalpy(db) / mass,
+                point.entropy(db) / mass,
+                point.cp_frozen(db) / mass,
+                point.total_moles,
+            ];
+            let mut row: Vec<Cell> = row.into_iter().map(Cell::Number).collect();
+            row.push(Cell::Count(point.iterations));
+            table.push(row, point);
+        }
+        Ok(table)
+    }
+
+    // Write one CSV row per point, with a mole-fraction column for each named species
+    pub fn write_csv(
+        &self,
+        db: &ThermoFile,
+        species: &[&str],
+        writer: impl std::io::Write,
+    ) -> Result<(), ExportError> {
+        self.table(db, species)?.write_csv(writer)
+    }
+
+    // An object of the database's "fingerprint", its "patches" and the "rows" of `write_csv`,
+    // an array of objects
+    pub fn to_json(&self, db: &ThermoFile, species: &[&str]) -> Result<String, ExportError> {
+        self.table(db, species)?.to_json()
+    }
+}
+
+// Solve for equilibrium at each assigned state in turn, starting each from the temperature and
+// composition of the one before
+pub fn solve_sweep(
+    db: &ThermoFile,
+    initial: &Mixture,
+    problems: &[Problem],
+    options: &EquilibriumOptions,
+) -> Result<SweepResult, SolverError> {
+    sweep_with(problems, options, |problem, options| {
+        solve_equilibrium(db, initial, problem, options)
+    })
+}
+
+// The equilibrium composition at `n_points` temperatures evenly spaced from `t_start` to
+// `t_end` (K) inclusive, at one pressure (Pa), as mole fractions for plotting against
+// temperature. Each point is solved from the composition of the one before.
+pub fn equilibrium_sweep(
+    db: &ThermoFile,
+    initial: &Mixture,
+    pressure: f64,
+    t_start: f64,
+    t_end: f64,
+    n_points: usize,
+) -> Result<Vec<(f64, Mixture)>, SolverError> {
+    let step = (t_end - t_start) / n_points.saturating_sub(1).max(1) as f64;
+    let problems: Vec<Problem> = (0..n_points)
+        .map(|i| Problem::TP {
+            temperature: t_start + step * i as f64,
+            pressure,
+        })
+        .collect();
+    let sweep = solve_sweep(db, initial, &problems, &EquilibriumOptions::default())?;
+    Ok(sweep
+        .points
+        .iter()
+        .map(|point| (point.temperature, point.to_mixture()))
+        .collect())
+}
+
+// A constant-volume problem, solved for its pressure by a secant iteration in ln p. Each step
+// solves at that pressure, at the assigned temperature or (for UV) the enthalpy U + pV, and
+// compares the products' volume n_gas RT/p with the mixture's mass over the density. The gas
+// moles shift with every solve as the mean molecular weight does, which is what the iteration
+// settles; condensed phases take up no volume.
+fn solve_at_density(
+    db: &ThermoFile,
+    initial: &Mixture,
+    problem: Problem,
+    options: &EquilibriumOptions,
+    cache: &mut PropertyCache,
+) -> Result<EquilibriumResult, SolverError> {
+    let (temperature, density) = match problem {
+        Problem::TV {
+            temperature,
+            density,
+        } => (temperature, density),
+        Problem::UV { density, .. } => (options.temperature_guess, density),
+        _ => unreachable!("only constant-volume problems are solved at a density"),
+    };
+    positive("density", density)?;
+    positive("temperature", temperature)?;
+    if let Problem::UV { energy, .. } = problem {
+        finite("the energy target", energy)?;
+    }
+    initial.check_amounts(db)?;
+    let gas = initial.gas_total(db);
+    if gas <= 0.0 {
+        return Err(SolverError::InvalidInput(
+            "no gas to fill the volume".to_string(),
+        ));
+    }
+    let volume = initial.mass_total(db) / density;
+
+    let mut options = options.clone();
+    let mut solve = |ln_p: f64, options: &EquilibriumOptions| {
+        let pressure = ln_p.exp();
+        let fixed = match problem {
+            Problem::UV { energy, .. } => Problem::HP {
+                enthalpy: energy + pressure * volume,
+                pressure,
+            },
+            _ => Problem::TP {
+                temperature,
+                pressure,
+            },
+        };
+        let result = solve_equilibrium_cached(db, initial, fixed, options, cache)?;
+        let residual = (result.volume() / volume).ln();
+        Ok::<_, SolverError>((result, residual))
+    };
+
+    // The unreacted gas at the starting temperature fixes the first pressure, and the first
+    // step is the one that would hold were the gas moles to stay put
+    let mut ln_p = (gas * GAS_CONSTANT * temperature / volume).ln();
+    let (mut result, mut residual) = solve(ln_p, &options)?;
+    let mut previous = (ln_p, residual);
+    ln_p += residual;
+    for _ in 0..options.max_iterations {
+        options.temperature_guess = result.temperature;
+        (result, residual) = solve(ln_p, &options)?;
+        if residual.abs() <= VOLUME_TOLERANCE {
+            return Ok(result);
+        }
+        let step = residual * (ln_p - previous.0) / (previous.1 - residual);
+        previous = (ln_p, residual);
+        ln_p += if step.is_finite() { step } else { residual };
+    }
+    Err(SolverError::NoConvergence {
+        iterations: options.max_iterations,
+    })
+}
+
+// Total equilibrium heat capacity of the products of a mixture at T and p, J/K: the enthalpy's
+// derivative at constant pressure with the composition shifting, differenced from equilibria
+// at T ± ΔT. It exceeds the frozen heat capacity by the heat taken up in dissociation, and is
+// the one that sets the isentropic exponent of a shifting expansion.
+pub fn equilibrium_cp(
+    db: &ThermoFile,
+    mixture: &Mixture,
+    temperature: f64,
+    pressure: f64,
+) -> Result<f64, SolverError> {
+    let step = positive("temperature", temperature)? * CP_TEMPERATURE_STEP;
+    let options = EquilibriumOptions::default();
+    let enthalpy = |temperature: f64| {
+        let problem = Problem::TP {
+            temperature,
+            pressure,
+        };
+        solve_equilibrium(db, mixture, problem, &options).map(|result| result.enthalpy(db))
+    };
+    Ok((enthalpy(temperature + step)? - enthalpy(temperature - step)?) / (2.0 * step))
+}
+
+// A sweep solving each point with the given solver, warm-starting as in `solve_sweep`
+pub(crate) fn sweep_with(
+    problems: &[Problem],
+    options: &EquilibriumOptions,
+    mut solve: impl FnMut(Problem, &EquilibriumOptions) -> Result<EquilibriumResult, SolverError>,
+) -> Result<SweepResult, SolverError> {
+    let mut options = options.clone();
+    let mut points = Vec::with_capacity(problems.len());
+    for &problem in problems {
+        let point = solve(problem, &options)?;
+        options.temperature_guess = point.temperature;
+        options.warm_start = Some(point.gas_moles_estimated());
+        points.push(point);
+    }
+    Ok(SweepResult { points })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mixture::ReactantConditions;
+    use crate::test_data::{database_with_reactants, full_database};
+
+    #[test]
+    fn test_candidates_leave_out_ions() {
+        let db = full_database();
+        let elements: Vec<Element> = ["C", "H", "O"].map(|s| Element::new(s).unwrap()).into();
+        let (candidates, _) =
+            candidate_species(db, &elements, None, &EquilibriumOptions::default()).unwrap();
+        let names: Vec<&str> = candidates
+            .iter()
+            .map(|&(index, _)| db.species[index].name.as_str())
+            .collect();
+        assert!(names.contains(&"HCO") && names.contains(&"CO2"));
+        assert!(!names.iter().any(|name| db[*name].is_ion()));
+    }
+
+    #[test]
+    fn test_candidate_report() {
+        let db = full_database();
+        let initial = Mixture::from_names(db, &[("CH4", 1.0), ("O2", 1.0)]).unwrap();
+        let solve = |temperature: f64, options: &EquilibriumOptions| {
+            let problem = Problem::TP {
+                temperature,
+                pressure: 1.0e5,
+            };
+            solve_equilibrium(db, &initial, problem, options).unwrap()
+        };
+        let reason_of = |result: &EquilibriumResult, name: &str| {
+            let index = db.index_of(name).unwrap();
+            let found = result
+                .candidate_species()
+                .iter()
+                .find(|&&(i, _)| i == index);
+            found.map(|&(_, reason)| reason)
+        };
+
+        for temperature in [1000.0, 3000.0, 8000.0] {
+            for preset in [Preset::GasOnly, Preset::GasPlusCondensed] {
+                let result = solve(temperature, &EquilibriumOptions::preset(preset));
+                // Every listed species is tagged by whether its fits reach T, and exactly
+                // those not omitted were solved for
+                for &(index, reason) in result.candidate_species() {
+                    let covered = db.species[index].range_at(temperature).is_some();
+                    let expected = if covered {
+                        CandidateReason::MatchedElements
+                    } else {
+                        CandidateReason::OmittedNoCoverage
+                    };
+                    assert_eq!(reason, expected, "{}", db.species[index].name);
+                    assert_eq!(result.species.contains(&index), covered);
+                }
+                assert_eq!(
+                    reason_of(&result, "CO"),
+                    Some(CandidateReason::MatchedElements)
+                );
+                assert_eq!(reason_of(&result, "N2"), None);
+                let graphite = reason_of(&result, "C(gr)");
+                assert_eq!(graphite.is_some(), preset == Preset::GasPlusCondensed);
+                assert_eq!(
+                    reason_of(&result, "H2O(L)").is_some(),
+                    preset == Preset::GasPlusCondensed
+                );
+            }
+        }
+
+        // Water's fits end at 6000 K: a candidate at 3000 K, flagged rather than dropped at 8000
+        let options = EquilibriumOptions::preset(Preset::GasPlusCondensed);
+        let hot = solve(8000.0, &options);
+        assert_eq!(
+            reason_of(&solve(3000.0, &options), "H2O"),
+            Some(CandidateReason::MatchedElements)
+        );
+        assert_eq!(
+            reason_of(&hot, "H2O"),
+            Some(CandidateReason::OmittedNoCoverage)
+        );
+        assert_eq!(
+            reason_of(&hot, "C(gr)"),
+            Some(CandidateReason::OmittedNoCoverage)
+        );
+        assert_eq!(
+            reason_of(&hot, "OH"),
+            Some(CandidateReason::MatchedElements)
+        );
+        let report = hot.report(db);
+        assert!(report.contains("omitted-no-temperature-coverage: CH2 CH3 "));
+        assert!(report.lines().all(|line| line.len() <= 80));
+
+        // Burnt at constant enthalpy, the flame is too hot for any condensed phase, each tagged
+        // against the temperature solved for; gases are extrapolated and kept
+        let problem = Problem::HP {
+            enthalpy: initial.enthalpy(db, 298.15).unwrap(),
+            pressure: 1.0e5,
+        };
+        let flame = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!(flame.temperature > 2000.0);
+        for &(index, reason) in flame.candidate_species() {
+            let species = &db.species[index];
+            let covered = species.range_at(flame.temperature).is_some();
+            assert_eq!(
+                reason == CandidateReason::OmittedNoCoverage,
+                species.is_condensed() && !covered,
+                "{}",
+                species.name
+            );
+            assert_eq!(flame.species.contains(&index), !reason.is_omitted());
+        }
+        assert_eq!(
+            reason_of(&flame, "H2O(L)"),
+            Some(CandidateReason::OmittedNoCoverage)
+        );
+
+        // Omitted and inserted species, the latter overriding the gas-only preset
+        let options = EquilibriumOptions {
+            omit: vec!["CH4".to_string()],
+            insert: vec!["C(gr)".to_string()],
+            ..EquilibriumOptions::preset(Preset::GasOnly)
+        };
+        let result = solve(1000.0, &options);
+        assert_eq!(
+            reason_of(&result, "CH4"),
+            Some(CandidateReason::OmittedByUser)
+        );
+        assert!(!result.species.contains(&db.index_of("CH4").unwrap()));
+        assert_eq!(result.mole_fraction(db.index_of("CH4").unwrap()), 0.0);
+        assert_eq!(reason_of(&result, "C(gr)"), Some(CandidateReason::Inserted));
+        assert!(result.report(db).contains("omitted-by-user: CH4\n"));
+
+        // An inserted species the mixture's elements can't make is reported, not lost
+        let options = EquilibriumOptions {
+            insert: vec!["NO".to_string(), "C(gr)".to_string()],
+            ..EquilibriumOptions::preset(Preset::GasOnly)
+        };
+        let result = solve(1000.0, &options);
+        assert_eq!(
+            reason_of(&result, "NO"),
+            Some(CandidateReason::OmittedForeignElements)
+        );
+        assert!(!result.species.contains(&db.index_of("NO").unwrap()));
+        assert!(result.report(db).contains("omitted-foreign-elements: NO\n"));
+        assert_eq!(
+            EquilibriumOptions::preset(Preset::Custom),
+            EquilibriumOptions::default()
+        );
+
+        let options = EquilibriumOptions {
+            omit: vec!["CH5".to_string()],
+            ..EquilibriumOptions::default()
+        };
+        let problem = Problem::TP {
+            temperature: 1000.0,
+            pressure: 1.0e5,
+        };
+        assert!(matches!(
+            solve_equilibrium(db, &initial, problem, &options),
+            Err(SolverError::InvalidInput(message)) if message.contains("'CH5'")
+        ));
+    }
+
+    #[test]
+    fn test_air_pseudo_species_as_reactant() {
+        // Air is listed by its mean molecule, N 1.5617 O .41959 AR .00937 C .00032
+        let db = database_with_reactants();
+        let air = &db["Air"];
+        assert!(air.is_pseudo_species());
+        assert!(!db["N2"].is_pseudo_species() && !db["HCO+"].is_pseudo_species());
+        assert!(
+            air.to_cea_string()
+                .unwrap()
+                .contains("N 1.5617O .41959AR.00937C .00032")
+        );
+
+        // Its fractional atoms enter the element totals whole, and come out balanced among
+        // real molecules; Air itself is never a product
+        let initial = Mixture::from_names(db, &[("Air", 1.0)]).unwrap();
+        let problem = Problem::TP {
+            temperature: 3000.0,
+            pressure: 1.0e5,
+        };
+        let result = solve_equilibrium(db, &initial, problem, &EquilibriumOptions::default());
+        let result = result.unwrap();
+        assert!(
+            !result
+                .species
+                .iter()
+                .any(|&i| db.species[i].is_pseudo_species())
+        );
+        for (element, abundance) in result.elements.iter().zip(&result.abundances) {
+            assert_eq!(*abundance, air.elements.count(*element), "{}", element);
+        }
+        assert!(result.element_residuals().iter().all(|r| r.abs() < 1e-9));
+        let fraction = |name| result.mole_fraction(db.species_index(name).unwrap());
+        assert!(fraction("N2") > 0.7 && fraction("Ar") > 0.009);
+    }
+
+    #[test]
+    fn test_pathological_inputs() {
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let options = EquilibriumOptions::default();
+        let non_positive = |error: Option<SolverError>, expected: &str| match error {
+            Some(SolverError::Numeric(NumericError::NonPositive { quantity, .. })) => {
+                assert_eq!(quantity, expected)
+            }
+            other => panic!("{:?} for a bad {}", other, expected),
+        };
+        let tp = |temperature, pressure| Problem::TP {
+            temperature,
+            pressure,
+        };
+
+        // T = 0, p = -1 and NaNs, at every solver entry point, and at a fit and the property
+        // cache, which would otherwise give NaN or, for Cp/R at -300 K, a finite wrong value
+        let fit = &db.species[db.species_index("N2").unwrap()].temperature_ranges[0];
+        let mut cache = PropertyCache::new(0.0);
+        for t in [0.0, -300.0, f64::NAN] {
+            for property in [
+                TemperatureRange::cp_over_r,
+                TemperatureRange::h_over_rt,
+                TemperatureRange::s_over_r,
+                TemperatureRange::g_over_rt,
+            ] {
+                non_positive(property(fit, t).err().map(Into::into), "temperature");
+            }
+            non_positive(cache.evaluate(db, &[0], t).err(), "temperature");
+            non_positive(
+                solve_equilibrium(db, &air, tp(t, 1.0e5), &options).err(),
+                "temperature",
+            );
+            non_positive(equilibrium_cp(db, &air, t, 1.0e5).err(), "temperature");
+            let problem = Problem::TV {
+                temperature: t,
+                density: 1.0,
+            };
+            non_positive(
+                solve_equilibrium(db, &air, problem, &options).err(),
+                "temperature",
+            );
+        }
+        for p in [0.0, -1.0, f64::INFINITY] {
+            non_positive(
+                solve_equilibrium(db, &air, tp(3000.0, p), &options).err(),
+                "pressure",
+            );
+            let problem = Problem::HP {
+                enthalpy: 0.0,
+                pressure: p,
+            };
+            non_positive(
+                solve_equilibrium(db, &air, problem, &options).err(),
+                "pressure",
+            );
+        }
+        let (reaction, initial) = dissociation();
+        non_positive(
+            equilibrium_extent(&reaction, db, &initial, 0.0, 1.0e5).err(),
+            "temperature",
+        );
+        non_positive(
+            equilibrium_extent(&reaction, db, &initial, 300.0, -1.0).err(),
+            "pressure",
+        );
+        let problem = Problem::HP {
+            enthalpy: f64::NAN,
+            pressure: 1.0e5,
+        };
+        assert!(matches!(
+            solve_equilibrium(db, &air, problem, &options),
+            Err(SolverError::Numeric(NumericError::NonFinite { .. }))
+        ));
+
+        // Mixtures of nothing: x = [0, 0], no components, a negative amount, the electron
+        let zeros = Mixture::from_names(db, &[("N2", 0.0), ("O2", 0.0)]).unwrap();
+        for mixture in [&zeros, &Mixture::default()] {
+            assert_eq!(
+                solve_equilibrium(db, mixture, tp(3000.0, 1.0e5), &options).unwrap_err(),
+                SolverError::Numeric(NumericError::EmptyMixture)
+            );
+        }
+        assert_eq!(
+            equilibrium_extent(&reaction, db, &Mixture::default(), 300.0, 1.0e5),
+            Err(SolverError::Numeric(NumericError::EmptyMixture))
+        );
+        let negative = Mixture::from_names(db, &[("N2", 1.0), ("O2", -0.5)]).unwrap();
+        assert!(matches!(
+            solve_equilibrium(db, &negative, tp(3000.0, 1.0e5), &options),
+            Err(SolverError::Numeric(NumericError::NegativeMoleFraction { species, value }))
+                if species == "O2" && value == -0.5
+        ));
+        let electrons = Mixture::from_names(db, &[("e-", 1.0)]).unwrap();
+        assert_eq!(
+            solve_equilibrium(db, &electrons, tp(3000.0, 1.0e5), &options).unwrap_err(),
+            SolverError::Numeric(NumericError::NoElements)
+        );
+
+        // Frozen gas dynamics and the rocket take the same checks
+        let flow = crate::gasdyn::GasDynamics::new(db, &air);
+        non_positive(flow.sound_speed(0.0, 1.0e5, true).err(), "temperature");
+        non_positive(flow.sound_speed(300.0, -1.0, true).err(), "pressure");
+        assert!(matches!(
+            flow.mach(f64::NAN, 300.0, 1.0e5, true),
+            Err(SolverError::Numeric(NumericError::NonFinite { .. }))
+        ));
+        let rocket = crate::rocket::RocketOptions::new(-1.0);
+        non_positive(
+            crate::rocket::rocket_performance(db, &air, &rocket).err(),
+            "chamber pressure",
+        );
+
+        // Where a property has no error to give, there is no value rather than a NaN
+        assert_eq!(air.entropy(db, 300.0, -1.0), None);
+        assert_eq!(air.density(db, 0.0, 1.0e5), None);
+        assert_eq!(zeros.cp_mass(db, 300.0), None);
+        assert_eq!(zeros.gamma(db, 300.0), None);
+        assert_eq!(Mixture::default().speed_of_sound(db, 300.0), None);
+    }
+
+    fn dissociation() -> (Reaction, Mixture) {
+        let db = full_database();
+        let reaction = Reaction::from_names(db, &[("N2O4", 1.0)], &[("NO2", 2.0)]).unwrap();
+        let initial = Mixture::from_names(db, &[("N2O4", 1.0)]).unwrap();
+        (reaction, initial)
+    }
+
+    #[test]
+    fn test_dinitrogen_tetroxide_dissociation() {
+        // The textbook degree of dissociation at 298 K and 1 bar is about 0.19
+        let db = full_database();
+        let (reaction, initial) = dissociation();
+        let extent = equilibrium_extent(&reaction, db, &initial, 298.15, 1.0e5).unwrap();
+        assert!((extent - 0.19).abs() < 0.02, "extent = {}", extent);
+
+        // The converged composition reproduces Kp
+        let kp = reaction.ln_kp(db, 298.15).unwrap().exp();
+        let x_no2 = 2.0 * extent / (1.0 + extent);
+        let x_n2o4 = (1.0 - extent) / (1.0 + extent);
+        assert!((x_no2 * x_no2 / x_n2o4 - kp).abs() < 1e-10 * kp);
+    }
+
+    #[test]
+    fn test_extent_vs_pressure() {
+        let db = full_database();
+        let pressures = [1.0e4, 1.0e5, 1.0e6, 1.0e7];
+        let extents = |reaction: &Reaction, temperature: f64| -> Vec<f64> {
+            let curve = reaction
+                .extent_vs_pressure(db, temperature, &pressures)
+                .unwrap();
+            assert!(curve.iter().map(|&(p, _)| p).eq(pressures));
+            curve.into_iter().map(|(_, extent)| extent).collect()
+        };
+
+        // N2O4 -> 2 NO2 makes gas, so dissociates less as the pressure rises; at 1 bar it
+        // matches the extent from a mole of N2O4
+        let (dissociation, initial) = dissociation();
+        let falling = extents(&dissociation, 298.15);
+        assert!(
+            falling.windows(2).all(|pair| pair[1] < pair[0]),
+            "{:?}",
+            falling
+        );
+        let at_one_bar = equilibrium_extent(&dissociation, db, &initial, 298.15, 1.0e5).unwrap();
+        assert!((falling[1] - at_one_bar).abs() < 1e-12);
+
+        // Ammonia synthesis consumes gas, so converts more
+        let synthesis = Reaction::from_names(db, &[("N2", 1.0), ("H2", 3.0)], &[("NH3", 2.0)]);
+        let rising = extents(&synthesis.unwrap(), 700.0);
+        assert!(
+            rising.windows(2).all(|pair| pair[1] > pair[0]),
+            "{:?}",
+            rising
+        );
+
+        // The water-gas shift leaves the moles of gas alone, and so the extent
+        let shift = Reaction::from_names(
+            db,
+            &[("CO", 1.0), ("H2O", 1.0)],
+            &[("CO2", 1.0), ("H2", 1.0)],
+        );
+        let flat = extents(&shift.unwrap(), 1100.0);
+        assert!(
+            flat.iter().all(|extent| (extent - flat[0]).abs() < 1e-12),
+            "{:?}",
+            flat
+        );
+
+        assert!(matches!(
+            dissociation.extent_vs_pressure(db, 298.15, &[1.0e5, 0.0]),
+            Err(SolverError::Numeric(NumericError::NonPositive { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_dissociation_nearly_complete_when_hot() {
+        let db = full_database();
+        let (reaction, initial) = dissociation();
+        let extent = equilibrium_extent(&reaction, db, &initial, 500.0, 1.0e5).unwrap();
+        assert!(extent > 0.99 && extent < 1.0, "extent = {}", extent);
+    }
+
+    #[test]
+    fn test_all_condensed_extent() {
+        // Thermite has no gas to weigh the pressure by, so simply runs to completion
+        let db = full_database();
+        let reaction = Reaction::from_names(
+            db,
+            &[("AL(cr)", 2.0), ("Fe2O3(cr)", 1.0)],
+            &[("AL2O3(a)", 1.0), ("Fe(a)", 2.0)],
+        )
+        .unwrap();
+        let initial = Mixture::from_names(db, &[("AL(cr)", 2.0), ("Fe2O3(cr)", 1.0)]).unwrap();
+        let extent = equilibrium_extent(&reaction, db, &initial, 400.0, 1.0e5).unwrap();
+        assert_eq!(
+            extent,
+            initial.fraction_of(db.species_index("Fe2O3(cr)").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let db = full_database();
+        let (reaction, initial) = dissociation();
+        let empty = Mixture::from_names(db, &[("N2", 1.0)]).unwrap();
+        assert!(matches!(
+            equilibrium_extent(&reaction, db, &empty, 298.15, 1.0e5),
+            Err(SolverError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            equilibrium_extent(&reaction, db, &initial, 50.0, 1.0e5),
+            Err(SolverError::OutOfRange { .. })
+        ));
+    }
+
+    fn hydrogen_air() -> Mixture {
+        Mixture::from_names(full_database(), &[("H2", 2.0), ("O2", 1.0), ("N2", 3.76)]).unwrap()
+    }
+
+    #[test]
+    fn test_hydrogen_air_flame() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let enthalpy = initial.enthalpy(db, 298.15).unwrap();
+        let problem = Problem::HP {
+            enthalpy,
+            pressure: 101325.0,
+        };
+        let result = solve_equilibrium(db, &initial, problem, &EquilibriumOptions::default());
+        let result = result.unwrap();
+        // CEA: 2382 K for stoichiometric hydrogen/air from 298.15 K at 1 atm
+        assert!(
+            (result.temperature - 2382.0).abs() < 5.0,
+            "T = {}",
+            result.temperature
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let problem = Problem::TP {
+            temperature: 2400.0,
+            pressure: 101325.0,
+        };
+        let result = solve_equilibrium(db, &initial, problem, &EquilibriumOptions::default());
+        let report = result.unwrap().report(db);
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("T = 2400.00 K, p = 1.013250 bar after "));
+        assert_eq!(lines[2], "mole fractions");
+        assert!(lines[3].starts_with("  N2  "), "{}", report);
+        assert!(lines[4].starts_with("  H2O "), "{}", report);
+        assert!(!report.contains("O3 "));
+
+        let balance = lines
+            .iter()
+            .position(|line| line.starts_with("element"))
+            .unwrap();
+        assert_eq!(lines.len(), balance + 5);
+        assert!(lines[balance + 1].starts_with("  H               4.000000e0  "));
+        for line in &lines[balance + 1..balance + 4] {
+            let residual: f64 = line.split_whitespace().last().unwrap().parse().unwrap();
+            assert!(residual.abs() < 1e-5, "{}", line);
+        }
+        // The solver notes the database it was given
+        let fingerprint = crate::database::fingerprint_hex(&db.fingerprint());
+        assert_eq!(lines[balance + 4], format!("database {}", fingerprint));
+
+        // Then any changes made to its fits, after the fingerprint they alter
+        let mut patched = db.clone();
+        patched.patch("OH", 1, 5, -2.0).unwrap();
+        let result = solve_equilibrium(&patched, &initial, problem, &EquilibriumOptions::default());
+        let report = result.unwrap().report(&patched);
+        let last = report.lines().last().unwrap();
+        assert_eq!(last, format!("patch {}", patched.patches()[0]));
+        assert!(last.starts_with("patch OH fit 1 a6: "), "{}", last);
+    }
+
+    #[test]
+    fn test_step_control() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let tp = Problem::TP {
+            temperature: 2400.0,
+            pressure: 101325.0,
+        };
+        let hp = Problem::HP {
+            enthalpy: initial.enthalpy(db, 298.15).unwrap(),
+            pressure: 101325.0,
+        };
+        let with_step = |step: StepControl| EquilibriumOptions {
+            step,
+            ..EquilibriumOptions::default()
+        };
+
+        // From the even split of the initial moles, a whole Newton step sends ln n of the
+        // minor species far past their equilibrium, and those of the majors with them, until
+        // the reduced system is left singular. The default bounds converge in a few iterations.
+        for problem in [tp, hp] {
+            let result =
+                solve_equilibrium(db, &initial, problem, &with_step(StepControl::unbounded()));
+            assert_eq!(result.unwrap_err(), NumericError::SingularMatrix.into());
+        }
+        let bounded = solve_equilibrium(db, &initial, tp, &EquilibriumOptions::default()).unwrap();
+        assert!(bounded.iterations <= 13, "{}", bounded.iterations);
+        let flame = solve_equilibrium(db, &initial, hp, &EquilibriumOptions::default()).unwrap();
+        assert!(flame.iterations <= 13, "{}", flame.iterations);
+
+        // The line search reaches the same flame, whether it backs up the bounds or stands in
+        // for them
+        let line_search = StepControl {
+            line_search: true,
+            ..StepControl::default()
+        };
+        for step in [
+            line_search,
+            StepControl {
+                line_search: true,
+                ..StepControl::unbounded()
+            },
+        ] {
+            let result = solve_equilibrium(db, &initial, hp, &with_step(step)).unwrap();
+            assert!((result.temperature - flame.temperature).abs() < 1e-3);
+            assert!(result.iterations <= 15, "{}", result.iterations);
+        }
+        let result = solve_equilibrium(db, &initial, tp, &with_step(line_search)).unwrap();
+        let h2o = db.index_of("H2O").unwrap();
+        let x = bounded.mole_fraction(h2o);
+        assert!((result.mole_fraction(h2o) - x).abs() < 1e-6 * x);
+    }
+
+    #[test]
+    fn test_kerosene_oxygen_flame() {
+        let db = database_with_reactants();
+        // RP-1 with liquid oxygen at a mixture ratio of 2.56 by mass, each reactant taking the
+        // assigned enthalpy of its record: 298.15 K for RP-1, 90.17 K for the oxygen
+        let initial = Mixture::from_mass_fractions(db, &[("RP-1", 1.0), ("O2(L)", 2.56)])
+            .unwrap()
+            .unwrap();
+        let enthalpy = initial.reactant_enthalpy(db, 298.15, &[]).unwrap();
+        let (elements, abundances) = element_abundances(db, &initial);
+        let problem = Problem::HP {
+            enthalpy,
+            pressure: 7.0e6,
+        };
+        let result = solve_equilibrium(db, &initial, problem, &EquilibriumOptions::default());
+        let result = result.unwrap();
+
+        // CEA gives about 3680 K for these propellants at 70 bar
+        assert!(
+            (3600.0..3750.0).contains(&result.temperature),
+            "T = {}",
+            result.temperature
+        );
+        assert!(result.iterations <= 20, "{}", result.iterations);
+        assert!((result.enthalpy(db) - enthalpy).abs() < 1e-6 * enthalpy.abs());
+        let products = Mixture::new(
+            result
+                .species
+                .iter()
+                .copied()
+                .zip(result.moles.clone())
+                .collect(),
+        );
+        let (symbols, products) = element_abundances(db, &products);
+        for (symbol, before) in elements.iter().zip(abundances) {
+            let k = symbols.iter().position(|s| s == symbol).unwrap();
+            assert!((before - products[k]).abs() < 1e-6 * before, "{}", symbol);
+        }
+    }
+
+    // An enthalpy assigned above the reactants' own, as for a preheated feed, burns hotter
+    #[test]
+    fn test_assigned_enthalpy_flame() {
+        let db = database_with_reactants();
+        let initial = Mixture::from_mass_fractions(db, &[("RP-1", 1.0), ("O2(L)", 2.56)])
+            .unwrap()
+            .unwrap();
+        let mass = initial.molar_mass(db) * initial.total();
+        let own = initial.reactant_enthalpy(db, 298.15, &[]).unwrap();
+        let flame = |state: ReactantState| {
+            let problem = Problem::hp_from_reactants(db, &initial, &state, &[], 7.0e6).unwrap();
+            let options = EquilibriumOptions::default();
+            (
+                problem,
+                solve_equilibrium(db, &initial, problem, &options).unwrap(),
+            )
+        };
+        let (problem, default) = flame(ReactantState::default());
+        assert_eq!(
+            problem,
+            Problem::HP {
+                enthalpy: own,
+                pressure: 7.0e6
+            }
+        );
+        let (problem, same) = flame(ReactantState::default().with_enthalpy(own / mass));
+        assert!(matches!(problem, Problem::HP { enthalpy, .. } if (enthalpy - own).abs() < 1e-6));
+        assert!((same.temperature - default.temperature).abs() < 1e-6);
+
+        let (problem, hot) = flame(ReactantState::default().with_enthalpy(own / mass + 5.0e5));
+        assert!(matches!(problem, Problem::HP { enthalpy, .. }
+            if (enthalpy - own - 5.0e5 * mass).abs() < 1e-6 * own.abs()));
+        assert!(
+            hot.temperature > default.temperature + 20.0,
+            "{} against {}",
+            hot.temperature,
+            default.temperature
+        );
+    }
+
+    #[test]
+    fn test_preheated_air_flame() {
+        let db = full_database();
+        let initial = Mixture::from_names(db, &[("CH4", 1.0), ("O2", 2.0), ("N2", 7.52)]).unwrap();
+        let [oxygen, nitrogen] = ["O2", "N2"].map(|name| db.species_index(name).unwrap());
+        let flame = |air_temperature| {
+            let air = ReactantConditions::at_temperature(air_temperature);
+            let conditions = [(oxygen, air), (nitrogen, air)];
+            let enthalpy = initial.reactant_enthalpy(db, 300.0, &conditions).unwrap();
+            let problem = Problem::HP {
+                enthalpy,
+                pressure: 101325.0,
+            };
+            let options = EquilibriumOptions::default();
+            (
+                enthalpy,
+                solve_equilibrium(db, &initial, problem, &options).unwrap(),
+            )
+        };
+        let (cold_enthalpy, cold) = flame(300.0);
+        let (hot_enthalpy, hot) = flame(600.0);
+
+        // About 2225 K from 300 K reactants
+        assert!(
+            (cold.temperature - 2225.0).abs() < 30.0,
+            "T = {}",
+            cold.temperature
+        );
+
+        // The extra enthalpy would heat frozen products by dH/Cp; dissociation absorbs some
+        let rise = hot.temperature - cold.temperature;
+        let frozen_rise = (hot_enthalpy - cold_enthalpy) / hot.cp_frozen(db);
+        assert!(
+            (0.5 * frozen_rise..frozen_rise).contains(&rise),
+            "rise {} K against {} K frozen",
+            rise,
+            frozen_rise
+        );
+    }
+
+    #[test]
+    fn test_condensed_phase_appears_and_vanishes() {
+        // Burnt hydrogen/oxygen diluted with nitrogen: two moles of water in six of gas. The
+        // spare oxygen keeps the element potentials of H and O apart once H2 is trace.
+        let db = full_database();
+        let initial = Mixture::from_names(db, &[("H2", 2.0), ("O2", 1.5), ("N2", 3.5)]).unwrap();
+        let liquid = db.species_index("H2O(L)").unwrap();
+        let ice = db.species_index("H2O(cr)").unwrap();
+        let vapour = db.species_index("H2O").unwrap();
+        let solve = |temperature| {
+            let problem = Problem::TP {
+                temperature,
+                pressure: 1.0e5,
+            };
+            solve_equilibrium(db, &initial, problem, &EquilibriumOptions::default()).unwrap()
+        };
+        let moles_of = |result: &EquilibriumResult, index| {
+            let j = result.species.iter().position(|&i| i == index);
+            j.map_or(0.0, |j| result.moles[j])
+        };
+
+        // At 300 K the vapour pressure is about 3.5 kPa, so most of the water condenses and the
+        // vapour left over sits at saturation: g(L) = g(gas) + ln(p_H2O / p°)
+        let cold = solve(300.0);
+        let condensed = moles_of(&cold, liquid);
+        assert!((1.8..2.0).contains(&condensed), "H2O(L) = {}", condensed);
+        let x_vapour = moles_of(&cold, vapour) / cold.gas_moles;
+        let saturation = (db.species[liquid].g_over_rt(300.0).unwrap()
+            - db.species[vapour].g_over_rt(300.0).unwrap())
+        .exp();
+        assert!((x_vapour - saturation).abs() < 1e-4 * saturation);
+        assert!(
+            (saturation * 1.0e5 - 3.5e3).abs() < 0.2e3,
+            "p_sat = {}",
+            saturation
+        );
+        assert_eq!(cold.total_moles, cold.gas_moles + condensed);
+        // The four moles of O2 and N2 carry the saturated vapour, and only the gas has volume
+        let gas_moles = 4.0 / (1.0 - saturation);
+        assert!((cold.gas_moles - gas_moles).abs() < 1e-4 * gas_moles);
+        assert!((cold.volume() - gas_moles * GAS_CONSTANT * 300.0 / 1.0e5).abs() < 1e-3);
+
+        // Below freezing it is ice instead
+        let frozen = solve(260.0);
+        assert!(moles_of(&frozen, ice) > 1.9);
+        assert_eq!(moles_of(&frozen, liquid), 0.0);
+
+        // At 350 K the water's partial pressure of 33 kPa is below saturation (42 kPa), so the
+        // liquid is left out and the water stays vapour
+        let warm = solve(350.0);
+        assert!(warm.species.contains(&liquid));
+        assert_eq!(moles_of(&warm, liquid), 0.0);
+        assert!((moles_of(&warm, vapour) - 2.0).abs() < 1e-6);
+        assert_eq!(warm.total_moles, warm.gas_moles);
+
+        // A phase that enters the solution and leaves it again is absent from the answer.
+        // Inserted, the liquid starts in the solution: at 350 K it evaporates, and in the flame
+        // it is carried past its fits as the temperature climbs.
+        let options = EquilibriumOptions {
+            insert: vec!["H2O(L)".to_string()],
+            ..EquilibriumOptions::default()
+        };
+        let problem = Problem::TP {
+            temperature: 350.0,
+            pressure: 1.0e5,
+        };
+        let evaporated = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert_eq!(moles_of(&evaporated, liquid), 0.0);
+        assert_eq!(evaporated.total_moles, evaporated.gas_moles);
+        assert!((moles_of(&evaporated, vapour) - 2.0).abs() < 1e-6);
+        let problem = Problem::HP {
+            enthalpy: initial.enthalpy(db, 298.15).unwrap(),
+            pressure: 1.0e5,
+        };
+        let flame = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!(flame.temperature > 1500.0, "T = {}", flame.temperature);
+        assert!(!flame.species.contains(&liquid));
+        assert_eq!(flame.total_moles, flame.gas_moles);
+        assert!(
+            flame
+                .candidate_species()
+                .contains(&(liquid, CandidateReason::OmittedNoCoverage))
+        );
+    }
+
+    #[test]
+    fn test_trace_threshold() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let enthalpy = initial.enthalpy(db, 298.15).unwrap();
+        let problem = Problem::HP {
+            enthalpy,
+            pressure: 101325.0,
+        };
+        let solve = |trace| {
+            let options = EquilibriumOptions {
+                trace,
+                ..Default::default()
+            };
+            solve_equilibrium(db, &initial, problem, &options).unwrap()
+        };
+        let loose = solve(1e-8);
+        let tight = solve(1e-25);
+        assert!((loose.temperature - tight.temperature).abs() < 1e-3);
+
+        // The major species agree
+        for (index, fraction) in loose.mole_fractions_above(1e-3) {
+            let other = tight.mole_fraction(index);
+            assert!(
+                (fraction - other).abs() < 1e-6 * fraction,
+                "{}",
+                db.species[index].name
+            );
+        }
+
+        // Only the tighter trace solves for species far below the loose threshold
+        let below = |result: &EquilibriumResult| result.mole_fractions_above(1e-20).len();
+        assert!(below(&tight) > below(&loose));
+        assert!(
+            loose
+                .mole_fractions_above(0.0)
+                .iter()
+                .all(|&(_, x)| x > 1e-8)
+        );
+
+        // Species held out as trace are still reported through the element potentials
+        let ozone = db.species_index("O3").unwrap();
+        let (estimated, solved) = (loose.mole_fraction(ozone), tight.mole_fraction(ozone));
+        assert!(estimated > 0.0 && estimated < 1e-8);
+        assert!(
+            (estimated - solved).abs() < 1e-3 * solved,
+            "{} vs {}",
+            estimated,
+            solved
+        );
+    }
+
+    #[test]
+    fn test_element_sensitivities_match_finite_differences() {
+        let db = full_database();
+        let problem = Problem::TP {
+            temperature: 2500.0,
+            pressure: 1.0e5,
+        };
+        let options = EquilibriumOptions::default();
+        let solve = |oxygen: f64| {
+            let initial =
+                Mixture::from_names(db, &[("CH4", 1.0), ("O2", oxygen), ("N2", 7.52)]).unwrap();
+            solve_equilibrium(db, &initial, problem, &options).unwrap()
+        };
+        let base = solve(2.0);
+        let sensitivities = base.element_sensitivities();
+        assert_eq!(sensitivities.len(), base.species.len());
+        assert_eq!(base.elements, ["C", "H", "O", "N"]);
+
+        // Perturb the oxygen abundance by 0.1% either side; it is held only by the O2
+        let column = base.elements.iter().position(|e| e == "O").unwrap();
+        let (low, high) = (solve(2.0 * 0.999), solve(2.0 * 1.001));
+        let step = (1.001f64 / 0.999).ln();
+        for (row, &index) in base.species.iter().enumerate() {
+            if base.mole_fraction(index) < 1e-4 {
+                continue;
+            }
+            let numeric = (high.mole_fraction(index).ln() - low.mole_fraction(index).ln()) / step;
+            let analytic = sensitivities[row][column];
+            assert!(
+                (numeric - analytic).abs() < 1e-3 * analytic.abs().max(1.0),
+                "{}: {} vs {}",
+                db.species[index].name,
+                numeric,
+                analytic
+            );
+        }
+    }
+
+    #[test]
+    fn test_assigned_entropy() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let options = EquilibriumOptions::default();
+        let problem = Problem::HP {
+            enthalpy: initial.enthalpy(db, 298.15).unwrap(),
+            pressure: 20.0e5,
+        };
+        let flame = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!((flame.enthalpy(db) - initial.enthalpy(db, 298.15).unwrap()).abs() < 1.0);
+
+        // Assigning the flame's own entropy and pressure recovers the flame
+        let entropy = flame.entropy(db);
+        let problem = Problem::SP {
+            entropy,
+            pressure: 20.0e5,
+        };
+        let same = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!((same.temperature - flame.temperature).abs() < 0.1);
+
+        // An isentropic expansion cools the gas and recombines radicals
+        let problem = Problem::SP {
+            entropy,
+            pressure: 1.0e5,
+        };
+        let expanded = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!((expanded.entropy(db) - entropy).abs() < 1e-4 * entropy.abs());
+        assert!(expanded.temperature < flame.temperature - 500.0);
+        let oh = db.species_index("OH").unwrap();
+        assert!(expanded.mole_fraction(oh) < flame.mole_fraction(oh));
+    }
+
+    #[test]
+    fn test_constant_volume_explosion() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let (t0, p0) = (300.0, 1.0e5);
+        let density = initial.density(db, t0, p0).unwrap();
+        let energy = initial.internal_energy(db, t0).unwrap();
+        let options = EquilibriumOptions::default();
+        let problem = Problem::UV { energy, density };
+        let result = solve_equilibrium(db, &initial, problem, &options).unwrap();
+
+        // Burning stoichiometric hydrogen/air in a closed vessel from 1 bar peaks near 8 bar
+        // (the AICC pressure of hydrogen safety studies), hotter than the 2382 K of the
+        // constant-pressure flame as no work is done on the surroundings
+        assert!(
+            (result.pressure / 8.0e5 - 1.0).abs() < 0.03,
+            "{:?}",
+            result.pressure
+        );
+        assert!(result.temperature > 2700.0, "{}", result.temperature);
+        assert!((result.density(db) / density - 1.0).abs() < 1e-8);
+        let u = result.enthalpy(db) - result.pressure * result.volume();
+        assert!(
+            (u - energy).abs() < 1e-6 * energy.abs().max(1.0),
+            "{} {}",
+            u,
+            energy
+        );
+
+        // At the explosion's temperature and density, TV finds the same pressure
+        let problem = Problem::TV {
+            temperature: result.temperature,
+            density,
+        };
+        let same = solve_equilibrium(db, &initial, problem, &options).unwrap();
+        assert!((same.pressure / result.pressure - 1.0).abs() < 1e-6);
+        let empty = Problem::TV {
+            temperature: 300.0,
+            density: 0.0,
+        };
+        assert!(matches!(
+            solve_equilibrium(db, &initial, empty, &options),
+            Err(SolverError::Numeric(NumericError::NonPositive {
+                quantity: "density",
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_equilibrium_cp() {
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let options = EquilibriumOptions::default();
+        let frozen = |temperature: f64| {
+            let problem = Problem::TP {
+                temperature,
+                pressure: 1.0e5,
+            };
+            solve_equilibrium(db, &air, problem, &options)
+                .unwrap()
+                .cp_frozen(db)
+        };
+
+        // Oxygen dissociating takes up heat, more than doubling the heat capacity
+        let shifting = equilibrium_cp(db, &air, 3500.0, 1.0e5).unwrap();
+        assert!(
+            shifting > 2.0 * frozen(3500.0),
+            "{} {}",
+            shifting,
+            frozen(3500.0)
+        );
+
+        // Where nothing dissociates the two agree
+        let cold = equilibrium_cp(db, &air, 500.0, 1.0e5).unwrap();
+        assert!(
+            (cold / frozen(500.0) - 1.0).abs() < 1e-4,
+            "{} {}",
+            cold,
+            frozen(500.0)
+        );
+    }
+
+    #[test]
+    fn test_equilibrium_sweep() {
+        let db = full_database();
+        let initial = hydrogen_air();
+        let sweep = equilibrium_sweep(db, &initial, 1.0e5, 1500.0, 3500.0, 9).unwrap();
+        assert_eq!(sweep.len(), 9);
+        assert_eq!((sweep[0].0, sweep[8].0), (1500.0, 3500.0));
+        assert_eq!(sweep[4].0, 2500.0);
+        assert!(
+            equilibrium_sweep(db, &initial, 1.0e5, 1500.0, 3500.0, 0)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Starting from the point before reaches the same answer as starting afresh, sooner
+        let options = EquilibriumOptions::default();
+        let problems: Vec<Problem> = sweep
+            .iter()
+            .map(|&(temperature, _)| Problem::TP {
+                temperature,
+                pressure: 1.0e5,
+            })
+            .collect();
+        let warm = solve_sweep(db, &initial, &problems, &options).unwrap();
+        let mut cold_iterations = 0;
+        for ((problem, point), (_, mixture)) in problems.iter().zip(&warm.points).zip(&sweep) {
+            let cold = solve_equilibrium(db, &initial, *problem, &options).unwrap();
+            cold_iterations += cold.iterations;
+            let fractions = mixture.mole_fractions(db).unwrap();
+            for (index, x) in cold.mole_fractions_above(1e-6) {
+                let name = &db.species[index].name;
+                assert!((fractions[name] - x).abs() < 1e-5 * x.max(1e-2), "{}", name);
+                assert!((point.mole_fraction(index) - x).abs() < 1e-5 * x.max(1e-2));
+            }
+        }
+        let warm_iterations: usize = warm.points.iter().map(|point| point.iterations).sum();
+        assert!(
+            warm_iterations < cold_iterations,
+            "{} against {}",
+            warm_iterations,
+            cold_iterations
+        );
+    }
+
+    #[test]
+    fn test_sweep_export() {
+        let db = full_database();
+        let problems: Vec<Problem> = [1500.0, 2500.0, 3500.0]
+            .into_iter()
+            .map(|temperature| Problem::TP {
+                temperature,
+                pressure: 1.0e5,
+            })
+            .collect();
+        let sweep = solve_sweep(
+            db,
+            &hydrogen_air(),
+            &problems,
+            &EquilibriumOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(sweep.points.len(), 3);
+
+        let mut csv = Vec::new();
+        sweep.write_csv(db, &["H2O", "OH"], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        let fingerprint = crate::database::fingerprint_hex(&db.fingerprint());
+        assert_eq!(lines[0], format!("# fingerprint: {}", fingerprint));
+        assert_eq!(
+            lines[1],
+            "temperature,pressure,density,enthalpy,entropy,cp_frozen,total_moles,iterations,\
+             X(H2O),X(OH)"
+        );
+        assert_eq!(lines.len(), 5);
+        assert!(lines[3].starts_with("2500,100000,"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&sweep.to_json(db, &["OH"]).unwrap()).unwrap();
+        let oh = db.species_index("OH").unwrap();
+        assert_eq!(json["fingerprint"], fingerprint);
+        assert_eq!(json["rows"][1]["X(OH)"], sweep.points[1].mole_fraction(oh));
+
+        assert_eq!(json["patches"], serde_json::json!([]));
+
+        // Patches applied to the database follow its fingerprint
+        let mut patched = db.clone();
+        patched.patch("H2O", 0, 7, 0.0).unwrap();
+        let note = format!("# patch: {}", patched.patches()[0]);
+        let mut csv = Vec::new();
+        sweep.write_csv(&patched, &["H2O"], &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap().lines().nth(1),
+            Some(note.as_str())
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&sweep.to_json(&patched, &[]).unwrap()).unwrap();
+        assert_eq!(json["patches"][0], patched.patches()[0].to_string());
+
+        let unknown = sweep.write_csv(db, &["XYZ"], Vec::new());
+        assert!(matches!(unknown, Err(ExportError::UnknownSpecies(e)) if e.name == "XYZ"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_reports_iterations() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        // Counts the events emitted at debug level or finer
+        struct Counter(Arc<AtomicUsize>);
+        impl Subscriber for Counter {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let db = full_database();
+        let initial = hydrogen_air();
+        let problem = Problem::TP {
+            temperature: 3000.0,
+            pressure: 1.0e5,
+        };
+        let options = EquilibriumOptions::default();
+        let quiet = solve_equilibrium(db, &initial, problem, &options).unwrap();
+
+        let events = Arc::new(AtomicUsize::new(0));
+        let traced = tracing::subscriber::with_default(Counter(events.clone()), || {
+            solve_equilibrium(db, &initial, problem, &options).unwrap()
+        });
+        assert_eq!(traced, quiet);
+
+        // Two events per iteration and one on convergence
+        assert_eq!(events.load(Ordering::SeqCst), 2 * quiet.iterations + 1);
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::database::{ThermoFile, fingerprint_hex};
+use crate::equilibrium::EquilibriumResult;
+use crate::lookup::UnknownSpecies;
+
+#[derive(Debug)]
+pub enum ExportError {
+    // A requested species is not in the database
+    UnknownSpecies(UnknownSpecies),
+    Io(io::Error),
+    Json(serde_json::Error),
+    #[cfg(feature = "arrow")]
+    Parquet(parquet::errors::ParquetError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExportError::UnknownSpecies(unknown) => write!(f, "{}", unknown),
+            ExportError::Io(error) => write!(f, "could not write table: {}", error),
+            ExportError::Json(error) => write!(f, "could not encode table: {}", error),
+            #[cfg(feature = "arrow")]
+            ExportError::Parquet(error) => write!(f, "could not write Parquet: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(error: io::Error) -> Self {
+        ExportError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(error: serde_json::Error) -> Self {
+        ExportError::Json(error)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl From<parquet::errors::ParquetError> for ExportError {
+    fn from(error: parquet::errors::ParquetError) -> Self {
+        ExportError::Parquet(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Cell {
+    Text(String),
+    Number(f64),
+    Count(usize),
+}
+
+// A result flattened into named columns, one row per station or grid point. Scalar columns
+// come first in the order given, followed by the mole fraction of each selected species.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Table {
+    fingerprint: String,  // Of the database, in hex, heading the CSV and JSON alike
+    patches: Vec<String>, // Changes made to the database's fits, following the fingerprint
+    columns: Vec<String>,
+    species: Vec<usize>, // Database indices of the species given mole-fraction columns
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub(crate) fn new(
+        db: &ThermoFile,
+        columns: &[&str],
+        species: &[&str],
+    ) -> Result<Self, ExportError> {
+        let indices = species
+            .iter()
+            .map(|&name| db.index_of(name).map_err(ExportError::UnknownSpecies))
+            .collect::<Result<Vec<_>, _>>()?;
+        let columns = columns
+            .iter()
+            .map(|column| column.to_string())
+            .chain(species.iter().map(|name| format!("X({})", name)))
+            .collect();
+        Ok(Table {
+            fingerprint: fingerprint_hex(&db.fingerprint()),
+            patches: db.patches().iter().map(|patch| patch.to_string()).collect(),
+            columns,
+            species: indices,
+            rows: Vec::new(),
+        })
+    }
+
+    // Add a row of scalars, completing it with the selected mole fractions of the composition
+    pub(crate) fn push(&mut self, mut row: Vec<Cell>, composition: &EquilibriumResult) {
+        row.extend(
+            self.species
+                .iter()
+                .map(|&index| Cell::Number(composition.mole_fraction(index))),
+        );
+        self.rows.push(row);
+    }
+
+    // Add a row of scalars alone, for a table without mole-fraction columns
+    pub(crate) fn push_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    // Comment lines naming the database and any patches to it, then the column names, then
+    // the rows
+    pub(crate) fn write_csv(&self, mut writer: impl Write) -> Result<(), ExportError> {
+        writeln!(writer, "# fingerprint: {}", self.fingerprint)?;
+        for patch in &self.patches {
+            writeln!(writer, "# patch: {}", patch)?;
+        }
+        let header: Vec<String> = self.columns.iter().map(|c| csv_field(c)).collect();
+        writeln!(writer, "{}", header.join(","))?;
+        for row in &self.rows {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|cell| match cell {
+                    Cell::Text(text) => csv_field(text),
+                    Cell::Number(value) => value.to_string(),
+                    Cell::Count(count) => count.to_string(),
+                })
+                .collect();
+            writeln!(writer, "{}", fields.join(","))?;
+        }
+        Ok(())
+    }
+
+    // An object of the database's "fingerprint", its "patches" and the "rows", an array of
+    // objects, one per row, with keys in column order. Non-finite numbers become null.
+    pub(crate) fn to_json(&self) -> Result<String, ExportError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl Serialize for Table {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("fingerprint", &self.fingerprint)?;
+        map.serialize_entry("patches", &self.patches)?;
+        map.serialize_entry("rows", &Rows(self))?;
+        map.end()
+    }
+}
+
+struct Rows<'a>(&'a Table);
+
+impl Serialize for Rows<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Rows(table) = self;
+        let mut seq = serializer.serialize_seq(Some(table.rows.len()))?;
+        for row in &table.rows {
+            seq.serialize_element(&Row {
+                columns: &table.columns,
+                cells: row,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct Row<'a> {
+    columns: &'a [String],
+    cells: &'a [Cell],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.cells.len()))?;
+        for (column, cell) in self.columns.iter().zip(self.cells) {
+            match cell {
+                Cell::Text(text) => map.serialize_entry(column, text)?,
+                Cell::Number(value) => map.serialize_entry(column, value)?,
+                Cell::Count(count) => map.serialize_entry(column, count)?,
+            }
+        }
+        map.end()
+    }
+}
+
+// Quote a field holding a separator, quote or line break, doubling any quotes
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(csv_field("X(H2O)"), "X(H2O)");
+        assert_eq!(csv_field("C2H4,cyclo"), "\"C2H4,cyclo\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,352 @@
+use crate::database::ThermoFile;
+use crate::equilibrium::{EquilibriumOptions, Problem, SolverError, solve_equilibrium};
+use crate::mixture::Mixture;
+use crate::numeric::{NumericError, finite, positive};
+use crate::rocket::SOUND_SPEED_STEP;
+
+// Step in ln p (or relative step in T for a frozen temperature) below which a state is accepted
+const TOLERANCE: f64 = 1.0e-10;
+
+const MAX_ITERATIONS: usize = 50;
+
+// A gas state, static or total (stagnation)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlowState {
+    pub temperature: f64, // K
+    pub pressure: f64,    // Pa
+}
+
+// One point of a state's isentrope, per unit mass
+#[derive(Debug, Clone, Copy)]
+struct Point {
+    temperature: f64, // K
+    pressure: f64,    // Pa
+    enthalpy: f64,    // J/kg
+    entropy: f64,     // J/(kg K)
+    density: f64,     // kg/m³
+}
+
+// Compressible-flow relations for a gas mixture with temperature-dependent properties. Each
+// takes a `frozen` flag: true holds the mixture's composition fixed, false re-equilibrates it
+// at every state visited, the mixture then giving only the element totals.
+#[derive(Debug, Clone)]
+pub struct GasDynamics<'a> {
+    db: &'a ThermoFile,
+    mixture: &'a Mixture,
+    options: EquilibriumOptions,
+}
+
+impl<'a> GasDynamics<'a> {
+    pub fn new(db: &'a ThermoFile, mixture: &'a Mixture) -> Self {
+        GasDynamics {
+            db,
+            mixture,
+            options: EquilibriumOptions::default(),
+        }
+    }
+
+    // Settings for the equilibrium solves
+    pub fn with_options(mut self, options: EquilibriumOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Speed of sound at T and p, m/s: sqrt(gamma R T / M) for a frozen composition, otherwise
+    // sqrt((dp/drho)_s) differenced along the equilibrium isentrope
+    pub fn sound_speed(
+        &self,
+        temperature: f64,
+        pressure: f64,
+        frozen: bool,
+    ) -> Result<f64, SolverError> {
+        let point = self.point(temperature, pressure, frozen)?;
+        self.sound_speed_at(&point, frozen)
+    }
+
+    // Mach number of a flow at a velocity (m/s) and static T and p
+    pub fn mach(
+        &self,
+        velocity: f64,
+        temperature: f64,
+        pressure: f64,
+        frozen: bool,
+    ) -> Result<f64, SolverError> {
+        finite("the velocity", velocity)?;
+        Ok(velocity / self.sound_speed(temperature, pressure, frozen)?)
+    }
+
+    // The state a flow at static T and p and a Mach number reaches when brought to rest
+    // isentropically: h0 = h + V²/2 on the static state's isentrope
+    pub fn total_from_static(
+        &self,
+        temperature: f64,
+        pressure: f64,
+        mach: f64,
+        frozen: bool,
+    ) -> Result<FlowState, SolverError> {
+        finite("the Mach number", mach)?;
+        let point = self.point(temperature, pressure, frozen)?;
+        let velocity = mach * self.sound_speed_at(&point, frozen)?;
+        let total_enthalpy = point.enthalpy + 0.5 * velocity * velocity;
+
+        // Newton's method in ln p, with dh/d(ln p) = p/rho along the isentrope
+        let mut current = point;
+        for _ in 0..MAX_ITERATIONS {
+            let step = (total_enthalpy - current.enthalpy) * current.density / current.pressure;
+            if !step.is_finite() {
+                break;
+            }
+            let pressure = current.pressure * step.exp();
+            current = self.on_isentrope(point.entropy, pressure, current.temperature, frozen)?;
+            if step.abs() <= TOLERANCE {
+                return Ok(FlowState {
+                    temperature: current.temperature,
+                    pressure: current.pressure,
+                });
+            }
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // The static state of a flow at a Mach number expanded isentropically from total T0 and
+    // p0: the point of the total state's isentrope where h0 - h = (M a)²/2
+    pub fn static_from_total(
+        &self,
+        temperature: f64,
+        pressure: f64,
+        mach: f64,
+        frozen: bool,
+    ) -> Result<FlowState, SolverError> {
+        finite("the Mach number", mach)?;
+        let total = self.point(temperature, pressure, frozen)?;
+        let residual = |point: &Point| -> Result<f64, SolverError> {
+            let velocity = mach * self.sound_speed_at(point, frozen)?;
+            Ok(2.0 * (total.enthalpy - point.enthalpy) - velocity * velocity)
+        };
+
+        // Secant iteration in ln p, from the total state and the constant-gamma estimate
+        let gamma = self
+            .mixture
+            .gamma(self.db, temperature)
+            .ok_or_else(no_fit)?;
+        let ratio = 1.0 + 0.5 * (gamma - 1.0) * mach * mach;
+        let mut previous = (pressure.ln(), residual(&total)?);
+        let mut guess = temperature / ratio;
+        let mut ln_p = pressure.ln() - gamma / (gamma - 1.0) * ratio.ln();
+        for _ in 0..MAX_ITERATIONS {
+            let point = self.on_isentrope(total.entropy, ln_p.exp(), guess, frozen)?;
+            let value = residual(&point)?;
+            let step = secant_step(ln_p, value, previous)?;
+            if step.abs() <= TOLERANCE {
+                return Ok(FlowState {
+                    temperature: point.temperature,
+                    pressure: point.pressure,
+                });
+            }
+            previous = (ln_p, value);
+            ln_p += step;
+            guess = point.temperature;
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // The mixture at T and p, frozen or brought to equilibrium
+    fn point(&self, temperature: f64, pressure: f64, frozen: bool) -> Result<Point, SolverError> {
+        positive("temperature", temperature)?;
+        positive("pressure", pressure)?;
+        self.mixture.check_amounts(self.db)?;
+        if frozen {
+            let (db, mixture) = (self.db, self.mixture);
+            return Ok(Point {
+                temperature,
+                pressure,
+                enthalpy: mixture.enthalpy_mass(db, temperature).ok_or_else(no_fit)?,
+                entropy: mixture
+                    .entropy_mass(db, temperature, pressure)
+                    .ok_or_else(no_fit)?,
+                density: mixture
+                    .density(db, temperature, pressure)
+                    .ok_or_else(no_fit)?,
+            });
+        }
+        let problem = Problem::TP {
+            temperature,
+            pressure,
+        };
+        self.solve(problem)
+    }
+
+    // The point at an entropy (J/(kg K)) and pressure, from a temperature guess if frozen
+    fn on_isentrope(
+        &self,
+        entropy: f64,
+        pressure: f64,
+        guess: f64,
+        frozen: bool,
+    ) -> Result<Point, SolverError> {
+        if !frozen {
+            let problem = Problem::SP {
+                entropy: entropy * self.mass(),
+                pressure,
+            };
+            return self.solve(problem);
+        }
+
+        // Newton's method on temperature, with ds/dT = cp/T at fixed composition
+        let mut temperature = guess;
+        for _ in 0..MAX_ITERATIONS {
+            let point = self.point(temperature, pressure, true)?;
+            let cp = self
+                .mixture
+                .cp_mass(self.db, temperature)
+                .ok_or_else(no_fit)?;
+            let step = (entropy - point.entropy) * temperature / cp;
+            temperature += step;
+            if step.abs() <= TOLERANCE * temperature {
+                return self.point(temperature, pressure, true);
+            }
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    fn sound_speed_at(&self, point: &Point, frozen: bool) -> Result<f64, SolverError> {
+        if frozen {
+            return self
+                .mixture
+                .speed_of_sound(self.db, point.temperature)
+                .ok_or_else(no_fit);
+        }
+        let guess = point.temperature;
+        let high = point.pressure * (1.0 + SOUND_SPEED_STEP);
+        let low = point.pressure * (1.0 - SOUND_SPEED_STEP);
+        let high = self.on_isentrope(point.entropy, high, guess, false)?;
+        let low = self.on_isentrope(point.entropy, low, guess, false)?;
+        Ok(((high.pressure - low.pressure) / (high.density - low.density)).sqrt())
+    }
+
+    // Starting each solve from the options' temperature guess rather than a nearby state's
+    // temperature: from below a few hundred kelvin the solver needs close to its iteration limit
+    fn solve(&self, problem: Problem) -> Result<Point, SolverError> {
+        let result = solve_equilibrium(self.db, self.mixture, problem, &self.options)?;
+        let mass = result.mass(self.db);
+        Ok(Point {
+            temperature: result.temperature,
+            pressure: result.pressure,
+            enthalpy: result.enthalpy(self.db) / mass,
+            entropy: result.entropy(self.db) / mass,
+            density: result.density(self.db),
+        })
+    }
+
+    // Mass of the amounts the mixture describes, kg
+    fn mass(&self) -> f64 {
+        self.mixture.molar_mass(self.db) * self.mixture.total()
+    }
+}
+
+// The secant step in ln p from the last residual and the one before, zero once the residual is.
+// A step lost to overflow or to a flat secant is an error, not convergence.
+fn secant_step(ln_p: f64, value: f64, previous: (f64, f64)) -> Result<f64, NumericError> {
+    if value == 0.0 {
+        return Ok(0.0);
+    }
+    finite(
+        "the static pressure iteration",
+        value * (ln_p - previous.0) / (previous.1 - value),
+    )
+}
+
+fn no_fit() -> SolverError {
+    SolverError::InvalidInput("mixture has no fit at this temperature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_mach_three_air() {
+        let db = full_database();
+        let air =
+            Mixture::from_names(db, &[("N2", 0.7808), ("O2", 0.2095), ("Ar", 0.0097)]).unwrap();
+        let flow = GasDynamics::new(db, &air);
+        let (t, p) = (300.0, 101_325.0);
+
+        let a = flow.sound_speed(t, p, true).unwrap();
+        assert!((a - 347.2).abs() < 0.5, "{}", a);
+        let mach = flow.mach(3.0 * a, t, p, true).unwrap();
+        assert!((mach - 3.0).abs() < 1e-12);
+
+        // Air tables with variable cp give h0 = 300.19 + 542.5 kJ/kg at about 819 K, where a
+        // constant gamma of 1.4 would give 840 K, and a relative pressure ratio of 37.7
+        let total = flow.total_from_static(t, p, 3.0, true).unwrap();
+        assert!((total.temperature - 819.0).abs() < 1.0, "{:?}", total);
+        assert!((total.pressure / p - 37.7).abs() < 0.1, "{:?}", total);
+
+        let back = flow
+            .static_from_total(total.temperature, total.pressure, 3.0, true)
+            .unwrap();
+        assert!((back.temperature - t).abs() < 1e-6, "{:?}", back);
+        assert!((back.pressure / p - 1.0).abs() < 1e-8, "{:?}", back);
+
+        // Nothing dissociates at these temperatures, so equilibrium agrees with frozen
+        let shifting = flow.total_from_static(t, p, 3.0, false).unwrap();
+        assert!((shifting.temperature - total.temperature).abs() < 0.5);
+        assert!((shifting.pressure / total.pressure - 1.0).abs() < 1e-3);
+        let back = flow
+            .static_from_total(shifting.temperature, shifting.pressure, 3.0, false)
+            .unwrap();
+        assert!((back.temperature - t).abs() < 1e-3, "{:?}", back);
+    }
+
+    #[test]
+    fn test_static_from_total_steps() {
+        // At Mach 0 the static state is the total state
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let flow = GasDynamics::new(db, &air);
+        let still = flow.static_from_total(3000.0, 1.0e6, 0.0, true).unwrap();
+        assert_eq!(still.temperature, 3000.0);
+        assert!((still.pressure / 1.0e6 - 1.0).abs() < 1e-12);
+
+        // A flat secant or an overflowing residual stops the iteration rather than ending it
+        assert_eq!(secant_step(1.0, 0.0, (0.5, 2.0)), Ok(0.0));
+        assert_eq!(secant_step(1.0, 2.0, (0.0, 4.0)), Ok(1.0));
+        for (value, previous) in [(2.0, (0.5, 2.0)), (f64::INFINITY, (0.5, 2.0))] {
+            assert_eq!(
+                secant_step(1.0, value, previous),
+                Err(NumericError::NonFinite {
+                    location: "the static pressure iteration"
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_equilibrium_sound_speed_is_lower() {
+        // Dissociating air: the composition shifts with the pressure wave, softening the gas
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let flow = GasDynamics::new(db, &air);
+        let equilibrium = flow.sound_speed(4000.0, 1.0e5, false).unwrap();
+        let problem = Problem::TP {
+            temperature: 4000.0,
+            pressure: 1.0e5,
+        };
+        let options = EquilibriumOptions::default();
+        let products = solve_equilibrium(db, &air, problem, &options)
+            .unwrap()
+            .to_mixture();
+        let frozen = GasDynamics::new(db, &products)
+            .sound_speed(4000.0, 1.0e5, true)
+            .unwrap();
+        assert!(equilibrium < frozen, "{} {}", equilibrium, frozen);
+    }
+}
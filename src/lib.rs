@@ -0,0 +1,28 @@
+pub mod cache;
+pub mod database;
+pub mod diff;
+pub mod elements;
+pub mod equilibrium;
+pub mod export;
+pub mod gasdyn;
+pub mod lookup;
+pub mod mixture;
+pub mod numeric;
+pub mod patch;
+pub mod phases;
+pub mod properties;
+pub mod property_table;
+pub mod reaction;
+pub mod repl;
+pub mod rocket;
+pub mod shomate;
+pub mod similarity;
+pub mod stats;
+pub mod units;
+pub mod validation;
+mod writer;
+
+#[cfg(test)]
+mod strategies;
+#[cfg(test)]
+mod test_data;
@@ -0,0 +1,562 @@
+use std::fmt;
+use std::ops::Index;
+
+use crate::database::{Species, ThermoFile};
+use crate::elements::Element;
+use crate::phases::base_name;
+
+// Most close names offered for one that isn't in the database
+const MAX_SUGGESTIONS: usize = 3;
+
+// Names in common use for species CEA files under another name. Each applies only when its
+// target is in the database, and after any alias added to the database itself.
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("ELECTRON", "e-"),
+    ("GRAPHITE", "C(gr)"),
+    ("JET-A", "Jet-A(g)"),
+    ("JP-10", "JP-10(g)"),
+    ("LOX", "O2(L)"),
+    ("LH2", "H2(L)"),
+    ("RP1", "RP-1"),
+];
+
+// A species name not in the database, with the closest names that are, or one matching more
+// than one species ignoring case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSpecies {
+    pub name: String,
+    pub suggestions: Vec<String>, // Closest first, at most three
+    pub ambiguous: Vec<String>,   // Every species the name matches ignoring case, if several
+}
+
+impl fmt::Display for UnknownSpecies {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some((last, rest)) = self.ambiguous.split_last() {
+            let quoted: Vec<String> = rest.iter().map(|s| format!("'{}'", s)).collect();
+            return write!(
+                f,
+                "'{}' matches {} and '{}' ignoring case; give the exact name",
+                self.name,
+                quoted.join(", "),
+                last
+            );
+        }
+        write!(f, "unknown species '{}'", self.name)?;
+        let quoted: Vec<String> = self
+            .suggestions
+            .iter()
+            .map(|s| format!("'{}'", s))
+            .collect();
+        match quoted.split_last() {
+            None => Ok(()),
+            Some((last, [])) => write!(f, "; did you mean {}?", last),
+            Some((last, rest)) => write!(f, "; did you mean {} or {}?", rest.join(", "), last),
+        }
+    }
+}
+
+impl std::error::Error for UnknownSpecies {}
+
+// Why an alias could not be added
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasError {
+    // The alias is itself the exact name of a species, which it would hide
+    Shadows(String),
+    // The target names neither a species nor another alias
+    MissingTarget(UnknownSpecies),
+    // Following the target's aliases leads back to the alias, listed from alias to alias
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasError::Shadows(name) => {
+                write!(f, "alias '{}' is already the name of a species", name)
+            }
+            AliasError::MissingTarget(error) => write!(f, "alias target: {}", error),
+            AliasError::Cycle(chain) => write!(f, "alias cycle: {}", chain.join(" -> ")),
+        }
+    }
+}
+
+impl std::error::Error for AliasError {}
+
+// A name folded for comparison: lower case, with the digit zero read as the letter O, the
+// commonest slip in typing a formula
+fn folded(name: &str) -> Vec<u8> {
+    name.bytes()
+        .map(|c| {
+            if c == b'0' {
+                b'o'
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+// Levenshtein distance between two folded names, or None once it is sure to exceed `limit`
+fn edit_distance(a: &str, b: &str, limit: usize) -> Option<usize> {
+    let (a, b) = (folded(a), folded(b));
+    if a.len().abs_diff(b.len()) > limit {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        // Distances along a row never shrink further down, so a row past the limit ends it
+        if current.iter().all(|&d| d > limit) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Some(previous[b.len()]).filter(|&d| d <= limit)
+}
+
+impl ThermoFile {
+    // Look up a species by its exact name, suggesting close names when there is none
+    pub fn get(&self, name: &str) -> Result<&Species, UnknownSpecies> {
+        self.index_of(name).map(|index| &self.species[index])
+    }
+
+    // Position of a species by its exact name, suggesting close names when there is none
+    pub fn index_of(&self, name: &str) -> Result<usize, UnknownSpecies> {
+        self.species_index(name).ok_or_else(|| UnknownSpecies {
+            name: name.to_string(),
+            suggestions: self.suggestions(name),
+            ambiguous: Vec::new(),
+        })
+    }
+
+    // Position of a species by its exact name, then an alias, then its name ignoring case,
+    // suggesting close names when none of these finds it. A name matching several species
+    // ignoring case, as "co" does CO and Co, is refused with them listed.
+    pub fn resolve(&self, name: &str) -> Result<usize, UnknownSpecies> {
+        if let Some(index) = self.species_index(name) {
+            return Ok(index);
+        }
+        let mut target = name;
+        let mut followed = 0;
+        while let Some(next) = self.alias_target(target) {
+            if let Some(index) = self.species_index(next) {
+                return Ok(index);
+            }
+            // Cycles are refused when aliases are added, so this only guards the built-ins
+            followed += 1;
+            if followed > self.aliases.len() + BUILTIN_ALIASES.len() {
+                break;
+            }
+            target = next;
+        }
+        let matches: Vec<usize> = (0..self.species.len())
+            .filter(|&index| self.species[index].name.eq_ignore_ascii_case(name))
+            .collect();
+        match matches[..] {
+            [index] => Ok(index),
+            [] => Err(UnknownSpecies {
+                name: name.to_string(),
+                suggestions: self.suggestions(name),
+                ambiguous: Vec::new(),
+            }),
+            _ => Err(UnknownSpecies {
+                name: name.to_string(),
+                suggestions: Vec::new(),
+                ambiguous: matches
+                    .iter()
+                    .map(|&index| self.species[index].name.clone())
+                    .collect(),
+            }),
+        }
+    }
+
+    // Look up a species as `resolve` does, by exact name, alias or name ignoring case
+    pub fn get_ci(&self, name: &str) -> Result<&Species, UnknownSpecies> {
+        self.resolve(name).map(|index| &self.species[index])
+    }
+
+    // Let `alias` stand for `target`, a species name or another alias, wherever names are
+    // resolved. An alias never hides a species of the same name, and chains of aliases must
+    // end at a species.
+    pub fn add_alias(&mut self, alias: &str, target: &str) -> Result<(), AliasError> {
+        if self.species_index(alias).is_some() {
+            return Err(AliasError::Shadows(alias.to_string()));
+        }
+        let mut chain = vec![alias.to_string()];
+        let mut next = target;
+        while self.species_index(next).is_none() {
+            chain.push(next.to_string());
+            if next.eq_ignore_ascii_case(alias) {
+                return Err(AliasError::Cycle(chain));
+            }
+            next = self.alias_target(next).ok_or_else(|| {
+                AliasError::MissingTarget(UnknownSpecies {
+                    name: target.to_string(),
+                    suggestions: self.suggestions(target),
+                    ambiguous: Vec::new(),
+                })
+            })?;
+        }
+        self.aliases
+            .insert(alias.to_ascii_lowercase(), target.to_string());
+        Ok(())
+    }
+
+    // What `alias` stands for, matched ignoring case, from the database's own aliases first
+    fn alias_target(&self, alias: &str) -> Option<&str> {
+        if let Some(target) = self.aliases.get(&alias.to_ascii_lowercase()) {
+            return Some(target);
+        }
+        BUILTIN_ALIASES
+            .iter()
+            .find(|(name, target)| {
+                name.eq_ignore_ascii_case(alias) && self.species_index(target).is_some()
+            })
+            .map(|&(_, target)| target)
+    }
+
+    // Up to three species names within a few edits of `name`, as folded, closest first.
+    // A name also matches through its base name, so "H20" finds "H2O(L)" as well as "H2O".
+    // Ties go to names of the same length, as a mistyped character is likelier than a
+    // missing one. Names differing in length by more than the allowed edits are passed over
+    // unexamined, which keeps a scan of the full database cheap.
+    pub fn suggestions(&self, name: &str) -> Vec<String> {
+        let limit = 1 + name.len() / 3;
+        let mut scored: Vec<((usize, usize, usize), &str)> = self
+            .species
+            .iter()
+            .filter_map(|species| {
+                let full = edit_distance(name, &species.name, limit);
+                let base = edit_distance(name, base_name(&species.name), limit);
+                let closest = full.into_iter().chain(base).min()?;
+                let length = name.len().abs_diff(species.name.len());
+                let score = (closest, full.unwrap_or(usize::MAX), length);
+                Some((score, species.name.as_str()))
+            })
+            .collect();
+        scored.sort();
+        scored.dedup_by_key(|&mut (_, name)| name);
+        scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+}
+
+// Which species to pick out by the elements they are made of. The electron pseudo-element E,
+// with which CEA lists a charge, is not taken for an element unless `include_charge` asks for
+// it; charge is better filtered with `Species::is_ion` or `Species::charge`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpeciesQuery {
+    only_elements: Option<Vec<Element>>,
+    include_charge: bool,
+}
+
+impl SpeciesQuery {
+    // A query every species matches
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Keep only species made of no elements but these. An element-free species, the electron,
+    // passes any such test unless charge is included.
+    pub fn only_elements(mut self, elements: &[Element]) -> Self {
+        self.only_elements = Some(elements.to_vec());
+        self
+    }
+
+    // Whether E counts as an element, so that a charged species passes `only_elements` only
+    // when E is among them
+    pub fn include_charge(mut self, include_charge: bool) -> Self {
+        self.include_charge = include_charge;
+        self
+    }
+
+    // Whether a species passes every test of the query
+    pub fn matches(&self, species: &Species) -> bool {
+        let Some(allowed) = &self.only_elements else {
+            return true;
+        };
+        let charge_allowed =
+            !self.include_charge || !species.is_ion() || allowed.contains(&Element::ELECTRON);
+        charge_allowed
+            && species
+                .chemical_elements()
+                .iter()
+                .all(|element| allowed.contains(element))
+    }
+}
+
+impl ThermoFile {
+    // Species matching a query, in database order
+    pub fn query(&self, query: &SpeciesQuery) -> Vec<&Species> {
+        self.species
+            .iter()
+            .filter(|species| query.matches(species))
+            .collect()
+    }
+
+    // Species made of no elements but these, charged or not (see `SpeciesQuery`)
+    pub fn containing_only_elements(&self, elements: &[Element]) -> Vec<&Species> {
+        self.query(&SpeciesQuery::new().only_elements(elements))
+    }
+
+    // Species whose names match a glob pattern, in database order: '*' stands for any run of
+    // characters and '?' for any one, so "C2H*" finds C2H2,acetylene and C2H6 alike
+    pub fn matching(&self, pattern: &str) -> Vec<&Species> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        self.species
+            .iter()
+            .filter(|species| glob_match(&pattern, &species.name.chars().collect::<Vec<_>>()))
+            .collect()
+    }
+}
+
+// Whether a name matches a glob pattern, backtracking to the last '*' on a mismatch
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star = None; // Pattern position after the last '*', and the name position it took
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, taken)) => {
+                    p = after;
+                    n = taken + 1;
+                    star = Some((after, taken + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// `db["N2"]` for a species known to be present, panicking as `get` would fail otherwise
+impl Index<&str> for ThermoFile {
+    type Output = Species;
+
+    fn index(&self, name: &str) -> &Species {
+        self.get(name).unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{database_with_reactants, full_database};
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("H20", "H2O", 2), Some(0));
+        assert_eq!(edit_distance("H20", "H2", 2), Some(1));
+        assert_eq!(edit_distance("co2", "CO2", 0), Some(0));
+        assert_eq!(edit_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("N2", "C2H5OH", 2), None);
+    }
+
+    #[test]
+    fn test_glob_matching() {
+        let glob = |pattern: &str, name: &str| {
+            let chars = |text: &str| text.chars().collect::<Vec<_>>();
+            glob_match(&chars(pattern), &chars(name))
+        };
+        assert!(glob("C2H*", "C2H2,acetylene"));
+        assert!(glob("*", ""));
+        assert!(glob("H2O(?)", "H2O(L)"));
+        assert!(glob("*O*O*", "HOCO"));
+        assert!(!glob("C2H*", "C2"));
+        assert!(!glob("H2O", "H2O2"));
+
+        let db = database_with_reactants();
+        let names: Vec<&str> = db
+            .matching("CH4*")
+            .iter()
+            .map(|species| species.name.as_str())
+            .collect();
+        assert_eq!(names, ["CH4", "CH4(L)"]);
+        assert!(db.matching("Q*").is_empty());
+    }
+
+    #[test]
+    fn test_typo_suggestions() {
+        let db = full_database();
+        let error = db.get("H20").unwrap_err();
+        assert_eq!(error.name, "H20");
+        assert_eq!(error.suggestions[0], "H2O");
+        assert_eq!(error.suggestions.len(), 3);
+        assert!(
+            error
+                .suggestions
+                .iter()
+                .all(|name| base_name(name) == "H2O"),
+            "{:?}",
+            error.suggestions
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "unknown species 'H20'; did you mean '{}', '{}' or '{}'?",
+                error.suggestions[0], error.suggestions[1], error.suggestions[2]
+            )
+        );
+        let error = db.get("CH3OHH").unwrap_err();
+        assert_eq!(error.suggestions[..2], ["CH3OOH", "CH3OH"]);
+    }
+
+    #[test]
+    fn test_case_difference_suggestions() {
+        let db = full_database();
+        let error = db.index_of("co2").unwrap_err();
+        assert_eq!(error.suggestions[0], "CO2");
+        assert_eq!(db.index_of("CO2"), Ok(db.species_index("CO2").unwrap()));
+    }
+
+    #[test]
+    fn test_alias_resolution_order() {
+        let mut db = full_database().clone();
+        let index = |name| db.species_index(name).unwrap();
+        let (co, co2, ar, electron) = (index("CO"), index("CO2"), index("Ar"), index("e-"));
+
+        assert_eq!(db.resolve("CO"), Ok(co));
+        assert_eq!(db.resolve("AR"), Ok(ar));
+        assert_eq!(db.resolve("co2"), Ok(co2));
+
+        // A name matching several species ignoring case matches none, listing them
+        let error = db.resolve("co").unwrap_err();
+        assert_eq!(error.ambiguous, ["CO", "Co"]);
+        assert_eq!(
+            error.to_string(),
+            "'co' matches 'CO' and 'Co' ignoring case; give the exact name"
+        );
+        assert_eq!(db.resolve("sn").unwrap_err().ambiguous, ["SN", "Sn"]);
+        assert_eq!(db.get_ci("electron").unwrap().name, "e-");
+        assert_eq!(db.resolve("Electron"), Ok(electron));
+        assert!(db.resolve("lox").is_err(), "O2(L) is not in the products");
+
+        // An alias wins over a case-insensitive match, but never over an exact name
+        db.add_alias("co", "CO2").unwrap();
+        assert_eq!(db.resolve("co"), Ok(co2));
+        assert_eq!(db.resolve("cO"), Ok(co2));
+        assert_eq!(db.resolve("CO"), Ok(co));
+        assert_eq!(db.get_ci("Co").unwrap().name, "Co", "cobalt");
+        assert_eq!(
+            db.add_alias("CO", "CO2"),
+            Err(AliasError::Shadows("CO".into()))
+        );
+
+        db.add_alias("dioxide", "co").unwrap();
+        assert_eq!(db.get_ci("DIOXIDE").unwrap().name, "CO2");
+
+        let mut reactants = database_with_reactants().clone();
+        reactants.add_alias("atmosphere", "Air").unwrap();
+        assert_eq!(reactants.get_ci("Atmosphere").unwrap().name, "Air");
+        assert_eq!(reactants.get_ci("LOX").unwrap().name, "O2(L)");
+    }
+
+    #[test]
+    fn test_alias_errors() {
+        let mut db = full_database().clone();
+        let error = db.add_alias("water", "H20").unwrap_err();
+        let AliasError::MissingTarget(missing) = &error else {
+            panic!("{:?}", error);
+        };
+        assert_eq!(missing.name, "H20");
+        assert_eq!(missing.suggestions[0], "H2O");
+        assert!(db.resolve("water").is_err());
+
+        db.add_alias("a", "CO2").unwrap();
+        db.add_alias("b", "a").unwrap();
+        assert_eq!(
+            db.add_alias("a", "b"),
+            Err(AliasError::Cycle(vec!["a".into(), "b".into(), "a".into()]))
+        );
+        assert_eq!(db.get_ci("b").unwrap().name, "CO2");
+        assert_eq!(
+            db.add_alias("c", "c").unwrap_err().to_string(),
+            "alias cycle: c -> c"
+        );
+    }
+
+    #[test]
+    fn test_index_by_name() {
+        let db = full_database();
+        assert_eq!(db["N2"].name, "N2");
+        assert!(std::ptr::eq(&db["CO2"], db.find_species("CO2").unwrap()));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown species 'N22'")]
+    fn test_index_by_absent_name() {
+        let _ = &full_database()["N22"];
+    }
+
+    #[test]
+    fn test_absent_species() {
+        let db = full_database();
+        let error = db.get("Unobtainium").unwrap_err();
+        assert_eq!(error.suggestions, Vec::<String>::new());
+        assert_eq!(error.to_string(), "unknown species 'Unobtainium'");
+    }
+
+    #[test]
+    fn test_charge_in_element_queries() {
+        let db = full_database();
+        let (ion, electron, neutral) = (&db["HCO+"], &db["e-"], &db["HCO"]);
+        assert_eq!((ion.charge(), ion.is_ion()), (1.0, true));
+        assert_eq!((electron.charge(), electron.is_ion()), (-1.0, true));
+        assert_eq!((neutral.charge(), neutral.is_ion()), (0.0, false));
+        let symbols = |species: &Species| -> Vec<String> {
+            let elements = species.chemical_elements();
+            elements.iter().map(|e| e.to_string()).collect()
+        };
+        assert_eq!(symbols(ion), ["H", "C", "O"]);
+        assert_eq!(symbols(electron), Vec::<String>::new());
+
+        let element = |symbol| Element::new(symbol).unwrap();
+        let cho = [element("C"), element("H"), element("O")];
+        let picked = |query: SpeciesQuery| -> Vec<&str> {
+            let names = ["HCO+", "e-", "HCO", "N2"];
+            let found = db.query(&query);
+            names
+                .into_iter()
+                .filter(|&name| found.iter().any(|species| species.name == name))
+                .collect()
+        };
+
+        // By default charge is no element: the ion and the electron qualify by what they are
+        // made of, and ions are told apart by `is_ion`
+        let only_cho = SpeciesQuery::new().only_elements(&cho);
+        assert_eq!(picked(only_cho.clone()), ["HCO+", "e-", "HCO"]);
+        let names: Vec<&str> = db
+            .containing_only_elements(&cho)
+            .into_iter()
+            .filter(|species| !species.is_ion())
+            .map(|species| species.name.as_str())
+            .collect();
+        assert!(names.contains(&"HCO") && !names.contains(&"HCO+"));
+
+        // Including charge, a charged species needs E among the elements
+        assert_eq!(picked(only_cho.include_charge(true)), ["HCO"]);
+        let with_e = [cho.as_slice(), &[Element::ELECTRON]].concat();
+        let query = SpeciesQuery::new()
+            .only_elements(&with_e)
+            .include_charge(true);
+        assert_eq!(picked(query), ["HCO+", "e-", "HCO"]);
+        assert_eq!(picked(SpeciesQuery::new()), ["HCO+", "e-", "HCO", "N2"]);
+    }
+}
@@ -1,28 +1,612 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-mod database;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
-fn main() {
-    let mut file = match File::open("./thermo-snippet.inp") {
-        Ok(file) => file,
-        Err(e) => {
-            eprintln!("{}", e);
-            return;
+use equilibrium_rs::database::{
+    self, ParseWarning, ParseWarningKind, ThermoFile, ThermoParseError,
+};
+use equilibrium_rs::elements::parse_formula;
+use equilibrium_rs::equilibrium::{EquilibriumOptions, Preset, Problem, solve_sweep};
+use equilibrium_rs::mixture::{Mixture, ReactantState};
+use equilibrium_rs::patch::PatchSpec;
+use equilibrium_rs::property_table::PropertyTable;
+use equilibrium_rs::repl;
+use equilibrium_rs::rocket::{
+    RocketOptions, SEA_LEVEL_PRESSURE, SEPARATION_RATIO, Station, rocket_performance,
+};
+use equilibrium_rs::stats::DbStats;
+use equilibrium_rs::units;
+
+#[derive(Parser)]
+#[command(about = "Chemical equilibrium and rocket performance from NASA thermo data")]
+struct Cli {
+    #[arg(
+        long,
+        global = true,
+        default_value = "./thermo-snippet.inp",
+        help = "Thermo database; when repeated, later files' species replace earlier ones'"
+    )]
+    db: Vec<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_delimiter = ',',
+        help = "Other names for species, as ALIAS=NAME"
+    )]
+    alias: Vec<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Change a fit's coefficient, as NAME:FIT:COEFF=VALUE (or *= or +=), fits from 0"
+    )]
+    patch: Vec<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Equilibrium at every pair of assigned temperature and pressure")]
+    Eq(EqArgs),
+    #[command(about = "Rocket performance through a nozzle")]
+    Rocket(RocketArgs),
+    #[command(about = "Species counts by kind, element and number of fits")]
+    Stats(StatsArgs),
+    #[command(about = "A species' record, and the file and line it was read from")]
+    Info(InfoArgs),
+    #[command(about = "Species' cp, h, s and g over a list of temperatures")]
+    Props(PropsArgs),
+    #[command(about = "Interactive queries against the database, loaded once; 'help' lists them")]
+    Repl,
+    #[command(about = "Species most like a missing one, by formula and then by Cp")]
+    Suggest(SuggestArgs),
+    #[command(about = "A hash of the loaded data, the same however its files order species")]
+    Fingerprint,
+}
+
+// Settings shared by every calculation
+#[derive(Args)]
+struct Common {
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        help = "Reactant moles, as H2=2,O2=1"
+    )]
+    reactants: Vec<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Species to report mole fractions of"
+    )]
+    species: Vec<String>,
+    #[arg(
+        long,
+        help = "Write the table to a .csv or .json file instead of stdout"
+    )]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct EqArgs {
+    #[command(flatten)]
+    common: Common,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_parser = temperature,
+        help = "Temperatures, K unless suffixed with C, F or R"
+    )]
+    temperature: Vec<f64>,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_parser = pressure,
+        help = "Pressures, Pa unless suffixed with kPa, MPa, bar, atm, psia or torr"
+    )]
+    pressure: Vec<f64>,
+    #[arg(long, help = "Leave condensed phases out of the products")]
+    gas_only: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Species never to consider as products"
+    )]
+    omit: Vec<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Species to consider whatever the phase, condensed ones starting in the products"
+    )]
+    insert: Vec<String>,
+}
+
+#[derive(Args)]
+struct RocketArgs {
+    #[command(flatten)]
+    common: Common,
+    #[arg(long, value_parser = pressure, help = "Chamber pressure, Pa or with a unit")]
+    chamber_pressure: f64,
+    #[arg(
+        long,
+        default_value_t = 298.15,
+        value_parser = temperature,
+        help = "Reactant temperature, K or with a unit"
+    )]
+    reactant_temperature: f64,
+    #[arg(long, help = "Reactant enthalpy, J/kg, in place of the reactants' own")]
+    reactant_enthalpy: Option<f64>,
+    #[arg(long, help = "Finite-area combustor contraction ratio")]
+    contraction_ratio: Option<f64>,
+    #[arg(long, value_delimiter = ',', help = "Chamber-to-exit pressure ratios")]
+    pressure_ratio: Vec<f64>,
+    #[arg(long, value_delimiter = ',', help = "Subsonic area ratios")]
+    subsonic_area_ratio: Vec<f64>,
+    #[arg(long, value_delimiter = ',', help = "Supersonic area ratios")]
+    supersonic_area_ratio: Vec<f64>,
+    #[arg(long, help = "Also expand with composition frozen from here")]
+    freeze_at: Option<Freeze>,
+    #[arg(
+        long,
+        default_value_t = SEA_LEVEL_PRESSURE,
+        value_parser = ambient_pressure,
+        help = "Ambient pressure for the ambient Isp and thrust coefficient, Pa or with a unit, \
+                0 for vacuum"
+    )]
+    ambient_pressure: f64,
+}
+
+#[derive(Args)]
+struct StatsArgs {
+    #[arg(long, value_enum, default_value_t = ReportFormat::Table, help = "Report format")]
+    format: ReportFormat,
+}
+
+#[derive(Args)]
+struct InfoArgs {
+    #[arg(help = "Species name or alias")]
+    name: String,
+}
+
+#[derive(Args)]
+struct PropsArgs {
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        help = "Species names or aliases"
+    )]
+    species: Vec<String>,
+    #[arg(
+        long,
+        required = true,
+        value_delimiter = ',',
+        value_parser = temperature,
+        help = "Temperatures, K unless suffixed with C, F or R"
+    )]
+    temperature: Vec<f64>,
+    #[arg(
+        long,
+        help = "Write the table to a .csv, .json or (with the arrow feature) .parquet file"
+    )]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct SuggestArgs {
+    #[arg(long, help = "Formula of the missing species, as C3H6")]
+    like_formula: String,
+    #[arg(
+        long,
+        help = "CSV of its heat capacity, rows of T (K) and Cp (J/(mol K))"
+    )]
+    cp_file: PathBuf,
+    #[arg(long, default_value_t = 5, help = "How many species to list")]
+    count: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Freeze {
+    Chamber,
+    Throat,
+}
+
+// Command-line values with an optional unit suffix, converted to SI
+fn temperature(text: &str) -> Result<f64, String> {
+    units::parse_temperature(text).map_err(|e| e.to_string())
+}
+
+fn pressure(text: &str) -> Result<f64, String> {
+    units::parse_pressure(text).map_err(|e| e.to_string())
+}
+
+fn ambient_pressure(text: &str) -> Result<f64, String> {
+    units::parse_ambient_pressure(text).map_err(|e| e.to_string())
+}
+
+// Output table format, chosen by the extension of the output path
+enum Format {
+    Csv,
+    Json,
+}
+
+fn output_format(path: &Path) -> Result<Format, String> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(Format::Csv),
+        Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Format::Json),
+        _ => Err(format!(
+            "cannot tell the format of '{}'; use a .csv or .json extension",
+            path.display()
+        )),
+    }
+}
+
+// Parse "NAME=moles" pairs into a mixture
+fn reactants(db: &ThermoFile, pairs: &[String]) -> Result<Mixture, String> {
+    let mut components = Vec::new();
+    for pair in pairs {
+        let (name, amount) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=moles, found '{}'", pair))?;
+        let index = db.resolve(name.trim()).map_err(|e| e.to_string())?;
+        let amount: f64 = amount
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid amount in '{}'", pair))?;
+        components.push((index, amount));
+    }
+    Ok(Mixture::new(components))
+}
+
+// Read a database file as text, decompressing a .gz file when built with flate2
+fn read_database(path: &Path) -> Result<String, String> {
+    let describe = |e: std::io::Error| format!("could not read '{}': {}", path.display(), e);
+    #[cfg(feature = "flate2")]
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+    {
+        use std::io::Read;
+
+        let mut raw_text = String::new();
+        let file = File::open(path).map_err(describe)?;
+        flate2::read::GzDecoder::new(file)
+            .read_to_string(&mut raw_text)
+            .map_err(describe)?;
+        return Ok(raw_text);
+    }
+    std::fs::read_to_string(path).map_err(describe)
+}
+
+// Print parse warnings, then how many there were of each kind. Comments are only counted, as
+// most files open with a block of them.
+fn report_warnings(warnings: &[ParseWarning]) {
+    let mut counts: BTreeMap<ParseWarningKind, usize> = BTreeMap::new();
+    for warning in warnings {
+        if warning.kind != ParseWarningKind::SkippedComment {
+            eprintln!("warning: {}", warning);
+        }
+        *counts.entry(warning.kind).or_default() += 1;
+    }
+    if !counts.is_empty() {
+        let counts: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| format!("{}: {}", kind, count))
+            .collect();
+        eprintln!("{} warnings ({})", warnings.len(), counts.join(", "));
+    }
+}
+
+// Load a database, reporting what was read with doubt or, if some entries can't be read,
+// skipping (and counting) them
+fn load_file(path: &Path) -> Result<ThermoFile, Box<dyn Error>> {
+    match database::load_thermo(path) {
+        Ok(outcome) => {
+            report_warnings(&outcome.warnings);
+            Ok(outcome.file)
+        }
+        Err(ThermoParseError::Io { message }) => Err(message.into()),
+        Err(_) => {
+            let (db, errors) = database::parse_thermo_lenient(&read_database(path)?);
+            eprintln!("skipped {} unreadable entries", errors.len());
+            Ok(db)
         }
+    }
+}
+
+// Load each database in turn, each replacing the species it shares with those before. Then add
+// the aliases given as "ALIAS=NAME" pairs, and apply the patches, noting each on stderr.
+fn load_database(
+    paths: &[PathBuf],
+    aliases: &[String],
+    patches: &[String],
+) -> Result<ThermoFile, Box<dyn Error>> {
+    let mut files = paths.iter().map(|path| load_file(path));
+    let mut db = files.next().ok_or("no database given")??;
+    for file in files {
+        db.merge(file?);
+    }
+    for pair in aliases {
+        let (alias, target) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected ALIAS=NAME, found '{}'", pair))?;
+        db.add_alias(alias.trim(), target.trim())?;
+    }
+    for patch in patches {
+        let applied = db.apply_patch(&patch.parse::<PatchSpec>()?)?;
+        eprintln!("patched {}", applied);
+    }
+    Ok(db)
+}
+
+// Write a table as CSV to stdout, or to a file in the format its extension names
+fn write_output(
+    output: Option<&Path>,
+    csv: impl FnOnce(&mut dyn Write) -> Result<(), Box<dyn Error>>,
+    json: impl FnOnce() -> Result<String, Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(path) = output else {
+        return csv(&mut std::io::stdout().lock());
     };
+    let format = output_format(path)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        Format::Csv => csv(&mut writer)?,
+        Format::Json => writeln!(writer, "{}", json()?)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
 
-    let mut raw_text = String::new();
-    file.read_to_string(&mut raw_text)
-        .expect("Could not read file.");
+fn run_eq(db: &ThermoFile, args: &EqArgs) -> Result<(), Box<dyn Error>> {
+    let initial = reactants(db, &args.common.reactants)?;
+    let problems: Vec<Problem> = args
+        .temperature
+        .iter()
+        .flat_map(|&temperature| {
+            args.pressure.iter().map(move |&pressure| Problem::TP {
+                temperature,
+                pressure,
+            })
+        })
+        .collect();
+    let preset = if args.gas_only {
+        Preset::GasOnly
+    } else {
+        Preset::GasPlusCondensed
+    };
+    let options = EquilibriumOptions {
+        omit: args.omit.clone(),
+        insert: args.insert.clone(),
+        ..EquilibriumOptions::preset(preset)
+    };
+    let sweep = solve_sweep(db, &initial, &problems, &options)?;
+    let species: Vec<&str> = args.common.species.iter().map(String::as_str).collect();
+    write_output(
+        args.common.output.as_deref(),
+        |writer| Ok(sweep.write_csv(db, &species, writer)?),
+        || Ok(sweep.to_json(db, &species)?),
+    )
+}
 
-    let thermo_db = match database::parse_thermo_file(&raw_text) {
-        Ok(db) => db,
-        Err(e) => {
-            eprintln!("{}", e);
-            return;
+fn run_rocket(db: &ThermoFile, args: &RocketArgs) -> Result<(), Box<dyn Error>> {
+    let reactants = reactants(db, &args.common.reactants)?;
+    let options = RocketOptions {
+        reactant_state: ReactantState {
+            temperature: args.reactant_temperature,
+            assigned_enthalpy: args.reactant_enthalpy,
+        },
+        contraction_ratio: args.contraction_ratio,
+        pressure_ratios: args.pressure_ratio.clone(),
+        subsonic_area_ratios: args.subsonic_area_ratio.clone(),
+        supersonic_area_ratios: args.supersonic_area_ratio.clone(),
+        freeze_at: args.freeze_at.map(|freeze| match freeze {
+            Freeze::Chamber => Station::Chamber,
+            Freeze::Throat => Station::Throat,
+        }),
+        ambient_pressure: args.ambient_pressure,
+        ..RocketOptions::new(args.chamber_pressure)
+    };
+    let performance = rocket_performance(db, &reactants, &options)?;
+    if performance.separation_risk {
+        eprintln!(
+            "warning: an exit is below {} of the ambient pressure; the flow may separate",
+            SEPARATION_RATIO
+        );
+    }
+    let species: Vec<&str> = args.common.species.iter().map(String::as_str).collect();
+    write_output(
+        args.common.output.as_deref(),
+        |writer| Ok(performance.write_csv(db, &species, writer)?),
+        || Ok(performance.to_json(db, &species)?),
+    )
+}
+
+// Stats need only the index of each file, not its coefficients. As when loading, a species is
+// counted from the last file defining it.
+fn run_stats(paths: &[PathBuf], args: &StatsArgs) -> Result<(), Box<dyn Error>> {
+    let mut entries = Vec::new();
+    let mut later = BTreeSet::new();
+    for path in paths.iter().rev() {
+        let raw_text = read_database(path)?;
+        let index = database::parse_index(&raw_text);
+        let names: Vec<String> = index.iter().map(|entry| entry.name.clone()).collect();
+        entries.extend(
+            index
+                .into_iter()
+                .filter(|entry| !later.contains(&entry.name)),
+        );
+        later.extend(names);
+    }
+    let stats = DbStats::from_index(&entries);
+    match args.format {
+        ReportFormat::Table => print!("{}", stats),
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+    }
+    Ok(())
+}
+
+fn run_props(db: &ThermoFile, args: &PropsArgs) -> Result<(), Box<dyn Error>> {
+    let species: Vec<&str> = args.species.iter().map(String::as_str).collect();
+    let table = PropertyTable::new(db, &species, &args.temperature)?;
+    if let Some(path) = &args.output
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("parquet"))
+    {
+        #[cfg(feature = "arrow")]
+        return Ok(table.write_parquet(path)?);
+        #[cfg(not(feature = "arrow"))]
+        return Err("writing Parquet needs a build with the arrow feature".into());
+    }
+    write_output(
+        args.output.as_deref(),
+        |writer| Ok(table.write_csv(db, writer)?),
+        || Ok(table.to_json(db)?),
+    )
+}
+
+// Read (T, Cp) pairs from CSV, skipping a header row and blank lines
+fn read_cp_samples(path: &Path) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut samples = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let pair = match fields[..] {
+            [""] => continue,
+            [t, cp] => t.parse().ok().zip(cp.parse().ok()),
+            _ => None,
+        };
+        match pair {
+            Some((t, cp)) if !(f64::is_finite(t) && f64::is_finite(cp)) => {
+                return Err(
+                    format!("line {}: '{}' is not a finite T,Cp pair", index + 1, line).into(),
+                );
+            }
+            Some(pair) => samples.push(pair),
+            None if index == 0 => continue,
+            None => {
+                return Err(format!("line {}: expected T,Cp, found '{}'", index + 1, line).into());
+            }
+        }
+    }
+    if samples.is_empty() {
+        return Err(format!("no Cp samples in {}", path.display()).into());
+    }
+    Ok(samples)
+}
+
+fn run_suggest(db: &ThermoFile, args: &SuggestArgs) -> Result<(), Box<dyn Error>> {
+    let composition = parse_formula(&args.like_formula)
+        .ok_or_else(|| format!("cannot read the formula '{}'", args.like_formula))?;
+    let samples = read_cp_samples(&args.cp_file)?;
+    println!("{:<18} {:>8} {:>12}", "species", "atoms", "Cp RMS");
+    for (name, similarity) in db.most_similar(&composition, &samples, args.count)? {
+        println!(
+            "{:<18} {:>8} {:>12.4}",
+            name, similarity.composition_difference, similarity.cp_distance
+        );
+    }
+    Ok(())
+}
+
+// A summary of one species' record, ending with where it was defined
+fn run_info(db: &ThermoFile, args: &InfoArgs) -> Result<(), Box<dyn Error>> {
+    let index = db.resolve(&args.name)?;
+    let species = &db.species()[index];
+    let elements: Vec<String> = species
+        .elements
+        .iter()
+        .map(|(element, count)| format!("{}{}", element, count))
+        .collect();
+    println!("{}  {}", species.name, species.description);
+    println!(
+        "  phase             {}",
+        if species.is_condensed() {
+            "condensed"
+        } else {
+            "gas"
         }
+    );
+    let pseudo = if species.is_pseudo_species() {
+        "  (pseudo-species)"
+    } else {
+        ""
     };
+    println!("  formula           {}{}", elements.join(" "), pseudo);
+    println!("  molecular weight  {} g/mol", species.molecular_weight);
+    match species.temperature_ranges() {
+        [] => println!("  fits              none"),
+        ranges => println!(
+            "  fits              {} over {} - {} K",
+            ranges.len(),
+            ranges[0].temp_low,
+            ranges[ranges.len() - 1].temp_high
+        ),
+    }
+    match &species.provenance {
+        Some(provenance) => {
+            println!(
+                "  defined in        {} line {}",
+                provenance.source, provenance.line
+            )
+        }
+        None => println!("  defined in        unknown"),
+    }
+    Ok(())
+}
 
-    println!("Success!\n{:?}", thermo_db);
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        None => cli.db.iter().try_for_each(|path| {
+            let raw_text = read_database(path)?;
+            let db = database::parse_thermo(&raw_text)?;
+            println!("Success!\n{:?}", db);
+            Ok(())
+        }),
+        Some(Command::Eq(args)) => {
+            load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| run_eq(&db, args))
+        }
+        Some(Command::Rocket(args)) => {
+            load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| run_rocket(&db, args))
+        }
+        Some(Command::Stats(args)) => run_stats(&cli.db, args),
+        Some(Command::Info(args)) => {
+            load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| run_info(&db, args))
+        }
+        Some(Command::Props(args)) => {
+            load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| run_props(&db, args))
+        }
+        Some(Command::Suggest(args)) => {
+            load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| run_suggest(&db, args))
+        }
+        Some(Command::Fingerprint) => load_database(&cli.db, &cli.alias, &cli.patch).map(|db| {
+            println!("{}", database::fingerprint_hex(&db.fingerprint()));
+        }),
+        Some(Command::Repl) => load_database(&cli.db, &cli.alias, &cli.patch).and_then(|db| {
+            let stdin = std::io::stdin();
+            Ok(repl::run(&db, stdin.lock(), std::io::stdout())?)
+        }),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
 }
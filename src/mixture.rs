@@ -0,0 +1,1416 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::database::{EnthalpyDatum, Species, TemperatureRange, ThermoFile};
+use crate::elements::Composition;
+use crate::equilibrium::SolverError;
+use crate::lookup::UnknownSpecies;
+use crate::numeric::{NumericError, positive};
+use crate::phases::nearest_range;
+use crate::properties::{GAS_CONSTANT, StandardState};
+
+// The state one reactant enters an HP problem in, overriding the mixture's defaults
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReactantConditions {
+    pub temperature: Option<f64>, // K, in place of the mixture's reactant temperature
+    pub cp: Option<f64>,          // J/(mol K), to move a reactant-only record off its temperature
+    pub out_of_range: OutOfRangePolicy, // For a temperature beyond the species' fits
+}
+
+// What to do with a reactant whose temperature lies beyond its fits, as a cryogenic liquid
+// below the lowest fitted temperature may
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRangePolicy {
+    // Refuse, with `SolverError::OutOfRange`
+    #[default]
+    Error,
+    // Take the enthalpy at the nearest fitted temperature instead
+    ClampToNearest,
+}
+
+impl ReactantConditions {
+    // A reactant entering at its own temperature, K
+    pub fn at_temperature(temperature: f64) -> Self {
+        ReactantConditions {
+            temperature: Some(temperature),
+            ..Default::default()
+        }
+    }
+
+    // The same, with a constant heat capacity (J/(mol K)) for a reactant-only record
+    pub fn with_cp(self, cp: f64) -> Self {
+        ReactantConditions {
+            cp: Some(cp),
+            ..self
+        }
+    }
+}
+
+// The reactant mixture entering a rocket chamber as a whole, as `RocketOptions` takes it: the
+// temperature its species are taken at, or an enthalpy assigned to it outright, as CEA's 'h'
+// sets it for a preheated or otherwise conditioned feed. `Mixture::state_enthalpy` finds the
+// enthalpy of a state, as `Problem::hp_from_reactants` does to pose the HP problem.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReactantState {
+    pub temperature: f64, // K, for species without conditions of their own
+    pub assigned_enthalpy: Option<f64>, // J/kg of reactants, replacing the species' enthalpies
+}
+
+impl Default for ReactantState {
+    fn default() -> Self {
+        ReactantState::at_temperature(298.15)
+    }
+}
+
+impl ReactantState {
+    // Reactants at a temperature, K, each with its own enthalpy there
+    pub fn at_temperature(temperature: f64) -> Self {
+        ReactantState {
+            temperature,
+            assigned_enthalpy: None,
+        }
+    }
+
+    // The same, with the mixture's enthalpy assigned instead, J/kg
+    pub fn with_enthalpy(self, enthalpy: f64) -> Self {
+        ReactantState {
+            assigned_enthalpy: Some(enthalpy),
+            ..self
+        }
+    }
+}
+
+// Largest departure from one allowed in the sum of a stream's fractions
+const FRACTION_TOLERANCE: f64 = 1.0e-6;
+
+// How much of a species a stream holds. Absolute amounts (moles and kilograms) may be mixed
+// freely, but fractions must all be of one kind and sum to one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Amount {
+    Moles(f64),
+    MassKg(f64),
+    MoleFraction(f64),
+    MassFraction(f64),
+}
+
+impl Amount {
+    fn kind(&self) -> &'static str {
+        match self {
+            Amount::Moles(_) | Amount::MassKg(_) => "absolute amounts",
+            Amount::MoleFraction(_) => "mole fractions",
+            Amount::MassFraction(_) => "mass fractions",
+        }
+    }
+}
+
+// One species of a reactant stream, by database index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reactant {
+    pub species: usize,
+    pub amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AmountError {
+    // Two kinds of amount that can't be combined in one stream
+    MixedKinds {
+        first: &'static str,
+        second: &'static str,
+    },
+    // The stream's fractions don't add up to one
+    FractionSum {
+        kind: &'static str,
+        total: f64,
+    },
+    // A negative or non-finite amount of the named species
+    InvalidAmount(String),
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AmountError::MixedKinds { first, second } => write!(
+                f,
+                "a stream can't mix {} with {}; give every species the same kind of amount",
+                first, second
+            ),
+            AmountError::FractionSum { kind, total } => {
+                write!(f, "{} sum to {} rather than one", kind, total)
+            }
+            AmountError::InvalidAmount(name) => {
+                write!(
+                    f,
+                    "the amount of '{}' must be finite and not negative",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+// What a mixture's properties do about a component whose fits don't reach the temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnRangeViolation {
+    // Give no value, or `SolverError::OutOfRange` from `Mixture::properties`
+    #[default]
+    Error,
+    // Leave the component out, rescaling the rest to the mixture's total
+    DropAndRenormalize,
+    // Evaluate the component's nearest fit past its bounds, as the equilibrium solver does
+    Extrapolate,
+}
+
+// A component beyond its fits at the temperature a mixture was evaluated at, and what the
+// mixture's policy did about it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeWarning {
+    pub species: String,
+    pub temperature: f64,                 // K
+    pub fitted_range: Option<(f64, f64)>, // Lowest and highest fitted temperature, K
+    pub fraction: f64,                    // Of the mixture as given
+    pub action: OnRangeViolation,         // DropAndRenormalize or Extrapolate
+}
+
+impl fmt::Display for RangeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {} K is beyond its fits",
+            self.species, self.temperature
+        )?;
+        if let Some((low, high)) = self.fitted_range {
+            write!(f, " ({}-{} K)", low, high)?;
+        }
+        match self.action {
+            OnRangeViolation::DropAndRenormalize => {
+                write!(f, "; dropped {:e} of the mixture", self.fraction)
+            }
+            _ => write!(f, "; extrapolated"),
+        }
+    }
+}
+
+// Frozen properties of a mixture at one state, per mole described by its fractions, with the
+// components its `OnRangeViolation` policy dropped or extrapolated to get them
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixtureProperties {
+    pub cp: f64,         // J/(mol K)
+    pub enthalpy: f64,   // J/mol
+    pub entropy: f64,    // J/(mol K)
+    pub gamma: f64,      // Frozen Cp/Cv
+    pub molar_mass: f64, // kg/mol, of the components kept
+    pub warnings: Vec<RangeWarning>,
+}
+
+// A mixture of database species, described by mole fractions
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mixture {
+    pub components: Vec<(usize, f64)>, // Species index and mole fraction
+    pub standard_state: StandardState, // Pressure the fits' values hold at, 1 bar by default
+    pub on_range_violation: OnRangeViolation, // For components beyond their fits
+}
+
+impl Mixture {
+    pub fn new(components: Vec<(usize, f64)>) -> Self {
+        Mixture {
+            components,
+            standard_state: StandardState::default(),
+            on_range_violation: OnRangeViolation::default(),
+        }
+    }
+
+    // The same mixture with another policy for components beyond their fits
+    pub fn with_range_policy(self, on_range_violation: OnRangeViolation) -> Self {
+        Mixture {
+            on_range_violation,
+            ..self
+        }
+    }
+
+    // The same mixture under another standard-state convention
+    pub fn with_standard_state(self, standard_state: StandardState) -> Self {
+        Mixture {
+            standard_state,
+            ..self
+        }
+    }
+
+    // Build a mixture from species names, aliases or names in another case, failing on the
+    // first that matches no species
+    pub fn from_names(db: &ThermoFile, components: &[(&str, f64)]) -> Result<Self, UnknownSpecies> {
+        let components = components
+            .iter()
+            .map(|(name, fraction)| Ok((db.resolve(name)?, *fraction)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Mixture::new(components))
+    }
+
+    // Build a mixture (in moles) from a stream given by any one kind of amount. Mole fractions
+    // describe one mole of mixture, and mass fractions one kilogram.
+    pub fn from_amounts(db: &ThermoFile, reactants: &[Reactant]) -> Result<Self, AmountError> {
+        let Some(first) = reactants.first() else {
+            return Ok(Mixture::default());
+        };
+        let kind = first.amount.kind();
+        let mut total = 0.0;
+        let mut components = Vec::new();
+        for reactant in reactants {
+            if reactant.amount.kind() != kind {
+                return Err(AmountError::MixedKinds {
+                    first: kind,
+                    second: reactant.amount.kind(),
+                });
+            }
+            let species = &db.species[reactant.species];
+            let (Amount::Moles(value)
+            | Amount::MassKg(value)
+            | Amount::MoleFraction(value)
+            | Amount::MassFraction(value)) = reactant.amount;
+            if !value.is_finite() || value < 0.0 {
+                return Err(AmountError::InvalidAmount(species.name.clone()));
+            }
+            total += value;
+            let moles = match reactant.amount {
+                Amount::Moles(moles) | Amount::MoleFraction(moles) => moles,
+                Amount::MassKg(mass) | Amount::MassFraction(mass) => {
+                    mass * 1000.0 / species.molecular_weight
+                }
+            };
+            components.push((reactant.species, moles));
+        }
+        let fractions = matches!(
+            first.amount,
+            Amount::MoleFraction(_) | Amount::MassFraction(_)
+        );
+        if fractions && (total - 1.0).abs() > FRACTION_TOLERANCE {
+            return Err(AmountError::FractionSum { kind, total });
+        }
+        Ok(Mixture::new(components))
+    }
+
+    // Moles of each element per kilogram of mixture, keyed by upper-case symbol, in the
+    // kg-mol/kg convention of `moles_per_kg`. None for a mixture without mass.
+    pub fn element_moles_per_kg(&self, db: &ThermoFile) -> Option<BTreeMap<String, f64>> {
+        let mass = self.mass_total(db);
+        if mass <= 0.0 || !mass.is_finite() {
+            return None;
+        }
+        let mut elements = BTreeMap::new();
+        for &(index, moles) in &self.components {
+            for (element, count) in db.species[index].elements.iter().filter(|(_, c)| *c != 0.0) {
+                *elements.entry(element.symbol().to_string()).or_insert(0.0) +=
+                    moles * count / 1000.0 / mass;
+            }
+        }
+        Some(elements)
+    }
+
+    // Sum of the mole fractions, which should be one
+    pub fn total(&self) -> f64 {
+        self.components.iter().map(|(_, fraction)| fraction).sum()
+    }
+
+    // That every amount is a number no less than zero, and some above it: what the solvers ask
+    // of a mixture before dividing by its total
+    pub fn check_amounts(&self, db: &ThermoFile) -> Result<(), NumericError> {
+        if let Some(&(index, value)) = self
+            .components
+            .iter()
+            .find(|(_, value)| value.is_nan() || *value < 0.0)
+        {
+            return Err(NumericError::NegativeMoleFraction {
+                species: db.species[index].name.clone(),
+                value,
+            });
+        }
+        if positive("total amount", self.total()).is_err() {
+            return Err(NumericError::EmptyMixture);
+        }
+        Ok(())
+    }
+
+    // Rescale the mole fractions to sum to one. An empty or all-zero mixture is left unchanged.
+    pub fn normalize(&mut self) {
+        let total = self.total();
+        if total > 0.0 {
+            for (_, fraction) in &mut self.components {
+                *fraction /= total;
+            }
+        }
+    }
+
+    // Whether the mole fractions sum to one within the given tolerance
+    pub fn is_normalized(&self, tol: f64) -> bool {
+        (self.total() - 1.0).abs() <= tol
+    }
+
+    // Mole fraction of a species, zero if it is absent
+    pub fn fraction_of(&self, index: usize) -> f64 {
+        self.components
+            .iter()
+            .filter(|(i, _)| *i == index)
+            .map(|(_, fraction)| fraction)
+            .sum()
+    }
+
+    // Add moles of a species, to its existing amount if it is already a component. The other
+    // amounts are left as they are, so a normalised mixture no longer is.
+    pub fn add_species(&mut self, index: usize, moles: f64) {
+        match self.components.iter_mut().find(|(i, _)| *i == index) {
+            Some((_, amount)) => *amount += moles,
+            None => self.components.push((index, moles)),
+        }
+    }
+
+    // Take a species out of the mixture, returning its amount (None if it was absent). With
+    // `renormalize`, the rest are rescaled to sum to one.
+    pub fn remove_species(&mut self, index: usize, renormalize: bool) -> Option<f64> {
+        let removed = self.fraction_of(index);
+        let present = self.components.iter().any(|(i, _)| *i == index);
+        self.components.retain(|(i, _)| *i != index);
+        if renormalize {
+            self.normalize();
+        }
+        present.then_some(removed)
+    }
+
+    // Absolute amounts (mol) of each species when the mixture totals the given moles. An empty
+    // or all-zero mixture is returned unchanged.
+    pub fn with_total_moles(&self, moles: f64) -> Vec<(usize, f64)> {
+        self.scaled_by(moles / self.total())
+    }
+
+    // Absolute amounts (mol) of each species when the mixture totals the given mass (kg)
+    pub fn with_total_mass(&self, db: &ThermoFile, mass: f64) -> Vec<(usize, f64)> {
+        self.scaled_by(mass / self.mass_total(db))
+    }
+
+    fn scaled_by(&self, factor: f64) -> Vec<(usize, f64)> {
+        if !factor.is_finite() {
+            return self.components.clone();
+        }
+        self.components
+            .iter()
+            .map(|&(index, fraction)| (index, fraction * factor))
+            .collect()
+    }
+
+    // Build a mixture from species names (or aliases, as `from_names`) and mass fractions (or
+    // masses). None if a species has no molecular weight or the masses sum to zero.
+    pub fn from_mass_fractions(
+        db: &ThermoFile,
+        components: &[(&str, f64)],
+    ) -> Result<Option<Self>, UnknownSpecies> {
+        let masses = components
+            .iter()
+            .map(|&(name, mass)| (name.to_string(), mass))
+            .fold(BTreeMap::new(), |mut masses, (name, mass)| {
+                *masses.entry(name).or_insert(0.0) += mass;
+                masses
+            });
+        let Some(fractions) = mass_to_mole_fractions(db, &masses)? else {
+            return Ok(None);
+        };
+        let components = fractions
+            .iter()
+            .map(|(name, fraction)| Ok((db.resolve(name)?, *fraction)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Mixture::new(components)))
+    }
+
+    // Amounts keyed by species name, merging repeated species
+    fn named_amounts(&self, db: &ThermoFile) -> BTreeMap<String, f64> {
+        let mut amounts = BTreeMap::new();
+        for &(index, amount) in &self.components {
+            *amounts.entry(db.species[index].name.clone()).or_insert(0.0) += amount;
+        }
+        amounts
+    }
+
+    // Normalised mole fractions keyed by species name, or None for an empty mixture
+    pub fn mole_fractions(&self, db: &ThermoFile) -> Option<BTreeMap<String, f64>> {
+        normalized(self.named_amounts(db))
+    }
+
+    // Normalised mass fractions keyed by species name, or None for an empty mixture
+    pub fn mass_fractions(&self, db: &ThermoFile) -> Option<BTreeMap<String, f64>> {
+        mole_to_mass_fractions(db, &self.named_amounts(db))
+            .expect("names are taken from the database")
+    }
+
+    // Moles of each species per kilogram of mixture, in CEA's kg-mol/kg convention
+    pub fn moles_per_kg(&self, db: &ThermoFile) -> Option<BTreeMap<String, f64>> {
+        let molar_mass = self.molar_mass(db) * 1000.0; // kg/kg-mol
+        let mut fractions = self.mole_fractions(db)?;
+        for fraction in fractions.values_mut() {
+            *fraction /= molar_mass;
+        }
+        Some(fractions)
+    }
+
+    // Mean molar mass of the normalised mixture, kg/mol
+    pub fn molar_mass(&self, db: &ThermoFile) -> f64 {
+        self.mass_total(db) / self.total()
+    }
+
+    // Mass of the amounts described by the fractions, kg
+    pub(crate) fn mass_total(&self, db: &ThermoFile) -> f64 {
+        self.components
+            .iter()
+            .map(|&(index, fraction)| fraction * db.species[index].molecular_weight / 1000.0)
+            .sum()
+    }
+
+    // A species property at a temperature, or under `Extrapolate` for a species beyond its fits,
+    // the property with the nearest fit stretched to reach the temperature. None at or below
+    // absolute zero, where no fit may be stretched.
+    fn species_value(
+        &self,
+        species: &Species,
+        temperature: f64,
+        property: impl Fn(&Species) -> Option<f64>,
+    ) -> Option<f64> {
+        let temperature = positive("temperature", temperature).ok()?;
+        let value = property(species);
+        if value.is_some() || self.on_range_violation != OnRangeViolation::Extrapolate {
+            return value;
+        }
+        let nearest = nearest_range(species, temperature)?;
+        let stretched = TemperatureRange {
+            temp_low: nearest.temp_low.min(temperature),
+            temp_high: nearest.temp_high.max(temperature),
+            ..nearest.clone()
+        };
+        // A stand-in holding only what the properties read, rather than a copy of the record
+        property(&Species {
+            name: String::new(),
+            description: String::new(),
+            reference_code: String::new(),
+            elements: Composition::default(),
+            phase: species.phase,
+            molecular_weight: species.molecular_weight,
+            enthalpy_datum: species.enthalpy_datum,
+            temperature_ranges: vec![stretched],
+            source_digits: species.source_digits,
+            provenance: None,
+        })
+    }
+
+    // The mixture to evaluate at a temperature: under `DropAndRenormalize`, the components with
+    // a fit covering it, rescaled to the same total (None if that leaves nothing); otherwise
+    // the mixture itself
+    fn in_range(&self, db: &ThermoFile, temperature: f64) -> Option<Cow<'_, Mixture>> {
+        if self.on_range_violation != OnRangeViolation::DropAndRenormalize {
+            return Some(Cow::Borrowed(self));
+        }
+        let kept: Vec<(usize, f64)> = self
+            .components
+            .iter()
+            .filter(|&&(index, _)| db.species[index].range_at(temperature).is_some())
+            .copied()
+            .collect();
+        if kept.len() == self.components.len() {
+            return Some(Cow::Borrowed(self));
+        }
+        let kept = Mixture {
+            components: kept,
+            ..self.clone()
+        };
+        let scale = self.total() / kept.total();
+        (scale.is_finite() && scale > 0.0).then(|| {
+            Cow::Owned(Mixture {
+                components: kept.scaled_by(scale),
+                ..kept
+            })
+        })
+    }
+
+    // Mass (kg) of the mixture evaluated at a temperature, as `in_range` takes it, or None if
+    // there is none to divide by
+    fn mass_in_range(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        let mass = self.in_range(db, temperature)?.mass_total(db);
+        positive("mass", mass).ok()
+    }
+
+    // Each component beyond its fits at a temperature, and what the mixture's policy does about
+    // it. Empty under `Error`, where such a mixture has no properties to give.
+    pub fn range_warnings(&self, db: &ThermoFile, temperature: f64) -> Vec<RangeWarning> {
+        if self.on_range_violation == OnRangeViolation::Error {
+            return Vec::new();
+        }
+        self.components
+            .iter()
+            .filter(|&&(index, _)| db.species[index].range_at(temperature).is_none())
+            .map(|&(index, fraction)| {
+                let species = &db.species[index];
+                let ranges = species.temperature_ranges();
+                RangeWarning {
+                    species: species.name.clone(),
+                    temperature,
+                    fitted_range: ranges
+                        .first()
+                        .zip(ranges.last())
+                        .map(|(first, last)| (first.temp_low, last.temp_high)),
+                    fraction,
+                    action: self.on_range_violation,
+                }
+            })
+            .collect()
+    }
+
+    // Frozen cp, enthalpy, entropy, gamma and molar mass at T and p under the mixture's range
+    // policy, along with the components it dropped or extrapolated. Under `Error`, the first
+    // component beyond its fits is refused with `SolverError::OutOfRange`.
+    pub fn properties(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        pressure: f64,
+    ) -> Result<MixtureProperties, SolverError> {
+        positive("temperature", temperature)?;
+        positive("pressure", pressure)?;
+        let out_of_range = |index: usize| SolverError::OutOfRange {
+            species: db.species[index].name.clone(),
+            temperature,
+        };
+        let unfitted = self.components.iter().find(|&&(index, _)| {
+            let species = &db.species[index];
+            species.range_at(temperature).is_none()
+                && (self.on_range_violation == OnRangeViolation::Error
+                    || nearest_range(species, temperature).is_none())
+        });
+        if let Some(&(index, _)) = unfitted
+            && self.on_range_violation != OnRangeViolation::DropAndRenormalize
+        {
+            return Err(out_of_range(index));
+        }
+        let Some(mixture) = self.in_range(db, temperature) else {
+            return Err(out_of_range(self.components[0].0));
+        };
+        // The component a property couldn't be found for, or a non-finite result if each was
+        let missing = |property: &dyn Fn(&Species) -> Option<f64>| {
+            let failed = mixture.components.iter().find(|&&(index, _)| {
+                let species = &db.species[index];
+                mixture
+                    .species_value(species, temperature, property)
+                    .is_none()
+            });
+            failed.map_or(
+                SolverError::Numeric(NumericError::NonFinite {
+                    location: "the mixture's properties",
+                }),
+                |&(index, _)| out_of_range(index),
+            )
+        };
+        Ok(MixtureProperties {
+            cp: mixture
+                .cp(db, temperature)
+                .ok_or_else(|| missing(&|s| s.cp(temperature)))?,
+            enthalpy: mixture
+                .enthalpy(db, temperature)
+                .ok_or_else(|| missing(&|s| s.enthalpy(temperature)))?,
+            entropy: mixture
+                .entropy(db, temperature, pressure)
+                .ok_or_else(|| missing(&|s| s.entropy(temperature)))?,
+            gamma: mixture
+                .gamma(db, temperature)
+                .ok_or_else(|| missing(&|s| s.cv(temperature)))?,
+            molar_mass: mixture.molar_mass(db),
+            warnings: self.range_warnings(db, temperature),
+        })
+    }
+
+    // Fraction-weighted sum of a per-species molar property over the mixture `in_range` gives
+    fn molar_sum(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        property: impl Fn(&Species) -> Option<f64>,
+    ) -> Option<f64> {
+        let mixture = self.in_range(db, temperature)?;
+        mixture
+            .components
+            .iter()
+            .map(|&(index, fraction)| {
+                let species = &db.species[index];
+                Some(fraction * self.species_value(species, temperature, &property)?)
+            })
+            .sum()
+    }
+
+    // Frozen heat capacity, J/(mol K) per mole described by the fractions
+    pub fn cp(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.molar_sum(db, temperature, |species| species.cp(temperature))
+    }
+
+    // Frozen constant-volume heat capacity, J/(mol K), assuming ideal gases
+    pub fn cv(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.molar_sum(db, temperature, |species| species.cv(temperature))
+    }
+
+    // Assigned enthalpy, J/mol (see `EnthalpyReference::Assigned`)
+    pub fn enthalpy(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.molar_sum(db, temperature, |species| species.enthalpy(temperature))
+    }
+
+    // Assigned enthalpy (J for the amounts given) of the mixture entering an HP problem as
+    // reactants, summing each one's enthalpy at its own temperature. Species are taken at
+    // `temperature` unless `conditions` (by species index) says otherwise, except that
+    // reactant-only records, which have no fits, default to the temperature of their assigned
+    // enthalpy. Away from it they need a heat capacity.
+    pub fn reactant_enthalpy(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        conditions: &[(usize, ReactantConditions)],
+    ) -> Result<f64, SolverError> {
+        self.components
+            .iter()
+            .map(|&(index, amount)| {
+                let species = &db.species[index];
+                let given = conditions
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|&(_, conditions)| conditions)
+                    .unwrap_or_default();
+                let assigned = match species.enthalpy_datum {
+                    EnthalpyDatum::Assigned { value, temperature }
+                        if species.temperature_ranges.is_empty() =>
+                    {
+                        Some((value, temperature))
+                    }
+                    _ => None,
+                };
+                let t = given
+                    .temperature
+                    .or(assigned.map(|(_, assigned_at)| assigned_at))
+                    .unwrap_or(temperature);
+                let enthalpy = match (species.enthalpy(t), assigned, given.cp) {
+                    (Some(enthalpy), _, _) => enthalpy,
+                    (None, Some((value, assigned_at)), Some(cp)) => value + cp * (t - assigned_at),
+                    (None, Some((_, assigned_at)), None) => {
+                        return Err(SolverError::ReactantOnly {
+                            species: species.name.clone(),
+                            assigned_at,
+                            temperature: t,
+                        });
+                    }
+                    (None, None, _) => {
+                        let clamped = match given.out_of_range {
+                            OutOfRangePolicy::Error => None,
+                            OutOfRangePolicy::ClampToNearest => species.nearest_fitted(t),
+                        };
+                        let Some(enthalpy) = clamped.and_then(|t| species.enthalpy(t)) else {
+                            return Err(SolverError::OutOfRange {
+                                species: species.name.clone(),
+                                temperature: t,
+                            });
+                        };
+                        enthalpy
+                    }
+                };
+                Ok(amount * enthalpy)
+            })
+            .sum()
+    }
+
+    // Total enthalpy (J for the amounts given) of the reactants in the given state: the
+    // assigned enthalpy if it has one, otherwise as `reactant_enthalpy` sums it
+    pub fn state_enthalpy(
+        &self,
+        db: &ThermoFile,
+        state: &ReactantState,
+        conditions: &[(usize, ReactantConditions)],
+    ) -> Result<f64, SolverError> {
+        match state.assigned_enthalpy {
+            Some(enthalpy) => Ok(enthalpy * self.molar_mass(db) * self.total()),
+            None => self.reactant_enthalpy(db, state.temperature, conditions),
+        }
+    }
+
+    // Internal energy, J/mol, assuming ideal gases
+    pub fn internal_energy(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.molar_sum(db, temperature, |species| {
+            species.internal_energy(temperature)
+        })
+    }
+
+    // Frozen isentropic exponent Cp/Cv, holding the composition fixed rather than letting it
+    // shift with temperature. For an all-gas mixture this is Cp/(Cp - R) per mole.
+    pub fn gamma(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        let gamma = self.cp(db, temperature)? / self.cv(db, temperature)?;
+        gamma.is_finite().then_some(gamma)
+    }
+
+    // Frozen speed of sound sqrt(gamma R T / M) for an ideal-gas mixture, m/s
+    pub fn speed_of_sound(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        let gamma = self.gamma(db, temperature)?;
+        let molar_mass =
+            positive("molar mass", self.in_range(db, temperature)?.molar_mass(db)).ok()?;
+        Some((gamma * GAS_CONSTANT * temperature / molar_mass).sqrt())
+    }
+
+    // Density at T and p, kg/m³, with the gases ideal and condensed phases taking up no
+    // volume. None for a mixture without gas, or unless T and p are positive.
+    pub fn density(&self, db: &ThermoFile, temperature: f64, pressure: f64) -> Option<f64> {
+        let (temperature, pressure) = (
+            positive("temperature", temperature).ok()?,
+            positive("pressure", pressure).ok()?,
+        );
+        let gas = self.gas_total(db);
+        (gas > 0.0).then(|| pressure * self.mass_total(db) / (gas * GAS_CONSTANT * temperature))
+    }
+
+    // Frozen heat capacity per unit mass, J/(kg K)
+    pub fn cp_mass(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        Some(self.cp(db, temperature)? / self.mass_in_range(db, temperature)?)
+    }
+
+    // Frozen constant-volume heat capacity per unit mass, J/(kg K)
+    pub fn cv_mass(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        Some(self.cv(db, temperature)? / self.mass_in_range(db, temperature)?)
+    }
+
+    // Enthalpy per unit mass, J/kg
+    pub fn enthalpy_mass(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        Some(self.enthalpy(db, temperature)? / self.mass_in_range(db, temperature)?)
+    }
+
+    // Internal energy per unit mass, J/kg
+    pub fn internal_energy_mass(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        Some(self.internal_energy(db, temperature)? / self.mass_in_range(db, temperature)?)
+    }
+
+    // Total mole fraction of the gaseous components
+    pub(crate) fn gas_total(&self, db: &ThermoFile) -> f64 {
+        self.components
+            .iter()
+            .filter(|(index, _)| !db.species[*index].is_condensed())
+            .map(|(_, fraction)| fraction)
+            .sum()
+    }
+
+    // Partial molar entropy of each component at T and p, J/(mol K), in component order.
+    // Gases are ideal: s° - R ln(x_gas) - R ln(p/p_ref), with x_gas the fraction within the gas
+    // phase and p_ref from the mixture's standard state. Condensed components are pure phases
+    // and keep s°. An absent gas has an unbounded partial entropy, which is returned as
+    // infinity. None if T is outside any species' fit, or p isn't positive.
+    pub fn partial_molar_entropies(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        pressure: f64,
+    ) -> Option<Vec<f64>> {
+        let pressure = positive("pressure", pressure).ok()?;
+        let gas_total = self.gas_total(db);
+        let pressure_term = GAS_CONSTANT * self.standard_state.ln_pressure_ratio(pressure);
+        self.components
+            .iter()
+            .map(|&(index, fraction)| {
+                let species = &db.species[index];
+                let standard =
+                    self.species_value(species, temperature, |s| s.entropy(temperature))?;
+                if species.is_condensed() {
+                    return Some(standard);
+                }
+                let mixing = if fraction > 0.0 {
+                    GAS_CONSTANT * (fraction / gas_total).ln()
+                } else {
+                    f64::NEG_INFINITY
+                };
+                Some(standard - mixing - pressure_term)
+            })
+            .collect()
+    }
+
+    // Chemical potential of each component at T and p, J/mol, in component order: g° plus
+    // RT ln(x_gas p/p_ref) for ideal gases, and g° alone for pure condensed phases. An absent
+    // gas has a potential of minus infinity. None if T is outside any species' fit, or p isn't
+    // positive.
+    pub fn chemical_potentials(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        pressure: f64,
+    ) -> Option<Vec<f64>> {
+        let pressure = positive("pressure", pressure).ok()?;
+        let gas_total = self.gas_total(db);
+        let rt = GAS_CONSTANT * temperature;
+        let pressure_term = self.standard_state.ln_pressure_ratio(pressure);
+        self.components
+            .iter()
+            .map(|&(index, fraction)| {
+                let species = &db.species[index];
+                let standard =
+                    self.species_value(species, temperature, |s| s.gibbs(temperature))?;
+                if species.is_condensed() {
+                    return Some(standard);
+                }
+                let mixing = if fraction > 0.0 {
+                    (fraction / gas_total).ln()
+                } else {
+                    f64::NEG_INFINITY
+                };
+                Some(standard + rt * (mixing + pressure_term))
+            })
+            .collect()
+    }
+
+    // Entropy of the mixture at T and p, J/(mol K) per mole described by the fractions.
+    // Components with zero fraction contribute nothing. None if T is outside any species' fit.
+    pub fn entropy(&self, db: &ThermoFile, temperature: f64, pressure: f64) -> Option<f64> {
+        let mixture = self.in_range(db, temperature)?;
+        let partials = mixture.partial_molar_entropies(db, temperature, pressure)?;
+        Some(
+            mixture
+                .components
+                .iter()
+                .zip(partials)
+                .filter(|((_, fraction), _)| *fraction > 0.0)
+                .map(|((_, fraction), partial)| fraction * partial)
+                .sum(),
+        )
+    }
+
+    // Entropy per unit mass at T and p, J/(kg K)
+    pub fn entropy_mass(&self, db: &ThermoFile, temperature: f64, pressure: f64) -> Option<f64> {
+        let mass = self.mass_in_range(db, temperature)?;
+        Some(self.entropy(db, temperature, pressure)? / mass)
+    }
+}
+
+// Rescale name-keyed amounts to fractions summing to one, or None if they sum to zero
+fn normalized(mut amounts: BTreeMap<String, f64>) -> Option<BTreeMap<String, f64>> {
+    let total: f64 = amounts.values().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    for amount in amounts.values_mut() {
+        *amount /= total;
+    }
+    Some(amounts)
+}
+
+// Scale each named amount by a per-species factor, merging repeated names. Names are resolved
+// as `ThermoFile::resolve` does, aliases included.
+fn scaled(
+    db: &ThermoFile,
+    amounts: &BTreeMap<String, f64>,
+    mut factor: impl FnMut(&Species) -> f64,
+) -> Result<BTreeMap<String, f64>, UnknownSpecies> {
+    let mut scaled = BTreeMap::new();
+    for (name, amount) in amounts {
+        let species = &db.species[db.resolve(name)?];
+        *scaled.entry(name.clone()).or_insert(0.0) += amount * factor(species);
+    }
+    Ok(scaled)
+}
+
+// Convert mole fractions (or amounts) keyed by species name into mass fractions, failing on a
+// name not in the database. None if the mixture is empty.
+pub fn mole_to_mass_fractions(
+    db: &ThermoFile,
+    mole_fractions: &BTreeMap<String, f64>,
+) -> Result<Option<BTreeMap<String, f64>>, UnknownSpecies> {
+    Ok(normalized(scaled(db, mole_fractions, |s| {
+        s.molecular_weight
+    })?))
+}
+
+// Convert mass fractions (or masses) keyed by species name into mole fractions, failing on a
+// name not in the database. None if a species has no molecular weight or the mixture is empty.
+pub fn mass_to_mole_fractions(
+    db: &ThermoFile,
+    mass_fractions: &BTreeMap<String, f64>,
+) -> Result<Option<BTreeMap<String, f64>>, UnknownSpecies> {
+    let mut weightless = false;
+    let moles = scaled(db, mass_fractions, |s| {
+        weightless |= s.molecular_weight <= 0.0;
+        1.0 / s.molecular_weight
+    })?;
+    Ok(normalized(moles).filter(|_| !weightless))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::properties::REFERENCE_PRESSURE;
+    use crate::test_data::{database_with_reactants, full_database};
+
+    #[test]
+    fn test_normalize() {
+        let mut mixture = Mixture::new(vec![(0, 3.76), (1, 1.0)]);
+        assert!(!mixture.is_normalized(1e-12));
+
+        mixture.normalize();
+        assert!(mixture.is_normalized(1e-12));
+        assert!((mixture.fraction_of(0) - 3.76 / 4.76).abs() < 1e-15);
+        assert!((mixture.fraction_of(1) - 1.0 / 4.76).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_add_and_remove_species() {
+        let db = full_database();
+        let (n2, o2, ar) = (
+            db.index_of("N2").unwrap(),
+            db.index_of("O2").unwrap(),
+            db.index_of("Ar").unwrap(),
+        );
+        let mut mixture = Mixture::new(vec![(n2, 3.0), (o2, 1.0)]);
+
+        // Adding to a component merges with it rather than listing the species twice
+        mixture.add_species(n2, 0.76);
+        assert_eq!(mixture.components, vec![(n2, 3.76), (o2, 1.0)]);
+        mixture.add_species(ar, 0.04);
+        assert_eq!(mixture.components.len(), 3);
+        assert!((mixture.total() - 4.8).abs() < 1e-12);
+
+        assert_eq!(mixture.remove_species(ar, false), Some(0.04));
+        assert_eq!(mixture.components, vec![(n2, 3.76), (o2, 1.0)]);
+        assert_eq!(mixture.remove_species(ar, true), None);
+        assert!(mixture.is_normalized(1e-12));
+        assert!((mixture.fraction_of(o2) - 1.0 / 4.76).abs() < 1e-15);
+
+        assert_eq!(mixture.remove_species(o2, true), Some(1.0 / 4.76));
+        assert_eq!(mixture.components, vec![(n2, 1.0)]);
+        assert_eq!(mixture.remove_species(n2, true), Some(1.0));
+        assert!(mixture.components.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_empty_mixture() {
+        let mut mixture = Mixture::default();
+        mixture.normalize();
+        assert!(mixture.components.is_empty());
+        assert!(!mixture.is_normalized(1e-12));
+    }
+
+    #[test]
+    fn test_entropy_of_mixing() {
+        let db = full_database();
+        let t = 298.15;
+        let air = Mixture::from_names(db, &[("N2", 0.5), ("O2", 0.5)]).unwrap();
+        let s_n2 = db.find_species("N2").unwrap().entropy(t).unwrap();
+        let s_o2 = db.find_species("O2").unwrap().entropy(t).unwrap();
+
+        let mixing = -GAS_CONSTANT * (0.5 * 0.5f64.ln() + 0.5 * 0.5f64.ln());
+        let expected = 0.5 * s_n2 + 0.5 * s_o2 + mixing;
+        let s = air.entropy(db, t, REFERENCE_PRESSURE).unwrap();
+        assert!((s - expected).abs() < 1e-9, "{} != {}", s, expected);
+
+        let partials = air
+            .partial_molar_entropies(db, t, REFERENCE_PRESSURE)
+            .unwrap();
+        assert!((partials[0] - (s_n2 + GAS_CONSTANT * 2f64.ln())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_density() {
+        // Air at 0 °C and one atmosphere is 1.29 kg/m³; water drops adds mass but no volume
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let rho = air.density(db, 273.15, 101_325.0).unwrap();
+        assert!((rho - 1.29).abs() < 0.01, "{}", rho);
+        let misty = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21), ("H2O(L)", 0.1)]);
+        let misty = misty.unwrap().density(db, 273.15, 101_325.0).unwrap();
+        assert!((misty - rho - 0.1 * 0.018015 * rho / 0.028850).abs() < 1e-3 * rho);
+        let water = Mixture::from_names(db, &[("H2O(L)", 1.0)]).unwrap();
+        assert_eq!(water.density(db, 300.0, 1e5), None);
+    }
+
+    #[test]
+    fn test_standard_state_conventions() {
+        // Half the mixture is gas, so the conventions differ by half of R ln(1.01325)
+        let db = full_database();
+        let t = 300.0;
+        let p = StandardState::ATMOSPHERE.p_ref;
+        let bar = Mixture::from_names(db, &[("N2", 0.5), ("H2O(L)", 0.5)]).unwrap();
+        let atm = bar.clone().with_standard_state(StandardState::ATMOSPHERE);
+        let shift = 0.5 * GAS_CONSTANT * 1.01325f64.ln();
+        let s_bar = bar.entropy(db, t, p).unwrap();
+        let s_atm = atm.entropy(db, t, p).unwrap();
+        assert!((s_atm - s_bar - shift).abs() < 1e-12, "{} {}", s_bar, s_atm);
+
+        // Chemical potentials shift by RT ln(1.01325) for the gas alone
+        let mu_bar = bar.chemical_potentials(db, t, p).unwrap();
+        let mu_atm = atm.chemical_potentials(db, t, p).unwrap();
+        let rt_shift = GAS_CONSTANT * t * 1.01325f64.ln();
+        assert!((mu_bar[0] - mu_atm[0] - rt_shift).abs() < 1e-9);
+        assert_eq!(mu_bar[1], mu_atm[1]);
+        let n2 = db.find_species("N2").unwrap();
+        let expected = n2.gibbs(t).unwrap() + GAS_CONSTANT * t * (p / REFERENCE_PRESSURE).ln();
+        assert!((mu_bar[0] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entropy_pressure_dependence() {
+        let db = full_database();
+        let t = 1000.0;
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let s1 = air.entropy(db, t, 1.0e5).unwrap();
+        let s2 = air.entropy(db, t, 2.0e5).unwrap();
+        assert!((s1 - s2 - GAS_CONSTANT * 2f64.ln()).abs() < 1e-9);
+
+        // Only the gas fraction of a two-phase mixture feels the pressure
+        let wet = Mixture::from_names(db, &[("H2O", 0.5), ("H2O(L)", 0.5)]).unwrap();
+        let s1 = wet.entropy(db, 350.0, 1.0e5).unwrap();
+        let s2 = wet.entropy(db, 350.0, 2.0e5).unwrap();
+        assert!((s1 - s2 - 0.5 * GAS_CONSTANT * 2f64.ln()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_entropy_skips_absent_species() {
+        let db = full_database();
+        let t = 500.0;
+        let pure = Mixture::from_names(db, &[("N2", 1.0)]).unwrap();
+        let padded = Mixture::from_names(db, &[("N2", 1.0), ("Ar", 0.0)]).unwrap();
+        let s = padded.entropy(db, t, 1.0e5).unwrap();
+        assert!(s.is_finite());
+        assert_eq!(s, pure.entropy(db, t, 1.0e5).unwrap());
+    }
+
+    #[test]
+    fn test_mass_basis_properties() {
+        let db = full_database();
+        let air =
+            Mixture::from_names(db, &[("N2", 0.7808), ("O2", 0.2095), ("Ar", 0.0093)]).unwrap();
+        let molar_mass = air.molar_mass(db);
+        assert!((molar_mass - 0.028965).abs() < 1e-5, "M = {}", molar_mass);
+
+        // Air near room temperature: cp = 1005 J/(kg K), cv = 718 J/(kg K)
+        let cp = air.cp_mass(db, 300.0).unwrap();
+        let cv = air.cv_mass(db, 300.0).unwrap();
+        assert!((cp - 1005.0).abs() < 5.0, "cp = {}", cp);
+        assert!((cv - 718.0).abs() < 5.0, "cv = {}", cv);
+
+        let h = air.enthalpy_mass(db, 500.0).unwrap();
+        let u = air.internal_energy_mass(db, 500.0).unwrap();
+        assert!((h - u - GAS_CONSTANT * 500.0 / molar_mass).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_reactant_only_enthalpy() {
+        let db = database_with_reactants();
+        let rp1 = db.species_index("RP-1").unwrap();
+        let nitrogen = db.species_index("N2").unwrap();
+        let fuel = Mixture::new(vec![(rp1, 2.0), (nitrogen, 1.0)]);
+        let n2_at = |t| db.species[nitrogen].enthalpy(t).unwrap();
+
+        // RP-1 stays at its assigned 298.15 K while the fitted N2 follows the mixture
+        let h = fuel.reactant_enthalpy(db, 400.0, &[]).unwrap();
+        assert!((h - (2.0 * -24717.7 + n2_at(400.0))).abs() < 1e-6);
+
+        // Moved off its assigned temperature, it needs a heat capacity
+        let warm = ReactantConditions::at_temperature(350.0);
+        assert_eq!(
+            fuel.reactant_enthalpy(db, 400.0, &[(rp1, warm)]),
+            Err(SolverError::ReactantOnly {
+                species: "RP-1".to_string(),
+                assigned_at: 298.15,
+                temperature: 350.0
+            })
+        );
+        let warm = warm.with_cp(30.0);
+        let h = fuel.reactant_enthalpy(db, 400.0, &[(rp1, warm)]).unwrap();
+        let sensible = 30.0 * (350.0 - 298.15);
+        assert!((h - (2.0 * (-24717.7 + sensible) + n2_at(400.0))).abs() < 1e-6);
+
+        // Other properties are simply unavailable
+        assert_eq!(fuel.cp(db, 298.15), None);
+    }
+
+    #[test]
+    fn test_reactant_below_its_fits() {
+        let db = full_database();
+        let oxygen = db.species_index("O2").unwrap();
+        let cold = Mixture::new(vec![(oxygen, 1.0)]);
+        let low = db.species[oxygen].temperature_ranges()[0].temp_low;
+
+        // Gaseous O2 at 90 K lies below its lowest fit: an error unless clamped to it
+        let conditions = ReactantConditions::at_temperature(90.0);
+        assert_eq!(
+            cold.reactant_enthalpy(db, 298.15, &[(oxygen, conditions)]),
+            Err(SolverError::OutOfRange {
+                species: "O2".to_string(),
+                temperature: 90.0
+            })
+        );
+        let conditions = ReactantConditions {
+            out_of_range: OutOfRangePolicy::ClampToNearest,
+            ..conditions
+        };
+        let h = cold.reactant_enthalpy(db, 298.15, &[(oxygen, conditions)]);
+        assert_eq!(h, Ok(db.species[oxygen].enthalpy(low).unwrap()));
+    }
+
+    #[test]
+    fn test_amounts_by_mass_and_by_moles() {
+        let db = full_database();
+        let [methane, oxygen, nitrogen] =
+            ["CH4", "O2", "N2"].map(|name| db.species_index(name).unwrap());
+        let weight = |index: usize| db.species[index].molecular_weight / 1000.0; // kg/mol
+        let moles = [(methane, 1.0), (oxygen, 2.0), (nitrogen, 7.52)];
+        let mass: f64 = moles.iter().map(|&(i, n)| n * weight(i)).sum();
+
+        let streams = [
+            moles.map(|(i, n)| (i, Amount::Moles(n))),
+            moles.map(|(i, n)| (i, Amount::MassKg(n * weight(i)))),
+            moles.map(|(i, n)| (i, Amount::MoleFraction(n / 10.52))),
+            moles.map(|(i, n)| (i, Amount::MassFraction(n * weight(i) / mass))),
+        ];
+        let abundances: Vec<BTreeMap<String, f64>> = streams
+            .iter()
+            .map(|stream| {
+                let reactants = stream.map(|(species, amount)| Reactant { species, amount });
+                let mixture = Mixture::from_amounts(db, &reactants).unwrap();
+                mixture.element_moles_per_kg(db).unwrap()
+            })
+            .collect();
+        for other in &abundances[1..] {
+            assert_eq!(other.keys().collect::<Vec<_>>(), ["C", "H", "N", "O"]);
+            for (symbol, b) in other {
+                let expected = abundances[0][symbol];
+                assert!((b - expected).abs() < 1e-12 * expected, "{}", symbol);
+            }
+        }
+        // One mole of CH4 in 0.2907 kg gives 3.44e-3 kg-mol of carbon per kilogram
+        assert!((abundances[0]["C"] - 1.0 / (mass * 1000.0)).abs() < 1e-15);
+
+        // Each stream sticks to one kind of fraction, and its fractions sum to one
+        let mixed = [
+            Reactant {
+                species: methane,
+                amount: Amount::MoleFraction(0.5),
+            },
+            Reactant {
+                species: oxygen,
+                amount: Amount::MassFraction(0.5),
+            },
+        ];
+        let error = Mixture::from_amounts(db, &mixed).unwrap_err();
+        assert_eq!(
+            error,
+            AmountError::MixedKinds {
+                first: "mole fractions",
+                second: "mass fractions"
+            }
+        );
+        assert!(error.to_string().contains("can't mix mole fractions"));
+        let short = [Reactant {
+            species: methane,
+            amount: Amount::MassFraction(0.9),
+        }];
+        assert!(matches!(
+            Mixture::from_amounts(db, &short),
+            Err(AmountError::FractionSum { .. })
+        ));
+        let negative = [Reactant {
+            species: methane,
+            amount: Amount::Moles(-1.0),
+        }];
+        assert_eq!(
+            Mixture::from_amounts(db, &negative),
+            Err(AmountError::InvalidAmount("CH4".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_scale_to_total() {
+        let db = full_database();
+        let air =
+            Mixture::from_names(db, &[("N2", 0.7808), ("O2", 0.2095), ("Ar", 0.0093)]).unwrap();
+
+        let nitrogen = db.species_index("N2").unwrap();
+        let amounts = Mixture::new(air.with_total_moles(3.5));
+        assert!((amounts.total() - 3.5).abs() < 1e-12);
+        let expected = 3.5 * 0.7808 / air.total();
+        assert!((amounts.fraction_of(nitrogen) - expected).abs() < 1e-12);
+
+        let amounts = Mixture::new(air.with_total_mass(db, 2.0));
+        assert!((amounts.mass_total(db) - 2.0).abs() < 1e-12);
+        assert!((amounts.molar_mass(db) - air.molar_mass(db)).abs() < 1e-12);
+
+        let empty = Mixture::new(Vec::new());
+        assert!(empty.with_total_moles(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_gamma_of_air() {
+        let db = full_database();
+        let air = Mixture::from_names(db, &[("N2", 79.0), ("O2", 21.0)]).unwrap();
+        let gamma = air.gamma(db, 300.0).unwrap();
+        assert!((gamma - 1.40).abs() < 0.002, "gamma = {}", gamma);
+
+        let cp = air.cp(db, 300.0).unwrap() / air.total();
+        assert!((gamma - cp / (cp - GAS_CONSTANT)).abs() < 1e-12);
+        assert!(air.gamma(db, 2000.0).unwrap() < gamma);
+    }
+
+    #[test]
+    fn test_speed_of_sound_in_air() {
+        let db = full_database();
+        let air =
+            Mixture::from_names(db, &[("N2", 0.7808), ("O2", 0.2095), ("Ar", 0.0093)]).unwrap();
+        let a = air.speed_of_sound(db, 288.15).unwrap();
+        assert!((a - 340.3).abs() < 1.0, "a = {}", a);
+    }
+
+    #[test]
+    fn test_methane_air_fractions() {
+        let db = full_database();
+        let fuel_air = Mixture::from_names(db, &[("CH4", 1.0), ("O2", 2.0), ("N2", 7.52)]).unwrap();
+
+        // By hand: 16.04246 + 2 * 31.9988 + 7.52 * 28.0134 = 290.700 g per mole of fuel
+        let mass = fuel_air.mass_fractions(db).unwrap();
+        assert!((mass["CH4"] - 16.04246 / 290.700_828).abs() < 1e-9);
+        assert!((mass["O2"] - 63.9976 / 290.700_828).abs() < 1e-9);
+        assert!((mass["N2"] - 210.660_768 / 290.700_828).abs() < 1e-9);
+        assert!((mass.values().sum::<f64>() - 1.0).abs() < 1e-15);
+
+        let moles = fuel_air.mole_fractions(db).unwrap();
+        assert!((moles["CH4"] - 1.0 / 10.52).abs() < 1e-15);
+        assert_eq!(moles.keys().collect::<Vec<_>>(), ["CH4", "N2", "O2"]);
+
+        // 10.52 moles weigh 290.7 g, so 1 kg holds 0.0362 kg-mol
+        let per_kg = fuel_air.moles_per_kg(db).unwrap();
+        let total: f64 = per_kg.values().sum();
+        assert!((total - 10.52 / 290.700_828).abs() < 1e-9, "{}", total);
+    }
+
+    #[test]
+    fn test_mole_mass_round_trip() {
+        let db = full_database();
+        let fuel_air = Mixture::from_names(db, &[("CH4", 1.0), ("O2", 2.0), ("N2", 7.52)]).unwrap();
+        let moles = fuel_air.mole_fractions(db).unwrap();
+        let mass = mole_to_mass_fractions(db, &moles).unwrap().unwrap();
+        let back = mass_to_mole_fractions(db, &mass).unwrap().unwrap();
+        for (name, fraction) in &moles {
+            assert!((back[name] - fraction).abs() < 1e-15, "{}", name);
+        }
+
+        let masses: Vec<(&str, f64)> = mass.iter().map(|(n, w)| (n.as_str(), *w)).collect();
+        let rebuilt = Mixture::from_mass_fractions(db, &masses).unwrap().unwrap();
+        assert_eq!(rebuilt.mole_fractions(db).unwrap().len(), 3);
+        for (name, fraction) in rebuilt.mole_fractions(db).unwrap() {
+            assert!((moles[&name] - fraction).abs() < 1e-15, "{}", name);
+        }
+
+        assert!(Mixture::default().mass_fractions(db).is_none());
+
+        // Aliases resolve, and an unknown name is reported with suggestions
+        let reactants = database_with_reactants();
+        let lox = Mixture::from_mass_fractions(reactants, &[("LOX", 1.0)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            lox.components,
+            [(reactants.index_of("O2(L)").unwrap(), 1.0)]
+        );
+        let unknown = Mixture::from_mass_fractions(db, &[("N2", 0.8), ("CH5", 0.2)]);
+        let unknown = unknown.unwrap_err();
+        assert_eq!(unknown.name, "CH5");
+        assert!(!unknown.suggestions.is_empty());
+        let unknown = mole_to_mass_fractions(db, &BTreeMap::from([("CO3".to_string(), 1.0)]));
+        assert_eq!(unknown.unwrap_err().name, "CO3");
+        assert_eq!(Mixture::from_mass_fractions(db, &[]), Ok(None));
+    }
+
+    // N2 and CO2 are fitted to 20000 K, NO2 only to 6000 K
+    fn beyond_no2(policy: OnRangeViolation) -> Mixture {
+        let db = full_database();
+        Mixture::from_names(db, &[("N2", 0.7), ("CO2", 0.2), ("NO2", 0.1)])
+            .unwrap()
+            .with_range_policy(policy)
+    }
+
+    #[test]
+    fn test_range_violation_error() {
+        let db = full_database();
+        let mixture = beyond_no2(OnRangeViolation::Error);
+        assert!(mixture.cp(db, 6500.0).is_none());
+        assert!(mixture.range_warnings(db, 6500.0).is_empty());
+        assert!(matches!(
+            mixture.properties(db, 6500.0, 1.0e5),
+            Err(SolverError::OutOfRange { species, temperature })
+                if species == "NO2" && temperature == 6500.0
+        ));
+        let inside = mixture.properties(db, 5500.0, 1.0e5).unwrap();
+        assert!(inside.warnings.is_empty());
+        assert_eq!(inside.cp, mixture.cp(db, 5500.0).unwrap());
+    }
+
+    #[test]
+    fn test_range_violation_drop() {
+        let db = full_database();
+        let mixture = beyond_no2(OnRangeViolation::DropAndRenormalize);
+        let (t, p) = (6500.0, 1.0e5);
+        let properties = mixture.properties(db, t, p).unwrap();
+        let rest = Mixture::from_names(db, &[("N2", 0.7 / 0.9), ("CO2", 0.2 / 0.9)]).unwrap();
+        assert!((properties.cp - rest.cp(db, t).unwrap()).abs() < 1e-9);
+        assert!((properties.enthalpy - rest.enthalpy(db, t).unwrap()).abs() < 1e-6);
+        assert!((properties.entropy - rest.entropy(db, t, p).unwrap()).abs() < 1e-9);
+        assert!((properties.molar_mass - rest.molar_mass(db)).abs() < 1e-15);
+        assert_eq!(mixture.cp_mass(db, t), rest.cp_mass(db, t));
+
+        assert_eq!(
+            properties.warnings,
+            [RangeWarning {
+                species: "NO2".to_string(),
+                temperature: t,
+                fitted_range: Some((200.0, 6000.0)),
+                fraction: 0.1,
+                action: OnRangeViolation::DropAndRenormalize,
+            }]
+        );
+        assert_eq!(
+            properties.warnings[0].to_string(),
+            "NO2 at 6500 K is beyond its fits (200-6000 K); dropped 1e-1 of the mixture"
+        );
+    }
+
+    // A failure with every component in range names no species, and bad conditions are refused
+    #[test]
+    fn test_properties_failures() {
+        let mut db = full_database().clone();
+        let co2 = db.index_of("CO2").unwrap();
+        let fit = &mut db.species_mut()[co2].temperature_ranges[0];
+        fit.coefficients[0] = f64::NAN;
+        let mixture = Mixture::from_names(&db, &[("N2", 0.8), ("CO2", 0.2)]).unwrap();
+        assert!(matches!(
+            mixture.properties(&db, 500.0, 1.0e5),
+            Err(SolverError::Numeric(NumericError::NonFinite { .. }))
+        ));
+        assert!(mixture.properties(&db, 1500.0, 1.0e5).is_ok());
+        for (t, p) in [(0.0, 1.0e5), (f64::NAN, 1.0e5), (500.0, -1.0)] {
+            assert!(matches!(
+                mixture.properties(&db, t, p),
+                Err(SolverError::Numeric(NumericError::NonPositive { .. }))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_range_violation_extrapolate() {
+        let db = full_database();
+        let mixture = beyond_no2(OnRangeViolation::Extrapolate);
+        let t = 6500.0;
+        let properties = mixture.properties(db, t, 1.0e5).unwrap();
+        let species_cp = |name: &str| {
+            let species = db.find_species(name).unwrap();
+            nearest_range(species, t).unwrap().cp_over_r(t).unwrap() * GAS_CONSTANT
+        };
+        let expected = 0.7 * species_cp("N2") + 0.2 * species_cp("CO2") + 0.1 * species_cp("NO2");
+        assert!((properties.cp - expected).abs() < 1e-9, "{}", properties.cp);
+        assert_eq!(properties.molar_mass, mixture.molar_mass(db));
+        assert_eq!(properties.warnings.len(), 1);
+        assert_eq!(properties.warnings[0].action, OnRangeViolation::Extrapolate);
+        assert!(
+            properties.warnings[0]
+                .to_string()
+                .ends_with("; extrapolated")
+        );
+    }
+}
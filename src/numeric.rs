@@ -0,0 +1,86 @@
+use std::fmt;
+
+// Why a numeric routine refused its inputs or gave up partway, given in place of a panic or a
+// NaN slipping through to the caller
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumericError {
+    // A value came out as NaN or infinite at the named step
+    NonFinite { location: &'static str },
+    // A temperature, pressure or density at or below zero, or not a number
+    NonPositive { quantity: &'static str, value: f64 },
+    // A linear system had no unique solution
+    SingularMatrix,
+    // A mixture component with a negative (or NaN) amount
+    NegativeMoleFraction { species: String, value: f64 },
+    // A mixture with nothing in it, or only zero amounts
+    EmptyMixture,
+    // A mixture made of no elements, such as the electron alone
+    NoElements,
+}
+
+impl fmt::Display for NumericError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NumericError::NonFinite { location } => {
+                write!(f, "non-finite value in {}", location)
+            }
+            NumericError::NonPositive { quantity, value } => {
+                write!(f, "{} must be positive, not {}", quantity, value)
+            }
+            NumericError::SingularMatrix => write!(f, "singular matrix"),
+            NumericError::NegativeMoleFraction { species, value } => {
+                write!(f, "'{}' has a negative amount, {}", species, value)
+            }
+            NumericError::EmptyMixture => write!(f, "the mixture is empty"),
+            NumericError::NoElements => write!(f, "the mixture contains no elements"),
+        }
+    }
+}
+
+impl std::error::Error for NumericError {}
+
+// The value, if it is finite and above zero (so not NaN)
+pub fn positive(quantity: &'static str, value: f64) -> Result<f64, NumericError> {
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err(NumericError::NonPositive { quantity, value })
+    }
+}
+
+// The value, if it is neither NaN nor infinite
+pub fn finite(location: &'static str, value: f64) -> Result<f64, NumericError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(NumericError::NonFinite { location })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks() {
+        assert_eq!(positive("pressure", 1.0e5), Ok(1.0e5));
+        for value in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                positive("pressure", value),
+                Err(NumericError::NonPositive {
+                    quantity: "pressure",
+                    ..
+                })
+            ));
+        }
+        assert_eq!(finite("step", -2.0), Ok(-2.0));
+        assert_eq!(
+            finite("step", f64::NAN),
+            Err(NumericError::NonFinite { location: "step" })
+        );
+        assert_eq!(
+            positive("temperature", 0.0).unwrap_err().to_string(),
+            "temperature must be positive, not 0"
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::database::{Species, ThermoFile};
+use crate::lookup::UnknownSpecies;
+
+// Names of a fit's adjustable numbers, indexed as `with_coefficient` and `patch` take them:
+// the seven polynomial coefficients, then the two integration constants
+pub const COEFFICIENT_NAMES: [&str; 9] = ["a1", "a2", "a3", "a4", "a5", "a6", "a7", "b1", "b2"];
+
+// Why a coefficient could not be patched
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    UnknownSpecies(UnknownSpecies),
+    // The species has only `count` fits, numbered from zero
+    NoInterval {
+        species: String,
+        interval: usize,
+        count: usize,
+    },
+    // Past the last entry of `COEFFICIENT_NAMES`
+    NoCoefficient {
+        coefficient: usize,
+    },
+    // A patch expression that doesn't read as NAME:INTERVAL:COEFF=VALUE
+    Syntax(String),
+    // A NaN or infinity, which would leave the fit unusable
+    NonFinite {
+        value: f64,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::UnknownSpecies(error) => write!(f, "{}", error),
+            PatchError::NoInterval {
+                species,
+                interval,
+                count,
+            } => write!(
+                f,
+                "{} has {} fits, numbered from 0, so no fit {}",
+                species, count, interval
+            ),
+            PatchError::NoCoefficient { coefficient } => write!(
+                f,
+                "coefficient {} is past the last, {}",
+                coefficient,
+                COEFFICIENT_NAMES.len() - 1
+            ),
+            PatchError::Syntax(message) => write!(f, "invalid patch: {}", message),
+            PatchError::NonFinite { value } => {
+                write!(f, "a coefficient must be finite, not {}", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+// How a patch expression's value changes the coefficient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PatchOperation {
+    Set,   // COEFF=VALUE
+    Scale, // COEFF*=VALUE
+    Add,   // COEFF+=VALUE
+}
+
+// One change to a coefficient, as written on the command line: "OH:1:a4*=1.01" scales a4 of
+// OH's second fit by 1.01
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchSpec {
+    pub species: String,
+    pub interval: usize,
+    pub coefficient: usize, // Index into `COEFFICIENT_NAMES`
+    pub operation: PatchOperation,
+    pub value: f64,
+}
+
+impl PatchSpec {
+    // The coefficient's value after the patch, from its value before
+    pub fn apply_to(&self, old: f64) -> f64 {
+        match self.operation {
+            PatchOperation::Set => self.value,
+            PatchOperation::Scale => old * self.value,
+            PatchOperation::Add => old + self.value,
+        }
+    }
+}
+
+impl FromStr for PatchSpec {
+    type Err = PatchError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let syntax = |message: String| PatchError::Syntax(message);
+        let mut parts = text.trim().rsplitn(3, ':');
+        let (Some(assignment), Some(interval), Some(species)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(syntax(format!(
+                "expected NAME:INTERVAL:COEFF=VALUE, found '{}'",
+                text
+            )));
+        };
+        let interval = interval
+            .trim()
+            .parse()
+            .map_err(|_| syntax(format!("'{}' is not a fit number", interval)))?;
+        let (target, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| syntax(format!("no '=' in '{}'", assignment)))?;
+        let (name, operation) = match target.trim() {
+            name if name.ends_with('*') => (&name[..name.len() - 1], PatchOperation::Scale),
+            name if name.ends_with('+') => (&name[..name.len() - 1], PatchOperation::Add),
+            name => (name, PatchOperation::Set),
+        };
+        let coefficient = COEFFICIENT_NAMES
+            .iter()
+            .position(|candidate| candidate.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| syntax(format!("'{}' is not one of a1 to a7, b1 or b2", name)))?;
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| syntax(format!("'{}' is not a number", value)))?;
+        Ok(PatchSpec {
+            species: species.trim().to_string(),
+            interval,
+            coefficient,
+            operation,
+            value,
+        })
+    }
+}
+
+// A coefficient changed by `ThermoFile::patch`, with its value before and after
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedPatch {
+    pub species: String,
+    pub interval: usize,
+    pub coefficient: usize, // Index into `COEFFICIENT_NAMES`
+    pub old: f64,
+    pub new: f64,
+}
+
+impl fmt::Display for AppliedPatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} fit {} {}: {:e} -> {:e}",
+            self.species, self.interval, COEFFICIENT_NAMES[self.coefficient], self.old, self.new
+        )
+    }
+}
+
+impl Species {
+    // A copy of the species with one number of one fit replaced, the coefficient indexed as in
+    // `COEFFICIENT_NAMES`. The species itself is left as it was.
+    pub fn with_coefficient(
+        &self,
+        interval: usize,
+        coefficient: usize,
+        value: f64,
+    ) -> Result<Species, PatchError> {
+        let mut patched = self.clone();
+        *patched.coefficient_mut(interval, coefficient)? = value;
+        Ok(patched)
+    }
+
+    // The number `with_coefficient` would replace
+    pub fn coefficient(&self, interval: usize, coefficient: usize) -> Result<f64, PatchError> {
+        let count = self.temperature_ranges.len();
+        let range =
+            self.temperature_ranges
+                .get(interval)
+                .ok_or_else(|| PatchError::NoInterval {
+                    species: self.name.clone(),
+                    interval,
+                    count,
+                })?;
+        match coefficient {
+            0..7 => Ok(range.coefficients[coefficient]),
+            7..9 => Ok(range.integration_constants[coefficient - 7]),
+            _ => Err(PatchError::NoCoefficient { coefficient }),
+        }
+    }
+
+    fn coefficient_mut(
+        &mut self,
+        interval: usize,
+        coefficient: usize,
+    ) -> Result<&mut f64, PatchError> {
+        self.coefficient(interval, coefficient)?;
+        let range = &mut self.temperature_ranges[interval];
+        Ok(match coefficient {
+            0..7 => &mut range.coefficients[coefficient],
+            _ => &mut range.integration_constants[coefficient - 7],
+        })
+    }
+}
+
+impl ThermoFile {
+    // Replace one number of a species' fit (found by name or alias), recording the change in
+    // `patches`. Other copies of the database are unaffected. A NaN or infinity is refused.
+    pub fn patch(
+        &mut self,
+        name: &str,
+        interval: usize,
+        coefficient: usize,
+        value: f64,
+    ) -> Result<&AppliedPatch, PatchError> {
+        let index = self.index_of(name).map_err(PatchError::UnknownSpecies)?;
+        self.species[index].coefficient(interval, coefficient)?;
+        if !value.is_finite() {
+            return Err(PatchError::NonFinite { value });
+        }
+        self.clear_derived();
+        let species = &mut self.species[index];
+        let slot = species.coefficient_mut(interval, coefficient)?;
+        let old = std::mem::replace(slot, value);
+        self.patches.push(AppliedPatch {
+            species: species.name.clone(),
+            interval,
+            coefficient,
+            old,
+            new: value,
+        });
+        Ok(self.patches.last().expect("just pushed"))
+    }
+
+    // Apply a patch expression, scaling or offsetting the coefficient's current value
+    pub fn apply_patch(&mut self, spec: &PatchSpec) -> Result<&AppliedPatch, PatchError> {
+        let index = self
+            .index_of(&spec.species)
+            .map_err(PatchError::UnknownSpecies)?;
+        let old = self.species[index].coefficient(spec.interval, spec.coefficient)?;
+        self.patch(
+            &spec.species,
+            spec.interval,
+            spec.coefficient,
+            spec.apply_to(old),
+        )
+    }
+
+    // Every change made with `patch`, oldest first: empty for a database as read
+    pub fn patches(&self) -> &[AppliedPatch] {
+        &self.patches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_parse_patch() {
+        let spec: PatchSpec = "OH:1:a4*=1.01".parse().unwrap();
+        assert_eq!(
+            spec,
+            PatchSpec {
+                species: "OH".to_string(),
+                interval: 1,
+                coefficient: 3,
+                operation: PatchOperation::Scale,
+                value: 1.01
+            }
+        );
+        assert_eq!(spec.apply_to(2.0), 2.02);
+        let spec: PatchSpec = "H2O:0:B1+=-50".parse().unwrap();
+        assert_eq!((spec.coefficient, spec.operation), (7, PatchOperation::Add));
+        let spec: PatchSpec = "N2:2:a1=0".parse().unwrap();
+        assert_eq!(spec.apply_to(7.0), 0.0);
+
+        for text in [
+            "OH:a4=1",
+            "OH:x:a4=1",
+            "OH:1:a8=1",
+            "OH:1:a4",
+            "OH:1:a4*=big",
+        ] {
+            assert!(
+                matches!(text.parse::<PatchSpec>(), Err(PatchError::Syntax(_))),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_patch_coefficient() {
+        let original = full_database();
+        let mut db = original.clone();
+        assert!(db.patches().is_empty());
+        let t = 2000.0;
+        let before = original.find_species("OH").unwrap();
+        let a4 = before.temperature_ranges()[1].a4();
+
+        let spec: PatchSpec = "OH:1:a4*=1.01".parse().unwrap();
+        let applied = db.apply_patch(&spec).unwrap().clone();
+        assert_eq!(applied.old, a4);
+        assert_eq!(applied.new, a4 * 1.01);
+        assert_eq!(db.patches(), &[applied]);
+
+        // Cp/R shifts by the change in a4 times T, and nothing else moves
+        let after = db.find_species("OH").unwrap();
+        let shift = after.cp(t).unwrap() - before.cp(t).unwrap();
+        let expected = 0.01 * a4 * t * crate::properties::GAS_CONSTANT;
+        assert!((shift - expected).abs() < 1e-9 * expected.abs());
+        assert_eq!(after.cp(500.0), before.cp(500.0));
+        assert_eq!(
+            before.temperature_ranges()[1].a4(),
+            a4,
+            "the original is untouched"
+        );
+        assert!(original.patches().is_empty());
+
+        // Species::with_coefficient leaves its species alone too
+        let copy = before.with_coefficient(1, 7, 0.0).unwrap();
+        assert_eq!(copy.temperature_ranges()[1].b1(), 0.0);
+        assert_ne!(before.temperature_ranges()[1].b1(), 0.0);
+
+        assert!(matches!(
+            db.patch("OH", 3, 0, 1.0),
+            Err(PatchError::NoInterval { count: 3, .. })
+        ));
+        assert_eq!(
+            db.patch("OH", 0, 9, 1.0),
+            Err(PatchError::NoCoefficient { coefficient: 9 })
+        );
+        for value in [f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                db.patch("OH", 0, 3, value),
+                Err(PatchError::NonFinite { .. })
+            ));
+        }
+        assert!(db.nonfinite_fit().is_none());
+        assert!(matches!(
+            db.patch("OHH", 0, 0, 1.0),
+            Err(PatchError::UnknownSpecies(_))
+        ));
+        assert_eq!(db.patches().len(), 1);
+    }
+}
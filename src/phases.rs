@@ -0,0 +1,220 @@
+use crate::database::{Species, TemperatureRange, ThermoFile};
+use crate::lookup::UnknownSpecies;
+use crate::properties::REFERENCE_PRESSURE;
+
+// Half-width of the window searched around the fits' shared temperatures, K
+const TRANSITION_SEARCH_MARGIN: f64 = 50.0;
+
+// Whether the text inside a trailing parenthesis is a phase label like "cr", "L", "a" or "III"
+// rather than part of a formula like "(OH)" or "(HCOOH)"
+fn is_phase_label(label: &str) -> bool {
+    !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || matches!(c, 'I' | 'V' | 'L' | '\'' | '-' | ','))
+}
+
+// Name with any trailing phase label removed, so "H2O(cr)" and "H2O(L)" both give "H2O"
+pub fn base_name(name: &str) -> &str {
+    if let Some(stripped) = name.strip_suffix(')')
+        && let Some(open) = stripped.rfind('(')
+        && open > 0
+        && is_phase_label(&stripped[open + 1..])
+    {
+        return &name[..open];
+    }
+    name
+}
+
+// Fit used for a temperature, extrapolating the nearest interval just outside the fitted range.
+// A fit holding a NaN or infinity is passed over for the nearest other.
+pub(crate) fn nearest_range(species: &Species, temperature: f64) -> Option<&TemperatureRange> {
+    let finite = || species.temperature_ranges.iter().filter(|r| r.is_finite());
+    finite()
+        .find(|range| range.contains(temperature))
+        .or_else(|| {
+            finite().min_by(|a, b| {
+                let distance = |r: &TemperatureRange| {
+                    (r.temp_low - temperature)
+                        .abs()
+                        .min((r.temp_high - temperature).abs())
+                };
+                distance(a).total_cmp(&distance(b))
+            })
+        })
+}
+
+// Molar Gibbs energy over RT of a pure phase at pressure (Pa), with condensed phases taken as
+// incompressible so only the gas picks up the pressure term
+fn phase_g_over_rt(
+    range: &TemperatureRange,
+    species: &Species,
+    t: f64,
+    pressure: f64,
+) -> Option<f64> {
+    let pressure_term = if species.is_condensed() {
+        0.0
+    } else {
+        (pressure / REFERENCE_PRESSURE).ln()
+    };
+    Some(range.g_over_rt(t).ok()? + pressure_term)
+}
+
+impl ThermoFile {
+    // Every phase of a substance: entries sharing the base name, like H2O, H2O(cr) and H2O(L)
+    pub fn phases_of(&self, base: &str) -> Vec<&Species> {
+        self.species
+            .iter()
+            .filter(|species| base_name(&species.name) == base)
+            .collect()
+    }
+
+    // The phase with the lowest Gibbs energy at temperature (K) and pressure (Pa), among the
+    // phases whose fits cover that temperature
+    pub fn stable_phase_at(&self, base: &str, temperature: f64, pressure: f64) -> Option<&Species> {
+        self.phases_of(base)
+            .into_iter()
+            .filter_map(|species| {
+                let range = species.range_at(temperature)?;
+                Some((
+                    species,
+                    phase_g_over_rt(range, species, temperature, pressure)?,
+                ))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(species, _)| species)
+    }
+
+    // Temperature (K) at which two phases, found by name or alias, have equal Gibbs energy at
+    // the given pressure (Pa). None where their fits give no crossing.
+    pub fn transition_temperature(
+        &self,
+        phase_a: &str,
+        phase_b: &str,
+        pressure: f64,
+    ) -> Result<Option<f64>, UnknownSpecies> {
+        let a = &self.species[self.resolve(phase_a)?];
+        let b = &self.species[self.resolve(phase_b)?];
+        Ok(gibbs_crossing(a, b, pressure))
+    }
+}
+
+// Temperature (K) at which two species have equal Gibbs energy at the given pressure (Pa),
+// found by bisection. Condensed fits only span their stable range, meeting exactly at the
+// transition, so each fit is extrapolated slightly past its bounds for the search.
+fn gibbs_crossing(a: &Species, b: &Species, pressure: f64) -> Option<f64> {
+    let bounds = |s: &Species| {
+        let low = s.temperature_ranges.first()?.temp_low;
+        let high = s.temperature_ranges.last()?.temp_high;
+        Some((low, high))
+    };
+    let (a_low, a_high) = bounds(a)?;
+    let (b_low, b_high) = bounds(b)?;
+
+    let delta_g = |t: f64| {
+        let range_a = nearest_range(a, t)?;
+        let range_b = nearest_range(b, t)?;
+        Some(phase_g_over_rt(range_a, a, t, pressure)? - phase_g_over_rt(range_b, b, t, pressure)?)
+    };
+
+    let mut low = (a_low.max(b_low) - TRANSITION_SEARCH_MARGIN).max(1.0);
+    let mut high = a_high.min(b_high) + TRANSITION_SEARCH_MARGIN;
+    let mut f_low = delta_g(low)?;
+    let f_high = delta_g(high)?;
+    if f_low.signum() == f_high.signum() {
+        return None;
+    }
+
+    while high - low > 1e-9 * high {
+        let mid = 0.5 * (low + high);
+        let f_mid = delta_g(mid)?;
+        if f_mid.signum() == f_low.signum() {
+            low = mid;
+            f_low = f_mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some(0.5 * (low + high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_base_name() {
+        assert_eq!(base_name("H2O(cr)"), "H2O");
+        assert_eq!(base_name("H2O(L)"), "H2O");
+        assert_eq!(base_name("SiO2(a-qz)"), "SiO2");
+        assert_eq!(base_name("WO3(III,II)"), "WO3");
+        assert_eq!(base_name("H2O"), "H2O");
+
+        // Parentheses that are part of the formula are left alone
+        assert_eq!(base_name("(HCOOH)2"), "(HCOOH)2");
+        assert_eq!(base_name("AL(OH)3"), "AL(OH)3");
+        assert_eq!(base_name("(CH3COOH)2"), "(CH3COOH)2");
+        assert_eq!(base_name("Fe(CO)5"), "Fe(CO)5");
+    }
+
+    #[test]
+    fn test_phases_of() {
+        let db = full_database();
+        let names: Vec<&str> = db
+            .phases_of("H2O")
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["H2O", "H2O(cr)", "H2O(L)"]);
+
+        let dimer: Vec<&str> = db
+            .phases_of("(HCOOH)2")
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(dimer, ["(HCOOH)2"]);
+        assert!(db.phases_of("HCOOH").iter().all(|s| s.name != "(HCOOH)2"));
+    }
+
+    #[test]
+    fn test_stable_phase_of_water() {
+        let db = full_database();
+        let stable = |t| db.stable_phase_at("H2O", t, 1.0e5).unwrap().name.as_str();
+        assert_eq!(stable(250.0), "H2O(cr)");
+        assert_eq!(stable(300.0), "H2O(L)");
+        assert_eq!(stable(400.0), "H2O");
+        assert!(db.stable_phase_at("H2O", 100.0, 1.0e5).is_none());
+    }
+
+    #[test]
+    fn test_transition_temperatures() {
+        let db = full_database();
+
+        let melting = db
+            .transition_temperature("H2O(cr)", "H2O(L)", 1.0e5)
+            .unwrap()
+            .unwrap();
+        assert!((melting - 273.15).abs() < 0.5, "melting = {}", melting);
+
+        // Boiling point at one atmosphere
+        let boiling = db
+            .transition_temperature("H2O(L)", "H2O", 101325.0)
+            .unwrap()
+            .unwrap();
+        assert!((boiling - 373.15).abs() < 1.0, "boiling = {}", boiling);
+
+        let alumina = db
+            .transition_temperature("AL2O3(a)", "AL2O3(L)", 1.0e5)
+            .unwrap()
+            .unwrap();
+        assert!((alumina - 2327.0).abs() < 5.0, "alumina = {}", alumina);
+
+        // A misspelt phase is named with suggestions; phases that never cross give None
+        let unknown = db
+            .transition_temperature("H2O(LQ)", "H2O", 1.0e5)
+            .unwrap_err();
+        assert!(unknown.suggestions.contains(&"H2O(L)".to_string()));
+        assert_eq!(db.transition_temperature("N2", "O2", 1.0e5), Ok(None));
+    }
+}
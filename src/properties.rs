@@ -0,0 +1,819 @@
+use crate::database::{EnthalpyDatum, NASA9_EXPONENTS, Species, TemperatureRange, ThermoFile};
+use crate::numeric::{NumericError, positive};
+
+// Temperature of the JANAF enthalpy datum, K
+const JANAF_REFERENCE_TEMPERATURE: f64 = 298.15;
+
+// How close a temperature must be to a reactant-only record's to take its assigned enthalpy, K
+const ASSIGNED_TEMPERATURE_TOLERANCE: f64 = 0.01;
+
+// The zero from which an enthalpy is measured. NASA-9 fits give the assigned enthalpy, and
+// every evaluator in this crate (`h_over_rt`, `enthalpy`, the mixture and equilibrium code)
+// works on that basis unless it says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnthalpyReference {
+    // CEA's assigned enthalpy H(T) = dfH(298.15) + [H(T) - H(298.15)], so elements in their
+    // reference states are zero at 298.15 K only
+    Assigned,
+    // Enthalpy of formation dfH(T) from the reference-state elements at the same T
+    Formation,
+    // Sensible enthalpy H(T) - H(298.15), zero for every species at 298.15 K
+    Sensible,
+}
+
+// A property disagreeing with a tabulated reference value
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyMismatch {
+    // The fit does not cover the tabulated temperature
+    OutOfRange,
+    // Heat capacity, J/(mol K)
+    HeatCapacity { computed: f64, expected: f64 },
+    // Sensible enthalpy H(T) - H(298.15), kJ/mol
+    Enthalpy { computed: f64, expected: f64 },
+    // Standard-state entropy, J/(mol K)
+    Entropy { computed: f64, expected: f64 },
+}
+
+// RMS and largest relative error of one property over a table
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ErrorStats {
+    pub rms: f64,
+    pub max: f64,
+}
+
+// How closely a fit follows reference table rows, from `Species::thermo_fit_quality`. Cp and S
+// errors are relative to the reference value; the enthalpy error, as H - H(298.15) passes
+// through zero, is relative to R*T.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FitStats {
+    pub points: usize,       // Rows the fit covers, which the errors are taken over
+    pub out_of_range: usize, // Rows outside the fit, left out
+    pub cp: ErrorStats,
+    pub enthalpy: ErrorStats,
+    pub entropy: ErrorStats,
+}
+
+// Universal gas constant, J/(mol K)
+pub const GAS_CONSTANT: f64 = 8.314_462_618;
+
+// Standard-state pressure of the NASA fits, Pa
+pub const REFERENCE_PRESSURE: f64 = 1.0e5;
+
+// The pressure at which the fitted standard-state values are taken to hold. Quantities of a
+// single species in its standard state (Cp, H, S°, G° and Kp) are the fits themselves and
+// don't depend on it. Anything with a pressure term ln(p/p_ref) does: the entropy, partial
+// entropies and chemical potentials of a gas mixture, Kc, and every equilibrium composition
+// and product entropy. Legacy 1 atm comparisons shift each mole of gas's entropy by
+// R ln(1.01325) against the 1 bar convention of the fits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StandardState {
+    pub p_ref: f64, // Pa
+}
+
+impl StandardState {
+    pub const BAR: StandardState = StandardState {
+        p_ref: REFERENCE_PRESSURE,
+    };
+    pub const ATMOSPHERE: StandardState = StandardState { p_ref: 101_325.0 };
+
+    // ln(p/p_ref), the pressure term of an ideal gas per RT (or per R, for entropy)
+    pub fn ln_pressure_ratio(&self, pressure: f64) -> f64 {
+        (pressure / self.p_ref).ln()
+    }
+}
+
+impl Default for StandardState {
+    fn default() -> Self {
+        StandardState::BAR
+    }
+}
+
+impl TemperatureRange {
+    // Whether this fit covers the given temperature (bounds inclusive)
+    pub fn contains(&self, temperature: f64) -> bool {
+        self.temp_low <= temperature && temperature <= self.temp_high
+    }
+
+    // Coefficient a_i of the polynomial, numbered from 1 as in the NASA-9 papers, multiplying
+    // T to the power `NASA9_EXPONENTS[i - 1]`. Panics unless 1 <= i <= 7.
+    pub fn a(&self, i: usize) -> f64 {
+        assert!(
+            (1..=7).contains(&i),
+            "NASA-9 coefficients are a1 to a7, not a{}",
+            i
+        );
+        self.coefficients[i - 1]
+    }
+
+    // Coefficient of T^-2
+    pub fn a1(&self) -> f64 {
+        self.a(1)
+    }
+
+    // Coefficient of T^-1
+    pub fn a2(&self) -> f64 {
+        self.a(2)
+    }
+
+    // Constant coefficient
+    pub fn a3(&self) -> f64 {
+        self.a(3)
+    }
+
+    // Coefficient of T
+    pub fn a4(&self) -> f64 {
+        self.a(4)
+    }
+
+    // Coefficient of T^2
+    pub fn a5(&self) -> f64 {
+        self.a(5)
+    }
+
+    // Coefficient of T^3
+    pub fn a6(&self) -> f64 {
+        self.a(6)
+    }
+
+    // Coefficient of T^4
+    pub fn a7(&self) -> f64 {
+        self.a(7)
+    }
+
+    // Integration constant of the enthalpy, b1/T in H/RT
+    pub fn b1(&self) -> f64 {
+        self.integration_constants[0]
+    }
+
+    // Integration constant of the entropy, added to S/R
+    pub fn b2(&self) -> f64 {
+        self.integration_constants[1]
+    }
+
+    // The fit's numbers by name, bounds and constants included, for checking them
+    pub fn named_values(&self) -> [(&'static str, f64); 12] {
+        [
+            ("T low", self.temp_low),
+            ("T high", self.temp_high),
+            ("a1", self.a1()),
+            ("a2", self.a2()),
+            ("a3", self.a3()),
+            ("a4", self.a4()),
+            ("a5", self.a5()),
+            ("a6", self.a6()),
+            ("a7", self.a7()),
+            ("b1", self.b1()),
+            ("b2", self.b2()),
+            ("H298 - H0", self.h298_minus_h0),
+        ]
+    }
+
+    // Whether every number of the fit is finite, as it must be to be evaluated
+    pub fn is_finite(&self) -> bool {
+        self.named_values()
+            .iter()
+            .all(|(_, value)| value.is_finite())
+    }
+
+    // Coefficients in use, paired with their temperature exponents
+    fn used_terms(&self) -> impl Iterator<Item = (f64, i32)> + '_ {
+        self.coefficients
+            .iter()
+            .copied()
+            .zip(NASA9_EXPONENTS)
+            .take(self.used_coefficients as usize)
+    }
+
+    // Dimensionless heat capacity Cp/R from the NASA-9 polynomial. This and the other
+    // properties of a fit refuse a temperature that isn't positive, as NaN or zero.
+    pub fn cp_over_r(&self, temperature: f64) -> Result<f64, NumericError> {
+        let t = positive("temperature", temperature)?;
+        Ok(self.used_terms().map(|(a, k)| a * t.powi(k)).sum())
+    }
+
+    // Dimensionless assigned enthalpy H/RT (see `EnthalpyReference::Assigned`)
+    pub fn h_over_rt(&self, temperature: f64) -> Result<f64, NumericError> {
+        let t = positive("temperature", temperature)?;
+        let terms: f64 = self
+            .used_terms()
+            .map(|(a, k)| match k {
+                -1 => a * t.ln() / t,
+                _ => a * t.powi(k) / (k + 1) as f64,
+            })
+            .sum();
+        Ok(terms + self.b1() / t)
+    }
+
+    // Dimensionless standard-state entropy S/R
+    pub fn s_over_r(&self, temperature: f64) -> Result<f64, NumericError> {
+        let t = positive("temperature", temperature)?;
+        let terms: f64 = self
+            .used_terms()
+            .map(|(a, k)| match k {
+                0 => a * t.ln(),
+                _ => a * t.powi(k) / k as f64,
+            })
+            .sum();
+        Ok(terms + self.b2())
+    }
+
+    // Dimensionless standard-state Gibbs energy G/RT
+    pub fn g_over_rt(&self, temperature: f64) -> Result<f64, NumericError> {
+        Ok(self.h_over_rt(temperature)? - self.s_over_r(temperature)?)
+    }
+}
+
+// A fit's Cp/R laid out for repeated evaluation. The NASA-9 exponents run up from -2, so the
+// sum is a polynomial in t over t^2, taken with one division and no `powi`. Evaluation has no
+// branches, leaving loops over many temperatures free to vectorise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpPolynomial {
+    powers: [i8; 7],  // Temperature exponent of each coefficient
+    coeffs: [f64; 7], // Zero for terms the fit doesn't use
+}
+
+impl CpPolynomial {
+    // The temperature exponent of each coefficient, -2 to 4
+    pub fn powers(&self) -> [i8; 7] {
+        self.powers
+    }
+
+    // Dimensionless heat capacity Cp/R, as `TemperatureRange::cp_over_r` gives it
+    pub fn cp_over_r(&self, temperature: f64) -> f64 {
+        let t = temperature;
+        let c = &self.coeffs;
+        // Estrin's scheme: Horner's on pairs of terms, which keeps the pairs independent
+        let t2 = t * t;
+        let low = (c[0] + c[1] * t) + t2 * (c[2] + c[3] * t);
+        let high = (c[4] + c[5] * t) + t2 * c[6];
+        (low + t2 * t2 * high) / t2
+    }
+}
+
+impl From<&TemperatureRange> for CpPolynomial {
+    fn from(range: &TemperatureRange) -> Self {
+        let mut coeffs = [0.0; 7];
+        for (slot, (a, _)) in coeffs.iter_mut().zip(range.used_terms()) {
+            *slot = a;
+        }
+        CpPolynomial {
+            powers: NASA9_EXPONENTS.map(|k| k as i8),
+            coeffs,
+        }
+    }
+}
+
+// H/RT divides each term by its exponent plus one, and S/R by its exponent. Zero stands in
+// for the terms integrating to logarithms (exponent -1 in H, 0 in S), added separately.
+const H_FACTORS: [f64; 7] = [-1.0, 0.0, 1.0, 1.0 / 2.0, 1.0 / 3.0, 1.0 / 4.0, 1.0 / 5.0];
+const S_FACTORS: [f64; 7] = [-1.0 / 2.0, -1.0, 0.0, 1.0, 1.0 / 2.0, 1.0 / 3.0, 1.0 / 4.0];
+
+// One temperature's powers, shared by every fit evaluated at it: T to each NASA-9 exponent,
+// and ln T
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperaturePowers {
+    temperature: f64,
+    powers: [f64; 7],
+    ln_t: f64,
+}
+
+impl TemperaturePowers {
+    pub fn new(temperature: f64) -> Self {
+        TemperaturePowers {
+            temperature,
+            powers: NASA9_EXPONENTS.map(|k| temperature.powi(k)),
+            ln_t: temperature.ln(),
+        }
+    }
+}
+
+impl TemperatureRange {
+    // G/RT, H/RT, S/R and Cp/R in one pass over the terms, as `g_over_rt` and the rest give
+    // them (to rounding), without a `powi` or logarithm of their own
+    pub fn properties_at(&self, powers: &TemperaturePowers) -> (f64, f64, f64, f64) {
+        let (mut cp, mut h, mut s) = (0.0, 0.0, 0.0);
+        for i in 0..7 {
+            let term = self.coefficients[i] * powers.powers[i];
+            cp += term;
+            h += term * H_FACTORS[i];
+            s += term * S_FACTORS[i];
+        }
+        let a = &self.coefficients;
+        let [b1, b2] = self.integration_constants;
+        let h = h + (a[1] * powers.ln_t + b1) / powers.temperature;
+        let s = s + a[2] * powers.ln_t + b2;
+        (h - s, h, s, cp)
+    }
+}
+
+impl Species {
+    // The temperature range whose fit covers the given temperature, if any. A fit holding a
+    // NaN or infinity gives non-finite properties; `ThermoFile::nonfinite_fit` finds one.
+    pub fn range_at(&self, temperature: f64) -> Option<&TemperatureRange> {
+        self.temperature_ranges
+            .iter()
+            .find(|range| range.contains(temperature))
+    }
+
+    // The temperature brought within the span of the fits, or None without any fits
+    pub fn nearest_fitted(&self, temperature: f64) -> Option<f64> {
+        let low = self.temperature_ranges.first()?.temp_low;
+        let high = self.temperature_ranges.last()?.temp_high;
+        Some(temperature.clamp(low, high))
+    }
+
+    // Dimensionless heat capacity Cp/R, or None outside the fitted temperatures
+    pub fn cp_over_r(&self, temperature: f64) -> Option<f64> {
+        self.range_at(temperature)?.cp_over_r(temperature).ok()
+    }
+
+    // Dimensionless enthalpy H/RT, or None outside the fitted temperatures. A reactant-only
+    // record has no fits, and is known only at the temperature of its assigned enthalpy.
+    pub fn h_over_rt(&self, temperature: f64) -> Option<f64> {
+        if let Some(range) = self.range_at(temperature) {
+            return range.h_over_rt(temperature).ok();
+        }
+        match self.enthalpy_datum {
+            EnthalpyDatum::Assigned {
+                value,
+                temperature: assigned_at,
+            } if (temperature - assigned_at).abs() <= ASSIGNED_TEMPERATURE_TOLERANCE => {
+                Some(value / (GAS_CONSTANT * temperature))
+            }
+            _ => None,
+        }
+    }
+
+    // Dimensionless entropy S/R, or None outside the fitted temperatures
+    pub fn s_over_r(&self, temperature: f64) -> Option<f64> {
+        self.range_at(temperature)?.s_over_r(temperature).ok()
+    }
+
+    // Dimensionless Gibbs energy G/RT, or None outside the fitted temperatures
+    pub fn g_over_rt(&self, temperature: f64) -> Option<f64> {
+        self.range_at(temperature)?.g_over_rt(temperature).ok()
+    }
+
+    // Heat capacity, J/(mol K)
+    pub fn cp(&self, temperature: f64) -> Option<f64> {
+        Some(self.cp_over_r(temperature)? * GAS_CONSTANT)
+    }
+
+    // Heat capacity (J/(mol K)) with any step where two fits meet smoothed away: within
+    // `blend_width` K centred on a boundary, both fits are evaluated and mixed linearly from
+    // one to the other. This is cosmetic, for plotting; the blend is not consistent with the
+    // enthalpy and entropy, which still switch fits at the boundary.
+    pub fn cp_smooth(&self, temperature: f64, blend_width: f64) -> Option<f64> {
+        let half_width = 0.5 * blend_width;
+        let boundary = self.temperature_ranges.windows(2).find(|pair| {
+            pair[0].temp_high == pair[1].temp_low
+                && (temperature - pair[0].temp_high).abs() < half_width
+        });
+        let Some([below, above]) = boundary else {
+            return self.cp(temperature);
+        };
+        let weight = (temperature - (below.temp_high - half_width)) / blend_width;
+        let cp_over_r = (1.0 - weight) * below.cp_over_r(temperature).ok()?
+            + weight * above.cp_over_r(temperature).ok()?;
+        Some(cp_over_r * GAS_CONSTANT)
+    }
+
+    // Assigned enthalpy, J/mol (see `EnthalpyReference::Assigned`)
+    pub fn enthalpy(&self, temperature: f64) -> Option<f64> {
+        Some(self.h_over_rt(temperature)? * GAS_CONSTANT * temperature)
+    }
+
+    // Standard-state entropy, J/(mol K)
+    pub fn entropy(&self, temperature: f64) -> Option<f64> {
+        Some(self.s_over_r(temperature)? * GAS_CONSTANT)
+    }
+
+    // Standard-state Gibbs energy, J/mol
+    pub fn gibbs(&self, temperature: f64) -> Option<f64> {
+        Some(self.g_over_rt(temperature)? * GAS_CONSTANT * temperature)
+    }
+
+    // Enthalpy measured from the given reference, J/mol. The formation basis needs the database
+    // for the reference-state elements.
+    pub fn enthalpy_relative_to(
+        &self,
+        reference: EnthalpyReference,
+        temperature: f64,
+        db: &ThermoFile,
+    ) -> Option<f64> {
+        match reference {
+            EnthalpyReference::Assigned => self.enthalpy(temperature),
+            EnthalpyReference::Formation => self.delta_f_enthalpy(temperature, db),
+            EnthalpyReference::Sensible => {
+                Some(self.enthalpy(temperature)? - self.enthalpy(JANAF_REFERENCE_TEMPERATURE)?)
+            }
+        }
+    }
+
+    // Re-express an enthalpy of this species at T (J/mol) on a different reference
+    pub fn convert_enthalpy(
+        &self,
+        value: f64,
+        temperature: f64,
+        from: EnthalpyReference,
+        to: EnthalpyReference,
+        db: &ThermoFile,
+    ) -> Option<f64> {
+        let shift = self.enthalpy_relative_to(to, temperature, db)?
+            - self.enthalpy_relative_to(from, temperature, db)?;
+        Some(value + shift)
+    }
+
+    // Internal energy, J/mol. Gases are ideal, so u = h - RT; condensed phases are treated as
+    // incompressible, where the pv term is negligible and u = h.
+    pub fn internal_energy(&self, temperature: f64) -> Option<f64> {
+        let enthalpy = self.enthalpy(temperature)?;
+        if self.is_condensed() {
+            Some(enthalpy)
+        } else {
+            Some(enthalpy - GAS_CONSTANT * temperature)
+        }
+    }
+
+    // Constant-volume heat capacity, J/(mol K). The ideal-gas relation cv = cp - R applies to
+    // gases; condensed phases have cv = cp under the same incompressible assumption.
+    pub fn cv(&self, temperature: f64) -> Option<f64> {
+        let cp = self.cp(temperature)?;
+        if self.is_condensed() {
+            Some(cp)
+        } else {
+            Some(cp - GAS_CONSTANT)
+        }
+    }
+
+    // Check the fit against JANAF table rows of (T in K, Cp in J/(mol K), H - H(298.15) in kJ/mol,
+    // S in J/(mol K)). Cp and S must agree within the relative tolerance `tol`; the enthalpy
+    // difference, which passes through zero, within `tol` of R*T. Every failing row is reported.
+    pub fn compare_to_janaf(
+        &self,
+        table: &[(f64, f64, f64, f64)],
+        tol: f64,
+    ) -> Result<(), Vec<(f64, PropertyMismatch)>> {
+        let mut mismatches = Vec::new();
+        let Some(h_reference) = self.enthalpy(JANAF_REFERENCE_TEMPERATURE) else {
+            mismatches.extend(
+                table
+                    .iter()
+                    .map(|row| (row.0, PropertyMismatch::OutOfRange)),
+            );
+            return Err(mismatches);
+        };
+
+        for &(t, cp, h, s) in table {
+            let (Some(cp_fit), Some(h_fit), Some(s_fit)) =
+                (self.cp(t), self.enthalpy(t), self.entropy(t))
+            else {
+                mismatches.push((t, PropertyMismatch::OutOfRange));
+                continue;
+            };
+            let h_fit = (h_fit - h_reference) / 1000.0;
+
+            if (cp_fit - cp).abs() > tol * cp.abs() {
+                mismatches.push((
+                    t,
+                    PropertyMismatch::HeatCapacity {
+                        computed: cp_fit,
+                        expected: cp,
+                    },
+                ));
+            }
+            if (h_fit - h).abs() > tol * GAS_CONSTANT * t / 1000.0 {
+                mismatches.push((
+                    t,
+                    PropertyMismatch::Enthalpy {
+                        computed: h_fit,
+                        expected: h,
+                    },
+                ));
+            }
+            if (s_fit - s).abs() > tol * s.abs() {
+                mismatches.push((
+                    t,
+                    PropertyMismatch::Entropy {
+                        computed: s_fit,
+                        expected: s,
+                    },
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatches)
+        }
+    }
+
+    // Measure the fit against reference rows laid out as for `compare_to_janaf`, giving the
+    // RMS and largest relative error of each property rather than a pass or fail
+    pub fn thermo_fit_quality(&self, janaf: &[(f64, f64, f64, f64)]) -> FitStats {
+        let mut stats = FitStats::default();
+        let mut squares = [0.0; 3];
+        let h_reference = self.enthalpy(JANAF_REFERENCE_TEMPERATURE);
+        for &(t, cp, h, s) in janaf {
+            let (Some(h_reference), Some(cp_fit), Some(h_fit), Some(s_fit)) =
+                (h_reference, self.cp(t), self.enthalpy(t), self.entropy(t))
+            else {
+                stats.out_of_range += 1;
+                continue;
+            };
+            let h_fit = (h_fit - h_reference) / 1000.0;
+            let errors = [
+                (cp_fit - cp).abs() / cp.abs(),
+                (h_fit - h).abs() / (GAS_CONSTANT * t / 1000.0),
+                (s_fit - s).abs() / s.abs(),
+            ];
+            let maxima = [&mut stats.cp, &mut stats.enthalpy, &mut stats.entropy];
+            for ((error, square), property) in errors.iter().zip(&mut squares).zip(maxima) {
+                *square += error * error;
+                property.max = property.max.max(*error);
+            }
+            stats.points += 1;
+        }
+        if stats.points > 0 {
+            let count = stats.points as f64;
+            stats.cp.rms = (squares[0] / count).sqrt();
+            stats.enthalpy.rms = (squares[1] / count).sqrt();
+            stats.entropy.rms = (squares[2] / count).sqrt();
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::parse_thermo_file;
+    use crate::test_data::{full_database, janaf_rows};
+
+    #[test]
+    fn test_air_properties() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        let air = &thermo_db.species[1];
+
+        // Cp of air near room temperature is about 29.1 J/(mol K)
+        let cp = air.cp(300.0).unwrap();
+        assert!((cp - 29.1).abs() < 0.1, "cp = {}", cp);
+
+        // Sensible enthalpy from 298.15 K to 1000 K is about 21.7 kJ/mol
+        let dh = air.enthalpy(1000.0).unwrap() - air.enthalpy(298.15).unwrap();
+        assert!((dh - 21.7e3).abs() < 0.5e3, "dh = {}", dh);
+
+        assert!(air.cp(100.0).is_none());
+        assert!(air.cp(7000.0).is_none());
+    }
+
+    #[test]
+    fn test_named_coefficients() {
+        let n2 = full_database().find_species("N2").unwrap();
+        for range in n2.temperature_ranges() {
+            assert_eq!(range.a1(), range.coefficients[0]);
+            assert_eq!(range.a4(), range.coefficients[3]);
+            assert_eq!(range.a7(), range.coefficients[6]);
+            assert_eq!(range.b1(), range.integration_constants[0]);
+            assert_eq!(range.b2(), range.integration_constants[1]);
+            for i in 1..=7 {
+                assert_eq!(range.a(i), range.coefficients[i - 1]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "NASA-9 coefficients are a1 to a7, not a8")]
+    fn test_coefficient_out_of_range() {
+        let n2 = full_database().find_species("N2").unwrap();
+        n2.temperature_ranges()[0].a(8);
+    }
+
+    #[test]
+    fn test_cp_smooth_across_boundary() {
+        let db = full_database();
+        let mut nitrogen = db.find_species("N2").unwrap().clone();
+        // Offset the upper fit so Cp steps by 0.1 R at 1000 K
+        nitrogen.temperature_ranges[1].coefficients[2] += 0.1;
+        let step = nitrogen.cp(1000.0 + 1e-9).unwrap() - nitrogen.cp(1000.0 - 1e-9).unwrap();
+        assert!((step - 0.1 * GAS_CONSTANT).abs() < 1e-6, "{}", step);
+
+        // Blended, neighbouring temperatures give neighbouring values through the window
+        let width = 20.0;
+        let smooth = |t| nitrogen.cp_smooth(t, width).unwrap();
+        assert!((smooth(1000.0 + 1e-9) - smooth(1000.0 - 1e-9)).abs() < 1e-6);
+        let mut t = 985.0;
+        while t < 1015.0 {
+            assert!((smooth(t + 0.01) - smooth(t)).abs() < 1e-3, "at {} K", t);
+            t += 0.01;
+        }
+
+        // Outside the window, or with no width, it is the plain heat capacity
+        assert_eq!(nitrogen.cp_smooth(900.0, width), nitrogen.cp(900.0));
+        assert_eq!(nitrogen.cp_smooth(1000.0, 0.0), nitrogen.cp(1000.0));
+        assert_eq!(nitrogen.cp_smooth(100.0, width), None);
+    }
+
+    #[test]
+    fn test_cp_polynomial_matches_fit() {
+        // Some condensed fits have large terms that nearly cancel, where summing in another
+        // order moves the ninth digit
+        for species in &full_database().species {
+            for range in species.temperature_ranges() {
+                let polynomial = CpPolynomial::from(range);
+                assert_eq!(polynomial.powers(), [-2, -1, 0, 1, 2, 3, 4]);
+                for fraction in [0.0, 0.3, 0.7, 1.0] {
+                    let t = range.temp_low + fraction * (range.temp_high - range.temp_low);
+                    let (cached, direct) = (polynomial.cp_over_r(t), range.cp_over_r(t).unwrap());
+                    assert!(
+                        (cached - direct).abs() <= 1e-8 * direct.abs().max(1.0),
+                        "{} at {} K: {} against {}",
+                        species.name,
+                        t,
+                        cached,
+                        direct
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_properties_at_matches_fit() {
+        // As for Cp/R, large cancelling terms in some condensed fits leave the ninth digit to
+        // the order of summation
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-8 * b.abs().max(1.0);
+        for species in &full_database().species {
+            for range in species.temperature_ranges() {
+                for fraction in [0.0, 0.5, 1.0] {
+                    let t = range.temp_low + fraction * (range.temp_high - range.temp_low);
+                    let (g, h, s, cp) = range.properties_at(&TemperaturePowers::new(t));
+                    let direct = [
+                        range.g_over_rt(t).unwrap(),
+                        range.h_over_rt(t).unwrap(),
+                        range.s_over_r(t).unwrap(),
+                        range.cp_over_r(t).unwrap(),
+                    ];
+                    for (value, direct) in [g, h, s, cp].into_iter().zip(direct) {
+                        assert!(close(value, direct), "{} at {} K", species.name, t);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_electron_is_monatomic() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        let electron = &thermo_db.species[0];
+
+        for temperature in [298.15, 1000.0, 5000.0, 15000.0] {
+            assert_eq!(electron.cp_over_r(temperature), Some(2.5));
+        }
+    }
+
+    #[test]
+    fn test_compare_to_janaf() {
+        let db = full_database();
+        for name in ["N2", "O2"] {
+            let species = db.find_species(name).unwrap();
+            if let Err(mismatches) = species.compare_to_janaf(&janaf_rows(name), 0.005) {
+                panic!("{} deviates from JANAF: {:?}", name, mismatches);
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare_to_janaf_reports_mismatches() {
+        let db = full_database();
+        let n2 = db.find_species("N2").unwrap();
+        let table = [
+            (1000.0, 32.697, 21.463, 228.170),
+            (1000.0, 40.0, 21.463, 228.170),
+            (50000.0, 37.0, 1000.0, 300.0),
+        ];
+        let mismatches = n2.compare_to_janaf(&table, 0.005).unwrap_err();
+        assert_eq!(mismatches.len(), 2);
+        assert!(matches!(
+            mismatches[0],
+            (
+                1000.0,
+                PropertyMismatch::HeatCapacity { expected: 40.0, .. }
+            )
+        ));
+        assert_eq!(mismatches[1], (50000.0, PropertyMismatch::OutOfRange));
+    }
+
+    #[test]
+    fn test_thermo_fit_quality() {
+        let n2 = full_database().find_species("N2").unwrap();
+        let h298 = n2.enthalpy(298.15).unwrap();
+
+        // A table taken from the fit itself is matched to rounding
+        let own: Vec<(f64, f64, f64, f64)> = (300..=6000)
+            .step_by(100)
+            .map(f64::from)
+            .map(|t| {
+                let h = (n2.enthalpy(t).unwrap() - h298) / 1000.0;
+                (t, n2.cp(t).unwrap(), h, n2.entropy(t).unwrap())
+            })
+            .collect();
+        let stats = n2.thermo_fit_quality(&own);
+        assert_eq!((stats.points, stats.out_of_range), (own.len(), 0));
+        for property in [stats.cp, stats.enthalpy, stats.entropy] {
+            assert!(property.max < 1e-12, "{:?}", stats);
+            assert!(property.rms <= property.max);
+        }
+
+        // JANAF itself within the tolerance `compare_to_janaf` passes it at, and a row past
+        // the fit counted but not measured
+        let mut janaf = janaf_rows("N2");
+        let points = janaf.len();
+        janaf.push((50000.0, 37.0, 1000.0, 300.0));
+        let stats = n2.thermo_fit_quality(&janaf);
+        assert_eq!((stats.points, stats.out_of_range), (points, 1));
+        for property in [stats.cp, stats.enthalpy, stats.entropy] {
+            assert!(property.max > 0.0 && property.max <= 0.005, "{:?}", stats);
+            assert!(property.rms > 0.0 && property.rms <= property.max);
+        }
+        assert_eq!(n2.thermo_fit_quality(&[]), FitStats::default());
+    }
+
+    #[test]
+    fn test_cv_and_internal_energy() {
+        let db = full_database();
+        let n2 = db.find_species("N2").unwrap();
+        let gamma = |t| n2.cp(t).unwrap() / n2.cv(t).unwrap();
+        assert!(
+            (gamma(300.0) - 1.40).abs() < 0.005,
+            "gamma = {}",
+            gamma(300.0)
+        );
+        assert!(
+            (gamma(2000.0) - 1.30).abs() < 0.01,
+            "gamma = {}",
+            gamma(2000.0)
+        );
+
+        let u = n2.internal_energy(1000.0).unwrap();
+        let h = n2.enthalpy(1000.0).unwrap();
+        assert!((h - u - GAS_CONSTANT * 1000.0).abs() < 1e-9);
+
+        let water = db.find_species("H2O(L)").unwrap();
+        assert_eq!(water.internal_energy(300.0), water.enthalpy(300.0));
+        assert_eq!(water.cv(300.0), water.cp(300.0));
+    }
+
+    #[test]
+    fn test_enthalpy_references() {
+        let db = full_database();
+        let t_ref = JANAF_REFERENCE_TEMPERATURE;
+        let h = |species: &Species, reference, t| {
+            species.enthalpy_relative_to(reference, t, db).unwrap()
+        };
+
+        // At 298.15 K the assigned enthalpy is the heat of formation
+        let water = db.find_species("H2O").unwrap();
+        let assigned = h(water, EnthalpyReference::Assigned, t_ref);
+        assert!(
+            (assigned - water.enthalpy_datum.value()).abs() < 5.0,
+            "{}",
+            assigned
+        );
+        assert!((h(water, EnthalpyReference::Formation, t_ref) - assigned).abs() < 5.0);
+        assert!(h(water, EnthalpyReference::Sensible, t_ref).abs() < 1e-9);
+
+        // Elements in their reference states: zero formation enthalpy at every temperature,
+        // but an assigned enthalpy that only vanishes at 298.15 K
+        let oxygen = db.find_species("O2").unwrap();
+        assert!(h(oxygen, EnthalpyReference::Assigned, t_ref).abs() < 5.0);
+        assert!(h(oxygen, EnthalpyReference::Assigned, 1000.0) > 20e3);
+        assert!(h(oxygen, EnthalpyReference::Formation, 1000.0).abs() < 1e-9);
+
+        // Conversions go through the value on each basis and round-trip
+        let assigned = h(water, EnthalpyReference::Assigned, 1000.0);
+        let formation = water
+            .convert_enthalpy(
+                assigned,
+                1000.0,
+                EnthalpyReference::Assigned,
+                EnthalpyReference::Formation,
+                db,
+            )
+            .unwrap();
+        assert!((formation - h(water, EnthalpyReference::Formation, 1000.0)).abs() < 1e-6);
+        let back = water
+            .convert_enthalpy(
+                formation,
+                1000.0,
+                EnthalpyReference::Formation,
+                EnthalpyReference::Assigned,
+                db,
+            )
+            .unwrap();
+        assert!((back - assigned).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::Write;
+
+use crate::database::ThermoFile;
+use crate::export::{Cell, ExportError, Table};
+
+// Columns of a property table, in order, with their units. Downstream readers key on these
+// names, so new columns go at the end and existing ones are never renamed or reordered:
+//   species  database name of the species
+//   T        temperature, K
+//   cp       heat capacity, J/(mol K)
+//   h        assigned enthalpy, J/mol
+//   s        standard-state entropy, J/(mol K)
+//   g        standard-state Gibbs energy, J/mol
+// A property is missing (null, or NaN in CSV) where the temperature is outside the fits.
+pub const PROPERTY_COLUMNS: [&str; 6] = ["species", "T", "cp", "h", "s", "g"];
+
+// Standard-state properties of species over a temperature grid, one row per species and
+// temperature, species by species
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PropertyTable {
+    pub rows: Vec<PropertyRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyRow {
+    pub species: String,
+    pub temperature: f64,      // K
+    pub cp: Option<f64>,       // J/(mol K)
+    pub enthalpy: Option<f64>, // J/mol
+    pub entropy: Option<f64>,  // J/(mol K)
+    pub gibbs: Option<f64>,    // J/mol
+}
+
+impl PropertyTable {
+    // Evaluate each named species (or alias) at each temperature, failing on an unknown name
+    pub fn new(
+        db: &ThermoFile,
+        species: &[&str],
+        temperatures: &[f64],
+    ) -> Result<Self, ExportError> {
+        let mut rows = Vec::with_capacity(species.len() * temperatures.len());
+        for &name in species {
+            let index = db.index_of(name).map_err(ExportError::UnknownSpecies)?;
+            let species = &db.species[index];
+            rows.extend(temperatures.iter().map(|&t| PropertyRow {
+                species: species.name.clone(),
+                temperature: t,
+                cp: species.cp(t),
+                enthalpy: species.enthalpy(t),
+                entropy: species.entropy(t),
+                gibbs: species.gibbs(t),
+            }));
+        }
+        Ok(PropertyTable { rows })
+    }
+
+    fn table(&self, db: &ThermoFile) -> Result<Table, ExportError> {
+        let mut table = Table::new(db, &PROPERTY_COLUMNS, &[])?;
+        let number = |value: Option<f64>| Cell::Number(value.unwrap_or(f64::NAN));
+        for row in &self.rows {
+            table.push_row(vec![
+                Cell::Text(row.species.clone()),
+                Cell::Number(row.temperature),
+                number(row.cp),
+                number(row.enthalpy),
+                number(row.entropy),
+                number(row.gibbs),
+            ]);
+        }
+        Ok(table)
+    }
+
+    // Write the table as CSV, with a header row of the column names
+    pub fn write_csv(&self, db: &ThermoFile, writer: impl Write) -> Result<(), ExportError> {
+        self.table(db)?.write_csv(writer)
+    }
+
+    // An object of the database's "fingerprint", its "patches" and the "rows", an array of
+    // objects, one per row
+    pub fn to_json(&self, db: &ThermoFile) -> Result<String, ExportError> {
+        self.table(db)?.to_json()
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod columnar {
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+
+    use super::{PROPERTY_COLUMNS, PropertyRow, PropertyTable};
+    use crate::database::ThermoFile;
+    use crate::export::ExportError;
+
+    // Arrow schema of a property table: the species as UTF-8, the rest as 64-bit floats, with
+    // only the properties nullable
+    pub fn property_schema() -> Schema {
+        let fields = PROPERTY_COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| match i {
+                0 => Field::new(name, DataType::Utf8, false),
+                1 => Field::new(name, DataType::Float64, false),
+                _ => Field::new(name, DataType::Float64, true),
+            });
+        Schema::new(fields.collect::<Vec<_>>())
+    }
+
+    impl PropertyTable {
+        // The table as one Arrow record batch with `property_schema`
+        pub fn to_arrow(&self) -> RecordBatch {
+            let column = |property: fn(&PropertyRow) -> Option<f64>| -> ArrayRef {
+                Arc::new(self.rows.iter().map(property).collect::<Float64Array>())
+            };
+            let species = self.rows.iter().map(|row| row.species.as_str());
+            let columns = vec![
+                Arc::new(StringArray::from_iter_values(species)) as ArrayRef,
+                column(|row| Some(row.temperature)),
+                column(|row| row.cp),
+                column(|row| row.enthalpy),
+                column(|row| row.entropy),
+                column(|row| row.gibbs),
+            ];
+            RecordBatch::try_new(Arc::new(property_schema()), columns)
+                .expect("columns follow the property schema")
+        }
+
+        // Write the table to a Parquet file
+        pub fn write_parquet(&self, path: &Path) -> Result<(), ExportError> {
+            let batch = self.to_arrow();
+            let mut writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+            writer.write(&batch)?;
+            writer.close()?;
+            Ok(())
+        }
+    }
+
+    // Properties of each named species at each temperature as an Arrow record batch
+    pub fn props_to_arrow(
+        db: &ThermoFile,
+        species: &[&str],
+        temperatures: &[f64],
+    ) -> Result<RecordBatch, ExportError> {
+        Ok(PropertyTable::new(db, species, temperatures)?.to_arrow())
+    }
+}
+
+#[cfg(feature = "arrow")]
+pub use columnar::{property_schema, props_to_arrow};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_property_table_csv() {
+        let db = full_database();
+        let table = PropertyTable::new(db, &["N2", "H2O(L)"], &[300.0, 1000.0]).unwrap();
+        assert_eq!(table.rows.len(), 4);
+        let mut csv = Vec::new();
+        table.write_csv(db, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("# fingerprint: "));
+        assert_eq!(lines[1], "species,T,cp,h,s,g");
+        let n2_cp = db.find_species("N2").unwrap().cp(300.0).unwrap();
+        assert_eq!(lines[2].split(',').nth(2).unwrap(), n2_cp.to_string());
+        assert!(lines[5].starts_with("H2O(L),1000,NaN,"));
+        assert!(matches!(
+            PropertyTable::new(db, &["N2", "unobtainium"], &[300.0]),
+            Err(ExportError::UnknownSpecies(_))
+        ));
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use arrow_array::{Array, Float64Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let db = full_database();
+        let temperatures = [300.0, 1500.0, 5000.0];
+        let table = PropertyTable::new(db, &["CO2", "H2O(L)"], &temperatures).unwrap();
+        let path = std::env::temp_dir().join("equilibrium-rs-props.parquet");
+        table.write_parquet(&path).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.schema().as_ref(), &property_schema());
+        assert_eq!(batch.num_rows(), 6);
+
+        let column = |name: &str| {
+            let array = batch.column_by_name(name).unwrap();
+            array
+                .as_any()
+                .downcast_ref::<Float64Array>()
+                .unwrap()
+                .clone()
+        };
+        let species = batch.column_by_name("species").unwrap();
+        let species = species.as_any().downcast_ref::<StringArray>().unwrap();
+        let co2 = db.find_species("CO2").unwrap();
+        assert_eq!(species.value(1), "CO2");
+        assert_eq!(column("T").value(1), 1500.0);
+        assert_eq!(column("cp").value(1), co2.cp(1500.0).unwrap());
+        assert_eq!(column("h").value(2), co2.enthalpy(5000.0).unwrap());
+        assert_eq!(column("g").value(0), co2.gibbs(300.0).unwrap());
+        // Liquid water has no fit at 1500 K
+        assert_eq!(species.value(4), "H2O(L)");
+        assert!(column("s").is_null(4));
+        assert!(column("s").is_valid(3));
+
+        let direct = props_to_arrow(db, &["CO2", "H2O(L)"], &temperatures).unwrap();
+        assert_eq!(&direct, batch);
+    }
+}
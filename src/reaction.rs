@@ -0,0 +1,696 @@
+use std::fmt;
+
+use crate::database::{Species, ThermoFile};
+use crate::elements::{Element, reference_species};
+use crate::lookup::UnknownSpecies;
+use crate::properties::{GAS_CONSTANT, StandardState};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    // A species name not in the database
+    UnknownSpecies(UnknownSpecies),
+    // An element found on only one side of the reaction
+    ElementMismatch(Element),
+    // No positive coefficients balance the species as they are split between the sides
+    Unbalanceable,
+    // More than one independent set of coefficients balances the reaction
+    Underdetermined { freedom: usize },
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BalanceError::UnknownSpecies(unknown) => write!(f, "{}", unknown),
+            BalanceError::ElementMismatch(symbol) => {
+                write!(f, "element '{}' appears on only one side", symbol)
+            }
+            BalanceError::Unbalanceable => write!(f, "no coefficients balance the reaction"),
+            BalanceError::Underdetermined { freedom } => write!(
+                f,
+                "the reaction balances in {} independent ways; split it into simpler ones",
+                freedom
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+// A chemical reaction between database species
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Reaction {
+    pub reactants: Vec<(usize, f64)>, // Species index and stoichiometric coefficient
+    pub products: Vec<(usize, f64)>,  // Species index and stoichiometric coefficient
+}
+
+impl Reaction {
+    pub fn new(reactants: Vec<(usize, f64)>, products: Vec<(usize, f64)>) -> Self {
+        Reaction {
+            reactants,
+            products,
+        }
+    }
+
+    // Build a reaction from species names, failing on the first name not in the database
+    pub fn from_names(
+        db: &ThermoFile,
+        reactants: &[(&str, f64)],
+        products: &[(&str, f64)],
+    ) -> Result<Self, UnknownSpecies> {
+        let lookup = |side: &[(&str, f64)]| {
+            side.iter()
+                .map(|(name, nu)| Ok((db.index_of(name)?, *nu)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Reaction {
+            reactants: lookup(reactants)?,
+            products: lookup(products)?,
+        })
+    }
+
+    // Net stoichiometric coefficients, negative for reactants and positive for products
+    pub fn net_coefficients(&self) -> Vec<(usize, f64)> {
+        let mut net: Vec<(usize, f64)> = Vec::new();
+        let signed = self
+            .reactants
+            .iter()
+            .map(|&(i, nu)| (i, -nu))
+            .chain(self.products.iter().copied());
+        for (index, nu) in signed {
+            match net.iter_mut().find(|(i, _)| *i == index) {
+                Some((_, total)) => *total += nu,
+                None => net.push((index, nu)),
+            }
+        }
+        net
+    }
+
+    // Change in moles of gas-phase species across the reaction
+    pub fn delta_gas_moles(&self, db: &ThermoFile) -> f64 {
+        self.net_coefficients()
+            .iter()
+            .filter(|(i, _)| !db.species[*i].is_condensed())
+            .map(|(_, nu)| nu)
+            .sum()
+    }
+
+    // The reaction between named species with the smallest whole-number coefficients, as
+    // `balance` finds them
+    pub fn balanced(
+        db: &ThermoFile,
+        reactants: &[&str],
+        products: &[&str],
+    ) -> Result<Self, BalanceError> {
+        let lookup = |side: &[&str]| {
+            side.iter()
+                .map(|&name| match db.index_of(name) {
+                    Ok(index) => Ok((index, 0.0)),
+                    Err(unknown) => Err(BalanceError::UnknownSpecies(unknown)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mut reaction = Reaction::new(lookup(reactants)?, lookup(products)?);
+        reaction.balance(db)?;
+        Ok(reaction)
+    }
+
+    // Replace the coefficients with the smallest whole numbers that balance every element,
+    // keeping each species on its side. Charge balances as the electron element 'E', which
+    // ions on one side can carry between them. Only the species themselves are read.
+    pub fn balance(&mut self, db: &ThermoFile) -> Result<(), BalanceError> {
+        let species: Vec<usize> = self
+            .reactants
+            .iter()
+            .chain(&self.products)
+            .map(|&(index, _)| index)
+            .collect();
+        let (elements, columns) = element_matrix(db, &species);
+        let (reacting, produced) = columns.split_at(self.reactants.len());
+        let present = |side: &[Vec<i128>], k: usize| side.iter().any(|column| column[k] != 0);
+        for (k, symbol) in elements.iter().enumerate() {
+            // Opposite charges can cancel within a side, unlike counts of atoms
+            if *symbol != Element::ELECTRON && present(reacting, k) != present(produced, k) {
+                return Err(BalanceError::ElementMismatch(*symbol));
+            }
+        }
+        if self.reactants.is_empty() || self.products.is_empty() {
+            return Err(BalanceError::Unbalanceable);
+        }
+
+        let matrix: Vec<&[i128]> = columns.iter().map(Vec::as_slice).collect();
+        let nu = match null_vector(&matrix) {
+            Ok(nu) => nu,
+            Err(0) => return Err(BalanceError::Unbalanceable),
+            Err(freedom) => return Err(BalanceError::Underdetermined { freedom }),
+        };
+        // Reactants need positive coefficients and products negative ones (or the reverse,
+        // which the first non-zero entry being positive rules out)
+        let (reacting, produced) = nu.split_at(self.reactants.len());
+        if reacting.iter().any(|&n| n <= 0) || produced.iter().any(|&n| n >= 0) {
+            return Err(BalanceError::Unbalanceable);
+        }
+        for ((_, coefficient), &n) in self.reactants.iter_mut().chain(&mut self.products).zip(&nu) {
+            *coefficient = n.unsigned_abs() as f64;
+        }
+        Ok(())
+    }
+
+    // Products-minus-reactants change in a per-species property, weighted by stoichiometry.
+    // None if any species is outside its fitted temperature range.
+    fn delta(&self, db: &ThermoFile, property: impl Fn(&Species) -> Option<f64>) -> Option<f64> {
+        let mut delta = 0.0;
+        for (index, nu) in self.net_coefficients() {
+            delta += nu * property(&db.species[index])?;
+        }
+        Some(delta)
+    }
+
+    // Standard enthalpy of reaction, J/mol
+    pub fn delta_h(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.delta(db, |s| s.enthalpy(temperature))
+    }
+
+    // Standard entropy of reaction, J/(mol K)
+    pub fn delta_s(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.delta(db, |s| s.entropy(temperature))
+    }
+
+    // Standard Gibbs energy of reaction, J/mol
+    pub fn delta_g(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        self.delta(db, |s| s.gibbs(temperature))
+    }
+
+    // Natural log of the equilibrium constant Kp = exp(-dG°/RT), on the 1 bar standard state.
+    // None if any species is outside its fitted temperature range.
+    pub fn ln_kp(&self, db: &ThermoFile, temperature: f64) -> Option<f64> {
+        Some(-self.delta(db, |s| s.g_over_rt(temperature))?)
+    }
+
+    // Equilibrium constant in concentrations, Kc = Kp (P°/RT)^Δn with Δn the change in moles
+    // of gas and P° the standard-state pressure, in (mol/m³)^Δn. None if any species is
+    // outside its fitted temperature range.
+    pub fn kc(
+        &self,
+        db: &ThermoFile,
+        temperature: f64,
+        standard_state: StandardState,
+    ) -> Option<f64> {
+        let ln_kp = self.ln_kp(db, temperature)?;
+        let molar_concentration = standard_state.p_ref / (GAS_CONSTANT * temperature);
+        Some((ln_kp + self.delta_gas_moles(db) * molar_concentration.ln()).exp())
+    }
+}
+
+// Most decimals of an element count the scaled element matrix keeps. The (F6.2) count fields
+// hold at most five, as in Air's C .00032.
+const MAX_COUNT_DECIMALS: i32 = 5;
+
+// Every independent element-balanced reaction among the given species with at most
+// `max_reactants` species on the reactant side. Each is a minimal set of species (no smaller
+// subset balances) with the smallest whole-number coefficients, oriented so that the side with
+// more species reacts, or on a tie so that the first-listed species does. A reaction that is
+// the sum of two others (H2 + O -> H + OH from H2 -> 2H and H + O -> OH) isn't minimal and is
+// left out. Electrons count as an element, so reactions among ions balance charge too.
+pub fn generate_reactions(
+    db: &ThermoFile,
+    species: &[usize],
+    max_reactants: usize,
+) -> Vec<Reaction> {
+    let (_, columns) = element_matrix(db, species);
+    let mut reactions = Vec::new();
+    let largest = (2 * max_reactants).min(species.len());
+    for size in 2..=largest {
+        for_each_subset(species.len(), size, &mut |subset| {
+            let matrix: Vec<&[i128]> = subset.iter().map(|&j| columns[j].as_slice()).collect();
+            let Ok(nu) = null_vector(&matrix) else {
+                return;
+            };
+            // Minimal sets have every species taking part
+            if nu.contains(&0) {
+                return;
+            }
+            let positive = nu.iter().filter(|&&n| n > 0).count();
+            let negative = subset.len() - positive;
+            // Positive entries react unless the other side is larger, or as large and holds
+            // the first species
+            let flip = negative > positive || (negative == positive && nu[0] < 0);
+            let mut reaction = Reaction::default();
+            for (&j, &n) in subset.iter().zip(&nu) {
+                let n = if flip { -n } else { n };
+                let side = if n > 0 {
+                    &mut reaction.reactants
+                } else {
+                    &mut reaction.products
+                };
+                side.push((species[j], n.unsigned_abs() as f64));
+            }
+            if reaction.reactants.len() <= max_reactants {
+                reactions.push(reaction);
+            }
+        });
+    }
+    reactions
+}
+
+// The elements (in order of appearance) of the given species, and the
+// element matrix as one column per species, in scaled whole numbers
+fn element_matrix(db: &ThermoFile, species: &[usize]) -> (Vec<Element>, Vec<Vec<i128>>) {
+    let mut elements: Vec<Element> = Vec::new();
+    for &index in species {
+        for &(element, count) in &db.species[index].elements {
+            if count != 0.0 && !elements.contains(&element) {
+                elements.push(element);
+            }
+        }
+    }
+    // Counts are mostly given to two decimals, but a pseudo-species may need more to be whole
+    let counts = || {
+        species
+            .iter()
+            .flat_map(|&index| db.species[index].elements.iter().map(|&(_, count)| count))
+    };
+    let scale = (2..=MAX_COUNT_DECIMALS)
+        .map(|decimals| 10f64.powi(decimals))
+        .find(|scale| counts().all(|count| ((count * scale).round() - count * scale).abs() < 1e-6))
+        .unwrap_or(10f64.powi(MAX_COUNT_DECIMALS));
+    let columns = species
+        .iter()
+        .map(|&index| {
+            let mut column = vec![0; elements.len()];
+            for (element, count) in &db.species[index].elements {
+                if let Some(k) = elements.iter().position(|e| e == element) {
+                    column[k] += (count * scale).round() as i128;
+                }
+            }
+            column
+        })
+        .collect();
+    (elements, columns)
+}
+
+// Call `visit` with every increasing selection of `size` positions out of `count`
+fn for_each_subset(count: usize, size: usize, visit: &mut impl FnMut(&[usize])) {
+    let mut subset: Vec<usize> = (0..size).collect();
+    loop {
+        visit(&subset);
+        // Advance the rightmost position that still has room
+        let Some(i) = (0..size).rev().find(|&i| subset[i] < count - size + i) else {
+            return;
+        };
+        subset[i] += 1;
+        for j in i + 1..size {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+// The whole-number null vector (smallest, first non-zero entry positive) of a matrix given by its
+// columns when the null space is one-dimensional, or else the dimension of the null space.
+// Found by fraction-free Gauss-Jordan elimination.
+fn null_vector(columns: &[&[i128]]) -> Result<Vec<i128>, usize> {
+    let size = columns.len();
+    let rows = columns.first().map_or(0, |column| column.len());
+    let mut matrix: Vec<Vec<i128>> = (0..rows)
+        .map(|r| columns.iter().map(|column| column[r]).collect())
+        .collect();
+
+    let mut pivots: Vec<usize> = Vec::new(); // Pivot column of each reduced row
+    for column in 0..size {
+        let row = pivots.len();
+        let Some(pivot) = (row..rows).find(|&r| matrix[r][column] != 0) else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+        for r in 0..rows {
+            if r == row || matrix[r][column] == 0 {
+                continue;
+            }
+            let (p, a) = (matrix[row][column], matrix[r][column]);
+            let pivot_row = matrix[row].clone();
+            for (x, &y) in matrix[r].iter_mut().zip(&pivot_row) {
+                *x = *x * p - y * a;
+            }
+            let divisor = matrix[r].iter().fold(0, |g, &x| gcd(g, x));
+            if divisor > 1 {
+                matrix[r].iter_mut().for_each(|x| *x /= divisor);
+            }
+        }
+        pivots.push(column);
+    }
+    if pivots.len() + 1 != size {
+        return Err(size - pivots.len());
+    }
+
+    // The one free column takes a common multiple of the pivots, the rest follow from it
+    let free = (0..size)
+        .find(|c| !pivots.contains(c))
+        .ok_or(size - pivots.len())?;
+    let scale = pivots.iter().enumerate().fold(1, |l, (r, &c)| {
+        let p = matrix[r][c].abs();
+        l / gcd(l, p) * p
+    });
+    let mut nu = vec![0; size];
+    nu[free] = scale;
+    for (r, &c) in pivots.iter().enumerate() {
+        nu[c] = -matrix[r][free] * scale / matrix[r][c];
+    }
+    let divisor = nu.iter().fold(0, |g, &x| gcd(g, x));
+    let sign = nu.iter().find(|&&x| x != 0).map_or(1, |x| x.signum());
+    nu.iter_mut().for_each(|x| *x = *x * sign / divisor);
+    Ok(nu)
+}
+
+impl Species {
+    // Reaction forming this species from its elements in their reference states at the given
+    // temperature, such as 1/2 O2 + C(gr) -> CO. Ions take or give up electrons (e-).
+    // None if the species is not in the database or an element has no reference at T.
+    pub fn formation_reaction(&self, temperature: f64, db: &ThermoFile) -> Option<Reaction> {
+        let mut reaction = Reaction::new(Vec::new(), vec![(db.species_index(&self.name)?, 1.0)]);
+        for (element, count) in &self.elements {
+            let (reference, atoms) = reference_species(db, element.symbol(), temperature)?;
+            let index = db.species_index(&reference.name)?;
+            let nu = count / atoms;
+            if nu >= 0.0 {
+                reaction.reactants.push((index, nu));
+            } else {
+                reaction.products.push((index, -nu));
+            }
+        }
+        Some(reaction)
+    }
+
+    // Formation quantity from reference-state elements, given a per-species property
+    fn formation_delta(
+        &self,
+        temperature: f64,
+        db: &ThermoFile,
+        property: impl Fn(&Species) -> Option<f64>,
+    ) -> Option<f64> {
+        let mut delta = property(self)?;
+        for (element, count) in &self.elements {
+            let (reference, atoms) = reference_species(db, element.symbol(), temperature)?;
+            delta -= count / atoms * property(reference)?;
+        }
+        Some(delta)
+    }
+
+    // Gibbs energy of formation from the reference-state elements, J/mol
+    pub fn delta_f_gibbs(&self, temperature: f64, db: &ThermoFile) -> Option<f64> {
+        self.formation_delta(temperature, db, |s| s.gibbs(temperature))
+    }
+
+    // Enthalpy of formation from the reference-state elements, J/mol
+    pub fn delta_f_enthalpy(&self, temperature: f64, db: &ThermoFile) -> Option<f64> {
+        self.formation_delta(temperature, db, |s| s.enthalpy(temperature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::Composition;
+    use crate::properties::REFERENCE_PRESSURE;
+    use crate::test_data::{database_with_reactants, full_database};
+
+    #[test]
+    fn test_net_coefficients() {
+        let db = full_database();
+        let reaction = Reaction::from_names(db, &[("N2O4", 1.0)], &[("NO2", 2.0)]).unwrap();
+        let net = reaction.net_coefficients();
+        assert_eq!(net.len(), 2);
+        assert_eq!(net[0].1, -1.0);
+        assert_eq!(net[1].1, 2.0);
+        assert_eq!(reaction.delta_gas_moles(db), 1.0);
+    }
+
+    #[test]
+    fn test_ln_kp_dinitrogen_tetroxide() {
+        // dG° = 2(51.3) - 97.9 = 4.7 kJ/mol at 298.15 K gives Kp ~ 0.15
+        let db = full_database();
+        let reaction = Reaction::from_names(db, &[("N2O4", 1.0)], &[("NO2", 2.0)]).unwrap();
+        let kp = reaction.ln_kp(db, 298.15).unwrap().exp();
+        assert!((kp - 0.15).abs() < 0.03, "Kp = {}", kp);
+        assert!(reaction.ln_kp(db, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_water_formation_deltas() {
+        // Textbook values at 298.15 K: dH = -241.83 kJ/mol, dG = -228.59 kJ/mol
+        let db = full_database();
+        let t = 298.15;
+        let reaction = Reaction::from_names(db, &[("H2", 1.0), ("O2", 0.5)], &[("H2O", 1.0)]);
+        let reaction = reaction.unwrap();
+        let dh = reaction.delta_h(db, t).unwrap();
+        let dg = reaction.delta_g(db, t).unwrap();
+        let ds = reaction.delta_s(db, t).unwrap();
+        assert!((dh + 241.83e3).abs() < 50.0, "dH = {}", dh);
+        assert!((dg + 228.59e3).abs() < 50.0, "dG = {}", dg);
+        assert!((dg - (dh - t * ds)).abs() < 1e-6 * dg.abs());
+        assert!((reaction.ln_kp(db, t).unwrap() + dg / (GAS_CONSTANT * t)).abs() < 1e-9);
+        assert!(reaction.delta_h(db, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_kc_from_kp() {
+        let db = full_database();
+        let t = 1500.0;
+        let concentration = REFERENCE_PRESSURE / (GAS_CONSTANT * t);
+
+        // One mole of gas gained: Kc = Kp P°/RT
+        let dissociation = Reaction::from_names(db, &[("N2O4", 1.0)], &[("NO2", 2.0)]).unwrap();
+        let kp = dissociation.ln_kp(db, t).unwrap().exp();
+        let kc = dissociation.kc(db, t, StandardState::BAR).unwrap();
+        let kc_atm = dissociation.kc(db, t, StandardState::ATMOSPHERE).unwrap();
+        assert!((kc_atm / kc - 1.01325).abs() < 1e-12);
+        assert!(
+            (kc / (kp * concentration) - 1.0).abs() < 1e-12,
+            "{} {}",
+            kc,
+            kp
+        );
+
+        // One mole lost, and no change at all, where Kc is Kp
+        let water = Reaction::from_names(db, &[("H2", 2.0), ("O2", 1.0)], &[("H2O", 2.0)]).unwrap();
+        let kp = water.ln_kp(db, t).unwrap().exp();
+        let kc = water.kc(db, t, StandardState::BAR).unwrap();
+        assert!((kc * concentration / kp - 1.0).abs() < 1e-9);
+        let nitric = Reaction::from_names(db, &[("N2", 1.0), ("O2", 1.0)], &[("NO", 2.0)]).unwrap();
+        let kp = nitric.ln_kp(db, t).unwrap().exp();
+        assert!((nitric.kc(db, t, StandardState::BAR).unwrap() / kp - 1.0).abs() < 1e-12);
+        assert!(dissociation.kc(db, 100.0, StandardState::BAR).is_none());
+    }
+
+    #[test]
+    fn test_formation_gibbs_energies() {
+        let db = full_database();
+
+        // JANAF: -394.389 kJ/mol for CO2 at 298.15 K
+        let co2 = db.find_species("CO2").unwrap();
+        let dg = co2.delta_f_gibbs(298.15, db).unwrap();
+        assert!((dg / 1000.0 + 394.4).abs() < 0.5, "dfG(CO2) = {}", dg);
+
+        // JANAF: -192.590 kJ/mol and -247.857 kJ/mol for H2O(g) at 1000 K
+        let h2o = db.find_species("H2O").unwrap();
+        let dg = h2o.delta_f_gibbs(1000.0, db).unwrap();
+        assert!((dg / 1000.0 + 192.59).abs() < 0.5, "dfG(H2O) = {}", dg);
+        let dh = h2o.delta_f_enthalpy(1000.0, db).unwrap();
+        assert!((dh / 1000.0 + 247.86).abs() < 0.5, "dfH(H2O) = {}", dh);
+    }
+
+    #[test]
+    fn test_reference_species_have_zero_formation_energy() {
+        let db = full_database();
+        for (name, temperatures) in [
+            ("O2", &[298.15, 1000.0, 5000.0][..]),
+            ("C(gr)", &[298.15, 1000.0, 3000.0][..]),
+            ("AL(cr)", &[298.15, 600.0][..]),
+            ("AL(L)", &[1000.0, 2500.0][..]),
+            ("e-", &[1000.0][..]),
+        ] {
+            let species = db.find_species(name).unwrap();
+            for &t in temperatures {
+                let dg = species.delta_f_gibbs(t, db).unwrap();
+                let dh = species.delta_f_enthalpy(t, db).unwrap();
+                assert!(dg.abs() < 1e-9 && dh.abs() < 1e-9, "{} at {} K", name, t);
+            }
+        }
+    }
+
+    #[test]
+    fn test_formation_reaction() {
+        let db = full_database();
+        let co = db.find_species("CO").unwrap();
+        let reaction = co.formation_reaction(298.15, db).unwrap();
+        let names: Vec<(&str, f64)> = reaction
+            .reactants
+            .iter()
+            .map(|(i, nu)| (db.species[*i].name.as_str(), *nu))
+            .collect();
+        assert_eq!(names, [("C(gr)", 1.0), ("O2", 0.5)]);
+
+        // The formation reaction's equilibrium constant carries the same information
+        let ln_kf = reaction.ln_kp(db, 298.15).unwrap();
+        let dg = co.delta_f_gibbs(298.15, db).unwrap();
+        assert!((ln_kf + dg / (GAS_CONSTANT * 298.15)).abs() < 1e-9);
+
+        // Positive ions release an electron
+        let ion = db.find_species("H2O+").unwrap();
+        let reaction = ion.formation_reaction(1000.0, db).unwrap();
+        let electron = db.species_index("e-").unwrap();
+        assert!(reaction.products.contains(&(electron, 1.0)));
+    }
+
+    #[test]
+    fn test_balance_methane_combustion() {
+        let db = full_database();
+        let mut reaction = Reaction::from_names(
+            db,
+            &[("CH4", 0.0), ("O2", 0.0)],
+            &[("CO2", 0.0), ("H2O", 0.0)],
+        )
+        .unwrap();
+        reaction.balance(db).unwrap();
+        let expected = Reaction::from_names(
+            db,
+            &[("CH4", 1.0), ("O2", 2.0)],
+            &[("CO2", 1.0), ("H2O", 2.0)],
+        )
+        .unwrap();
+        assert_eq!(reaction, expected);
+
+        let mut ozone = Reaction::from_names(db, &[("O2", 0.0)], &[("O3", 0.0)]).unwrap();
+        ozone.balance(db).unwrap();
+        assert_eq!(ozone.reactants[0].1, 3.0);
+        assert_eq!(ozone.products[0].1, 2.0);
+    }
+
+    #[test]
+    fn test_balance_by_name() {
+        let db = full_database();
+        let names = |side: &[(usize, f64)]| -> Vec<(String, f64)> {
+            side.iter()
+                .map(|&(i, nu)| (db.species[i].name.clone(), nu))
+                .collect()
+        };
+        let combustion = Reaction::balanced(db, &["CH4", "O2"], &["CO2", "H2O"]).unwrap();
+        assert_eq!(
+            names(&combustion.products),
+            [("CO2".to_string(), 1.0), ("H2O".to_string(), 2.0)]
+        );
+
+        // Charge balances along with the atoms, including between ions on one side
+        let ionization = Reaction::balanced(db, &["H2O"], &["H+", "OH-"]).unwrap();
+        assert_eq!(
+            ionization.products.iter().map(|p| p.1).collect::<Vec<_>>(),
+            [1.0, 1.0]
+        );
+        let attachment = Reaction::balanced(db, &["O2", "e-"], &["O-"]).unwrap();
+        assert_eq!(
+            names(&attachment.reactants),
+            [("O2".to_string(), 1.0), ("e-".to_string(), 2.0)]
+        );
+        assert_eq!(names(&attachment.products), [("O-".to_string(), 2.0)]);
+
+        // A lost electron can't be made up, and no reaction can be built from a typo
+        assert_eq!(
+            Reaction::balanced(db, &["H"], &["H+"]),
+            Err(BalanceError::Unbalanceable)
+        );
+        assert_eq!(
+            Reaction::balanced(db, &["H2", "O2"], &["H20"]),
+            Err(BalanceError::UnknownSpecies(
+                db.index_of("H20").unwrap_err()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_balance_fractional_composition() {
+        // Air's counts run to five decimals, beyond the two most species need
+        let db = database_with_reactants();
+        let reaction = Reaction::balanced(db, &["Air"], &["N2", "O2", "Ar", "CO2"]).unwrap();
+        assert_eq!(reaction.reactants[0].1, 200_000.0);
+        let products: Vec<f64> = reaction.products.iter().map(|&(_, nu)| nu).collect();
+        assert_eq!(products, [156_170.0, 41_895.0, 1_874.0, 64.0]);
+        assert!(balanced(db, &reaction));
+    }
+
+    #[test]
+    fn test_balance_failures() {
+        let db = full_database();
+        let attempt = |reactants: &[&'static str], products: &[&'static str]| {
+            let side = |names: &[&'static str]| names.iter().map(|&n| (n, 0.0)).collect::<Vec<_>>();
+            let mut reaction = Reaction::from_names(db, &side(reactants), &side(products)).unwrap();
+            reaction.balance(db)
+        };
+        assert_eq!(
+            attempt(&["H2", "O2"], &["H2O", "N2"]),
+            Err(BalanceError::ElementMismatch(Element::new("N").unwrap()))
+        );
+        assert_eq!(attempt(&["H2O"], &["OH"]), Err(BalanceError::Unbalanceable));
+        // Balances only with H2 on the product side: 2 H2O -> H2 + 2 OH
+        assert_eq!(
+            attempt(&["H2O", "H2"], &["OH"]),
+            Err(BalanceError::Unbalanceable)
+        );
+        assert_eq!(
+            attempt(&["H2", "O2"], &["H2O", "H2O2"]),
+            Err(BalanceError::Underdetermined { freedom: 2 })
+        );
+    }
+
+    fn balanced(db: &ThermoFile, reaction: &Reaction) -> bool {
+        let mut totals = Composition::new();
+        for (index, nu) in reaction.net_coefficients() {
+            totals.add(&db.species[index].elements.scale(nu));
+        }
+        totals.iter().all(|(_, total)| total.abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_generate_water_formation() {
+        let db = full_database();
+        let species = ["H2", "O2", "H2O"].map(|name| db.species_index(name).unwrap());
+        let reactions = generate_reactions(db, &species, 2);
+        let expected =
+            Reaction::from_names(db, &[("H2", 2.0), ("O2", 1.0)], &[("H2O", 2.0)]).unwrap();
+        assert_eq!(reactions, [expected]);
+
+        // Only one species may react: nothing fits
+        assert!(generate_reactions(db, &species, 1).is_empty());
+    }
+
+    #[test]
+    fn test_generate_hydrogen_oxygen_network() {
+        let db = full_database();
+        let names = ["H2", "O2", "H", "O", "OH", "H2O"];
+        let species = names.map(|name| db.species_index(name).unwrap());
+        let reactions = generate_reactions(db, &species, 2);
+        assert!(reactions.iter().all(|reaction| balanced(db, reaction)));
+        assert!(
+            reactions
+                .iter()
+                .all(|reaction| reaction.reactants.len() <= 2)
+        );
+
+        // Dissociations and recombinations are among them, with species on each side in the
+        // order they were listed
+        let contains = |reactants: &[(&str, f64)], products: &[(&str, f64)]| {
+            let reaction = Reaction::from_names(db, reactants, products).unwrap();
+            reactions.contains(&reaction)
+        };
+        assert!(contains(&[("H2", 1.0)], &[("H", 2.0)]));
+        assert!(contains(&[("H", 1.0), ("O", 1.0)], &[("OH", 1.0)]));
+        assert!(contains(&[("O", 1.0), ("H2O", 1.0)], &[("OH", 2.0)]));
+
+        // A reaction adding up two others, like H2 + O -> H + OH, isn't minimal
+        assert!(!contains(
+            &[("H2", 1.0), ("O", 1.0)],
+            &[("H", 1.0), ("OH", 1.0)]
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+use std::io::{self, BufRead, Write};
+
+use crate::database::ThermoFile;
+use crate::equilibrium::{EquilibriumOptions, EquilibriumResult, Problem, solve_equilibrium};
+use crate::mixture::Mixture;
+use crate::units;
+
+const PROMPT: &str = "> ";
+
+// Reactant temperature an 'eq hp' mixture enters at when none is given, K
+const DEFAULT_REACTANT_TEMPERATURE: f64 = 298.15;
+
+const HELP: &str = "\
+commands:
+  props NAME T          cp, h, s and g of a species at a temperature
+  mix NAME=MOLES ...    set the current mixture
+  eq tp T P             equilibrium of the mixture at a temperature and pressure
+  eq hp P [T]           adiabatic equilibrium of the mixture entering at T (298.15 K)
+  last                  the last equilibrium result again
+  find PATTERN          species names matching a pattern, with * and ?
+  help                  this list
+  quit                  leave (as does end of input)
+Temperatures are in K and pressures in Pa unless given a unit, as 25C or 1bar.";
+
+// One line of input to the interactive session
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Props { species: String, temperature: f64 },
+    Mix(Vec<(String, f64)>),
+    EqTp { temperature: f64, pressure: f64 },
+    EqHp { pressure: f64, temperature: f64 }, // Reactant temperature, K
+    Last,
+    Find(String),
+    Help,
+    Quit,
+}
+
+// Read one line as a command, or None for a blank line. The command word is matched ignoring
+// case; species names are kept as typed.
+pub fn parse_command(line: &str) -> Result<Option<Command>, String> {
+    let mut words = line.split_whitespace();
+    let Some(word) = words.next() else {
+        return Ok(None);
+    };
+    let arguments: Vec<&str> = words.collect();
+    let expect = |count: usize, usage: &str| {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(format!("usage: {}", usage))
+        }
+    };
+    let temperature = |text: &str| units::parse_temperature(text).map_err(|e| e.to_string());
+    let pressure = |text: &str| units::parse_pressure(text).map_err(|e| e.to_string());
+
+    let command = match word.to_ascii_lowercase().as_str() {
+        "props" => {
+            expect(2, "props NAME T")?;
+            Command::Props {
+                species: arguments[0].to_string(),
+                temperature: temperature(arguments[1])?,
+            }
+        }
+        "mix" => {
+            if arguments.is_empty() {
+                return Err("usage: mix NAME=MOLES ...".to_string());
+            }
+            let components = arguments
+                .iter()
+                .map(|pair| {
+                    let (name, amount) = pair
+                        .split_once('=')
+                        .ok_or_else(|| format!("expected NAME=MOLES, found '{}'", pair))?;
+                    let amount: f64 = amount
+                        .parse()
+                        .map_err(|_| format!("invalid amount in '{}'", pair))?;
+                    Ok((name.to_string(), amount))
+                })
+                .collect::<Result<_, String>>()?;
+            Command::Mix(components)
+        }
+        "eq" => match arguments
+            .first()
+            .map(|kind| kind.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("tp") => {
+                expect(3, "eq tp T P")?;
+                Command::EqTp {
+                    temperature: temperature(arguments[1])?,
+                    pressure: pressure(arguments[2])?,
+                }
+            }
+            Some("hp") => {
+                if !(2..=3).contains(&arguments.len()) {
+                    return Err("usage: eq hp P [T]".to_string());
+                }
+                Command::EqHp {
+                    pressure: pressure(arguments[1])?,
+                    temperature: match arguments.get(2) {
+                        Some(text) => temperature(text)?,
+                        None => DEFAULT_REACTANT_TEMPERATURE,
+                    },
+                }
+            }
+            _ => return Err("usage: eq tp T P, or eq hp P [T]".to_string()),
+        },
+        "last" => {
+            expect(0, "last")?;
+            Command::Last
+        }
+        "find" => {
+            expect(1, "find PATTERN")?;
+            Command::Find(arguments[0].to_string())
+        }
+        "help" | "?" => Command::Help,
+        "quit" | "exit" => Command::Quit,
+        other => return Err(format!("unknown command '{}'; try 'help'", other)),
+    };
+    Ok(Some(command))
+}
+
+// What an interactive session keeps between commands: the database, parsed once, the current
+// mixture and the last equilibrium result
+pub struct Session<'a> {
+    db: &'a ThermoFile,
+    mixture: Option<Mixture>,
+    last: Option<EquilibriumResult>,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(db: &'a ThermoFile) -> Self {
+        Session {
+            db,
+            mixture: None,
+            last: None,
+        }
+    }
+
+    // Carry out a command, returning the text to show. Errors leave the session as it was.
+    pub fn execute(&mut self, command: &Command) -> Result<String, String> {
+        let db = self.db;
+        match command {
+            Command::Props {
+                species,
+                temperature: t,
+            } => {
+                let species = db.get_ci(species).map_err(|e| e.to_string())?;
+                let (Some(cp), Some(h), Some(s), Some(g)) = (
+                    species.cp(*t),
+                    species.enthalpy(*t),
+                    species.entropy(*t),
+                    species.gibbs(*t),
+                ) else {
+                    return Err(format!("{} has no fit at {} K", species.name, t));
+                };
+                Ok(format!(
+                    "{} at {} K: cp {:.4} J/(mol K), h {:.2} J/mol, s {:.4} J/(mol K), \
+                     g {:.2} J/mol",
+                    species.name, t, cp, h, s, g
+                ))
+            }
+            Command::Mix(components) => {
+                let names: Vec<(&str, f64)> = components
+                    .iter()
+                    .map(|(name, amount)| (name.as_str(), *amount))
+                    .collect();
+                let mixture = Mixture::from_names(db, &names).map_err(|e| e.to_string())?;
+                let summary = format!(
+                    "mixture of {} species, {} mol, {:.4} g/mol",
+                    mixture.components.len(),
+                    mixture.total(),
+                    mixture.molar_mass(db) * 1000.0
+                );
+                self.mixture = Some(mixture);
+                Ok(summary)
+            }
+            Command::EqTp {
+                temperature,
+                pressure,
+            } => self.solve(Problem::TP {
+                temperature: *temperature,
+                pressure: *pressure,
+            }),
+            Command::EqHp {
+                pressure,
+                temperature,
+            } => {
+                let mixture = self.mixture()?;
+                let enthalpy = mixture
+                    .enthalpy(db, *temperature)
+                    .ok_or_else(|| format!("the mixture has no enthalpy at {} K", temperature))?;
+                self.solve(Problem::HP {
+                    enthalpy,
+                    pressure: *pressure,
+                })
+            }
+            Command::Last => match &self.last {
+                Some(result) => Ok(result.report(db).trim_end().to_string()),
+                None => Err("no equilibrium solved yet".to_string()),
+            },
+            Command::Find(pattern) => {
+                let names: Vec<&str> = db
+                    .matching(pattern)
+                    .iter()
+                    .map(|species| species.name.as_str())
+                    .collect();
+                if names.is_empty() {
+                    Ok(format!("no species match '{}'", pattern))
+                } else {
+                    Ok(names.join("\n"))
+                }
+            }
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+
+    fn mixture(&self) -> Result<&Mixture, String> {
+        self.mixture
+            .as_ref()
+            .ok_or_else(|| "no mixture; set one with 'mix'".to_string())
+    }
+
+    fn solve(&mut self, problem: Problem) -> Result<String, String> {
+        let options = EquilibriumOptions::default();
+        let result = solve_equilibrium(self.db, self.mixture()?, problem, &options)
+            .map_err(|e| e.to_string())?;
+        let report = result.report(self.db).trim_end().to_string();
+        self.last = Some(result);
+        Ok(report)
+    }
+}
+
+// Read commands line by line until 'quit' or the end of input, writing a prompt before each
+// and the result (or "error: ...") after. A command that fails doesn't end the session.
+pub fn run(db: &ThermoFile, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut session = Session::new(db);
+    write!(output, "{}", PROMPT)?;
+    output.flush()?;
+    for line in input.lines() {
+        let outcome = match parse_command(&line?) {
+            Ok(Some(Command::Quit)) => return Ok(()),
+            Ok(Some(command)) => session.execute(&command),
+            Ok(None) => Ok(String::new()),
+            Err(message) => Err(message),
+        };
+        match outcome {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => writeln!(output, "{}", text)?,
+            Err(message) => writeln!(output, "error: {}", message)?,
+        }
+        write!(output, "{}", PROMPT)?;
+        output.flush()?;
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse_command("   "), Ok(None));
+        assert_eq!(
+            parse_command("props N2 1500"),
+            Ok(Some(Command::Props {
+                species: "N2".to_string(),
+                temperature: 1500.0
+            }))
+        );
+        assert_eq!(
+            parse_command("MIX CH4=1 O2=2"),
+            Ok(Some(Command::Mix(vec![
+                ("CH4".to_string(), 1.0),
+                ("O2".to_string(), 2.0)
+            ])))
+        );
+        assert_eq!(
+            parse_command("eq tp 3000 1bar"),
+            Ok(Some(Command::EqTp {
+                temperature: 3000.0,
+                pressure: 1.0e5
+            }))
+        );
+        assert_eq!(
+            parse_command("eq HP 1atm"),
+            Ok(Some(Command::EqHp {
+                pressure: 101_325.0,
+                temperature: 298.15
+            }))
+        );
+        assert_eq!(
+            parse_command("find C2H*"),
+            Ok(Some(Command::Find("C2H*".to_string())))
+        );
+        assert_eq!(parse_command("exit"), Ok(Some(Command::Quit)));
+
+        assert_eq!(
+            parse_command("props N2"),
+            Err("usage: props NAME T".to_string())
+        );
+        assert_eq!(
+            parse_command("mix CH4"),
+            Err("expected NAME=MOLES, found 'CH4'".to_string())
+        );
+        assert_eq!(
+            parse_command("eq tp 3000 1 furlong"),
+            Err("usage: eq tp T P".to_string())
+        );
+        assert!(
+            parse_command("eq tp 3000 1furlong")
+                .unwrap_err()
+                .contains("furlong")
+        );
+        assert_eq!(
+            parse_command("plot N2"),
+            Err("unknown command 'plot'; try 'help'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_scripted_session() {
+        let script = "\
+props N2 1500
+eq tp 3000 1bar
+mix CH4=1 O2=2
+bogus
+eq tp 3000 1bar
+find CO*
+last
+quit
+props N2 300
+";
+        let mut output = Vec::new();
+        run(full_database(), script.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let n2 = full_database().find_species("N2").unwrap();
+        let cp = format!("cp {:.4} J/(mol K)", n2.cp(1500.0).unwrap());
+        assert!(output.starts_with("> N2 at 1500 K: "), "{}", output);
+        assert!(output.contains(&cp));
+        assert!(output.contains("> error: no mixture; set one with 'mix'\n"));
+        assert!(output.contains("> mixture of 2 species, 3 mol, "));
+        assert!(output.contains("> error: unknown command 'bogus'; try 'help'\n"));
+        assert!(output.contains("> T = 3000.00 K, p = 1.000000 bar"));
+        let fingerprint = crate::database::fingerprint_hex(&full_database().fingerprint());
+        assert!(output.contains(&format!("database {}\n", fingerprint)));
+        assert!(output.contains("\nCO2\n"));
+        // 'last' repeats the report, and nothing runs after 'quit'
+        assert_eq!(output.matches("T = 3000.00 K").count(), 2);
+        assert!(!output.contains("at 300 K"));
+        assert!(output.ends_with("> "));
+    }
+}
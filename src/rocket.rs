@@ -0,0 +1,1151 @@
+use crate::database::ThermoFile;
+use crate::equilibrium::{
+    EquilibriumOptions, EquilibriumResult, Problem, SolverError, solve_equilibrium,
+};
+use crate::export::{Cell, ExportError, Table};
+use crate::mixture::{Mixture, ReactantConditions, ReactantState};
+use crate::numeric::positive;
+
+// Relative pressure step used to differentiate along an isentrope for the speed of sound
+pub(crate) const SOUND_SPEED_STEP: f64 = 1.0e-3;
+
+// Width in ln p below which the search for the throat's maximum mass flux stops
+const THROAT_TOLERANCE: f64 = 1.0e-6;
+
+// Throat pressure is searched for between these fractions of the upstream pressure
+const THROAT_SEARCH: (f64, f64) = (0.3, 0.9);
+
+// Relative tolerances on the combustor-end pressure (and stagnation pressure) of a finite-area
+// combustor, and on its mass flux matching the throat's
+const COMBUSTOR_TOLERANCE: f64 = 1.0e-9;
+const CONTINUITY_TOLERANCE: f64 = 1.0e-6;
+
+// Relative tolerance on the area ratio of an exit station given by area ratio
+const AREA_TOLERANCE: f64 = 1.0e-7;
+
+// Factor by which the pressure is lowered while bracketing a supersonic area ratio
+const BRACKET_STEP: f64 = 10.0;
+
+// Relative temperature step below which a frozen isentropic state is accepted
+const FROZEN_TOLERANCE: f64 = 1.0e-10;
+
+const MAX_ITERATIONS: usize = 50;
+
+// Default ambient pressure, one standard atmosphere, Pa
+pub const SEA_LEVEL_PRESSURE: f64 = 101_325.0;
+
+// Exit-to-ambient pressure ratio below which an overexpanded nozzle's flow may separate
+pub const SEPARATION_RATIO: f64 = 0.4;
+
+// Where along the engine a station lies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Station {
+    // Stagnant chamber of an infinite-area combustor
+    Chamber,
+    // Injector face of a finite-area combustor, where the gas is still at rest
+    Injector,
+    // End of a finite-area combustor, where the gas enters the nozzle
+    CombustorEnd,
+    Throat,
+    Exit,
+}
+
+// Settings for a rocket performance calculation
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocketOptions {
+    pub chamber_pressure: f64, // Pa, at the injector face for a finite-area combustor
+    pub reactant_state: ReactantState,
+    pub reactant_conditions: Vec<(usize, ReactantConditions)>, // Per-species overrides, by index
+    pub contraction_ratio: Option<f64>, // Ac/At, or None for an infinite-area combustor
+    pub pressure_ratios: Vec<f64>,      // Chamber-to-exit pressure ratios, one exit station each
+    pub subsonic_area_ratios: Vec<f64>, // A/At upstream of the throat, one exit station each
+    pub supersonic_area_ratios: Vec<f64>, // A/At downstream of the throat, one exit station each
+    // Where to start a second expansion with frozen composition: the Chamber or Throat, or
+    // only the Throat with a contraction ratio, any other giving `SolverError::FreezeStation`
+    pub freeze_at: Option<Station>,
+    pub ambient_pressure: f64, // Pa, outside the nozzle, for the ambient Isp and thrust coefficient
+    pub equilibrium: EquilibriumOptions,
+}
+
+impl RocketOptions {
+    // Infinite-area combustor at the given chamber pressure (Pa), reactants at 298.15 K
+    pub fn new(chamber_pressure: f64) -> Self {
+        RocketOptions {
+            chamber_pressure,
+            reactant_state: ReactantState::default(),
+            reactant_conditions: Vec::new(),
+            contraction_ratio: None,
+            pressure_ratios: Vec::new(),
+            subsonic_area_ratios: Vec::new(),
+            supersonic_area_ratios: Vec::new(),
+            freeze_at: None,
+            ambient_pressure: SEA_LEVEL_PRESSURE,
+            equilibrium: EquilibriumOptions::default(),
+        }
+    }
+}
+
+// The flow at one station, per unit mass of propellant
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationState {
+    pub station: Station,
+    pub pressure: f64,    // Pa
+    pub temperature: f64, // K
+    pub density: f64,     // kg/m^3
+    pub enthalpy: f64,    // J/kg
+    pub entropy: f64,     // J/(kg K)
+    pub velocity: f64,    // m/s
+    pub sound_speed: f64, // Equilibrium speed of sound, m/s
+    pub mach: f64,
+    pub area_ratio: f64, // A/At, infinite for the chamber of an infinite-area combustor
+    pub isp: f64,        // Specific impulse expanding to the local pressure, N s/kg
+    pub isp_vacuum: f64, // Specific impulse into vacuum, N s/kg, zero where the gas is at rest
+    // Specific impulse at the ambient pressure, N s/kg: the vacuum value less pa Ae/mdot
+    pub isp_ambient: f64,
+    pub cf: f64,         // Thrust coefficient Isp/c*
+    pub cf_vacuum: f64,  // Isp_vacuum/c*
+    pub cf_ambient: f64, // Isp_ambient/c*
+    pub composition: EquilibriumResult,
+}
+
+// Stations from the chamber to each exit, with the characteristic velocity of the engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct RocketPerformance {
+    pub stations: Vec<StationState>,
+    pub c_star: f64, // Nozzle stagnation pressure times throat area over mass flow, m/s
+    pub ambient_pressure: f64, // Pa, as in the options
+    // Whether an exit is overexpanded far enough for the flow to separate from the nozzle wall,
+    // its pressure under `SEPARATION_RATIO` times the ambient (Summerfield's criterion)
+    pub separation_risk: bool,
+    pub frozen: Option<Box<RocketPerformance>>, // The same engine expanding with frozen composition
+}
+
+impl RocketPerformance {
+    // The first station of the given kind
+    pub fn station(&self, station: Station) -> Option<&StationState> {
+        self.stations.iter().find(|s| s.station == station)
+    }
+
+    // The exit stations: pressure ratios, then subsonic and supersonic area ratios, each in the
+    // order given
+    pub fn exits(&self) -> impl Iterator<Item = &StationState> {
+        self.stations.iter().filter(|s| s.station == Station::Exit)
+    }
+
+    fn table(&self, db: &ThermoFile, species: &[&str]) -> Result<Table, ExportError> {
+        let mut table = Table::new(db, STATION_COLUMNS, species)?;
+        let expansions = std::iter::once(("equilibrium", self))
+            .chain(self.frozen.as_deref().map(|frozen| ("frozen", frozen)));
+        for (expansion, performance) in expansions {
+            for s in &performance.stations {
+                let mut row = vec![
+                    Cell::Text(expansion.to_string()),
+                    Cell::Text(format!("{:?}", s.station)),
+                ];
+                row.extend(
+                    [
+                        s.pressure,
+                        s.temperature,
+                        s.density,
+                        s.enthalpy,
+                        s.entropy,
+                        s.velocity,
+                        s.sound_speed,
+                        s.mach,
+                        s.area_ratio,
+                        s.isp,
+                        s.isp_vacuum,
+                        s.isp_ambient,
+                        s.cf,
+                        s.cf_vacuum,
+                        s.cf_ambient,
+                        performance.c_star,
+                    ]
+                    .map(Cell::Number),
+                );
+                table.push(row, &s.composition);
+            }
+        }
+        Ok(table)
+    }
+
+    // Write one CSV row per station, equilibrium stations first and then any frozen ones, with
+    // a mole-fraction column for each named species
+    pub fn write_csv(
+        &self,
+        db: &ThermoFile,
+        species: &[&str],
+        writer: impl std::io::Write,
+    ) -> Result<(), ExportError> {
+        self.table(db, species)?.write_csv(writer)
+    }
+
+    // An object of the database's "fingerprint", its "patches" and the "rows" of `write_csv`,
+    // an array of objects
+    pub fn to_json(&self, db: &ThermoFile, species: &[&str]) -> Result<String, ExportError> {
+        self.table(db, species)?.to_json()
+    }
+}
+
+// Scalar columns of an exported station table, before the selected mole fractions
+const STATION_COLUMNS: &[&str] = &[
+    "expansion",
+    "station",
+    "pressure",
+    "temperature",
+    "density",
+    "enthalpy",
+    "entropy",
+    "velocity",
+    "sound_speed",
+    "mach",
+    "area_ratio",
+    "isp",
+    "isp_vac",
+    "isp_amb",
+    "cf",
+    "cf_vac",
+    "cf_amb",
+    "c_star",
+];
+
+// Equilibrium (or frozen) states of a fixed propellant charge, per unit mass
+#[derive(Clone)]
+struct Expansion<'a> {
+    db: &'a ThermoFile,
+    reactants: &'a Mixture,
+    options: &'a EquilibriumOptions,
+    mass: f64,                         // Mass of the reactant amounts, kg
+    total_enthalpy: f64,               // Stagnation enthalpy, J/kg
+    frozen: Option<EquilibriumResult>, // Composition held fixed along the isentrope, if any
+}
+
+impl Expansion<'_> {
+    fn solve(&self, problem: Problem, guess: f64) -> Result<EquilibriumResult, SolverError> {
+        let options = EquilibriumOptions {
+            temperature_guess: guess,
+            ..self.options.clone()
+        };
+        solve_equilibrium(self.db, self.reactants, problem, &options)
+    }
+
+    // Equilibrium at a specific enthalpy (J/kg) and pressure
+    fn at_enthalpy(
+        &self,
+        enthalpy: f64,
+        pressure: f64,
+        guess: f64,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let problem = Problem::HP {
+            enthalpy: enthalpy * self.mass,
+            pressure,
+        };
+        self.solve(problem, guess)
+    }
+
+    // Equilibrium at a specific entropy (J/(kg K)) and pressure, or the frozen composition at
+    // the temperature giving that entropy
+    fn at_entropy(
+        &self,
+        entropy: f64,
+        pressure: f64,
+        guess: f64,
+    ) -> Result<EquilibriumResult, SolverError> {
+        if let Some(composition) = &self.frozen {
+            return self.frozen_at_entropy(composition, entropy, pressure, guess);
+        }
+        let problem = Problem::SP {
+            entropy: entropy * self.mass,
+            pressure,
+        };
+        self.solve(problem, guess)
+    }
+
+    // Newton's method on temperature, with ds/dT = cp/T at fixed composition
+    fn frozen_at_entropy(
+        &self,
+        composition: &EquilibriumResult,
+        entropy: f64,
+        pressure: f64,
+        guess: f64,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let mut state = composition.clone();
+        state.pressure = pressure;
+        state.temperature = guess;
+        for _ in 0..MAX_ITERATIONS {
+            let shortfall = entropy * self.mass - state.entropy(self.db);
+            let step = shortfall * state.temperature / state.cp_frozen(self.db);
+            if !step.is_finite() {
+                return Err(SolverError::InvalidInput(
+                    "frozen composition has no fit at this temperature".to_string(),
+                ));
+            }
+            state.temperature += step;
+            if step.abs() <= FROZEN_TOLERANCE * state.temperature {
+                return Ok(state);
+            }
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // Flow velocity reached by a state from the stagnation enthalpy, m/s
+    fn velocity(&self, state: &EquilibriumResult) -> f64 {
+        let drop = self.total_enthalpy - state.enthalpy(self.db) / self.mass;
+        (2.0 * drop).max(0.0).sqrt()
+    }
+
+    // Mass flux through unit area at a state, kg/(m^2 s)
+    fn mass_flux(&self, state: &EquilibriumResult) -> f64 {
+        state.density(self.db) * self.velocity(state)
+    }
+
+    // Equilibrium speed of sound sqrt((dp/drho)_s), differencing along the isentrope
+    fn sound_speed(&self, state: &EquilibriumResult) -> Result<f64, SolverError> {
+        let entropy = state.entropy(self.db) / self.mass;
+        let guess = state.temperature;
+        let high = self.at_entropy(entropy, state.pressure * (1.0 + SOUND_SPEED_STEP), guess)?;
+        let low = self.at_entropy(entropy, state.pressure * (1.0 - SOUND_SPEED_STEP), guess)?;
+        let dp = high.pressure - low.pressure;
+        let drho = high.density(self.db) - low.density(self.db);
+        Ok((dp / drho).sqrt())
+    }
+
+    // Pressure at which the flow on an isentrope comes to rest (h = h0), by Newton's method on
+    // dh/d(ln p) = p/rho starting from a state on the isentrope
+    fn stagnation_pressure(
+        &self,
+        entropy: f64,
+        start: &EquilibriumResult,
+    ) -> Result<f64, SolverError> {
+        let mut pressure = start.pressure;
+        let mut guess = start.temperature;
+        for _ in 0..MAX_ITERATIONS {
+            let state = self.at_entropy(entropy, pressure, guess)?;
+            let shortfall = self.total_enthalpy - state.enthalpy(self.db) / self.mass;
+            let step = shortfall * state.density(self.db) / pressure;
+            pressure *= step.exp();
+            guess = state.temperature;
+            if step.abs() <= COMBUSTOR_TOLERANCE {
+                return Ok(pressure);
+            }
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // The throat on the isentrope through an upstream state: the pressure of maximum mass flux,
+    // found by golden-section search in ln p
+    fn throat(
+        &self,
+        entropy: f64,
+        upstream: &EquilibriumResult,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let mut guess = upstream.temperature;
+        let mut flux_at = |ln_p: f64| -> Result<(f64, EquilibriumResult), SolverError> {
+            let state = self.at_entropy(entropy, ln_p.exp(), guess)?;
+            guess = state.temperature;
+            Ok((self.mass_flux(&state), state))
+        };
+
+        let mut low = (upstream.pressure * THROAT_SEARCH.0).ln();
+        let mut high = (upstream.pressure * THROAT_SEARCH.1).ln();
+        let mut left = high - ratio * (high - low);
+        let mut right = low + ratio * (high - low);
+        let mut left_state = flux_at(left)?;
+        let mut right_state = flux_at(right)?;
+        for _ in 0..MAX_ITERATIONS {
+            if high - low < THROAT_TOLERANCE {
+                break;
+            }
+            if left_state.0 > right_state.0 {
+                high = right;
+                right = left;
+                right_state = left_state;
+                left = high - ratio * (high - low);
+                left_state = flux_at(left)?;
+            } else {
+                low = left;
+                left = right;
+                left_state = right_state;
+                right = low + ratio * (high - low);
+                right_state = flux_at(right)?;
+            }
+        }
+        let best = if left_state.0 > right_state.0 {
+            left_state
+        } else {
+            right_state
+        };
+        Ok(best.1)
+    }
+
+    // The state on the isentrope where A/At reaches the target area ratio, by regula falsi
+    // (Illinois variant) in ln p. The area ratio must fall short of the target at `inner` and
+    // exceed it at `outer`; with the throat as one end the search stays on a single branch,
+    // never crossing the flat maximum of the mass flux.
+    fn at_area_ratio(
+        &self,
+        entropy: f64,
+        throat_flux: f64,
+        target: f64,
+        inner: &EquilibriumResult,
+        outer: &EquilibriumResult,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let residual =
+            |state: &EquilibriumResult| (throat_flux / self.mass_flux(state) / target).ln();
+        let mut inner = (inner.pressure.ln(), residual(inner), inner.temperature);
+        let mut outer = (outer.pressure.ln(), residual(outer), outer.temperature);
+        // Which end the previous step replaced, to halve the stale end's residual (Illinois)
+        let mut last_inner = None;
+        for _ in 0..MAX_ITERATIONS {
+            // Fall back to bisection while an end has no finite residual (gas at rest)
+            let ln_p = if inner.1.is_finite() && outer.1.is_finite() {
+                (inner.0 * outer.1 - outer.0 * inner.1) / (outer.1 - inner.1)
+            } else {
+                0.5 * (inner.0 + outer.0)
+            };
+            let state = self.at_entropy(entropy, ln_p.exp(), 0.5 * (inner.2 + outer.2))?;
+            let value = residual(&state);
+            if value.abs() <= AREA_TOLERANCE {
+                return Ok(state);
+            }
+            let replaced_inner = value < 0.0;
+            if replaced_inner {
+                inner = (ln_p, value, state.temperature);
+                if last_inner == Some(true) {
+                    outer.1 *= 0.5;
+                }
+            } else {
+                outer = (ln_p, value, state.temperature);
+                if last_inner == Some(false) {
+                    inner.1 *= 0.5;
+                }
+            }
+            last_inner = Some(replaced_inner);
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // The supersonic state with the target area ratio, first bracketing it by stepping the
+    // pressure down from the throat
+    fn supersonic(
+        &self,
+        entropy: f64,
+        throat_flux: f64,
+        target: f64,
+        throat: &EquilibriumResult,
+    ) -> Result<EquilibriumResult, SolverError> {
+        let mut inner = throat.clone();
+        for _ in 0..MAX_ITERATIONS {
+            let outer =
+                self.at_entropy(entropy, inner.pressure / BRACKET_STEP, inner.temperature)?;
+            if throat_flux / self.mass_flux(&outer) >= target {
+                return self.at_area_ratio(entropy, throat_flux, target, &inner, &outer);
+            }
+            inner = outer;
+        }
+        Err(SolverError::NoConvergence {
+            iterations: MAX_ITERATIONS,
+        })
+    }
+
+    // Exit stations for each pressure and area ratio downstream of a throat. Subsonic states are
+    // taken from `subsonic`, which differs from `self` when freezing at the throat.
+    fn exits(
+        &self,
+        subsonic: &Expansion,
+        entropy: f64,
+        throat: &EquilibriumResult,
+        upstream: &EquilibriumResult,
+        options: &RocketOptions,
+    ) -> Result<Vec<StationState>, SolverError> {
+        let throat_flux = self.mass_flux(throat);
+        let mut exits: Vec<EquilibriumResult> = Vec::new();
+        for &ratio in &options.pressure_ratios {
+            let pressure = options.chamber_pressure / ratio;
+            let guess = exits.last().map_or(throat.temperature, |s| s.temperature);
+            let branch = if pressure > throat.pressure {
+                subsonic
+            } else {
+                self
+            };
+            exits.push(branch.at_entropy(entropy, pressure, guess)?);
+        }
+        for &ratio in &options.subsonic_area_ratios {
+            exits.push(subsonic.at_area_ratio(entropy, throat_flux, ratio, throat, upstream)?);
+        }
+        for &ratio in &options.supersonic_area_ratios {
+            exits.push(self.supersonic(entropy, throat_flux, ratio, throat)?);
+        }
+        exits
+            .into_iter()
+            .map(|state| {
+                let velocity = self.velocity(&state);
+                let area_ratio = throat_flux / self.mass_flux(&state);
+                self.station(Station::Exit, state, velocity, area_ratio)
+            })
+            .collect()
+    }
+
+    // A station record for a solved state moving at the given velocity
+    fn station(
+        &self,
+        station: Station,
+        state: EquilibriumResult,
+        velocity: f64,
+        area_ratio: f64,
+    ) -> Result<StationState, SolverError> {
+        let sound_speed = self.sound_speed(&state)?;
+        let density = state.density(self.db);
+        let isp_vacuum = if velocity > 0.0 {
+            velocity + state.pressure / (density * velocity)
+        } else {
+            0.0
+        };
+        Ok(StationState {
+            station,
+            pressure: state.pressure,
+            temperature: state.temperature,
+            density,
+            enthalpy: state.enthalpy(self.db) / self.mass,
+            entropy: state.entropy(self.db) / self.mass,
+            velocity,
+            sound_speed,
+            mach: velocity / sound_speed,
+            area_ratio,
+            isp: velocity,
+            isp_vacuum,
+            isp_ambient: 0.0,
+            cf: 0.0,
+            cf_vacuum: 0.0,
+            cf_ambient: 0.0,
+            composition: state,
+        })
+    }
+}
+
+// The combustor end of a finite-area combustor for a trial gas velocity there: the state
+// satisfying the energy balance h = h0 - u^2/2 and the momentum balance p = p_inj - rho u^2
+fn combustor_end(
+    expansion: &Expansion,
+    injector: &EquilibriumResult,
+    velocity: f64,
+) -> Result<EquilibriumResult, SolverError> {
+    let enthalpy = expansion.total_enthalpy - 0.5 * velocity * velocity;
+    let mut pressure = injector.pressure;
+    let mut state = expansion.at_enthalpy(enthalpy, pressure, injector.temperature)?;
+    for _ in 0..MAX_ITERATIONS {
+        let next = injector.pressure - state.density(expansion.db) * velocity * velocity;
+        if next <= 0.0 {
+            return Err(SolverError::InvalidInput(
+                "combustor flow is choked; increase the contraction ratio".to_string(),
+            ));
+        }
+        if (next - pressure).abs() <= COMBUSTOR_TOLERANCE * pressure {
+            return Ok(state);
+        }
+        pressure = next;
+        state = expansion.at_enthalpy(enthalpy, pressure, state.temperature)?;
+    }
+    Err(SolverError::NoConvergence {
+        iterations: MAX_ITERATIONS,
+    })
+}
+
+// Rocket performance for reactants (in moles) burning at the chamber pressure and expanding in
+// equilibrium through the throat to each exit condition, as in CEA's 'rocket' problem. Exits
+// given by area ratio are found on the subsonic or supersonic branch as asked.
+//
+// With a contraction ratio the combustor has finite area (CEA's FAC option): the gas
+// accelerates along it, losing stagnation pressure, so the combustor-end state is iterated
+// until its mass flux matches the throat's. Pressure ratios are then taken from the injector,
+// and c* from the stagnation pressure of the flow entering the nozzle.
+//
+// Asking to freeze at the chamber or throat (CEA's 'frozen' with 'nfz') also expands the same
+// engine with its composition fixed from there on, reported alongside in `frozen`. A
+// finite-area combustor freezes at the throat only.
+pub fn rocket_performance(
+    db: &ThermoFile,
+    reactants: &Mixture,
+    options: &RocketOptions,
+) -> Result<RocketPerformance, SolverError> {
+    positive("chamber pressure", options.chamber_pressure)?;
+    if !(options.ambient_pressure >= 0.0 && options.ambient_pressure.is_finite()) {
+        return Err(SolverError::InvalidInput(
+            "ambient pressure must be zero or more".to_string(),
+        ));
+    }
+    let ambient = options.ambient_pressure;
+    reactants.check_amounts(db)?;
+    if options
+        .pressure_ratios
+        .iter()
+        .any(|&ratio| ratio.is_nan() || ratio <= 1.0)
+    {
+        return Err(SolverError::InvalidInput(
+            "pressure ratios must exceed one".to_string(),
+        ));
+    }
+    if options
+        .contraction_ratio
+        .is_some_and(|ratio| ratio.is_nan() || ratio <= 1.0)
+    {
+        return Err(SolverError::InvalidInput(
+            "contraction ratio must exceed one".to_string(),
+        ));
+    }
+    if let Some(station) = options.freeze_at {
+        let finite_area = options.contraction_ratio.is_some();
+        let allowed = match station {
+            Station::Throat => true,
+            Station::Chamber => !finite_area,
+            _ => false,
+        };
+        if !allowed {
+            return Err(SolverError::FreezeStation {
+                station,
+                finite_area,
+            });
+        }
+    }
+    let largest_subsonic = options.contraction_ratio.unwrap_or(f64::INFINITY);
+    if options
+        .subsonic_area_ratios
+        .iter()
+        .any(|&ratio| ratio.is_nan() || ratio <= 1.0 || ratio >= largest_subsonic)
+    {
+        return Err(SolverError::InvalidInput(
+            "subsonic area ratios must exceed one and be less than the contraction ratio"
+                .to_string(),
+        ));
+    }
+    if options
+        .supersonic_area_ratios
+        .iter()
+        .any(|&ratio| !ratio.is_finite() || ratio <= 1.0)
+    {
+        return Err(SolverError::InvalidInput(
+            "supersonic area ratios must exceed one".to_string(),
+        ));
+    }
+
+    let enthalpy =
+        reactants.state_enthalpy(db, &options.reactant_state, &options.reactant_conditions)?;
+    let mass = reactants.molar_mass(db) * reactants.total();
+    let expansion = Expansion {
+        db,
+        reactants,
+        options: &options.equilibrium,
+        mass,
+        total_enthalpy: enthalpy / mass,
+        frozen: None,
+    };
+
+    let chamber = expansion.at_enthalpy(
+        expansion.total_enthalpy,
+        options.chamber_pressure,
+        options.equilibrium.temperature_guess,
+    )?;
+    let mut stations = Vec::new();
+    let (entropy, upstream, throat, stagnation) = match options.contraction_ratio {
+        None => {
+            let entropy = chamber.entropy(db) / mass;
+            let throat = expansion.throat(entropy, &chamber)?;
+            let stagnation = chamber.pressure;
+            let upstream = chamber.clone();
+            stations.push(expansion.station(Station::Chamber, chamber, 0.0, f64::INFINITY)?);
+            (entropy, upstream, throat, stagnation)
+        }
+        Some(contraction) => {
+            // Secant iteration on the combustor-end velocity for continuity with the throat
+            let continuity = |velocity: f64| -> Result<_, SolverError> {
+                let end = combustor_end(&expansion, &chamber, velocity)?;
+                let entropy = end.entropy(db) / mass;
+                let throat = expansion.throat(entropy, &end)?;
+                let residual =
+                    end.density(db) * velocity * contraction / expansion.mass_flux(&throat) - 1.0;
+                Ok((residual, end, throat))
+            };
+            let ideal_throat = expansion.throat(chamber.entropy(db) / mass, &chamber)?;
+            let mut velocity =
+                expansion.mass_flux(&ideal_throat) / (contraction * chamber.density(db));
+            let mut previous = (0.0, -1.0);
+            let mut solution = None;
+            for _ in 0..MAX_ITERATIONS {
+                let (residual, end, throat) = continuity(velocity)?;
+                let converged = residual.abs() <= CONTINUITY_TOLERANCE;
+                let slope = (residual - previous.1) / (velocity - previous.0);
+                previous = (velocity, residual);
+                if converged {
+                    solution = Some((end, throat));
+                    break;
+                }
+                velocity -= residual / slope;
+            }
+            let (end, throat) = solution.ok_or(SolverError::NoConvergence {
+                iterations: MAX_ITERATIONS,
+            })?;
+            let entropy = end.entropy(db) / mass;
+            let end_velocity = previous.0;
+            let stagnation = expansion.stagnation_pressure(entropy, &end)?;
+            let upstream = end.clone();
+            stations.push(expansion.station(Station::Injector, chamber, 0.0, contraction)?);
+            stations.push(expansion.station(
+                Station::CombustorEnd,
+                end,
+                end_velocity,
+                contraction,
+            )?);
+            (entropy, upstream, throat, stagnation)
+        }
+    };
+
+    let c_star = stagnation / expansion.mass_flux(&throat);
+    let throat_velocity = expansion.velocity(&throat);
+    stations.push(expansion.station(Station::Throat, throat.clone(), throat_velocity, 1.0)?);
+    stations.extend(expansion.exits(&expansion, entropy, &throat, &upstream, options)?);
+
+    // Frozen expansion from the chamber reuses the chamber state but finds its own throat;
+    // from the throat it shares the equilibrium stations upstream (the chamber, or injector and
+    // combustor end), the throat itself and the subsonic exits
+    let frozen = match options.freeze_at {
+        None => None,
+        Some(freeze_at) => {
+            let freeze_state = match freeze_at {
+                Station::Throat => throat.clone(),
+                _ => upstream.clone(),
+            };
+            let frozen = Expansion {
+                frozen: Some(freeze_state),
+                ..expansion.clone()
+            };
+            let (subsonic, throat, mut stations) = match freeze_at {
+                Station::Throat => {
+                    let combustor = stations.iter().take_while(|s| s.station != Station::Throat);
+                    (&expansion, throat, combustor.cloned().collect())
+                }
+                _ => (
+                    &frozen,
+                    frozen.throat(entropy, &upstream)?,
+                    vec![frozen.station(Station::Chamber, upstream.clone(), 0.0, f64::INFINITY)?],
+                ),
+            };
+            let c_star = stagnation / frozen.mass_flux(&throat);
+            let throat_velocity = frozen.velocity(&throat);
+            stations.push(frozen.station(Station::Throat, throat.clone(), throat_velocity, 1.0)?);
+            stations.extend(frozen.exits(subsonic, entropy, &throat, &upstream, options)?);
+            Some(Box::new(with_thrust_coefficients(
+                stations, c_star, ambient,
+            )))
+        }
+    };
+
+    Ok(RocketPerformance {
+        frozen,
+        ..with_thrust_coefficients(stations, c_star, ambient)
+    })
+}
+
+// Fill in each station's ambient Isp and thrust coefficients, and judge the exits for
+// separation, for a nozzle exhausting at the ambient pressure (Pa)
+fn with_thrust_coefficients(
+    mut stations: Vec<StationState>,
+    c_star: f64,
+    ambient: f64,
+) -> RocketPerformance {
+    for station in &mut stations {
+        // Ae/mdot is 1/(rho u), so the pressure thrust pa Ae/mdot is the vacuum excess over u
+        // scaled by pa/p
+        if station.velocity > 0.0 {
+            let excess = station.isp_vacuum - station.isp;
+            station.isp_ambient = station.isp_vacuum - excess * ambient / station.pressure;
+        }
+        station.cf = station.isp / c_star;
+        station.cf_vacuum = station.isp_vacuum / c_star;
+        station.cf_ambient = station.isp_ambient / c_star;
+    }
+    let separation_risk = stations
+        .iter()
+        .any(|s| s.station == Station::Exit && s.pressure < SEPARATION_RATIO * ambient);
+    RocketPerformance {
+        stations,
+        c_star,
+        ambient_pressure: ambient,
+        separation_risk,
+        frozen: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data::{database_with_reactants, full_database};
+
+    fn hydrogen_oxygen() -> Mixture {
+        Mixture::from_names(full_database(), &[("H2", 2.5), ("O2", 1.0)]).unwrap()
+    }
+
+    // Liquid hydrogen and oxygen at a mixture ratio of 6 by mass, each at the temperature of
+    // its record's assigned enthalpy
+    fn liquid_hydrogen_oxygen() -> Mixture {
+        let db = database_with_reactants();
+        Mixture::from_mass_fractions(db, &[("H2(L)", 1.0), ("O2(L)", 6.0)])
+            .unwrap()
+            .unwrap()
+    }
+
+    // LOX/RP-1 at a mixture ratio of 2.56 by mass, the kerosene flame of the equilibrium tests
+    fn kerosene_oxygen() -> Mixture {
+        let db = database_with_reactants();
+        Mixture::from_mass_fractions(db, &[("RP-1", 1.0), ("O2(L)", 2.56)])
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_infinite_area_combustor() {
+        let db = full_database();
+        let options = RocketOptions {
+            pressure_ratios: vec![10.0, 100.0],
+            ..RocketOptions::new(20.0e5)
+        };
+        let performance = rocket_performance(db, &hydrogen_oxygen(), &options).unwrap();
+        let chamber = performance.station(Station::Chamber).unwrap();
+        let throat = performance.station(Station::Throat).unwrap();
+
+        // The throat is where the flow turns sonic
+        assert!((throat.mach - 1.0).abs() < 0.01, "M = {}", throat.mach);
+        let ratio = chamber.pressure / throat.pressure;
+        assert!(ratio > 1.7 && ratio < 1.9, "pc/pt = {}", ratio);
+        assert!((throat.entropy - chamber.entropy).abs() < 1e-6 * chamber.entropy.abs());
+
+        // Expanding further cools the gas, speeds it up and needs a wider nozzle
+        let exits: Vec<&StationState> = performance.exits().collect();
+        assert_eq!(exits.len(), 2);
+        assert!(exits[0].temperature < throat.temperature);
+        assert!(exits[1].temperature < exits[0].temperature);
+        assert!(exits[1].isp > exits[0].isp && exits[1].area_ratio > exits[0].area_ratio);
+        assert!(exits[1].isp_vacuum > exits[1].isp);
+        assert!((exits[1].cf - exits[1].isp / performance.c_star).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_assigned_reactant_enthalpy() {
+        let db = full_database();
+        let reactants = hydrogen_oxygen();
+        let default = RocketOptions::new(20.0e5);
+        let chamber_temperature = |options: &RocketOptions| {
+            let performance = rocket_performance(db, &reactants, options).unwrap();
+            performance.station(Station::Chamber).unwrap().temperature
+        };
+
+        // Assigning the enthalpy the reactants have anyway changes nothing
+        let mass = reactants.molar_mass(db) * reactants.total();
+        let own = reactants.reactant_enthalpy(db, 298.15, &[]).unwrap() / mass;
+        let same = RocketOptions {
+            reactant_state: ReactantState::default().with_enthalpy(own),
+            ..default.clone()
+        };
+        let t_default = chamber_temperature(&default);
+        assert!((chamber_temperature(&same) - t_default).abs() < 1e-6 * t_default);
+
+        // A feed carrying 500 kJ/kg more burns hotter
+        let preheated = RocketOptions {
+            reactant_state: ReactantState::default().with_enthalpy(own + 5.0e5),
+            ..default
+        };
+        let t_preheated = chamber_temperature(&preheated);
+        assert!(
+            t_preheated > t_default + 20.0,
+            "{} against {}",
+            t_preheated,
+            t_default
+        );
+    }
+
+    #[test]
+    fn test_finite_area_combustor() {
+        let db = database_with_reactants();
+        let reactants = kerosene_oxygen();
+        let infinite = RocketOptions {
+            pressure_ratios: vec![100.0],
+            ..RocketOptions::new(7.0e6)
+        };
+        let finite = RocketOptions {
+            contraction_ratio: Some(3.0),
+            ..infinite.clone()
+        };
+        let iac = rocket_performance(db, &reactants, &infinite).unwrap();
+        let fac = rocket_performance(db, &reactants, &finite).unwrap();
+
+        let stations: Vec<Station> = fac.stations.iter().map(|s| s.station).collect();
+        assert_eq!(
+            stations,
+            [
+                Station::Injector,
+                Station::CombustorEnd,
+                Station::Throat,
+                Station::Exit
+            ]
+        );
+
+        // Momentum, energy and mass balances across the combustor
+        let injector = fac.station(Station::Injector).unwrap();
+        let end = fac.station(Station::CombustorEnd).unwrap();
+        let throat = fac.station(Station::Throat).unwrap();
+        let momentum = end.pressure + end.density * end.velocity * end.velocity;
+        assert!((momentum - injector.pressure).abs() < 1e-6 * injector.pressure);
+        let energy = end.enthalpy + 0.5 * end.velocity * end.velocity;
+        assert!((energy - injector.enthalpy).abs() < 1.0);
+        let end_flux = end.density * end.velocity * 3.0;
+        let throat_flux = throat.density * throat.velocity;
+        assert!((end_flux - throat_flux).abs() < 1e-4 * throat_flux);
+
+        // The stagnation pressure lost along the combustor costs a little performance
+        assert!(end.pressure < injector.pressure && end.entropy > injector.entropy);
+        let (fac_exit, iac_exit) = (fac.exits().next().unwrap(), iac.exits().next().unwrap());
+        assert!(fac_exit.isp < iac_exit.isp && fac_exit.isp > 0.99 * iac_exit.isp);
+        assert!(fac.c_star < iac.c_star && fac.c_star > 0.99 * iac.c_star);
+    }
+
+    #[test]
+    fn test_area_ratio_exits() {
+        let db = database_with_reactants();
+        let options = RocketOptions {
+            subsonic_area_ratios: vec![4.0],
+            supersonic_area_ratios: vec![1.05, 40.0],
+            ..RocketOptions::new(20.0e5)
+        };
+        let performance = rocket_performance(db, &liquid_hydrogen_oxygen(), &options).unwrap();
+        let chamber = performance.station(Station::Chamber).unwrap();
+        let throat = performance.station(Station::Throat).unwrap();
+        let exits: Vec<&StationState> = performance.exits().collect();
+        assert_eq!(exits.len(), 3);
+        for (exit, target) in exits.iter().zip([4.0, 1.05, 40.0]) {
+            assert!((exit.area_ratio - target).abs() < 1e-6 * target);
+        }
+
+        // Each branch is kept, even for an area ratio just past the flat throat
+        let (subsonic, near, wide) = (exits[0], exits[1], exits[2]);
+        assert!(subsonic.mach < 1.0 && subsonic.pressure > throat.pressure);
+        assert!(subsonic.pressure < chamber.pressure);
+        assert!(near.mach > 1.0 && near.pressure < throat.pressure);
+        assert!(wide.mach > near.mach);
+
+        // The vacuum Isp lands in the 4.4-4.6 km/s band of hydrogen-oxygen engines at this
+        // expansion
+        assert!(wide.isp_vacuum > 4400.0 && wide.isp_vacuum < 4600.0);
+        assert!(wide.isp_vacuum > wide.isp);
+
+        // Expanding to the pressure found reproduces the same exit
+        let by_pressure = RocketOptions {
+            pressure_ratios: vec![chamber.pressure / wide.pressure],
+            ..RocketOptions::new(20.0e5)
+        };
+        let check = rocket_performance(db, &liquid_hydrogen_oxygen(), &by_pressure).unwrap();
+        let exit = check.exits().next().unwrap();
+        assert!((exit.area_ratio - 40.0).abs() < 1e-4);
+        assert!((exit.isp_vacuum - wide.isp_vacuum).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_frozen_expansion() {
+        let db = full_database();
+        let reactants = hydrogen_oxygen();
+        let options = |freeze_at| RocketOptions {
+            pressure_ratios: vec![100.0],
+            supersonic_area_ratios: vec![10.0],
+            freeze_at: Some(freeze_at),
+            ..RocketOptions::new(20.0e5)
+        };
+        let at_chamber = rocket_performance(db, &reactants, &options(Station::Chamber)).unwrap();
+        let at_throat = rocket_performance(db, &reactants, &options(Station::Throat)).unwrap();
+        assert_eq!(at_chamber.stations, at_throat.stations);
+        let equilibrium = at_chamber.stations.last().unwrap();
+        let from_chamber = at_chamber.frozen.as_deref().unwrap();
+        let from_throat = at_throat.frozen.as_deref().unwrap();
+
+        // The composition stays that of the freezing point all the way to the exit
+        let chamber = from_chamber.station(Station::Chamber).unwrap();
+        for station in &from_chamber.stations {
+            assert_eq!(station.composition.moles, chamber.composition.moles);
+            assert!((station.entropy - chamber.entropy).abs() < 1e-6 * chamber.entropy.abs());
+        }
+        let throat = from_chamber.station(Station::Throat).unwrap();
+        assert!((throat.mach - 1.0).abs() < 0.01, "M = {}", throat.mach);
+        assert_eq!(from_throat.c_star, at_throat.c_star);
+        assert!(from_chamber.c_star < at_chamber.c_star);
+
+        // Recombination in the nozzle is lost, so freezing earlier costs more impulse
+        let exits = |performance: &RocketPerformance| -> Vec<f64> {
+            performance.exits().map(|s| s.isp).collect()
+        };
+        for ((&chamber, &throat), &equilibrium) in exits(from_chamber)
+            .iter()
+            .zip(&exits(from_throat))
+            .zip(&exits(&at_chamber))
+        {
+            assert!(chamber < throat && throat < equilibrium);
+        }
+        let frozen_exit = from_chamber.stations.last().unwrap();
+        assert!((frozen_exit.area_ratio - 10.0).abs() < 1e-5);
+        assert!(frozen_exit.temperature < equilibrium.temperature);
+
+        // A finite-area combustor freezes at the throat, sharing the stations up to it and the
+        // throat state, there given its frozen sound speed
+        let finite = |freeze_at| RocketOptions {
+            contraction_ratio: Some(3.0),
+            ..options(freeze_at)
+        };
+        let fac = rocket_performance(db, &reactants, &finite(Station::Throat)).unwrap();
+        let from_fac_throat = fac.frozen.as_deref().unwrap();
+        assert_eq!(from_fac_throat.stations[..2], fac.stations[..2]);
+        assert_eq!(
+            from_fac_throat.stations[2].composition,
+            fac.stations[2].composition
+        );
+        assert_eq!(from_fac_throat.c_star, fac.c_star);
+        for (frozen, equilibrium) in from_fac_throat.exits().zip(fac.exits()) {
+            assert!(frozen.isp < equilibrium.isp);
+        }
+        assert!(matches!(
+            rocket_performance(db, &reactants, &finite(Station::Chamber)),
+            Err(SolverError::FreezeStation {
+                station: Station::Chamber,
+                finite_area: true
+            })
+        ));
+        assert!(matches!(
+            rocket_performance(db, &reactants, &options(Station::Exit)),
+            Err(SolverError::FreezeStation {
+                station: Station::Exit,
+                finite_area: false
+            })
+        ));
+    }
+
+    #[test]
+    fn test_ambient_thrust_correction() {
+        // LOX/RP-1 at 70 bar, with an exit matched to sea level and nozzles of area ratio 16
+        // and 40
+        let db = database_with_reactants();
+        let reactants = kerosene_oxygen();
+        let chamber_pressure = 7.0e6;
+        let options = RocketOptions {
+            pressure_ratios: vec![chamber_pressure / SEA_LEVEL_PRESSURE],
+            supersonic_area_ratios: vec![16.0, 40.0],
+            ..RocketOptions::new(chamber_pressure)
+        };
+        let performance = rocket_performance(db, &reactants, &options).unwrap();
+        let c_star = performance.c_star;
+        let [matched, sixteen, forty] = performance.exits().collect::<Vec<_>>()[..] else {
+            panic!("three exits");
+        };
+
+        // The matched nozzle gains nothing from pressure thrust at sea level
+        assert!((matched.isp_ambient - matched.isp).abs() < 1e-9 * matched.isp);
+        assert!((matched.cf_ambient - matched.cf).abs() < 1e-12);
+
+        // The sea-level loss is the pressure thrust pa Ae/mdot, which is pa (Ae/At) c*/pc
+        let spread = sixteen.isp_vacuum - sixteen.isp_ambient;
+        let expected = SEA_LEVEL_PRESSURE * sixteen.area_ratio * c_star / chamber_pressure;
+        assert!(
+            (spread - expected).abs() < 1e-6 * expected,
+            "{} {}",
+            spread,
+            expected
+        );
+        assert!((sixteen.cf_vacuum - sixteen.cf_ambient - spread / c_star).abs() < 1e-12);
+        // With no CEA deck for this engine in the tree, the vacuum Isp is held to the 3.3 km/s of
+        // ideal LOX/RP-1 at this area ratio, and the spread to the 0.4 km/s that pressure
+        // thrust takes from it at sea level
+        assert!(
+            (3250.0..3400.0).contains(&sixteen.isp_vacuum),
+            "{}",
+            sixteen.isp_vacuum
+        );
+        assert!((380.0..450.0).contains(&spread), "{}", spread);
+        assert!(sixteen.isp_ambient < matched.isp_ambient);
+
+        // Only the area ratio 40 exit, at under 0.4 atm, risks separation
+        assert!(sixteen.pressure > SEPARATION_RATIO * SEA_LEVEL_PRESSURE);
+        assert!(forty.pressure < SEPARATION_RATIO * SEA_LEVEL_PRESSURE);
+        assert!(performance.separation_risk);
+        let options = RocketOptions {
+            supersonic_area_ratios: vec![16.0],
+            ..options
+        };
+        let performance = rocket_performance(db, &reactants, &options).unwrap();
+        assert!(!performance.separation_risk);
+
+        // Into vacuum the ambient and vacuum figures agree
+        let vacuum = RocketOptions {
+            ambient_pressure: 0.0,
+            ..options.clone()
+        };
+        let performance = rocket_performance(db, &reactants, &vacuum).unwrap();
+        assert!(performance.exits().all(|s| s.isp_ambient == s.isp_vacuum));
+        let negative = RocketOptions {
+            ambient_pressure: -1.0,
+            ..options
+        };
+        assert!(matches!(
+            rocket_performance(db, &reactants, &negative),
+            Err(SolverError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_station_table_export() {
+        let db = full_database();
+        let options = RocketOptions {
+            pressure_ratios: vec![10.0],
+            freeze_at: Some(Station::Chamber),
+            ..RocketOptions::new(20.0e5)
+        };
+        let performance = rocket_performance(db, &hydrogen_oxygen(), &options).unwrap();
+
+        let mut csv = Vec::new();
+        performance.write_csv(db, &["H2O"], &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().skip(1).collect();
+        assert_eq!(
+            lines[0],
+            "expansion,station,pressure,temperature,density,enthalpy,entropy,velocity,\
+             sound_speed,mach,area_ratio,isp,isp_vac,isp_amb,cf,cf_vac,cf_amb,c_star,X(H2O)"
+        );
+        let labels: Vec<&str> = lines[1..]
+            .iter()
+            .map(|line| line.split(',').take(2).collect::<Vec<_>>().join(","))
+            .map(|label| match label.as_str() {
+                "equilibrium,Chamber" | "frozen,Chamber" => "chamber",
+                "equilibrium,Throat" | "frozen,Throat" => "throat",
+                _ => "exit",
+            })
+            .collect();
+        assert_eq!(labels, ["chamber", "throat", "exit"].repeat(2));
+        assert!(lines[4].starts_with("frozen,Chamber,2000000,"));
+
+        // JSON objects keep the CSV column order
+        let text = performance.to_json(db, &["H2O"]).unwrap();
+        let positions: Vec<usize> = ["\"expansion\"", "\"mach\"", "\"c_star\"", "\"X(H2O)\""]
+            .iter()
+            .map(|key| text.find(key).unwrap())
+            .collect();
+        assert!(positions.is_sorted());
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let rows = &json["rows"];
+        let keys: Vec<&String> = rows[0].as_object().unwrap().keys().collect();
+        assert_eq!(keys.len(), 19);
+        assert_eq!(rows[0]["station"], "Chamber");
+        // The chamber of an infinite-area combustor has no finite area ratio
+        assert!(rows[0]["area_ratio"].is_null());
+        assert_eq!(rows[2]["isp"], performance.exits().next().unwrap().isp);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::database::{EnthalpyDatum, Species, SpeciesError, TemperatureRange, ThermoParseError};
+use crate::elements::{Composition, formula_weight};
+use crate::properties::GAS_CONSTANT;
+
+// Shomate fits take their temperature in kilokelvin, t = T/1000
+const KILOKELVIN: f64 = 1000.0;
+
+// Row labels of the eight coefficients, in the order NIST lists them
+const COEFFICIENT_LABELS: [&str; 8] = ["A", "B", "C", "D", "E", "F", "G", "H"];
+
+// One temperature range of a Shomate fit, as the NIST WebBook gives them:
+//   Cp = A + B t + C t² + D t³ + E/t²                               J/(mol K)
+//   H - H(298.15) = A t + B t²/2 + C t³/3 + D t⁴/4 - E/t + F - H     kJ/mol
+//   S = A ln t + B t + C t²/2 + D t³/3 - E/(2t²) + G                  J/(mol K)
+// with t = T/1000 and H the heat of formation at 298.15 K, kJ/mol. The Cp terms are a subset
+// of NASA-9's, so each range converts exactly to a `TemperatureRange` and a species built from
+// Shomate data works everywhere a NASA-9 one does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShomateRange {
+    pub temp_low: f64,
+    pub temp_high: f64,
+    pub coefficients: [f64; 8], // A to H
+}
+
+impl ShomateRange {
+    // Heat capacity, J/(mol K)
+    pub fn cp(&self, temperature: f64) -> f64 {
+        let [a, b, c, d, e, ..] = self.coefficients;
+        let t = temperature / KILOKELVIN;
+        a + b * t + c * t * t + d * t.powi(3) + e / (t * t)
+    }
+
+    // Assigned enthalpy, J/mol: the heat of formation plus H - H(298.15), as `Species::enthalpy`
+    pub fn enthalpy(&self, temperature: f64) -> f64 {
+        let [a, b, c, d, e, f, ..] = self.coefficients;
+        let t = temperature / KILOKELVIN;
+        let kj = a * t + b * t * t / 2.0 + c * t.powi(3) / 3.0 + d * t.powi(4) / 4.0 - e / t + f;
+        kj * 1000.0
+    }
+
+    // Standard-state entropy, J/(mol K)
+    pub fn entropy(&self, temperature: f64) -> f64 {
+        let [a, b, c, d, e, _, g, _] = self.coefficients;
+        let t = temperature / KILOKELVIN;
+        a * t.ln() + b * t + c * t * t / 2.0 + d * t.powi(3) / 3.0 - e / (2.0 * t * t) + g
+    }
+
+    // Heat of formation at 298.15 K, J/mol
+    pub fn formation_enthalpy(&self) -> f64 {
+        self.coefficients[7] * 1000.0
+    }
+}
+
+impl From<&ShomateRange> for TemperatureRange {
+    fn from(range: &ShomateRange) -> Self {
+        let [a, b, c, d, e, f, g, _] = range.coefficients.map(|x| x / GAS_CONSTANT);
+        TemperatureRange {
+            temp_low: range.temp_low,
+            temp_high: range.temp_high,
+            coefficients: [
+                e * KILOKELVIN.powi(2),
+                0.0,
+                a,
+                b / KILOKELVIN,
+                c / KILOKELVIN.powi(2),
+                d / KILOKELVIN.powi(3),
+                0.0,
+            ],
+            used_coefficients: 7,
+            integration_constants: [f * 1000.0, g - a * KILOKELVIN.ln()],
+            h298_minus_h0: 0.0, // Not given by a Shomate fit
+        }
+    }
+}
+
+impl Species {
+    // A species from Shomate fits, in ascending order, its molecular weight from the formula
+    pub fn from_shomate(
+        name: impl Into<String>,
+        elements: Composition,
+        phase: u8,
+        ranges: &[ShomateRange],
+    ) -> Result<Species, SpeciesError> {
+        let value = ranges.first().map_or(0.0, ShomateRange::formation_enthalpy);
+        let molecular_weight = formula_weight(&elements).unwrap_or(f64::NAN);
+        Species::new(
+            name,
+            elements,
+            phase,
+            molecular_weight,
+            EnthalpyDatum::FormationAt298 { value },
+            ranges.iter().map(TemperatureRange::from).collect(),
+        )
+    }
+}
+
+// Parse the Shomate table of a NIST WebBook page, one row per coefficient and one column per
+// temperature range, separated by whitespace or commas:
+//   Temperature (K)   298. - 1200.   1200. - 6000.
+//   A                 24.99735       58.16639
+//   ...
+//   H                 -393.5224      -393.5224
+// Reference and Comment rows are skipped.
+pub fn parse_shomate(input: &str) -> Result<Vec<ShomateRange>, ThermoParseError> {
+    let error = |line: usize, message: String| ThermoParseError::Species {
+        name: String::new(),
+        line,
+        message,
+    };
+    let mut bounds: Option<(usize, Vec<f64>)> = None;
+    let mut rows: [Option<Vec<f64>>; 8] = Default::default();
+
+    for (index, text) in input.lines().enumerate() {
+        let line = index + 1;
+        let text = text.trim();
+        let label = text
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .next()
+            .unwrap_or("");
+        if label.is_empty()
+            || label.eq_ignore_ascii_case("reference")
+            || label.eq_ignore_ascii_case("comment")
+        {
+            continue;
+        }
+        let rest = &text[label.len()..];
+        if label.eq_ignore_ascii_case("temperature") {
+            // "(K)" then pairs of bounds, each written "low - high"
+            let rest = rest.split_once(')').map_or(rest, |(_, after)| after);
+            let values = rest
+                .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+                .filter(|token| !token.is_empty())
+                .map(|token| {
+                    token
+                        .parse::<f64>()
+                        .map_err(|_| error(line, format!("'{}' is not a temperature", token)))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.is_empty() || values.len() % 2 != 0 {
+                return Err(error(line, "expected pairs of bounds".to_string()));
+            }
+            bounds = Some((line, values));
+            continue;
+        }
+        let Some(slot) = COEFFICIENT_LABELS.iter().position(|&name| name == label) else {
+            return Err(ThermoParseError::UnexpectedLine {
+                line,
+                text: text.to_string(),
+            });
+        };
+        let values = rest
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                token
+                    .parse::<f64>()
+                    .map_err(|_| error(line, format!("'{}' is not a number", token)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        rows[slot] = Some(values);
+    }
+
+    let Some((line, bounds)) = bounds else {
+        return Err(error(1, "no temperature row".to_string()));
+    };
+    let count = bounds.len() / 2;
+    let mut ranges: Vec<ShomateRange> = bounds
+        .chunks(2)
+        .map(|pair| ShomateRange {
+            temp_low: pair[0],
+            temp_high: pair[1],
+            coefficients: [0.0; 8],
+        })
+        .collect();
+    for (slot, row) in rows.iter().enumerate() {
+        let label = COEFFICIENT_LABELS[slot];
+        let row = row
+            .as_ref()
+            .ok_or_else(|| error(line, format!("no row for coefficient {}", label)))?;
+        if row.len() != count {
+            let message = format!("{} values of {} for {} ranges", row.len(), label, count);
+            return Err(error(line, message));
+        }
+        for (range, &value) in ranges.iter_mut().zip(row) {
+            range.coefficients[slot] = value;
+        }
+    }
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::Element;
+    use crate::mixture::Mixture;
+    use crate::test_data::full_database;
+
+    // Carbon dioxide from the NIST WebBook (Chase, 1998)
+    const CO2: &str = "\
+Temperature (K)\t298. - 1200.\t1200. - 6000.
+A\t24.99735\t58.16639
+B\t55.18696\t2.720074
+C\t-33.69137\t-0.492289
+D\t7.948387\t0.038844
+E\t-0.136638\t-6.447293
+F\t-403.6075\t-425.9186
+G\t228.2431\t263.6125
+H\t-393.5224\t-393.5224
+Reference\tChase, 1998\tChase, 1998
+Comment\tData last reviewed in September, 1965\tData last reviewed in September, 1965
+";
+
+    fn carbon_dioxide() -> Species {
+        let elements = [
+            (Element::new("C").unwrap(), 1.0),
+            (Element::new("O").unwrap(), 2.0),
+        ];
+        let ranges = parse_shomate(CO2).unwrap();
+        Species::from_shomate("CO2", elements.into_iter().collect(), 0, &ranges).unwrap()
+    }
+
+    #[test]
+    fn test_parse_shomate() {
+        let ranges = parse_shomate(CO2).unwrap();
+        assert_eq!(ranges.len(), 2);
+        assert_eq!((ranges[1].temp_low, ranges[1].temp_high), (1200.0, 6000.0));
+        assert_eq!(ranges[0].coefficients[4], -0.136638);
+
+        let csv = "Temperature (K),298.-1200.\nA,24.99735\nB,55.18696\nC,-33.69137\n\
+                   D,7.948387\nE,-0.136638\nF,-403.6075\nG,228.2431\nH,-393.5224\n";
+        assert_eq!(parse_shomate(csv).unwrap(), ranges[..1]);
+
+        let missing = CO2.replace("G\t228.2431\t263.6125\n", "");
+        assert!(matches!(
+            parse_shomate(&missing),
+            Err(ThermoParseError::Species { message, .. }) if message.contains("coefficient G")
+        ));
+        let short = CO2.replace("\t2.720074", "");
+        assert!(parse_shomate(&short).is_err());
+        assert!(matches!(
+            parse_shomate("Cv\t1.0\n"),
+            Err(ThermoParseError::UnexpectedLine { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_shomate_agrees_with_nasa9() {
+        let shomate = carbon_dioxide();
+        let nasa9 = full_database().find_species("CO2").unwrap();
+        let fit = parse_shomate(CO2).unwrap()[0];
+        for t in (300..=1200).step_by(50).map(f64::from) {
+            // The converted fit reproduces the Shomate formulas themselves
+            assert!((shomate.cp(t).unwrap() - fit.cp(t)).abs() < 1e-9 * fit.cp(t));
+            assert!((shomate.enthalpy(t).unwrap() - fit.enthalpy(t)).abs() < 1e-6);
+            assert!((shomate.entropy(t).unwrap() - fit.entropy(t)).abs() < 1e-9);
+
+            let close = |a: f64, b: f64| (a / b - 1.0).abs() < 5e-3;
+            assert!(
+                close(shomate.cp(t).unwrap(), nasa9.cp(t).unwrap()),
+                "cp at {}",
+                t
+            );
+            assert!(close(
+                shomate.enthalpy(t).unwrap(),
+                nasa9.enthalpy(t).unwrap()
+            ));
+            assert!(close(
+                shomate.entropy(t).unwrap(),
+                nasa9.entropy(t).unwrap()
+            ));
+        }
+
+        // A mixture doesn't care which form its species came from
+        let mut db = full_database().clone();
+        let index = db.index_of("CO2").unwrap();
+        db.species_mut()[index] = shomate;
+        let mixture = Mixture::from_names(&db, &[("CO2", 1.0), ("N2", 3.0)]).unwrap();
+        let reference = Mixture::from_names(full_database(), &[("CO2", 1.0), ("N2", 3.0)]).unwrap();
+        let (cp, expected) = (mixture.cp(&db, 800.0), reference.cp(full_database(), 800.0));
+        assert!((cp.unwrap() / expected.unwrap() - 1.0).abs() < 5e-3);
+    }
+}
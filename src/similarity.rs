@@ -0,0 +1,116 @@
+use crate::database::ThermoFile;
+use crate::elements::Composition;
+use crate::lookup::SpeciesQuery;
+use crate::numeric::{NumericError, finite};
+
+// How far a database species is from one being looked for. Ordering compares the composition
+// first and the heat capacity second, so any species with the wanted formula ranks above every
+// one without it.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Similarity {
+    pub composition_difference: f64, // Atoms of each element differing, summed
+    pub cp_distance: f64,            // RMS difference over the samples, J/(mol K)
+}
+
+impl ThermoFile {
+    // The `n` species most like one missing from the database, given its composition and
+    // samples of its heat capacity as (T in K, Cp in J/(mol K)) pairs, most similar first.
+    // Candidates are the neutral species made of no other elements, whose fits cover every
+    // sample; a species with several records is ranked by its best. A sample that is NaN or
+    // infinite is refused.
+    pub fn most_similar(
+        &self,
+        composition: &Composition,
+        cp_samples: &[(f64, f64)],
+        n: usize,
+    ) -> Result<Vec<(String, Similarity)>, NumericError> {
+        for &(t, cp) in cp_samples {
+            finite("Cp sample temperature", t)?;
+            finite("Cp sample", cp)?;
+        }
+        let wanted = composition.element_moles();
+        let elements: Vec<_> = wanted.iter().map(|&(element, _)| element).collect();
+        let query = SpeciesQuery::new().only_elements(&elements);
+
+        let mut ranked: Vec<(String, Similarity)> = Vec::new();
+        for species in self.query(&query).into_iter().filter(|s| !s.is_ion()) {
+            let squares: Option<f64> = cp_samples
+                .iter()
+                .map(|&(t, cp)| Some((species.cp(t)? - cp).powi(2)))
+                .sum();
+            let Some(squares) = squares else {
+                continue;
+            };
+            let composition_difference = elements
+                .iter()
+                .map(|&element| (wanted.count(element) - species.elements.count(element)).abs())
+                .sum();
+            let similarity = Similarity {
+                composition_difference,
+                cp_distance: (squares / cp_samples.len().max(1) as f64).sqrt(),
+            };
+            match ranked.iter_mut().find(|(name, _)| *name == species.name) {
+                Some((_, best)) if similarity < *best => *best = similarity,
+                Some(_) => {}
+                None => ranked.push((species.name.clone(), similarity)),
+            }
+        }
+        ranked.sort_by(|(_, a), (_, b)| {
+            let composition = a
+                .composition_difference
+                .total_cmp(&b.composition_difference);
+            composition.then(a.cp_distance.total_cmp(&b.cp_distance))
+        });
+        ranked.truncate(n);
+        Ok(ranked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::elements::parse_formula;
+    use crate::numeric::NumericError;
+    use crate::test_data::full_database;
+
+    #[test]
+    fn test_propene_neighbours() {
+        let propene = full_database().find_species("C3H6,propylene").unwrap();
+        let samples: Vec<(f64, f64)> = [300.0, 500.0, 800.0, 1200.0, 2000.0]
+            .iter()
+            .map(|&t| (t, propene.cp(t).unwrap()))
+            .collect();
+        let formula = parse_formula("C3H6").unwrap();
+
+        let found = full_database().most_similar(&formula, &samples, 3).unwrap();
+        assert_eq!(found[0].0, "C3H6,propylene");
+        assert_eq!(found[0].1.cp_distance, 0.0);
+        assert_eq!(found[1].0, "C3H6,cyclo-");
+        assert_eq!(found[1].1.composition_difference, 0.0);
+        assert!(found[1].1.cp_distance > 0.0);
+        assert!(found[2].1.composition_difference > 0.0);
+
+        // With propene missing, its isomer is the nearest, ahead of any species closer in Cp
+        let mut db = full_database().clone();
+        db.species
+            .retain(|species| species.name != "C3H6,propylene");
+        let found = db.most_similar(&formula, &samples, 10).unwrap();
+        assert_eq!(found[0].0, "C3H6,cyclo-");
+        assert_eq!(found.len(), 10);
+        assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        let species = |name: &str| db.find_species(name).unwrap();
+        assert!(
+            found
+                .iter()
+                .all(|(name, _)| species(name).chemical_elements().len() <= 2)
+        );
+
+        // A sample that isn't a number is refused rather than ranked
+        for bad in [(300.0, f64::NAN), (f64::INFINITY, 64.0)] {
+            let samples = [(500.0, 90.0), bad];
+            assert!(matches!(
+                db.most_similar(&formula, &samples, 3),
+                Err(NumericError::NonFinite { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::database::{SpeciesIndexEntry, ThermoFile};
+use crate::elements::{Composition, Element};
+
+// What a database holds, at a glance: how many species of each kind, which elements they are
+// made of and over what temperatures they are fitted
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DbStats {
+    pub species: usize,
+    pub gas: usize,
+    pub liquid: usize, // Condensed species labelled "(L)"
+    pub solid: usize,  // Every other condensed species
+    pub ions: usize,   // Species with a charge, the electron included
+    pub neutral: usize,
+    pub reactant_only: usize,                    // Species with no fits
+    pub elements: BTreeMap<Element, usize>,      // Species containing each element
+    pub fitted_range: Option<(f64, f64)>,        // Lowest and highest fitted temperature, K
+    pub interval_counts: BTreeMap<usize, usize>, // Species with each number of fits
+}
+
+impl ThermoFile {
+    // Counts of the species in the database by kind, element and number of fits
+    pub fn stats(&self) -> DbStats {
+        let mut stats = DbStats::default();
+        for species in &self.species {
+            let ranges = species.temperature_ranges();
+            let fitted = ranges
+                .first()
+                .zip(ranges.last())
+                .map(|(first, last)| (first.temp_low, last.temp_high));
+            stats.count(
+                &species.name,
+                &species.elements,
+                species.phase,
+                ranges.len(),
+                fitted,
+            );
+        }
+        stats
+    }
+}
+
+impl DbStats {
+    // The same counts as `ThermoFile::stats`, from an index of a file's records
+    pub fn from_index(entries: &[SpeciesIndexEntry]) -> DbStats {
+        let mut stats = DbStats::default();
+        for entry in entries {
+            let SpeciesIndexEntry {
+                name,
+                elements,
+                phase,
+                interval_count,
+                fitted_range,
+                ..
+            } = entry;
+            stats.count(name, elements, *phase, *interval_count, *fitted_range);
+        }
+        stats
+    }
+
+    // Add one species to the counts
+    fn count(
+        &mut self,
+        name: &str,
+        elements: &Composition,
+        phase: u8,
+        interval_count: usize,
+        fitted_range: Option<(f64, f64)>,
+    ) {
+        self.species += 1;
+        if phase == 0 {
+            self.gas += 1;
+        } else if name.ends_with("(L)") {
+            self.liquid += 1;
+        } else {
+            self.solid += 1;
+        }
+        if elements.count(Element::ELECTRON) != 0.0 {
+            self.ions += 1;
+        } else {
+            self.neutral += 1;
+        }
+        for (element, _) in elements.element_moles().iter() {
+            if *element != Element::ELECTRON {
+                *self.elements.entry(*element).or_default() += 1;
+            }
+        }
+
+        *self.interval_counts.entry(interval_count).or_default() += 1;
+        let Some((first, last)) = fitted_range else {
+            self.reactant_only += 1;
+            return;
+        };
+        self.fitted_range = Some(match self.fitted_range {
+            None => (first, last),
+            Some((low, high)) => (low.min(first), high.max(last)),
+        });
+    }
+}
+
+// An aligned two-column report, one count per line
+impl fmt::Display for DbStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows = [
+            ("species", self.species),
+            ("  gas", self.gas),
+            ("  liquid", self.liquid),
+            ("  solid", self.solid),
+            ("  ions", self.ions),
+            ("  neutral", self.neutral),
+            ("  reactant-only", self.reactant_only),
+        ];
+        for (label, count) in rows {
+            writeln!(f, "{:<20}{:>8}", label, count)?;
+        }
+        match self.fitted_range {
+            Some((low, high)) => writeln!(f, "{:<20}{:>8} - {} K", "fitted range", low, high)?,
+            None => writeln!(f, "{:<20}{:>8}", "fitted range", "none")?,
+        }
+        writeln!(f, "fits per species")?;
+        for (intervals, count) in &self.interval_counts {
+            writeln!(f, "  {:<18}{:>8}", intervals, count)?;
+        }
+        writeln!(f, "species per element")?;
+        for (element, count) in &self.elements {
+            writeln!(f, "  {:<18}{:>8}", element, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{parse_index, parse_thermo};
+    use crate::test_data::database_with_reactants;
+
+    #[test]
+    fn test_snippet_stats() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let stats = parse_thermo(&raw_text).unwrap().stats();
+        let element = |symbol| Element::new(symbol).unwrap();
+        assert_eq!(
+            stats,
+            DbStats {
+                species: 2,
+                gas: 2,
+                liquid: 0,
+                solid: 0,
+                ions: 1,
+                neutral: 1,
+                reactant_only: 0,
+                elements: BTreeMap::from([(element("N"), 1), (element("O"), 1)]),
+                fitted_range: Some((200.0, 20000.0)),
+                interval_counts: BTreeMap::from([(2, 1), (3, 1)]),
+            }
+        );
+
+        let report = stats.to_string();
+        assert!(report.starts_with("species                    2\n  gas                      2\n"));
+        assert!(report.contains("fitted range             200 - 20000 K\n"));
+        assert!(report.ends_with(
+            "species per element\n  N                        1\n  O                        1\n"
+        ));
+        let json: serde_json::Value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["elements"]["N"], 1);
+        assert_eq!(json["interval_counts"]["3"], 1);
+        assert_eq!(json["fitted_range"], serde_json::json!([200.0, 20000.0]));
+    }
+
+    #[test]
+    fn test_full_database_stats() {
+        let db = database_with_reactants();
+        let stats = db.stats();
+        assert_eq!(stats.species, db.species.len());
+        assert_eq!(stats.gas + stats.liquid + stats.solid, stats.species);
+        assert_eq!(stats.ions + stats.neutral, stats.species);
+        assert_eq!(stats.interval_counts.values().sum::<usize>(), stats.species);
+        assert_eq!(stats.interval_counts[&0], stats.reactant_only);
+        assert!(stats.reactant_only > 0 && stats.liquid > 0 && stats.solid > 0);
+        assert!(
+            stats.elements[&Element::new("C").unwrap()]
+                > stats.elements[&Element::new("AR").unwrap()]
+        );
+        assert_eq!(stats.fitted_range, Some((80.0, 20000.0)));
+    }
+
+    #[test]
+    fn test_stats_from_index() {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let index = parse_index(&raw_text);
+        assert_eq!(
+            DbStats::from_index(&index),
+            database_with_reactants().stats()
+        );
+    }
+}
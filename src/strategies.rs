@@ -0,0 +1,152 @@
+// Proptest strategies generating random-but-valid database records, shared between test modules
+use proptest::prelude::*;
+
+use crate::database::{
+    EnthalpyDatum, Species, TemperatureRange, ThermoFile, ThermoFormat, ThermoHeader,
+};
+use crate::elements::{Composition, Element};
+
+// Element symbols as they appear in the formula fields of thermo.inp, with the mixed case of
+// some other sources, which `Element` reads (and the writer gives back) as upper-case
+pub const ELEMENT_SYMBOLS: &[&str] = &[
+    "E", "H", "HE", "LI", "BE", "B", "C", "N", "O", "F", "NE", "NA", "MG", "AL", "SI", "P", "S",
+    "CL", "AR", "K", "CA", "TI", "V", "CR", "MN", "FE", "NI", "CU", "ZN", "BR", "KR", "I", "XE",
+    "D", "Ar", "Cl", "Na",
+];
+
+// A coefficient of either sign with magnitude spanning 1e-20..1e+20, or exactly zero
+pub fn coefficient() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        1 => Just(0.0),
+        9 => (any::<bool>(), -20.0..20.0f64).prop_map(|(negative, exponent)| {
+            let magnitude = 10f64.powf(exponent);
+            if negative { -magnitude } else { magnitude }
+        }),
+    ]
+}
+
+// Between one and five distinct elements, with counts given to two decimals
+pub fn elements() -> impl Strategy<Value = Composition> {
+    proptest::sample::subsequence(ELEMENT_SYMBOLS, 1..=5)
+        .prop_flat_map(|symbols| {
+            let counts = proptest::collection::vec(1u32..=2000, symbols.len());
+            (Just(symbols), counts)
+        })
+        .prop_map(|(symbols, counts)| {
+            symbols
+                .into_iter()
+                .zip(counts)
+                .map(|(symbol, count)| (Element::new(symbol).unwrap(), count as f64 / 100.0))
+                .collect()
+        })
+}
+
+// A single temperature range with the given bounds
+pub fn temperature_range(temp_low: f64, temp_high: f64) -> impl Strategy<Value = TemperatureRange> {
+    (
+        proptest::array::uniform7(coefficient()),
+        prop_oneof![4 => Just(7u8), 1 => 1u8..7],
+        proptest::array::uniform2(coefficient()),
+        0u32..=50_000_000,
+    )
+        .prop_map(
+            move |(mut coefficients, used_coefficients, integration_constants, h298_minus_h0)| {
+                for coefficient in coefficients.iter_mut().skip(used_coefficients as usize) {
+                    *coefficient = 0.0;
+                }
+                TemperatureRange {
+                    temp_low,
+                    temp_high,
+                    coefficients,
+                    used_coefficients,
+                    integration_constants,
+                    h298_minus_h0: h298_minus_h0 as f64 / 1000.0,
+                }
+            },
+        )
+}
+
+// One to three temperature ranges with contiguous bounds
+pub fn temperature_ranges() -> impl Strategy<Value = Vec<TemperatureRange>> {
+    (
+        100_000u32..=400_000,
+        proptest::collection::vec(1_000u32..=10_000_000, 1..=3),
+    )
+        .prop_flat_map(|(start, widths)| {
+            let mut bounds = vec![start as f64 / 1000.0];
+            let mut current = start;
+            for width in widths {
+                current += width;
+                bounds.push(current as f64 / 1000.0);
+            }
+            bounds
+                .windows(2)
+                .map(|pair| temperature_range(pair[0], pair[1]))
+                .collect::<Vec<_>>()
+        })
+}
+
+// A complete species record whose every field fits its fixed-column budget
+pub fn species() -> impl Strategy<Value = Species> {
+    (
+        "[A-Z][A-Za-z0-9()+,-]{0,14}",
+        "([A-Za-z0-9.,:/()]([A-Za-z0-9.,:/() ]{0,58}[A-Za-z0-9.,:/()])?)?",
+        prop_oneof![
+            "[a-z] [0-9]/[0-9]{2}",
+            "tpis[0-9]{2}",
+            "[a-z]{1,2}[0-9]/[0-9]{2}"
+        ],
+        elements(),
+        0u8..=9,
+        0u64..=9_999_999_999,
+        -999_999_999i64..=999_999_999,
+        prop_oneof![
+            4 => temperature_ranges().prop_map(|ranges| (ranges, None)),
+            // Reactant-only records, with an assigned enthalpy in place of fits
+            1 => (10_000u32..=6_000_000).prop_map(|t| (Vec::new(), Some(t as f64 / 1000.0))),
+        ],
+    )
+        .prop_map(
+            |(
+                name,
+                description,
+                reference_code,
+                elements,
+                phase,
+                molecular_weight,
+                heat_of_formation,
+                (temperature_ranges, assigned_at),
+            )| Species {
+                name,
+                description,
+                reference_code,
+                elements,
+                phase,
+                molecular_weight: molecular_weight as f64 / 1e7,
+                enthalpy_datum: match assigned_at {
+                    Some(temperature) => EnthalpyDatum::Assigned {
+                        value: heat_of_formation as f64 / 1e3,
+                        temperature,
+                    },
+                    None => EnthalpyDatum::FormationAt298 {
+                        value: heat_of_formation as f64 / 1e3,
+                    },
+                },
+                temperature_ranges,
+                source_digits: Default::default(),
+                provenance: None,
+            },
+        )
+}
+
+// A file of a few species under the standard CEA header
+pub fn thermo_file() -> impl Strategy<Value = ThermoFile> {
+    proptest::collection::vec(species(), 1..=4).prop_map(|species| {
+        let header = ThermoHeader {
+            temp_ranges: [200.0, 1000.0, 6000.0, 20000.0],
+            date: "9/09/04".to_string(),
+            format: ThermoFormat::Nasa9,
+        };
+        ThermoFile::new(header, species)
+    })
+}
@@ -0,0 +1,64 @@
+// Shared database fixtures for unit tests
+use std::sync::OnceLock;
+
+use crate::database::{ThermoFile, parse_thermo, parse_thermo_file};
+
+// The full product database shipped with the repository, parsed once per test run
+pub fn full_database() -> &'static ThermoFile {
+    static DATABASE: OnceLock<ThermoFile> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        thermo_db
+    })
+}
+
+// The shipped database read through to its reactant section, parsed once per test run. Its
+// species are those of `full_database` followed by the reactant-only and reactant records.
+pub fn database_with_reactants() -> &'static ThermoFile {
+    static DATABASE: OnceLock<ThermoFile> = OnceLock::new();
+    DATABASE.get_or_init(|| {
+        let raw_text = std::fs::read_to_string("thermo.inp").unwrap();
+        parse_thermo(&raw_text).unwrap()
+    })
+}
+
+// The snippet with a 0.01 K gap between the electron's first two fits, and a 0.5 K
+// overlap between those of air
+pub fn mismatched_snippet() -> String {
+    let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+    let fits = "0007 -2.0 -1.0  0.0  1.0  2.0  3.0  4.0  0.0";
+    raw_text
+        .replacen(
+            &format!("   1000.000   6000.{}         6197.428", fits),
+            &format!("   1000.010   6000.{}         6197.428", fits),
+            1,
+        )
+        .replacen(
+            &format!("   1000.000   6000.{}         8672.434", fits),
+            &format!("    999.500   6000.{}         8672.434", fits),
+            1,
+        )
+}
+
+// JANAF rows of a species in the golden suite's transcription, tests/reference/cases.toml, as
+// (T in K, Cp in J/(mol K), H - H(298.15) in kJ/mol, S in J/(mol K)). Its enthalpies are on
+// the heat-of-formation basis, which differs only by a constant, zero for N2 and O2.
+pub fn janaf_rows(name: &str) -> Vec<(f64, f64, f64, f64)> {
+    let text = std::fs::read_to_string("tests/reference/cases.toml").unwrap();
+    let cases: toml::Table = text.parse().unwrap();
+    let species = cases["species"].as_array().unwrap();
+    let case = species
+        .iter()
+        .find(|case| case["name"].as_str() == Some(name));
+    let points = case.unwrap()["points"].as_array().unwrap();
+    let value = |point: &toml::Value, key: &str| point[key].as_float().unwrap();
+    let h_reference = value(&points[0], "h");
+    points
+        .iter()
+        .map(|point| {
+            let h = value(point, "h") - h_reference;
+            (value(point, "t"), value(point, "cp"), h, value(point, "s"))
+        })
+        .collect()
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+
+use crate::numeric::{self, NumericError};
+
+// Pressure units accepted as suffixes, with their size in Pa. A bare number is in Pa.
+const PRESSURE_UNITS: &[(&str, f64)] = &[
+    ("Pa", 1.0),
+    ("kPa", 1.0e3),
+    ("MPa", 1.0e6),
+    ("bar", 1.0e5),
+    ("atm", 101_325.0),
+    ("psi", 6_894.757_293_168),
+    ("psia", 6_894.757_293_168),
+    ("torr", 101_325.0 / 760.0),
+];
+
+// Temperature units accepted as suffixes, with the scale and offset taking them to K. A bare
+// number is in K, and a degree sign before the unit is allowed.
+const TEMPERATURE_UNITS: &[(&str, (f64, f64))] = &[
+    ("K", (1.0, 0.0)),
+    ("C", (1.0, 273.15)),
+    ("F", (5.0 / 9.0, 273.15 - 32.0 * 5.0 / 9.0)),
+    ("R", (5.0 / 9.0, 0.0)),
+];
+
+// Why a quantity with a unit suffix could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnitError {
+    // The text before the suffix is not a number
+    InvalidNumber(String),
+    // The suffix names no supported unit
+    UnknownUnit {
+        unit: String,
+        supported: Vec<&'static str>,
+    },
+    // The value, taken to SI, is at or below zero
+    Numeric(NumericError),
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnitError::InvalidNumber(text) => write!(f, "'{}' is not a number", text),
+            UnitError::UnknownUnit { unit, supported } => write!(
+                f,
+                "unknown unit '{}'; supported units are {}",
+                unit,
+                supported.join(", ")
+            ),
+            UnitError::Numeric(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+// Split "10bar" or "80 F" into its number and its (possibly empty) unit, matched against the
+// supported units case-insensitively
+fn split_unit<T: Copy>(
+    text: &str,
+    units: &[(&'static str, T)],
+) -> Result<(f64, Option<T>), UnitError> {
+    let text = text.trim();
+    let split = text
+        .rfind(|c: char| c.is_ascii_digit() || c == '.')
+        .map_or(0, |i| i + 1);
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| UnitError::InvalidNumber(text.to_string()))?;
+    let unit = unit.trim().trim_start_matches('°');
+    if unit.is_empty() {
+        return Ok((value, None));
+    }
+    match units
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+    {
+        Some(&(_, conversion)) => Ok((value, Some(conversion))),
+        None => Err(UnitError::UnknownUnit {
+            unit: unit.to_string(),
+            supported: units.iter().map(|(name, _)| *name).collect(),
+        }),
+    }
+}
+
+// A pressure such as "10bar", "145psia" or "2.5 MPa", in Pa, which must be above zero
+pub fn parse_pressure(text: &str) -> Result<f64, UnitError> {
+    let (value, scale) = split_unit(text, PRESSURE_UNITS)?;
+    numeric::positive("pressure", value * scale.unwrap_or(1.0)).map_err(UnitError::Numeric)
+}
+
+// A pressure read as `parse_pressure` reads it, but which may also be zero, as the vacuum
+// outside a nozzle
+pub fn parse_ambient_pressure(text: &str) -> Result<f64, UnitError> {
+    let (value, scale) = split_unit(text, PRESSURE_UNITS)?;
+    let pressure = value * scale.unwrap_or(1.0);
+    if pressure == 0.0 {
+        return Ok(0.0);
+    }
+    numeric::positive("ambient pressure", pressure).map_err(UnitError::Numeric)
+}
+
+// A temperature such as "80F", "25C" or "300 K", in K, which must be above absolute zero
+pub fn parse_temperature(text: &str) -> Result<f64, UnitError> {
+    let (value, to_kelvin) = split_unit(text, TEMPERATURE_UNITS)?;
+    let (scale, offset) = to_kelvin.unwrap_or((1.0, 0.0));
+    numeric::positive("temperature", value * scale + offset).map_err(UnitError::Numeric)
+}
+
+// Typed shims over the f64 API, for callers who want the compiler to check their units
+#[cfg(feature = "uom")]
+mod typed {
+    use uom::si::f64::{
+        MassDensity, MolarEnergy, MolarHeatCapacity, Pressure, ThermodynamicTemperature,
+    };
+    use uom::si::mass_density::kilogram_per_cubic_meter;
+    use uom::si::molar_energy::joule_per_mole;
+    use uom::si::molar_heat_capacity::joule_per_kelvin_mole;
+    use uom::si::pressure::pascal;
+    use uom::si::thermodynamic_temperature::kelvin;
+
+    use crate::database::{Species, ThermoFile};
+    use crate::mixture::Mixture;
+
+    impl Species {
+        pub fn cp_si(&self, temperature: ThermodynamicTemperature) -> Option<MolarHeatCapacity> {
+            let cp = self.cp(temperature.get::<kelvin>())?;
+            Some(MolarHeatCapacity::new::<joule_per_kelvin_mole>(cp))
+        }
+
+        pub fn enthalpy_si(&self, temperature: ThermodynamicTemperature) -> Option<MolarEnergy> {
+            let h = self.enthalpy(temperature.get::<kelvin>())?;
+            Some(MolarEnergy::new::<joule_per_mole>(h))
+        }
+
+        pub fn entropy_si(
+            &self,
+            temperature: ThermodynamicTemperature,
+        ) -> Option<MolarHeatCapacity> {
+            let s = self.entropy(temperature.get::<kelvin>())?;
+            Some(MolarHeatCapacity::new::<joule_per_kelvin_mole>(s))
+        }
+
+        pub fn gibbs_si(&self, temperature: ThermodynamicTemperature) -> Option<MolarEnergy> {
+            let g = self.gibbs(temperature.get::<kelvin>())?;
+            Some(MolarEnergy::new::<joule_per_mole>(g))
+        }
+    }
+
+    impl Mixture {
+        pub fn density_si(
+            &self,
+            db: &ThermoFile,
+            temperature: ThermodynamicTemperature,
+            pressure: Pressure,
+        ) -> Option<MassDensity> {
+            let t = temperature.get::<kelvin>();
+            let rho = self.density(db, t, pressure.get::<pascal>())?;
+            Some(MassDensity::new::<kilogram_per_cubic_meter>(rho))
+        }
+
+        pub fn cp_si(
+            &self,
+            db: &ThermoFile,
+            temperature: ThermodynamicTemperature,
+        ) -> Option<MolarHeatCapacity> {
+            let cp = self.cp(db, temperature.get::<kelvin>())?;
+            Some(MolarHeatCapacity::new::<joule_per_kelvin_mole>(cp))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn test_pressure_suffixes() {
+        let cases = [
+            ("101325", 101_325.0),
+            ("5e6", 5.0e6),
+            ("200Pa", 200.0),
+            ("68.9 kPa", 68_900.0),
+            ("7MPa", 7.0e6),
+            ("10bar", 1.0e6),
+            ("1atm", 101_325.0),
+            ("145psia", 999_739.807_509),
+            ("14.7 PSI", 101_352.932_209),
+            ("760torr", 101_325.0),
+            ("1 Torr", 133.322_368_421),
+            ("0.1MPa", 1.0e5),
+            ("1.5E3 kPa", 1.5e6),
+        ];
+        for (text, expected) in cases {
+            let pressure = parse_pressure(text).unwrap();
+            assert!(close(pressure, expected), "{}: {}", text, pressure);
+        }
+    }
+
+    #[test]
+    fn test_temperature_suffixes() {
+        let cases = [
+            ("298.15", 298.15),
+            ("300K", 300.0),
+            ("25C", 298.15),
+            ("-40 C", 233.15),
+            ("80F", 299.816_666_667),
+            ("-40F", 233.15),
+            ("540R", 300.0),
+            ("26.85C", 300.0),
+            ("536.67R", 298.15),
+            ("25°C", 298.15),
+            ("77 °F", 298.15),
+            ("1e3k", 1000.0),
+        ];
+        for (text, expected) in cases {
+            let temperature = parse_temperature(text).unwrap();
+            assert!(close(temperature, expected), "{}: {}", text, temperature);
+        }
+    }
+
+    #[test]
+    fn test_non_positive_values() {
+        for text in ["0", "-5K", "-273.15C", "-500F", "0R", "-1e-3"] {
+            assert!(
+                matches!(
+                    parse_temperature(text),
+                    Err(UnitError::Numeric(NumericError::NonPositive {
+                        quantity: "temperature",
+                        ..
+                    }))
+                ),
+                "{}",
+                text
+            );
+        }
+        for text in ["0", "-1bar", "0 torr", "-2.5 MPa"] {
+            assert!(
+                matches!(
+                    parse_pressure(text),
+                    Err(UnitError::Numeric(NumericError::NonPositive {
+                        quantity: "pressure",
+                        ..
+                    }))
+                ),
+                "{}",
+                text
+            );
+        }
+        // Only outside a nozzle may the pressure be nil
+        assert_eq!(parse_ambient_pressure("0"), Ok(0.0));
+        assert_eq!(parse_ambient_pressure("-0 bar"), Ok(0.0));
+        assert_eq!(parse_ambient_pressure("1atm"), Ok(101_325.0));
+        assert!(matches!(
+            parse_ambient_pressure("-1 psia"),
+            Err(UnitError::Numeric(NumericError::NonPositive {
+                quantity: "ambient pressure",
+                ..
+            }))
+        ));
+        assert_eq!(
+            parse_temperature("-300C").unwrap_err().to_string(),
+            format!("temperature must be positive, not {}", -300.0 + 273.15)
+        );
+    }
+
+    #[test]
+    fn test_unknown_units() {
+        let error = parse_pressure("3 furlongs").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown unit 'furlongs'; supported units are Pa, kPa, MPa, bar, atm, psi, psia, torr"
+        );
+        assert_eq!(
+            parse_temperature("300 kelvin").unwrap_err().to_string(),
+            "unknown unit 'kelvin'; supported units are K, C, F, R"
+        );
+        assert!(matches!(
+            parse_temperature("hot"),
+            Err(UnitError::InvalidNumber(_))
+        ));
+        assert!(matches!(
+            parse_temperature("80 Fahrenheit"),
+            Err(UnitError::UnknownUnit { .. })
+        ));
+    }
+
+    #[cfg(feature = "uom")]
+    #[test]
+    fn test_typed_shims() {
+        use crate::mixture::Mixture;
+        use crate::test_data::full_database;
+        use uom::si::f64::{Pressure, ThermodynamicTemperature};
+        use uom::si::mass_density::kilogram_per_cubic_meter;
+        use uom::si::molar_heat_capacity::joule_per_kelvin_mole;
+        use uom::si::pressure::bar;
+        use uom::si::thermodynamic_temperature::kelvin;
+
+        let db = full_database();
+        let t = ThermodynamicTemperature::new::<kelvin>(1000.0);
+        let n2 = db.find_species("N2").unwrap();
+        let cp = n2.cp_si(t).unwrap().get::<joule_per_kelvin_mole>();
+        assert_eq!(cp, n2.cp(1000.0).unwrap());
+
+        let air = Mixture::from_names(db, &[("N2", 0.79), ("O2", 0.21)]).unwrap();
+        let rho = air.density_si(db, t, Pressure::new::<bar>(1.0)).unwrap();
+        let expected = air.density(db, 1000.0, 1.0e5).unwrap();
+        assert!(close(rho.get::<kilogram_per_cubic_meter>(), expected));
+    }
+}
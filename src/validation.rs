@@ -0,0 +1,673 @@
+use std::fmt;
+
+use crate::database::{
+    EnthalpyDatum, NASA9_EXPONENTS, Provenance, Species, TemperatureRange, ThermoFile,
+};
+use crate::elements::AtomicWeights;
+use crate::equilibrium::solve_linear;
+use crate::properties::GAS_CONSTANT;
+
+// Temperature at which the assigned enthalpy of a fit equals the heat of formation, K
+const FORMATION_TEMPERATURE: f64 = 298.15;
+
+// Largest jump in Cp/R, H/RT or S/R allowed where one fit hands over to the next, relative to
+// values of at least one
+const CONTINUITY_TOLERANCE: f64 = 1.0e-2;
+
+// Relative disagreement allowed between the listed and formula molecular weights
+const MASS_TOLERANCE: f64 = 1.0e-4;
+
+// Disagreement allowed between the heat of formation and the fit at 298.15 K: an absolute part
+// in J/mol, and a relative part covering the fits' use of R = 8.31451 J/(mol K)
+const FORMATION_TOLERANCE: (f64, f64) = (10.0, 1.0e-5);
+
+// What is wrong with a species record
+#[derive(Debug, Clone, PartialEq)]
+pub enum IssueKind {
+    // Consecutive fits don't meet: the upper bound of one isn't the lower bound of the next
+    RangeGap {
+        upper: f64,
+        next_lower: f64,
+    },
+    // A dimensionless property jumps where two fits meet
+    Discontinuity {
+        temperature: f64,
+        property: &'static str,
+        jump: f64,
+    },
+    // A fit (by index) holds a NaN or infinite coefficient, bound or constant, named as in
+    // `TemperatureRange::named_values`
+    NonFiniteCoefficient {
+        range: usize,
+        values: Vec<&'static str>,
+    },
+    // The formula holds an element missing from the atomic weights
+    UnknownElement(String),
+    // The listed molecular weight disagrees with the formula, g/mol
+    MassBalance {
+        listed: f64,
+        computed: f64,
+    },
+    // The heat of formation disagrees with the fit's enthalpy at 298.15 K, J/mol
+    HeatOfFormation {
+        listed: f64,
+        computed: f64,
+    },
+}
+
+// What to do where consecutive fits don't meet exactly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepairPolicy {
+    // Move both bounds to their midpoint when they are within `tolerance` (K) of each other
+    SnapBoundaries { tolerance: f64 },
+    // Change nothing, failing on the first mismatch
+    Error,
+}
+
+// One boundary moved by `Species::repair_intervals`: the fit (by index) whose upper bound was
+// `upper` and the next fit, whose lower bound was `next_lower`, now meet at `boundary`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalRepair {
+    pub range: usize,
+    pub upper: f64,
+    pub next_lower: f64,
+    pub boundary: f64,
+}
+
+impl fmt::Display for IntervalRepair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fit {} ended at {} K and the next started at {} K; both now meet at {} K",
+            self.range, self.upper, self.next_lower, self.boundary
+        )
+    }
+}
+
+// Why `Species::repair_continuity` left a species as it was. Fits are named by index, that of
+// the lower of the two meeting at the boundary concerned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepairError {
+    // The fits don't meet, so have no one boundary to match at
+    RangeGap {
+        range: usize,
+        upper: f64,
+        next_lower: f64,
+    },
+    // The jump in Cp/R is too large to be smoothed away rather than refitted
+    TooLarge {
+        range: usize,
+        temperature: f64,
+        jump: f64,
+    },
+    // No adjustment could be solved for, as when the upper fit has too few terms in use
+    Singular {
+        range: usize,
+    },
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepairError::RangeGap {
+                range,
+                upper,
+                next_lower,
+            } => write!(
+                f,
+                "fit {} ends at {} K but the next starts at {} K",
+                range, upper, next_lower
+            ),
+            RepairError::TooLarge {
+                range,
+                temperature,
+                jump,
+            } => write!(
+                f,
+                "Cp/R jumps by {:e} at {} K, after fit {}; too far to repair",
+                jump, temperature, range
+            ),
+            RepairError::Singular { range } => {
+                write!(f, "no adjustment to the fit after fit {} matches it", range)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepairError {}
+
+// One problem found in a database, keyed by the name of the species at fault and, when it was
+// loaded from a file, where its record is
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub species: String,
+    pub provenance: Option<Provenance>,
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(provenance) = &self.provenance {
+            write!(f, "{}: ", provenance)?;
+        }
+        write!(f, "{}: ", self.species)?;
+        match &self.kind {
+            IssueKind::RangeGap { upper, next_lower } => {
+                write!(
+                    f,
+                    "fit ends at {} K but the next starts at {} K",
+                    upper, next_lower
+                )
+            }
+            IssueKind::Discontinuity {
+                temperature,
+                property,
+                jump,
+            } => write!(f, "{} jumps by {:e} at {} K", property, jump, temperature),
+            IssueKind::NonFiniteCoefficient { range, values } => {
+                write!(f, "fit {} has non-finite {}", range, values.join(", "))
+            }
+            IssueKind::UnknownElement(symbol) => write!(f, "no atomic weight for '{}'", symbol),
+            IssueKind::MassBalance { listed, computed } => write!(
+                f,
+                "molecular weight {} differs from the formula's {}",
+                listed, computed
+            ),
+            IssueKind::HeatOfFormation { listed, computed } => write!(
+                f,
+                "heat of formation {} J/mol differs from the fit's {} J/mol",
+                listed, computed
+            ),
+        }
+    }
+}
+
+impl Species {
+    // Gaps between fits and jumps in Cp/R, H/RT and S/R where they meet
+    pub fn check_continuity(&self) -> Vec<IssueKind> {
+        let mut issues = Vec::new();
+        for pair in self.temperature_ranges.windows(2) {
+            let (low, high) = (&pair[0], &pair[1]);
+            if low.temp_high != high.temp_low {
+                issues.push(IssueKind::RangeGap {
+                    upper: low.temp_high,
+                    next_lower: high.temp_low,
+                });
+                continue;
+            }
+            let t = low.temp_high;
+            let properties = ["Cp/R", "H/RT", "S/R"];
+            let values = values_at(low, t).into_iter().zip(values_at(high, t));
+            for (property, (below, above)) in properties.into_iter().zip(values) {
+                let jump = (above - below).abs();
+                if jump > CONTINUITY_TOLERANCE * below.abs().max(1.0) {
+                    issues.push(IssueKind::Discontinuity {
+                        temperature: t,
+                        property,
+                        jump,
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    // Close small gaps and overlaps between consecutive fits as the policy allows, returning
+    // each boundary moved. A mismatch the policy can't repair is returned as a `RangeGap`,
+    // with the species left untouched.
+    pub fn repair_intervals(
+        &mut self,
+        policy: RepairPolicy,
+    ) -> Result<Vec<IntervalRepair>, IssueKind> {
+        let mut repairs = Vec::new();
+        for (range, pair) in self.temperature_ranges.windows(2).enumerate() {
+            let (upper, next_lower) = (pair[0].temp_high, pair[1].temp_low);
+            if upper == next_lower {
+                continue;
+            }
+            let gap = IssueKind::RangeGap { upper, next_lower };
+            let RepairPolicy::SnapBoundaries { tolerance } = policy else {
+                return Err(gap);
+            };
+            let boundary = 0.5 * (upper + next_lower);
+            // Written to also reject NaN bounds, and fits the snap would turn inside out
+            let snaps = (upper - next_lower).abs() <= tolerance
+                && pair[0].temp_low < boundary
+                && boundary < pair[1].temp_high;
+            if !snaps {
+                return Err(gap);
+            }
+            repairs.push(IntervalRepair {
+                range,
+                upper,
+                next_lower,
+                boundary,
+            });
+        }
+        for repair in &repairs {
+            self.temperature_ranges[repair.range].temp_high = repair.boundary;
+            self.temperature_ranges[repair.range + 1].temp_low = repair.boundary;
+        }
+        Ok(repairs)
+    }
+
+    // Adjust each fit's coefficients so its Cp/R meets the fit below at their shared boundary,
+    // as `repair_continuity_with` does without matching the slope
+    pub fn repair_continuity(&mut self, tol: f64) -> Result<(), RepairError> {
+        self.repair_continuity_with(tol, false)
+    }
+
+    // Adjust each fit's coefficients so its Cp/R, and its slope if asked, meets the fit below
+    // at their shared boundary. Of the adjustments that do so, the one changing Cp/R least
+    // (in the least-squares sense) over the fit is taken, and the integration constants are
+    // then moved to keep H/RT and S/R continuous too. Boundaries are taken from the bottom up,
+    // so a change carried to the top of one fit is made good by the next. A jump in Cp/R
+    // larger than `tol` (relative to values of at least one) is refused, leaving the species
+    // as it was.
+    pub fn repair_continuity_with(
+        &mut self,
+        tol: f64,
+        match_slope: bool,
+    ) -> Result<(), RepairError> {
+        let mut ranges = self.temperature_ranges.clone();
+        for range in 0..ranges.len().saturating_sub(1) {
+            let (below, above) = ranges.split_at_mut(range + 1);
+            let (low, high) = (&below[range], &mut above[0]);
+            if low.temp_high != high.temp_low {
+                return Err(RepairError::RangeGap {
+                    range,
+                    upper: low.temp_high,
+                    next_lower: high.temp_low,
+                });
+            }
+            let t = low.temp_high;
+            let (below, above) = (values_at(low, t), values_at(high, t));
+            let cp_below = below[0];
+            let jump = cp_below - above[0];
+            // Written to also refuse a NaN jump
+            let repairable = jump.abs() <= tol * cp_below.abs().max(1.0);
+            if !repairable {
+                return Err(RepairError::TooLarge {
+                    range,
+                    temperature: t,
+                    jump: jump.abs(),
+                });
+            }
+            let slope_jump = cp_slope(low, t) - cp_slope(high, t);
+            let mut targets = vec![jump];
+            if match_slope {
+                // Scaled by the boundary temperature, as the terms are below
+                targets.push(slope_jump * t);
+            }
+            if targets.iter().all(|&target| target == 0.0) {
+                continue;
+            }
+            let changes =
+                continuity_adjustment(high, t, &targets).ok_or(RepairError::Singular { range })?;
+            for ((coefficient, change), &power) in high
+                .coefficients
+                .iter_mut()
+                .zip(changes)
+                .zip(&NASA9_EXPONENTS)
+            {
+                *coefficient += change / t.powi(power);
+            }
+            let adjusted = values_at(high, t);
+            high.integration_constants[0] += (below[1] - adjusted[1]) * t;
+            high.integration_constants[1] += below[2] - adjusted[2];
+        }
+        self.temperature_ranges = ranges;
+        Ok(())
+    }
+
+    // Fits holding a NaN or infinity anywhere in their numbers
+    pub fn check_coefficients(&self) -> Vec<IssueKind> {
+        self.temperature_ranges
+            .iter()
+            .enumerate()
+            .filter_map(|(range, fit)| {
+                let values: Vec<&'static str> = fit
+                    .named_values()
+                    .into_iter()
+                    .filter(|(_, value)| !value.is_finite())
+                    .map(|(name, _)| name)
+                    .collect();
+                (!values.is_empty()).then_some(IssueKind::NonFiniteCoefficient { range, values })
+            })
+            .collect()
+    }
+
+    // The listed molecular weight against the one summed from the formula
+    pub fn check_mass_balance(&self, weights: &AtomicWeights) -> Vec<IssueKind> {
+        let unknown: Vec<IssueKind> = self
+            .elements
+            .iter()
+            .filter(|(element, _)| weights.get(element.symbol()).is_none())
+            .map(|(element, _)| IssueKind::UnknownElement(element.symbol().to_string()))
+            .collect();
+        if !unknown.is_empty() {
+            return unknown;
+        }
+        let computed = weights.formula_weight(&self.elements).unwrap_or(f64::NAN);
+        let listed = self.molecular_weight;
+        if (listed - computed).abs() <= MASS_TOLERANCE * computed.abs() {
+            return Vec::new();
+        }
+        vec![IssueKind::MassBalance { listed, computed }]
+    }
+
+    // The heat of formation against the fit's assigned enthalpy at 298.15 K, which CEA defines
+    // to be equal. Species with no fit at 298.15 K, and reactant-only records (whose enthalpy
+    // is assigned rather than formed), can't be checked.
+    pub fn check_heat_of_formation(&self) -> Vec<IssueKind> {
+        let EnthalpyDatum::FormationAt298 { value: listed } = self.enthalpy_datum else {
+            return Vec::new();
+        };
+        let Some(range) = self.range_at(FORMATION_TEMPERATURE) else {
+            return Vec::new();
+        };
+        let computed =
+            values_at(range, FORMATION_TEMPERATURE)[1] * GAS_CONSTANT * FORMATION_TEMPERATURE;
+        let (absolute, relative) = FORMATION_TOLERANCE;
+        if (listed - computed).abs() <= absolute + relative * listed.abs() {
+            return Vec::new();
+        }
+        vec![IssueKind::HeatOfFormation { listed, computed }]
+    }
+}
+
+// Cp/R, H/RT and S/R of a fit, each NaN at a temperature that isn't positive, which the checks
+// take as too large a jump
+fn values_at(range: &TemperatureRange, temperature: f64) -> [f64; 3] {
+    [
+        range.cp_over_r(temperature),
+        range.h_over_rt(temperature),
+        range.s_over_r(temperature),
+    ]
+    .map(|value| value.unwrap_or(f64::NAN))
+}
+
+// d(Cp/R)/dT of a fit
+fn cp_slope(range: &TemperatureRange, temperature: f64) -> f64 {
+    range
+        .coefficients
+        .iter()
+        .zip(NASA9_EXPONENTS)
+        .take(range.used_coefficients as usize)
+        .map(|(a, k)| a * k as f64 * temperature.powi(k - 1))
+        .sum()
+}
+
+// The smallest change to a fit's terms, in the least-squares sense over samples of its range,
+// raising its Cp/R at `boundary` by `targets[0]` and, if given, its slope (times `boundary`)
+// by `targets[1]`. Terms are scaled to (T / boundary)^k to keep the system well conditioned,
+// so change k is to the coefficient times boundary^k. Terms not in use are left alone.
+fn continuity_adjustment(
+    range: &TemperatureRange,
+    boundary: f64,
+    targets: &[f64],
+) -> Option<[f64; 7]> {
+    const SAMPLES: usize = 32;
+    let terms = range.used_coefficients as usize;
+    let size = terms + targets.len();
+    let mut matrix = vec![vec![0.0; size]; size];
+    for sample in 0..SAMPLES {
+        let fraction = sample as f64 / (SAMPLES - 1) as f64;
+        let t = range.temp_low + fraction * (range.temp_high - range.temp_low);
+        let basis: Vec<f64> = NASA9_EXPONENTS[..terms]
+            .iter()
+            .map(|&k| (t / boundary).powi(k))
+            .collect();
+        for (row, &u) in basis.iter().enumerate() {
+            for (col, &v) in basis.iter().enumerate() {
+                matrix[row][col] += u * v;
+            }
+        }
+    }
+    // Lagrange conditions: at the boundary every scaled term is one, and its slope k
+    for (constraint, _) in targets.iter().enumerate() {
+        for (col, &k) in NASA9_EXPONENTS[..terms].iter().enumerate() {
+            let value = if constraint == 0 { 1.0 } else { k as f64 };
+            matrix[terms + constraint][col] = value;
+            matrix[col][terms + constraint] = value;
+        }
+    }
+    let mut rhs = vec![0.0; size];
+    rhs[terms..].copy_from_slice(targets);
+    let solution = solve_linear(matrix, rhs)?;
+    let mut changes = [0.0; 7];
+    changes[..terms].copy_from_slice(&solution[..terms]);
+    Some(changes)
+}
+
+impl ThermoFile {
+    // Every integrity check run over every species, in database order
+    pub fn validate_all(&self, weights: &AtomicWeights) -> Vec<ValidationIssue> {
+        self.species
+            .iter()
+            .flat_map(|species| {
+                let issues = [
+                    species.check_continuity(),
+                    species.check_coefficients(),
+                    species.check_mass_balance(weights),
+                    species.check_heat_of_formation(),
+                ];
+                issues.into_iter().flatten().map(|kind| ValidationIssue {
+                    species: species.name.clone(),
+                    provenance: species.provenance.clone(),
+                    kind,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elements::Element;
+    use crate::test_data::{full_database, mismatched_snippet};
+
+    #[test]
+    fn test_shipped_database_issues() {
+        let issues = full_database().validate_all(&AtomicWeights::default());
+        let mut species: Vec<&str> = issues.iter().map(|i| i.species.as_str()).collect();
+        species.dedup();
+        // The lower-case 'air' entry is a placeholder, and ALN(L) has a step between its fits
+        assert_eq!(species, ["air", "ALN(L)"]);
+    }
+
+    #[test]
+    fn test_repair_continuity() {
+        let db = full_database();
+        let original = db.find_species("CO2").unwrap().clone();
+        let jumps = |species: &Species| -> Vec<[f64; 4]> {
+            species
+                .temperature_ranges()
+                .windows(2)
+                .map(|pair| {
+                    let (low, high, t) = (&pair[0], &pair[1], pair[0].temp_high);
+                    [
+                        (values_at(high, t)[0] - values_at(low, t)[0]).abs(),
+                        (cp_slope(high, t) - cp_slope(low, t)).abs(),
+                        (values_at(high, t)[1] - values_at(low, t)[1]).abs(),
+                        (values_at(high, t)[2] - values_at(low, t)[2]).abs(),
+                    ]
+                })
+                .collect()
+        };
+
+        // A nudge to the constant term of the upper fit opens a jump of 0.02 in Cp/R
+        let mut species = original.clone();
+        species.temperature_ranges[1].coefficients[2] += 0.02;
+        let before = jumps(&species)[0];
+        assert!(before[0] > 0.019);
+
+        // Too large a jump for the tolerance is refused, changing nothing
+        let refused = species.clone().repair_continuity(1e-3);
+        assert!(matches!(
+            refused,
+            Err(RepairError::TooLarge {
+                range: 0,
+                temperature: 1000.0,
+                ..
+            })
+        ));
+
+        species.repair_continuity(1e-2).unwrap();
+        let after = jumps(&species);
+        for boundary in &after {
+            assert!(boundary[0] < 1e-10, "{:?}", after);
+            assert!(boundary[2] < 1e-10 && boundary[3] < 1e-10, "{:?}", after);
+        }
+        // The adjustment is felt near the boundary, leaving the fit as it was further up
+        let fit = &species.temperature_ranges[1];
+        for t in [2000.0, 3000.0, 4000.0, 5000.0, 6000.0] {
+            let nudged = original.temperature_ranges[1].cp_over_r(t).unwrap() + 0.02;
+            let change = (fit.cp_over_r(t).unwrap() - nudged).abs();
+            assert!(change < 1e-3, "{} K: {}", t, change);
+        }
+
+        // Matching the slope too removes its jump as well
+        let mut smooth = original.clone();
+        smooth.temperature_ranges[1].coefficients[2] += 0.02;
+        smooth.temperature_ranges[1].coefficients[3] += 1e-6;
+        assert!(jumps(&smooth)[0][1] > 9e-7);
+        smooth.repair_continuity_with(1e-2, true).unwrap();
+        let after = jumps(&smooth)[0];
+        assert!(after[0] < 1e-10 && after[1] < 1e-12, "{:?}", after);
+
+        // Fits that don't meet can't be matched
+        let mut gapped = original.clone();
+        gapped.temperature_ranges[1].temp_low = 1000.5;
+        assert_eq!(
+            gapped.repair_continuity(1e-2),
+            Err(RepairError::RangeGap {
+                range: 0,
+                upper: 1000.0,
+                next_lower: 1000.5
+            })
+        );
+    }
+
+    #[test]
+    fn test_issue_provenance() {
+        let db = crate::database::load_thermo("thermo-snippet.inp")
+            .unwrap()
+            .file;
+        let issues = db.validate_all(&AtomicWeights::default());
+        assert!(!issues.is_empty());
+        for issue in &issues {
+            assert_eq!(issue.species, "air");
+            assert!(
+                issue
+                    .to_string()
+                    .starts_with("thermo-snippet.inp:14: air: ")
+            );
+        }
+    }
+
+    #[test]
+    fn test_repair_intervals() {
+        let db = crate::database::parse_thermo(&mismatched_snippet()).unwrap();
+        let (mut electron, mut air) = (db.species[0].clone(), db.species[1].clone());
+        assert_eq!(electron.check_continuity().len(), 1);
+
+        // Refusing leaves the species as it was
+        let before = electron.clone();
+        let refused = electron.repair_intervals(RepairPolicy::Error);
+        assert_eq!(
+            refused,
+            Err(IssueKind::RangeGap {
+                upper: 1000.0,
+                next_lower: 1000.01
+            })
+        );
+        assert_eq!(
+            electron.to_cea_string().unwrap(),
+            before.to_cea_string().unwrap()
+        );
+        let too_far = air.repair_intervals(RepairPolicy::SnapBoundaries { tolerance: 0.1 });
+        assert!(too_far.is_err());
+
+        let snap = RepairPolicy::SnapBoundaries { tolerance: 1.0 };
+        let repairs = electron.repair_intervals(snap).unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert!((repairs[0].boundary - 1000.005).abs() < 1e-9);
+        assert_eq!(
+            electron.temperature_ranges[0].temp_high,
+            repairs[0].boundary
+        );
+        assert_eq!(electron.temperature_ranges[1].temp_low, repairs[0].boundary);
+        assert!(electron.check_continuity().is_empty());
+
+        let repairs = air.repair_intervals(snap).unwrap();
+        let expected = "fit 0 ended at 1000 K and the next started at 999.5 K; both now meet \
+                        at 999.75 K";
+        assert_eq!(repairs[0].to_string(), expected);
+        assert!(air.repair_intervals(snap).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupted_database() {
+        let db = full_database();
+        let pick = |name: &str| db.find_species(name).unwrap().clone();
+
+        let mut nitrogen = pick("N2");
+        nitrogen.temperature_ranges[2].coefficients[0] = f64::NAN;
+        let mut carbon_dioxide = pick("CO2");
+        carbon_dioxide.molecular_weight = 45.0;
+        let mut water = pick("H2O");
+        water.enthalpy_datum = EnthalpyDatum::FormationAt298 {
+            value: water.enthalpy_datum.value() + 1000.0,
+        };
+        let mut oxygen = pick("O2");
+        oxygen.temperature_ranges[1].temp_low = 1100.0;
+        let mut unknown = pick("Ar");
+        unknown.name = "Xx".to_string();
+        unknown.elements = [(Element::new("XX").unwrap(), 1.0)].into_iter().collect();
+
+        let species = vec![nitrogen, carbon_dioxide, water, oxygen, unknown, pick("Ar")];
+        let corrupted = ThermoFile::new(db.header.clone(), species);
+        let issues = corrupted.validate_all(&AtomicWeights::default());
+        let kinds: Vec<(&str, &IssueKind)> = issues
+            .iter()
+            .map(|i| (i.species.as_str(), &i.kind))
+            .collect();
+        assert_eq!(issues.len(), 5, "{:?}", kinds);
+        assert_eq!(
+            kinds[0],
+            (
+                "N2",
+                &IssueKind::NonFiniteCoefficient {
+                    range: 2,
+                    values: vec!["a1"]
+                }
+            )
+        );
+        assert!(matches!(kinds[1], ("CO2", IssueKind::MassBalance { .. })));
+        assert!(matches!(
+            kinds[2],
+            ("H2O", IssueKind::HeatOfFormation { .. })
+        ));
+        assert_eq!(
+            kinds[3],
+            (
+                "O2",
+                &IssueKind::RangeGap {
+                    upper: 1000.0,
+                    next_lower: 1100.0
+                }
+            )
+        );
+        assert_eq!(
+            kinds[4],
+            ("Xx", &IssueKind::UnknownElement("XX".to_string()))
+        );
+
+        // A table with the missing element clears its issue
+        let mut weights = AtomicWeights::default();
+        weights.set("Xx", 39.948);
+        assert_eq!(corrupted.validate_all(&weights).len(), 4);
+    }
+}
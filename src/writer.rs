@@ -0,0 +1,664 @@
+use std::fmt;
+
+use crate::database::{
+    EnthalpyDatum, NASA9_EXPONENTS, Species, TemperatureRange, ThermoFile, ThermoHeader,
+    decimals_for,
+};
+use crate::elements::{AtomicWeights, Element};
+use crate::patch::COEFFICIENT_NAMES;
+
+// Longest species name the first record line holds (A15), and longest description after it
+const NAME_WIDTH: usize = 15;
+const DESCRIPTION_WIDTH: usize = 62;
+
+// Relative error a value may take on when shortened to fit its field
+const FIELD_TOLERANCE: f64 = 1.0e-8;
+
+// Why a species could not be written in the fixed-column layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteError {
+    // A field holding more than its columns allow, or a value (NaN or infinite) that no field
+    // can. A fit written on its own names no species.
+    FieldOverflow { species: String, field: String },
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriteError::FieldOverflow { species, field } => {
+                write!(f, "'{}': the {} doesn't fit its columns", species, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+// Format a value in Fortran 'D' notation (like "-3.947960830D+04") with the given digits after
+// the point, right-aligned in 16 characters
+fn fortran_d(value: f64, digits: usize) -> String {
+    let formatted = format!("{:.digits$E}", value);
+    let (mantissa, exponent) = formatted
+        .split_once('E')
+        .expect("exponential format always contains 'E'");
+    let exponent: i32 = exponent.parse().expect("exponent is an integer");
+    let exp_sign = if exponent < 0 { '-' } else { '+' };
+
+    format!(
+        "{:>16}",
+        format!("{}D{}{:02}", mantissa, exp_sign, exponent.abs())
+    )
+}
+
+// A coefficient in its 16-character 'D' field, a digit shorter for a negative value with a
+// three-digit exponent. None for NaN or infinity.
+fn fit_fortran_d(value: f64) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    [9, 8]
+        .into_iter()
+        .map(|digits| fortran_d(value, digits))
+        .find(|field| field.len() == 16)
+}
+
+// A value in a fixed-width field as `format_fixed` writes it or, where that is too wide, with
+// fewer decimals or in E notation, so long as it reads back to within `FIELD_TOLERANCE`. None
+// if no form fits, or for NaN or infinity.
+fn fit_fixed(value: f64, width: usize, decimals: usize) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    let field = format_fixed(value, width, decimals);
+    if field.len() <= width {
+        return Some(field);
+    }
+    let reads_back = |text: &str| {
+        text.parse::<f64>()
+            .is_ok_and(|read| (read - value).abs() <= FIELD_TOLERANCE * value.abs())
+    };
+    let fewer_decimals = (0..decimals).rev().map(|d| format!("{:.d$}", value));
+    let scientific = (0..=9).rev().map(|d| format!("{:.d$E}", value));
+    fewer_decimals
+        .chain(scientific)
+        .find(|text| text.len() <= width && reads_back(text))
+        .map(|text| format!("{:>width$}", text))
+}
+
+// Format a value into a fixed-width field, adding decimals beyond the default only when needed
+fn format_fixed(value: f64, width: usize, decimals: usize) -> String {
+    // Fortran drops the leading zero of fractional values to save a column
+    let fit = |candidate: String| {
+        if candidate.len() > width {
+            candidate.replacen("0.", ".", 1)
+        } else {
+            candidate
+        }
+    };
+    let mut field = format!("{:>width$}", fit(format!("{:.decimals$}", value)));
+    let mut extra = decimals + 1;
+    while field.trim().parse::<f64>() != Ok(value) {
+        let candidate = fit(format!("{:.extra$}", value));
+        if candidate.len() > width {
+            break;
+        }
+        field = format!("{:>width$}", candidate);
+        extra += 1;
+    }
+    field
+}
+
+// A JSON string literal, escaping quotes, backslashes and control characters
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// A JSON number with the shortest digits that read back to the same value, or null for a value
+// JSON can't hold
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_array(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|&v| json_number(v)).collect();
+    format!("[{}]", values.join(","))
+}
+
+impl ThermoHeader {
+    // Write the 'thermo' keyword and the global temperature range line
+    pub fn to_cea_string(&self) -> String {
+        let [t1, t2, t3, t4] = self.temp_ranges;
+        format!(
+            "thermo\n{:10.2}{:10.2}{:10.2}{:10.2}{:>12}\n",
+            t1, t2, t3, t4, self.date
+        )
+    }
+}
+
+impl TemperatureRange {
+    // Write the interval record and its two coefficient lines
+    pub fn to_cea_string(&self) -> Result<String, WriteError> {
+        let overflow = |field: &str| WriteError::FieldOverflow {
+            species: String::new(),
+            field: field.to_string(),
+        };
+        let fixed = |value: f64, width: usize, field: &str| {
+            fit_fixed(value, width, 3).ok_or_else(|| overflow(field))
+        };
+        let coefficient = |slot: usize, value: f64| {
+            fit_fortran_d(value).ok_or_else(|| overflow(COEFFICIENT_NAMES[slot]))
+        };
+
+        // Eight exponent fields, listing only those of the terms in use
+        let used = self.used_coefficients as usize;
+        let exponents: String = (0..8)
+            .map(|i| match NASA9_EXPONENTS.get(i) {
+                Some(exponent) if i < used => format!("{:5.1}", *exponent as f64),
+                _ => format!("{:5.1}", 0.0),
+            })
+            .collect();
+
+        let mut out = format!(
+            "{}{}{}{}  {}\n",
+            fixed(self.temp_low, 11, "temp_low")?,
+            fixed(self.temp_high, 11, "temp_high")?,
+            self.used_coefficients,
+            exponents,
+            fixed(self.h298_minus_h0, 15, "h298_minus_h0")?
+        );
+
+        for (slot, value) in self.coefficients[..5].iter().enumerate() {
+            out.push_str(&coefficient(slot, *value)?);
+        }
+        out.push('\n');
+
+        for (slot, value) in self.coefficients.iter().enumerate().skip(5) {
+            out.push_str(&coefficient(slot, *value)?);
+        }
+        out.push_str(&" ".repeat(16));
+        for (slot, value) in self.integration_constants.iter().enumerate() {
+            out.push_str(&coefficient(7 + slot, *value)?);
+        }
+        out.push('\n');
+
+        Ok(out)
+    }
+}
+
+impl Species {
+    // Write the species in the fixed-column CEA thermo.inp layout. A field too wide for its
+    // columns is written in fewer decimals or E notation where that keeps its value, and is
+    // otherwise refused, rather than shifting the columns after it.
+    pub fn to_cea_string(&self) -> Result<String, WriteError> {
+        let overflow = |field: &str| WriteError::FieldOverflow {
+            species: self.name.clone(),
+            field: field.to_string(),
+        };
+        let name_fits = !self.name.is_empty()
+            && self.name.chars().count() <= NAME_WIDTH
+            && !self.name.contains(char::is_whitespace);
+        if !name_fits {
+            return Err(overflow("name"));
+        }
+        if self.description.chars().count() > DESCRIPTION_WIDTH {
+            return Err(overflow("description"));
+        }
+        if self.reference_code.chars().count() > 6 {
+            return Err(overflow("reference code"));
+        }
+        if self.temperature_ranges.len() > 99 {
+            return Err(overflow("interval count"));
+        }
+        if self.elements.len() > 5 {
+            return Err(overflow("formula"));
+        }
+        if self.phase > 9 {
+            return Err(overflow("phase"));
+        }
+        let mut out = format!("{:<18}{}", self.name, self.description)
+            .trim_end()
+            .to_string();
+        out.push('\n');
+
+        // Five (A2, F6.2) element fields, blank ones written as zero counts
+        let mut formula = String::new();
+        for slot in 0..5 {
+            match self.elements.get(slot) {
+                Some((symbol, count)) => {
+                    let count = fit_fixed(*count, 6, 2)
+                        .ok_or_else(|| overflow(&format!("count of {}", symbol)))?;
+                    formula.push_str(&format!("{:<2}{}", symbol, count))
+                }
+                None => formula.push_str("    0.00"),
+            }
+        }
+
+        // Constants read from text keep the digits they had, rather than gaining zeros
+        let enthalpy = self.enthalpy_datum.value();
+        let digits = self.source_digits;
+        let weight_decimals = digits
+            .molecular_weight
+            .map_or(7, |d| decimals_for(self.molecular_weight, d));
+        let enthalpy_decimals = digits.enthalpy.map_or(3, |d| decimals_for(enthalpy, d));
+        out.push_str(&format!(
+            "{:>2} {:<6} {} {:1}{}{}\n",
+            self.temperature_ranges.len(),
+            self.reference_code,
+            formula,
+            self.phase,
+            fit_fixed(self.molecular_weight, 13, weight_decimals)
+                .ok_or_else(|| overflow("molecular weight"))?,
+            fit_fixed(enthalpy, 15, enthalpy_decimals).ok_or_else(|| overflow("enthalpy"))?
+        ));
+
+        for (interval, range) in self.temperature_ranges.iter().enumerate() {
+            let lines = range.to_cea_string().map_err(|error| match error {
+                WriteError::FieldOverflow { field, .. } => {
+                    overflow(&format!("{} of fit {}", field, interval))
+                }
+            })?;
+            out.push_str(&lines);
+        }
+
+        // A reactant-only record closes with the temperature of its assigned enthalpy
+        if let EnthalpyDatum::Assigned { temperature, .. } = self.enthalpy_datum {
+            out.push_str(&format!(
+                "{}      0.0000  0.0  0.0  0.0  0.0  0.0  0.0  0.0  0.0            0.000\n",
+                fit_fixed(temperature, 11, 3).ok_or_else(|| overflow("assigned temperature"))?
+            ));
+        }
+
+        Ok(out)
+    }
+
+    // A compact JSON object of the record's fields, numbers at full precision. Elements are
+    // [symbol, count] pairs as listed, and the enthalpy carries its kind: "formation" at
+    // 298.15 K, or "assigned" at the temperature given with it. A "provenance" object of
+    // source and line follows only for a species loaded from a file.
+    pub fn to_json(&self) -> String {
+        let elements: Vec<String> = self
+            .elements
+            .iter()
+            .map(|(symbol, count)| {
+                format!("[{},{}]", json_string(symbol.symbol()), json_number(*count))
+            })
+            .collect();
+        let enthalpy = match self.enthalpy_datum {
+            EnthalpyDatum::FormationAt298 { value } => {
+                format!(
+                    "{{\"kind\":\"formation\",\"value\":{}}}",
+                    json_number(value)
+                )
+            }
+            EnthalpyDatum::Assigned { value, temperature } => format!(
+                "{{\"kind\":\"assigned\",\"value\":{},\"temperature\":{}}}",
+                json_number(value),
+                json_number(temperature)
+            ),
+        };
+        let ranges: Vec<String> = self
+            .temperature_ranges
+            .iter()
+            .map(|range| {
+                format!(
+                    "{{\"temp_low\":{},\"temp_high\":{},\"coefficients\":{},\
+                     \"integration_constants\":{},\"h298_minus_h0\":{}}}",
+                    json_number(range.temp_low),
+                    json_number(range.temp_high),
+                    json_array(&range.coefficients[..range.used_coefficients as usize]),
+                    json_array(&range.integration_constants),
+                    json_number(range.h298_minus_h0)
+                )
+            })
+            .collect();
+        let provenance = match &self.provenance {
+            Some(provenance) => format!(
+                ",\"provenance\":{{\"source\":{},\"line\":{}}}",
+                json_string(&provenance.source),
+                provenance.line
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{{\"name\":{},\"description\":{},\"reference_code\":{},\"phase\":{},\
+             \"molecular_weight\":{},\"elements\":[{}],\"enthalpy\":{},\
+             \"temperature_ranges\":[{}]{}}}",
+            json_string(&self.name),
+            json_string(&self.description),
+            json_string(&self.reference_code),
+            self.phase,
+            json_number(self.molecular_weight),
+            elements.join(","),
+            enthalpy,
+            ranges.join(","),
+            provenance
+        )
+    }
+}
+
+impl ThermoFile {
+    // Write the complete file, ready to be parsed back by `parse_thermo_file`, or the first
+    // species that won't fit the layout
+    pub fn to_cea_string(&self) -> Result<String, WriteError> {
+        let mut out = self.header.to_cea_string();
+        for species in &self.species {
+            out.push_str(&species.to_cea_string()?);
+        }
+        out.push_str("END PRODUCTS\nEND REACTANTS\n");
+        Ok(out)
+    }
+
+    // The `elements` section of a Cantera YAML input: each element of the database with its
+    // atomic weight, g/mol. One with no weight in the table is noted in a comment instead.
+    pub fn to_cantera_elements_yaml(&self) -> String {
+        let weights = AtomicWeights::default();
+        let mut out = "elements:\n".to_string();
+        for element in self.elements() {
+            let symbol = cantera_symbol(element);
+            match weights.get(element.symbol()) {
+                Some(weight) => out.push_str(&format!(
+                    "- symbol: {}\n  atomic-weight: {}\n",
+                    symbol, weight
+                )),
+                None => out.push_str(&format!("# {}: no atomic weight known\n", symbol)),
+            }
+        }
+        out
+    }
+}
+
+// An element as Cantera writes it, its second letter in lower case ("Ar" for "AR")
+fn cantera_symbol(element: Element) -> String {
+    let mut chars = element.symbol().chars();
+    chars
+        .next()
+        .into_iter()
+        .chain(chars.flat_map(char::to_lowercase))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Provenance, ThermoFormat, parse_thermo_file};
+    use crate::strategies;
+    use proptest::prelude::*;
+
+    // Relative agreement to the 10 significant digits written by the D16.9 fields
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() <= tol * a.abs().max(b.abs()).max(f64::MIN_POSITIVE)
+    }
+
+    #[test]
+    fn test_fortran_d_formatting() {
+        assert_eq!(fit_fortran_d(-39479.6083).unwrap(), "-3.947960830D+04");
+        assert_eq!(fit_fortran_d(575.573102).unwrap(), " 5.755731020D+02");
+        assert_eq!(fit_fortran_d(1.066859930e-5).unwrap(), " 1.066859930D-05");
+        assert_eq!(fit_fortran_d(0.0).unwrap(), " 0.000000000D+00");
+    }
+
+    #[test]
+    fn test_fixed_formatting() {
+        assert_eq!(format_fixed(2.0, 6, 2), "  2.00");
+        assert_eq!(format_fixed(1.5617, 6, 2), "1.5617");
+        assert_eq!(format_fixed(0.41959, 6, 2), ".41959");
+        assert_eq!(format_fixed(-1.0, 6, 2), " -1.00");
+        assert_eq!(format_fixed(0.000548579903, 13, 7), ".000548579903");
+
+        // Values too wide for their columns shorten where they can, and are refused otherwise
+        assert_eq!(fit_fixed(1.5617, 6, 2).unwrap(), "1.5617");
+        assert_eq!(fit_fixed(-1.23456789e14, 15, 3).unwrap(), "-1.234567890E14");
+        assert_eq!(fit_fixed(123456.78, 6, 2), None);
+        assert_eq!(fit_fixed(f64::NAN, 13, 7), None);
+        assert_eq!(fit_fortran_d(-1.0e-300).unwrap(), "-1.00000000D-300");
+        assert_eq!(fit_fortran_d(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_field_overflow() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        let air = thermo_db.find_species("air").unwrap();
+        let overflow = |species: &Species| match species.to_cea_string() {
+            Err(WriteError::FieldOverflow { field, .. }) => field,
+            Ok(_) => panic!("{} was written", species.name),
+        };
+
+        let mut long = air.clone();
+        long.name = "a-sixteen-letter".to_string();
+        assert_eq!(overflow(&long), "name");
+        let mut crowded = air.clone();
+        crowded.elements = crowded
+            .elements
+            .iter()
+            .map(|&(element, count)| (element, count * 61_728.39))
+            .collect();
+        assert_eq!(overflow(&crowded), "count of N");
+        let mut weightless = air.clone();
+        weightless.molecular_weight = f64::NAN;
+        assert_eq!(overflow(&weightless), "molecular weight");
+        let mut broken = air.clone();
+        broken.temperature_ranges[1].coefficients[3] = f64::NAN;
+        assert_eq!(overflow(&broken), "a4 of fit 1");
+        assert_eq!(
+            broken.to_cea_string().unwrap_err().to_string(),
+            "'air': the a4 of fit 1 doesn't fit its columns"
+        );
+
+        // Extreme values that do fit read back as they were
+        let mut extreme = air.clone();
+        extreme.name = "fifteen-letters".to_string();
+        extreme.molecular_weight = 3500.123456;
+        extreme.enthalpy_datum = EnthalpyDatum::FormationAt298 {
+            value: -1.23456789e14,
+        };
+        extreme.temperature_ranges[0].coefficients[0] = -1.0e-300;
+        let mut file = thermo_db.clone();
+        file.species = vec![extreme.clone()];
+        let (_, reparsed) = parse_thermo_file(&file.to_cea_string().unwrap()).unwrap();
+        let read = &reparsed.species[0];
+        assert_eq!(read.name, extreme.name);
+        assert_eq!(read.molecular_weight, extreme.molecular_weight);
+        assert_eq!(read.enthalpy_datum, extreme.enthalpy_datum);
+        assert_eq!(read.temperature_ranges[0].coefficients[0], -1.0e-300);
+    }
+
+    #[test]
+    fn test_snippet_round_trip() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        assert_eq!(thermo_db.species.len(), 2);
+
+        let written = thermo_db.to_cea_string().unwrap();
+        let (_, reparsed) = parse_thermo_file(&written).unwrap();
+        assert_eq!(reparsed.to_cea_string().unwrap(), written);
+
+        // Numeric record lines are reproduced column for column
+        for (original, rewritten) in raw_text.lines().skip(2).zip(written.lines().skip(2)) {
+            if original.starts_with([' ', '-']) {
+                assert_eq!(original.trim_end(), rewritten.trim_end());
+            }
+        }
+    }
+
+    #[test]
+    fn test_source_digits_are_kept() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let shortened = raw_text.replacen(
+            "   28.9600000          0.000",
+            "   28.96               0.0",
+            1,
+        );
+        let (_, thermo_db) = parse_thermo_file(&shortened).unwrap();
+        let air = thermo_db.find_species("air").unwrap();
+        assert_eq!(air.source_digits.molecular_weight, Some(4));
+        assert_eq!(air.source_digits.enthalpy, Some(1));
+
+        // Written with the digits it was read with, and read back the same way
+        let written = thermo_db.to_cea_string().unwrap();
+        let formula = written.lines().find(|line| line.contains("28.96")).unwrap();
+        assert!(
+            formula.ends_with("        28.96            0.0"),
+            "{}",
+            formula
+        );
+        let (_, reparsed) = parse_thermo_file(&written).unwrap();
+        assert_eq!(
+            reparsed.find_species("air").unwrap().source_digits,
+            air.source_digits
+        );
+    }
+
+    #[test]
+    fn test_cantera_elements() {
+        let db = crate::test_data::database_with_reactants();
+        let yaml = db.to_cantera_elements_yaml();
+        assert!(yaml.starts_with("elements:\n- symbol: "), "{}", yaml);
+        assert!(!yaml.contains('#'), "every element has a weight: {}", yaml);
+
+        // Each distinct element once, as Cantera spells it
+        let elements = db.elements();
+        assert!(elements.contains(&Element::ELECTRON));
+        assert_eq!(yaml.matches("- symbol: ").count(), elements.len());
+        for element in &elements {
+            let weight = AtomicWeights::default().get(element.symbol()).unwrap();
+            let entry = format!(
+                "- symbol: {}\n  atomic-weight: {}\n",
+                cantera_symbol(*element),
+                weight
+            );
+            assert_eq!(yaml.matches(&entry).count(), 1, "{}", entry);
+        }
+        assert!(yaml.contains("- symbol: Ar\n  atomic-weight: 39.948\n"));
+        assert!(yaml.contains("- symbol: E\n"));
+    }
+
+    #[test]
+    fn test_species_json() {
+        let raw_text = std::fs::read_to_string("thermo-snippet.inp").unwrap();
+        let (_, thermo_db) = parse_thermo_file(&raw_text).unwrap();
+        let air = thermo_db.find_species("air").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&air.to_json()).unwrap();
+
+        assert_eq!(json["name"], "air");
+        assert_eq!(
+            json["elements"],
+            serde_json::json!([["N", 2.0], ["O", 2.0]])
+        );
+        assert_eq!(json["enthalpy"]["kind"], "formation");
+        let ranges = json["temperature_ranges"].as_array().unwrap();
+        assert_eq!(ranges.len(), air.temperature_ranges().len());
+        for (json, range) in ranges.iter().zip(air.temperature_ranges()) {
+            let coefficients: Vec<f64> =
+                serde_json::from_value(json["coefficients"].clone()).unwrap();
+            assert_eq!(coefficients, range.coefficients);
+            assert_eq!(json["temp_high"], range.temp_high);
+        }
+
+        // Text needing escapes survives, and a reactant-only record gives its temperature
+        let mut species = air.clone();
+        species.description = "say \"hi\"\\\t".to_string();
+        let json: serde_json::Value = serde_json::from_str(&species.to_json()).unwrap();
+        assert_eq!(json["description"], species.description);
+        species.temperature_ranges.clear();
+        species.enthalpy_datum = EnthalpyDatum::Assigned {
+            value: -12_345.678_9,
+            temperature: 90.17,
+        };
+        let json: serde_json::Value = serde_json::from_str(&species.to_json()).unwrap();
+        assert_eq!(
+            json["enthalpy"],
+            serde_json::json!({"kind": "assigned", "value": -12_345.678_9, "temperature": 90.17})
+        );
+        assert_eq!(json["temperature_ranges"], serde_json::json!([]));
+        assert!(json.get("provenance").is_none());
+
+        species.provenance = Some(Provenance {
+            source: "C:\\data\\thermo.inp".to_string(),
+            line: 42,
+        });
+        let json: serde_json::Value = serde_json::from_str(&species.to_json()).unwrap();
+        assert_eq!(
+            json["provenance"],
+            serde_json::json!({"source": "C:\\data\\thermo.inp", "line": 42})
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn write_parse_write_is_fixed_point(thermo_db in strategies::thermo_file()) {
+            let written = thermo_db.to_cea_string().unwrap();
+            let (_, parsed) = parse_thermo_file(&written).unwrap();
+            prop_assert_eq!(parsed.to_cea_string().unwrap(), written);
+        }
+
+        #[test]
+        fn parse_recovers_written_species(species in strategies::species()) {
+            let header = ThermoHeader {
+                temp_ranges: [200.0, 1000.0, 6000.0, 20000.0],
+                date: "9/09/04".to_string(),
+                format: ThermoFormat::Nasa9,
+            };
+            let thermo_db = ThermoFile::new(header, vec![species.clone()]);
+            let written = thermo_db.to_cea_string().unwrap();
+            let (_, parsed) = parse_thermo_file(&written).unwrap();
+            prop_assert_eq!(parsed.species.len(), 1);
+            let parsed = &parsed.species[0];
+
+            prop_assert_eq!(&parsed.name, &species.name);
+            prop_assert_eq!(&parsed.description, &species.description);
+            prop_assert_eq!(&parsed.reference_code, &species.reference_code);
+            prop_assert_eq!(&parsed.elements, &species.elements);
+            // Symbols in either case, as "Ar", are written upper-case as CEA lists them
+            let formula = &written.lines().nth(3).unwrap()[10..50];
+            prop_assert!(!formula.contains(|c: char| c.is_ascii_lowercase()), "{}", formula);
+            prop_assert_eq!(parsed.phase, species.phase);
+            prop_assert!(close(parsed.molecular_weight, species.molecular_weight, 1e-12));
+            match (parsed.enthalpy_datum, species.enthalpy_datum) {
+                (
+                    EnthalpyDatum::FormationAt298 { value: got },
+                    EnthalpyDatum::FormationAt298 { value: expected },
+                ) => prop_assert!(close(got, expected, 1e-12)),
+                (
+                    EnthalpyDatum::Assigned { value: got, temperature: got_t },
+                    EnthalpyDatum::Assigned { value: expected, temperature: expected_t },
+                ) => {
+                    prop_assert!(close(got, expected, 1e-12));
+                    prop_assert!(close(got_t, expected_t, 1e-12));
+                }
+                (got, expected) => prop_assert!(false, "{:?} != {:?}", got, expected),
+            }
+
+            prop_assert_eq!(parsed.temperature_ranges.len(), species.temperature_ranges.len());
+            for (got, expected) in parsed.temperature_ranges.iter().zip(&species.temperature_ranges) {
+                prop_assert!(close(got.temp_low, expected.temp_low, 1e-12));
+                prop_assert!(close(got.temp_high, expected.temp_high, 1e-12));
+                prop_assert!(close(got.h298_minus_h0, expected.h298_minus_h0, 1e-12));
+                prop_assert_eq!(got.used_coefficients, expected.used_coefficients);
+                for (a, b) in got.coefficients.iter().zip(&expected.coefficients) {
+                    prop_assert!(close(*a, *b, 1e-9), "{} != {}", a, b);
+                }
+                for (a, b) in got.integration_constants.iter().zip(&expected.integration_constants) {
+                    prop_assert!(close(*a, *b, 1e-9), "{} != {}", a, b);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+// Golden regression suite: compares evaluated properties and adiabatic flame temperatures
+// against reference values.
+//
+// Cases live in cases.toml next to this file, so new reference points can be added without
+// touching Rust code. On failure every deviation is printed as a table before the test fails.
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use equilibrium_rs::database::{ThermoFile, parse_thermo_file};
+use equilibrium_rs::equilibrium::{EquilibriumOptions, Problem, solve_equilibrium};
+use equilibrium_rs::mixture::Mixture;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct Cases {
+    tolerance: Tolerance,
+    species: Vec<SpeciesCase>,
+    equilibrium: Vec<EquilibriumCase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tolerance {
+    cp: f64, // Relative
+    s: f64,  // Relative
+    h: f64,  // Absolute, kJ/mol
+    t: f64,  // Absolute, K
+}
+
+#[derive(Debug, Deserialize)]
+struct SpeciesCase {
+    name: String,
+    points: Vec<ReferencePoint>,
+}
+
+// Reactants burnt at constant enthalpy and pressure, with the flame temperature expected
+#[derive(Debug, Deserialize)]
+struct EquilibriumCase {
+    name: String,
+    source: String,                   // Where the expected value comes from
+    reactants: BTreeMap<String, f64>, // Moles of each
+    pressure: f64,                    // Pa
+    reactant_temperature: f64,        // K
+    t: f64,                           // K
+}
+
+#[derive(Debug, Deserialize)]
+struct ReferencePoint {
+    t: f64,
+    cp: Option<f64>,
+    h: Option<f64>,
+    s: Option<f64>,
+}
+
+// A single compared quantity, kept for the deviation table
+struct Comparison {
+    species: String,
+    temperature: f64,
+    property: &'static str,
+    expected: f64,
+    actual: f64,
+    deviation: f64,
+    tolerance: f64,
+}
+
+impl Comparison {
+    fn passed(&self) -> bool {
+        self.deviation.abs() <= self.tolerance
+    }
+}
+
+fn load_fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/reference")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn load_database() -> ThermoFile {
+    let raw_text = load_fixture("thermo.inp");
+    let (_, thermo_db) = parse_thermo_file(&raw_text).expect("fixture database parses");
+    thermo_db
+}
+
+fn format_table(comparisons: &[&Comparison]) -> String {
+    let mut table = format!(
+        "{:<10} {:>9} {:>4} {:>12} {:>12} {:>10} {:>10}\n",
+        "species", "T [K]", "prop", "expected", "actual", "deviation", "tolerance"
+    );
+    for c in comparisons {
+        table.push_str(&format!(
+            "{:<10} {:>9.2} {:>4} {:>12.3} {:>12.3} {:>10.4} {:>10.4}{}\n",
+            c.species,
+            c.temperature,
+            c.property,
+            c.expected,
+            c.actual,
+            c.deviation,
+            c.tolerance,
+            if c.passed() { "" } else { "  <-- FAIL" }
+        ));
+    }
+    table
+}
+
+#[test]
+fn species_properties_match_reference_tables() {
+    let cases: Cases = toml::from_str(&load_fixture("cases.toml")).expect("cases.toml is valid");
+    let thermo_db = load_database();
+    let tol = &cases.tolerance;
+
+    let mut comparisons = Vec::new();
+    for case in &cases.species {
+        let species = thermo_db
+            .species()
+            .iter()
+            .find(|s| s.name == case.name)
+            .unwrap_or_else(|| panic!("{} missing from fixture database", case.name));
+
+        for point in &case.points {
+            let t = point.t;
+            let mut compare = |property, expected: Option<f64>, actual: Option<f64>, relative| {
+                let Some(expected) = expected else { return };
+                let actual =
+                    actual.unwrap_or_else(|| panic!("{} out of range at {} K", case.name, t));
+                let (deviation, tolerance) = match relative {
+                    Some(tolerance) => ((actual - expected) / expected, tolerance),
+                    None => (actual - expected, tol.h),
+                };
+                comparisons.push(Comparison {
+                    species: case.name.clone(),
+                    temperature: t,
+                    property,
+                    expected,
+                    actual,
+                    deviation,
+                    tolerance,
+                });
+            };
+
+            compare("cp", point.cp, species.cp(t), Some(tol.cp));
+            compare("h", point.h, species.enthalpy(t).map(|h| h / 1000.0), None);
+            compare("s", point.s, species.entropy(t), Some(tol.s));
+        }
+    }
+
+    let failures = comparisons.iter().filter(|c| !c.passed()).count();
+    if failures > 0 {
+        let all: Vec<&Comparison> = comparisons.iter().collect();
+        panic!(
+            "{} of {} reference comparisons out of tolerance:\n{}",
+            failures,
+            comparisons.len(),
+            format_table(&all)
+        );
+    }
+}
+
+#[test]
+fn flame_temperatures_match_references() {
+    let cases: Cases = toml::from_str(&load_fixture("cases.toml")).expect("cases.toml is valid");
+    let thermo_db = load_database();
+
+    let mut comparisons = Vec::new();
+    for case in &cases.equilibrium {
+        let reactants: Vec<(&str, f64)> = case
+            .reactants
+            .iter()
+            .map(|(name, &moles)| (name.as_str(), moles))
+            .collect();
+        let initial = Mixture::from_names(&thermo_db, &reactants)
+            .unwrap_or_else(|e| panic!("{}: {}", case.name, e));
+        let enthalpy = initial
+            .reactant_enthalpy(&thermo_db, case.reactant_temperature, &[])
+            .unwrap_or_else(|e| panic!("{}: {}", case.name, e));
+        let problem = Problem::HP {
+            enthalpy,
+            pressure: case.pressure,
+        };
+        let result = solve_equilibrium(
+            &thermo_db,
+            &initial,
+            problem,
+            &EquilibriumOptions::default(),
+        )
+        .unwrap_or_else(|e| panic!("{}: {}", case.name, e));
+        comparisons.push(Comparison {
+            species: case.name.clone(),
+            temperature: case.reactant_temperature,
+            property: "Tad",
+            expected: case.t,
+            actual: result.temperature,
+            deviation: result.temperature - case.t,
+            tolerance: cases.tolerance.t,
+        });
+    }
+
+    let failed: Vec<&Comparison> = comparisons.iter().filter(|c| !c.passed()).collect();
+    if !failed.is_empty() {
+        let sources: Vec<String> = cases
+            .equilibrium
+            .iter()
+            .map(|case| format!("{}: {}", case.name, case.source))
+            .collect();
+        panic!(
+            "{} of {} flame temperatures out of tolerance:\n{}\n{}",
+            failed.len(),
+            comparisons.len(),
+            format_table(&comparisons.iter().collect::<Vec<_>>()),
+            sources.join("\n")
+        );
+    }
+}